@@ -1,3 +1,112 @@
+//! Metadonnees de compilation lues par `appinfo` : commit git, date de
+//! compilation, cible et version de keyring. Une valeur introuvable, une
+//! archive des sources sans `.git` par exemple, devient `unknown`.
+
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const UNKNOWN: &str = "unknown";
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Version resolue dans `Cargo.lock`, absent avant la premiere resolution.
+fn locked_version(lock: &Path, package: &str) -> Option<String> {
+    let text = std::fs::read_to_string(lock).ok()?;
+    let name = format!("name = \"{}\"", package);
+    let mut lines = text.lines();
+    lines.find(|line| line.trim() == name)?;
+    let version = lines.next()?.trim().strip_prefix("version = \"")?;
+    Some(version.strip_suffix('"')?.to_string())
+}
+
+/// `SOURCE_DATE_EPOCH` fixe la date pour une compilation reproductible.
+fn build_time() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        })
+}
+
+/// L'updater verifie chaque archive avec la cle publique minisign de
+/// `tauri.conf.json`. Sans cle, une release ne pourrait installer aucune mise
+/// a jour : la compilation release echoue, les autres avertissent seulement.
+fn check_updater_pubkey(conf: &Path, release: bool) {
+    let Some(updater) = std::fs::read_to_string(conf)
+        .ok()
+        .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+        .map(|conf| conf["tauri"]["updater"].clone())
+    else {
+        return;
+    };
+    let active = updater["active"].as_bool().unwrap_or(false);
+    let missing = updater["pubkey"]
+        .as_str()
+        .is_none_or(|key| key.trim().is_empty());
+    if !active || !missing {
+        return;
+    }
+    let message = "tauri.updater.pubkey est vide : coller la cle publique de \
+                   `tauri signer generate` dans tauri.conf.json";
+    if release {
+        panic!("{}", message);
+    }
+    println!("cargo:warning={}", message);
+}
+
 fn main() {
+    let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let commit = git(&["rev-parse", "HEAD"]);
+    // Un nouveau commit ou un changement de branche recompile les metadonnees.
+    if let Some(git_dir) = git(&["rev-parse", "--absolute-git-dir"]) {
+        for file in ["HEAD", "packed-refs"] {
+            let path = Path::new(&git_dir).join(file);
+            if path.exists() {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+        if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            let path = Path::new(&git_dir).join(branch);
+            if path.exists() {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+        }
+    }
+    let lock = Path::new(&manifest_dir).join("Cargo.lock");
+    if lock.exists() {
+        println!("cargo:rerun-if-changed={}", lock.display());
+    }
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    check_updater_pubkey(
+        &Path::new(&manifest_dir).join("tauri.conf.json"),
+        std::env::var("PROFILE").as_deref() == Ok("release"),
+    );
+    println!(
+        "cargo:rustc-env=ARGOS_GIT_COMMIT={}",
+        commit.as_deref().unwrap_or(UNKNOWN)
+    );
+    println!("cargo:rustc-env=ARGOS_BUILD_TIME={}", build_time());
+    println!(
+        "cargo:rustc-env=ARGOS_TARGET={}",
+        std::env::var("TARGET").unwrap_or_else(|_| UNKNOWN.to_string())
+    );
+    println!(
+        "cargo:rustc-env=ARGOS_KEYRING_VERSION={}",
+        locked_version(&lock, "keyring")
+            .as_deref()
+            .unwrap_or(UNKNOWN)
+    );
     tauri_build::build();
 }
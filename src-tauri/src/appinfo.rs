@@ -0,0 +1,66 @@
+//! Version et metadonnees de compilation, renvoyees par `app_info` et
+//! reprises telles quelles par le diagnostic et le rapport de panique.
+//! `build.rs` les injecte ; une valeur introuvable vaut `unknown`.
+
+use std::sync::OnceLock;
+
+use serde::Serialize;
+use tauri::State;
+
+use crate::keychain::KeychainManager;
+
+const UNKNOWN: &str = "unknown";
+
+/// Backend du keychain, connu une fois le manager ouvert : une panique
+/// anterieure l'indique comme `unknown`.
+static BACKEND: OnceLock<&'static str> = OnceLock::new();
+
+#[derive(Serialize, Clone)]
+pub(crate) struct AppInfo {
+    version: &'static str,
+    commit: &'static str,
+    /// RFC 3339, en UTC.
+    built_at: String,
+    tauri: &'static str,
+    keyring: &'static str,
+    backend: &'static str,
+    target: &'static str,
+    profile: &'static str,
+}
+
+impl AppInfo {
+    pub(crate) fn current() -> Self {
+        let built_at = option_env!("ARGOS_BUILD_TIME")
+            .and_then(|secs| secs.parse().ok())
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map_or_else(|| UNKNOWN.to_string(), |time| time.to_rfc3339());
+        AppInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            commit: option_env!("ARGOS_GIT_COMMIT").unwrap_or(UNKNOWN),
+            built_at,
+            tauri: tauri::VERSION,
+            keyring: option_env!("ARGOS_KEYRING_VERSION").unwrap_or(UNKNOWN),
+            backend: BACKEND.get().copied().unwrap_or(UNKNOWN),
+            target: option_env!("ARGOS_TARGET").unwrap_or(UNKNOWN),
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            },
+        }
+    }
+}
+
+/// Retient le backend detecte a l'ouverture du keychain.
+pub(crate) fn set_backend(manager: &KeychainManager) {
+    let _ = BACKEND.set(manager.backend_name());
+}
+
+/// Version et metadonnees de compilation, pour l'ecran A propos.
+#[tauri::command]
+pub(crate) fn app_info(manager: State<'_, KeychainManager>) -> AppInfo {
+    AppInfo {
+        backend: manager.backend_name(),
+        ..AppInfo::current()
+    }
+}
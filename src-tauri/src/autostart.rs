@@ -0,0 +1,362 @@
+//! Lancement d'ARGOS a l'ouverture de session : cle `Run` du registre sous
+//! Windows, LaunchAgent sous macOS, lanceur XDG `autostart` sous Linux.
+//!
+//! L'etat est toujours relu a l'emplacement du systeme : une strategie de
+//! groupe ou une modification des elements de connexion y est visible.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::keychain::{KeychainError, KeychainErrorCode};
+
+/// Option ajoutee a la commande enregistree pour demarrer cache.
+const MINIMIZED_FLAG: &str = "--minimized";
+
+#[derive(Serialize)]
+pub(crate) struct Autostart {
+    enabled: bool,
+    start_minimized: bool,
+    /// Fichier ou cle du registre qui porte l'entree.
+    location: String,
+}
+
+/// Entree lue par `platform::read`.
+struct Entry {
+    enabled: bool,
+    minimized: bool,
+}
+
+fn autostart_error(location: &str, e: std::io::Error) -> KeychainError {
+    let code = match e.kind() {
+        std::io::ErrorKind::PermissionDenied | std::io::ErrorKind::ReadOnlyFilesystem => {
+            KeychainErrorCode::AutostartDenied
+        }
+        _ => KeychainErrorCode::AutostartFailed,
+    };
+    KeychainError::rejected(code, format!("{} : {}", location, e))
+}
+
+fn current_exe() -> Result<PathBuf, KeychainError> {
+    std::env::current_exe().map_err(|e| autostart_error("executable", e))
+}
+
+fn state() -> Result<Autostart, KeychainError> {
+    let location = platform::location();
+    let entry = platform::read().map_err(|e| autostart_error(&location, e))?;
+    Ok(Autostart {
+        enabled: entry.as_ref().is_some_and(|entry| entry.enabled),
+        start_minimized: entry.is_some_and(|entry| entry.minimized),
+        location,
+    })
+}
+
+/// Relit l'entree au demarrage : une erreur de lecture est rapportee par
+/// `startup_report`.
+pub(crate) fn check() -> Result<(), KeychainError> {
+    state().map(drop)
+}
+
+#[tauri::command]
+pub(crate) fn autostart_get() -> Result<Autostart, KeychainError> {
+    state()
+}
+
+/// Ecrit ou supprime l'entree, puis renvoie l'etat relu.
+#[tauri::command]
+pub(crate) fn autostart_set(
+    enabled: bool,
+    start_minimized: Option<bool>,
+) -> Result<Autostart, KeychainError> {
+    let written = if enabled {
+        let exe = current_exe()?;
+        platform::write(&exe, start_minimized.unwrap_or(false))
+    } else {
+        platform::remove()
+    };
+    written.map_err(|e| autostart_error(&platform::location(), e))?;
+    state()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::Path;
+
+    use windows::core::HSTRING;
+    use windows::Win32::Foundation::{ERROR_FILE_NOT_FOUND, WIN32_ERROR};
+    use windows::Win32::System::Registry::{
+        RegDeleteKeyValueW, RegGetValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_ROUTINE_FLAGS,
+        REG_SZ, RRF_RT_REG_BINARY, RRF_RT_REG_SZ,
+    };
+
+    use super::{Entry, MINIMIZED_FLAG};
+
+    const RUN_KEY: &str = "Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+    /// Entrees desactivees par le gestionnaire des taches ou une strategie.
+    const APPROVED_KEY: &str =
+        "Software\\Microsoft\\Windows\\CurrentVersion\\Explorer\\StartupApproved\\Run";
+    const VALUE_NAME: &str = "ARGOS";
+
+    fn io_result(status: WIN32_ERROR) -> std::io::Result<()> {
+        if status.is_ok() {
+            return Ok(());
+        }
+        Err(std::io::Error::from_raw_os_error(status.0 as i32))
+    }
+
+    /// `None` si la valeur n'existe pas.
+    fn get_value(key: &str, flags: REG_ROUTINE_FLAGS) -> std::io::Result<Option<Vec<u8>>> {
+        let (key, name) = (HSTRING::from(key), HSTRING::from(VALUE_NAME));
+        let mut size = 0u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                &key,
+                &name,
+                flags,
+                None,
+                None,
+                Some(&mut size),
+            )
+        };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        io_result(status)?;
+        let mut data = vec![0u8; size as usize];
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                &key,
+                &name,
+                flags,
+                None,
+                Some(data.as_mut_ptr().cast()),
+                Some(&mut size),
+            )
+        };
+        io_result(status)?;
+        data.truncate(size as usize);
+        Ok(Some(data))
+    }
+
+    pub(super) fn location() -> String {
+        format!("HKCU\\{}\\{}", RUN_KEY, VALUE_NAME)
+    }
+
+    pub(super) fn read() -> std::io::Result<Option<Entry>> {
+        let Some(data) = get_value(RUN_KEY, RRF_RT_REG_SZ)? else {
+            return Ok(None);
+        };
+        let wide: Vec<u16> = data
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|&c| c != 0)
+            .collect();
+        let command = String::from_utf16_lossy(&wide);
+        // Premier octet impair : entree desactivee hors d'ARGOS.
+        let disabled = get_value(APPROVED_KEY, RRF_RT_REG_BINARY)?
+            .and_then(|data| data.first().copied())
+            .is_some_and(|state| state & 1 == 1);
+        Ok(Some(Entry {
+            enabled: !disabled,
+            minimized: command.split_whitespace().any(|arg| arg == MINIMIZED_FLAG),
+        }))
+    }
+
+    pub(super) fn write(exe: &Path, minimized: bool) -> std::io::Result<()> {
+        let mut command = format!("\"{}\"", exe.display());
+        if minimized {
+            command.push(' ');
+            command.push_str(MINIMIZED_FLAG);
+        }
+        let data: Vec<u8> = command
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        io_result(unsafe {
+            RegSetKeyValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(RUN_KEY),
+                &HSTRING::from(VALUE_NAME),
+                REG_SZ.0,
+                Some(data.as_ptr().cast()),
+                data.len() as u32,
+            )
+        })?;
+        // Reactive l'entree si elle avait ete desactivee.
+        delete(APPROVED_KEY)
+    }
+
+    fn delete(key: &str) -> std::io::Result<()> {
+        let status = unsafe {
+            RegDeleteKeyValueW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(key),
+                &HSTRING::from(VALUE_NAME),
+            )
+        };
+        if status == ERROR_FILE_NOT_FOUND {
+            return Ok(());
+        }
+        io_result(status)
+    }
+
+    pub(super) fn remove() -> std::io::Result<()> {
+        delete(RUN_KEY)?;
+        delete(APPROVED_KEY)
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::{entry_file, Entry, MINIMIZED_FLAG};
+
+    const LABEL: &str = "fr.bbrain.argos";
+
+    fn path() -> PathBuf {
+        let home = std::env::var_os("HOME")
+            .map(PathBuf::from)
+            .unwrap_or_default();
+        home.join("Library/LaunchAgents")
+            .join(format!("{}.plist", LABEL))
+    }
+
+    fn escape(text: &str) -> String {
+        text.replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+    }
+
+    pub(super) fn location() -> String {
+        path().display().to_string()
+    }
+
+    /// `Disabled` ou `RunAtLoad` a faux desactivent l'agent.
+    pub(super) fn read() -> std::io::Result<Option<Entry>> {
+        let Some(plist) = entry_file(&path())? else {
+            return Ok(None);
+        };
+        let compact: String = plist.split_whitespace().collect();
+        let disabled = compact.contains("<key>Disabled</key><true/>")
+            || compact.contains("<key>RunAtLoad</key><false/>");
+        Ok(Some(Entry {
+            enabled: !disabled,
+            minimized: compact.contains(&format!("<string>{}</string>", MINIMIZED_FLAG)),
+        }))
+    }
+
+    pub(super) fn write(exe: &Path, minimized: bool) -> std::io::Result<()> {
+        let mut arguments = format!("<string>{}</string>", escape(&exe.display().to_string()));
+        if minimized {
+            arguments.push_str(&format!("<string>{}</string>", MINIMIZED_FLAG));
+        }
+        let plist = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \
+             \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n<dict>\n\
+             <key>Label</key><string>{}</string>\n\
+             <key>ProgramArguments</key><array>{}</array>\n\
+             <key>RunAtLoad</key><true/>\n\
+             </dict>\n</plist>\n",
+            LABEL, arguments
+        );
+        super::write_file(&path(), &plist)
+    }
+
+    pub(super) fn remove() -> std::io::Result<()> {
+        super::remove_file(&path())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::path::{Path, PathBuf};
+
+    use super::{entry_file, Entry, MINIMIZED_FLAG};
+
+    const DESKTOP_FILE: &str = "fr.bbrain.argos.desktop";
+
+    fn path() -> PathBuf {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+            .unwrap_or_default()
+            .join("autostart")
+            .join(DESKTOP_FILE)
+    }
+
+    pub(super) fn location() -> String {
+        path().display().to_string()
+    }
+
+    /// `Hidden=true` ou `X-GNOME-Autostart-enabled=false`, ecrits par les
+    /// reglages du bureau, desactivent le lanceur.
+    pub(super) fn read() -> std::io::Result<Option<Entry>> {
+        let Some(desktop) = entry_file(&path())? else {
+            return Ok(None);
+        };
+        let value = |name: &str| {
+            desktop.lines().find_map(|line| {
+                line.trim()
+                    .strip_prefix(name)
+                    .and_then(|rest| rest.trim_start().strip_prefix('='))
+                    .map(|value| value.trim().to_string())
+            })
+        };
+        let disabled = value("Hidden").is_some_and(|hidden| hidden == "true")
+            || value("X-GNOME-Autostart-enabled").is_some_and(|enabled| enabled == "false");
+        Ok(Some(Entry {
+            enabled: !disabled,
+            minimized: value("Exec")
+                .is_some_and(|exec| exec.split_whitespace().any(|arg| arg == MINIMIZED_FLAG)),
+        }))
+    }
+
+    pub(super) fn write(exe: &Path, minimized: bool) -> std::io::Result<()> {
+        let mut exec = format!("\"{}\"", exe.display());
+        if minimized {
+            exec.push(' ');
+            exec.push_str(MINIMIZED_FLAG);
+        }
+        let desktop = format!(
+            "[Desktop Entry]\nType=Application\nName=ARGOS\nExec={}\nX-GNOME-Autostart-enabled=true\n",
+            exec
+        );
+        super::write_file(&path(), &desktop)
+    }
+
+    pub(super) fn remove() -> std::io::Result<()> {
+        super::remove_file(&path())
+    }
+}
+
+/// Contenu du fichier d'entree, `None` s'il n'existe pas.
+#[cfg(not(target_os = "windows"))]
+fn entry_file(path: &std::path::Path) -> std::io::Result<Option<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn write_file(path: &std::path::Path, content: &str) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    crate::config::write_atomic(path, content.as_bytes())
+}
+
+#[cfg(not(target_os = "windows"))]
+fn remove_file(path: &std::path::Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,101 @@
+//! Options de ligne de commande, lues avant la creation de l'application :
+//! un script de connexion peut lancer ARGOS verrouille et cache dans la zone
+//! de notification.
+
+use std::path::PathBuf;
+
+use crate::deeplink;
+
+const USAGE: &str = "usage : argos [--locked] [--minimized | --tray] [--profile <nom>] \
+                     [--portable [<coffre>]] [argos://...]";
+
+/// Longueur maximale d'un nom de profil.
+const MAX_PROFILE_CHARS: usize = 32;
+
+#[derive(Default)]
+pub(crate) struct Args {
+    /// Demarre la session verrouillee.
+    pub(crate) locked: bool,
+    /// Demarre fenetre cachee, dans la zone de notification.
+    pub(crate) minimized: bool,
+    /// Profil dont le prefixe de service remplace `fr.bbrain.argos.`.
+    pub(crate) profile: Option<String>,
+    pub(crate) portable: bool,
+    /// Coffre portable, relatif au dossier courant s'il n'est pas absolu.
+    pub(crate) vault_path: Option<PathBuf>,
+    /// Secrets dans `MockSecretStore`, pour les tests sans keychain.
+    #[cfg(feature = "mock-store")]
+    pub(crate) mock_store: bool,
+}
+
+/// Minuscules, chiffres et tirets : le nom entre dans un nom de service.
+pub(crate) fn is_valid_profile(profile: &str) -> bool {
+    !profile.is_empty()
+        && profile.len() <= MAX_PROFILE_CHARS
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+}
+
+/// Les arguments sans tiret, dont les liens `argos://`, sont laisses au
+/// traitement des liens profonds.
+pub(crate) fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter().peekable();
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg, None),
+        };
+        match flag.as_str() {
+            "--locked" if inline.is_none() => parsed.locked = true,
+            "--minimized" | "--tray" if inline.is_none() => parsed.minimized = true,
+            #[cfg(feature = "mock-store")]
+            "--mock-store" if inline.is_none() => parsed.mock_store = true,
+            "--locked" | "--minimized" | "--tray" => {
+                return Err(format!("{} n'attend pas de valeur", flag))
+            }
+            "--profile" => {
+                let profile = inline
+                    .or_else(|| args.next_if(|next| !next.starts_with('-')))
+                    .ok_or("--profile attend un nom de profil")?;
+                if !is_valid_profile(&profile) {
+                    return Err(format!(
+                        "profil invalide : minuscules, chiffres et tirets, {} caracteres au plus",
+                        MAX_PROFILE_CHARS
+                    ));
+                }
+                parsed.profile = Some(profile);
+            }
+            "--portable" => {
+                parsed.portable = true;
+                let path = inline.or_else(|| {
+                    args.next_if(|next| !next.starts_with('-') && !deeplink::is_deep_link(next))
+                });
+                if let Some(path) = path.filter(|path| !path.is_empty()) {
+                    let path = PathBuf::from(path);
+                    parsed.vault_path = Some(match std::env::current_dir() {
+                        Ok(dir) if path.is_relative() => dir.join(path),
+                        _ => path,
+                    });
+                }
+            }
+            // Numero de processus ajoute par les anciennes versions de macOS.
+            flag if flag.starts_with("-psn_") => {}
+            flag if flag.starts_with('-') => return Err(format!("option inconnue : {}", flag)),
+            _ => {}
+        }
+    }
+    Ok(parsed)
+}
+
+/// Une option inconnue ou incomplete arrete ARGOS avec l'usage sur la sortie
+/// d'erreur, plutot que d'etre ignoree.
+pub(crate) fn from_env() -> Args {
+    parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}\n{}", e, USAGE);
+        std::process::exit(2);
+    })
+}
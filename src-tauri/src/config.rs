@@ -0,0 +1,628 @@
+//! Configuration locale lue au demarrage dans `argos.json`, a cote de
+//! l'executable : sur une cle USB, elle voyage avec ARGOS.
+//!
+//! Les reglages de l'utilisateur sont a part, dans `config.json` du dossier
+//! de donnees : le frontend les modifie par `config_set`, et les valeurs de
+//! `argos.json` ne servent que de valeurs par defaut.
+
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{AppHandle, Manager, State};
+
+use crate::cli::{self, Args};
+use crate::keychain::autobackup::BackupInterval;
+use crate::keychain::backup::io_error;
+use crate::keychain::collection::LinuxCollection;
+use crate::keychain::messages::{self, Locale};
+use crate::keychain::session::DEFAULT_IDLE_MINUTES;
+use crate::keychain::{self, KeychainError, KeychainErrorCode, KeychainManager, LinuxBackend};
+use crate::links;
+use crate::permissions::WindowGrant;
+use crate::startup::{StartupFeature, StartupReport};
+use crate::telemetry;
+
+pub(crate) const CONFIG_FILE: &str = "argos.json";
+/// `config-<profil>.json` pour un profil : chacun garde ses reglages.
+pub(crate) const SETTINGS_FILE: &str = "config.json";
+
+pub(crate) const CONFIG_CHANGED: &str = "argos://config-changed";
+
+/// Au-dela d'une journee, autant desactiver le verrouillage (0).
+const MAX_IDLE_MINUTES: u64 = 24 * 60;
+const MAX_PREFIX_CHARS: usize = 128;
+const MAX_BACKUP_RETENTION: u32 = 365;
+const MAX_EXPIRY_SCAN_MINUTES: u64 = 24 * 60;
+const MAX_EXPIRY_WARN_HOURS: u64 = 30 * 24;
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct AppConfig {
+    /// Secrets dans un coffre chiffre plutot que dans le keychain OS.
+    pub(crate) portable: bool,
+    /// Emplacement du coffre portable, relatif a l'executable s'il n'est pas
+    /// absolu ; par defaut dans le dossier de donnees de l'application.
+    pub(crate) vault_path: Option<PathBuf>,
+    /// Prefixes de service acceptes en plus de `fr.bbrain.argos.`.
+    pub(crate) allowed_service_prefixes: Vec<String>,
+    /// Taille du journal d'audit au-dela de laquelle il est archive.
+    pub(crate) audit_max_bytes: Option<u64>,
+    /// Lectures de secrets autorisees par minute pour une meme cle (30 par
+    /// defaut, 0 pour ne pas limiter).
+    pub(crate) reads_per_key_per_minute: Option<u32>,
+    /// Lectures autorisees par minute toutes cles confondues (120 par
+    /// defaut, 0 pour ne pas limiter).
+    pub(crate) reads_per_minute: Option<u32>,
+    /// Protege la fenetre `secrets` des captures des son chargement (oui
+    /// par defaut).
+    pub(crate) protect_secrets_window: Option<bool>,
+    /// Boite de confirmation native avant `keychain_delete` d'un secret
+    /// avec historique, `keychain_clear_service`, `profile_delete` avec
+    /// effacement des secrets et `wipe_all_data` (oui par defaut).
+    pub(crate) confirm_destructive: Option<bool>,
+    /// Confirme aussi `keychain_delete` d'un secret sans historique (non
+    /// par defaut).
+    pub(crate) confirm_every_delete: bool,
+    /// Minutes d'inactivite avant le verrouillage de la session (15 par
+    /// defaut, 0 pour ne jamais verrouiller).
+    pub(crate) session_idle_minutes: Option<u64>,
+    /// Duree de vie, en secondes, d'un secret dans le cache des lectures
+    /// (300 par defaut, 0 pour desactiver le cache).
+    pub(crate) cache_ttl_seconds: Option<u64>,
+    /// Nombre maximal de secrets dans le cache (256 par defaut).
+    pub(crate) cache_max_entries: Option<usize>,
+    /// Fermer la fenetre principale la cache dans la zone de notification
+    /// au lieu de quitter ARGOS (non par defaut).
+    pub(crate) close_to_tray: bool,
+    /// Raccourci global qui verrouille la session et vide le presse-papiers
+    /// (`CmdOrCtrl+Shift+L` par defaut, vide pour n'en enregistrer aucun).
+    pub(crate) lock_hotkey: Option<String>,
+    /// Profil actif au demarrage, dont les services sont
+    /// `fr.bbrain.argos.<profil>.*` ; remplace par `--profile`.
+    pub(crate) profile: Option<String>,
+    /// Collection Secret Service des secrets sous Linux : `"default"`,
+    /// `"session"` ou `{ "named": "ARGOS" }`, creee si elle n'existe pas.
+    pub(crate) linux_collection: LinuxCollection,
+    /// Backend des secrets sous Linux : `auto` (Secret Service, puis
+    /// keyutils s'il ne repond pas), `secret_service` ou `keyutils`.
+    pub(crate) linux_backend: LinuxBackend,
+    /// Minutes apres lesquelles une rotation non confirmee est signalee par
+    /// `keychain_list` (60 par defaut).
+    pub(crate) rotation_window_minutes: Option<u64>,
+    /// Chemins absolus des programmes que `process_run_with_secret` peut
+    /// lancer ; vide, aucun.
+    pub(crate) allowed_programs: Vec<PathBuf>,
+    /// Hotes ouverts par `open_external` avant tout reglage de
+    /// `links.allowed_hosts`.
+    pub(crate) allowed_url_hosts: Vec<String>,
+    /// Droits par libelle de fenetre, par exemple
+    /// `{ "quickcopy": { "access": "read-only", "services": [...] } }` ;
+    /// une fenetre absente a un acces complet.
+    pub(crate) window_permissions: HashMap<String, WindowGrant>,
+    /// Adresse http(s) de la sonde de `network_status`, a remplacer sur un
+    /// reseau isole ; vide pour ne consulter que le systeme.
+    pub(crate) network_probe_url: Option<String>,
+    /// Adresse https ou partent les statistiques d'usage consenties ; sans
+    /// elle, les compteurs restent sur le poste.
+    pub(crate) telemetry_endpoint: Option<String>,
+    /// Store de test choisi par `--mock-store`, jamais lu dans `argos.json`.
+    #[cfg(feature = "mock-store")]
+    #[serde(skip)]
+    pub(crate) mock_store: bool,
+}
+
+/// Lit la configuration puis applique la ligne de commande. Un fichier
+/// absent donne la configuration par defaut ; un fichier invalide aussi,
+/// rapporte dans `report` comme chaque reglage ignore.
+pub(crate) fn load(args: &Args, report: &StartupReport) -> AppConfig {
+    let dir = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from));
+    let mut config: AppConfig = match dir.as_ref().map(|dir| std::fs::read(dir.join(CONFIG_FILE))) {
+        Some(Ok(data)) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+            report.record(
+                StartupFeature::Config,
+                format!("{} ignore : {}", CONFIG_FILE, e),
+            );
+            AppConfig::default()
+        }),
+        _ => AppConfig::default(),
+    };
+    if let (Some(dir), Some(path)) = (dir, config.vault_path.as_mut()) {
+        if path.is_relative() {
+            *path = dir.join(&*path);
+        }
+    }
+    if args.portable {
+        config.portable = true;
+    }
+    if let Some(path) = &args.vault_path {
+        config.vault_path = Some(path.clone());
+    }
+    if let Some(profile) = &args.profile {
+        config.profile = Some(profile.clone());
+    }
+    #[cfg(feature = "mock-store")]
+    if args.mock_store {
+        config.mock_store = true;
+    }
+    if let Some(profile) = config.profile.take() {
+        if cli::is_valid_profile(&profile) {
+            config.profile = Some(profile);
+        } else {
+            report.record(
+                StartupFeature::Config,
+                format!("{} : profil {} ignore", CONFIG_FILE, profile),
+            );
+        }
+    }
+    if let Some(url) = config.network_probe_url.take() {
+        if url.is_empty() || url.starts_with("http://") || url.starts_with("https://") {
+            config.network_probe_url = Some(url);
+        } else {
+            report.record(
+                StartupFeature::Config,
+                format!("{} : network_probe_url ignore", CONFIG_FILE),
+            );
+        }
+    }
+    if let Some(url) = config.telemetry_endpoint.take() {
+        if url.starts_with("https://") {
+            config.telemetry_endpoint = Some(url);
+        } else {
+            report.record(
+                StartupFeature::Config,
+                format!("{} : telemetry_endpoint ignore", CONFIG_FILE),
+            );
+        }
+    }
+    if !config.linux_collection.is_valid() {
+        report.record(
+            StartupFeature::Config,
+            format!("{} : linux_collection ignore", CONFIG_FILE),
+        );
+        config.linux_collection = LinuxCollection::Default;
+    }
+    config
+}
+
+/// Reglages de l'utilisateur. Un champ inconnu est refuse plutot qu'ignore :
+/// une faute de frappe dans le frontend ne doit pas passer inapercue.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct Config {
+    pub(crate) session: SessionConfig,
+    pub(crate) keychain: KeychainConfig,
+    pub(crate) telemetry: TelemetryConfig,
+    pub(crate) notifications: NotificationsConfig,
+    /// Langue des messages d'erreur, `fr` ou `en`.
+    pub(crate) locale: Locale,
+    pub(crate) profiles: ProfilesConfig,
+    pub(crate) links: LinksConfig,
+    pub(crate) backup: BackupConfig,
+    pub(crate) expiry: ExpiryConfig,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct SessionConfig {
+    /// Minutes d'inactivite avant le verrouillage, 0 pour ne jamais
+    /// verrouiller.
+    pub(crate) idle_minutes: u64,
+    /// Verrouillage a la mise en veille.
+    pub(crate) lock_on_suspend: bool,
+    /// Verrouillage au changement d'utilisateur ou au verrouillage de la
+    /// session du systeme.
+    pub(crate) lock_on_user_switch: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct KeychainConfig {
+    /// Prefixe des services crees par le frontend, qui remplace
+    /// `fr.bbrain.argos.` dans la liste des services autorises. Il doit
+    /// commencer par un prefixe d'`argos.json` : `config_set` peut
+    /// restreindre la liste, pas l'etendre.
+    pub(crate) service_prefix: String,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct TelemetryConfig {
+    pub(crate) enabled: bool,
+}
+
+/// Profils crees par `profile_create` et profil actif, `None` pour le profil
+/// par defaut.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ProfilesConfig {
+    pub(crate) active: Option<String>,
+    pub(crate) names: Vec<String>,
+}
+
+/// Hotes ouverts par `open_external` : `exemple.fr` exactement, ou
+/// `*.exemple.fr` pour ses sous-domaines.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct LinksConfig {
+    pub(crate) allowed_hosts: Vec<String>,
+}
+
+/// Sauvegardes automatiques chiffrees, `daily` ou `weekly`. Chaque service
+/// garde ses `retention` fichiers les plus recents.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct BackupConfig {
+    pub(crate) enabled: bool,
+    pub(crate) interval: BackupInterval,
+    /// Dossier absolu ; par defaut `backups` dans le dossier de donnees.
+    pub(crate) directory: Option<PathBuf>,
+    pub(crate) retention: u32,
+    /// Noms logiques des services, comme pour `keychain_export`.
+    pub(crate) services: Vec<String>,
+}
+
+/// Avertissement avant l'expiration des secrets a TTL.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct ExpiryConfig {
+    /// Minutes entre deux analyses des secrets.
+    pub(crate) scan_minutes: u64,
+    /// Heures avant l'expiration a partir desquelles un secret est signale.
+    pub(crate) warn_hours: u64,
+    /// Notification native en plus de l'evenement `argos://secret-expiring`.
+    pub(crate) notify: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NotificationsConfig {
+    /// `false` coupe toutes les notifications, frontend compris.
+    pub(crate) enabled: bool,
+}
+
+impl Config {
+    /// Valeurs de `config_reset`, reprises d'`argos.json`.
+    pub(crate) fn defaults(app_config: &AppConfig) -> Self {
+        Config {
+            session: SessionConfig {
+                idle_minutes: app_config
+                    .session_idle_minutes
+                    .unwrap_or(DEFAULT_IDLE_MINUTES)
+                    .min(MAX_IDLE_MINUTES),
+                lock_on_suspend: true,
+                lock_on_user_switch: true,
+            },
+            keychain: KeychainConfig {
+                service_prefix: keychain::SERVICE_PREFIX.to_string(),
+            },
+            telemetry: TelemetryConfig { enabled: false },
+            notifications: NotificationsConfig { enabled: true },
+            locale: Locale::default(),
+            profiles: ProfilesConfig {
+                active: app_config.profile.clone(),
+                names: app_config.profile.iter().cloned().collect(),
+            },
+            links: LinksConfig {
+                allowed_hosts: app_config.allowed_url_hosts.clone(),
+            },
+            backup: BackupConfig {
+                enabled: false,
+                interval: BackupInterval::Weekly,
+                directory: None,
+                retention: 7,
+                services: Vec::new(),
+            },
+            expiry: ExpiryConfig {
+                scan_minutes: 60,
+                warn_hours: 72,
+                notify: true,
+            },
+        }
+    }
+
+    fn validate(&self, manager: &KeychainManager) -> Result<(), KeychainError> {
+        if self.session.idle_minutes > MAX_IDLE_MINUTES {
+            return Err(invalid_config(format!(
+                "session.idle_minutes doit etre compris entre 0 et {}",
+                MAX_IDLE_MINUTES
+            )));
+        }
+        let prefix = &self.keychain.service_prefix;
+        if prefix.is_empty()
+            || prefix.chars().count() > MAX_PREFIX_CHARS
+            || !prefix.ends_with('.')
+            || !prefix
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'))
+        {
+            return Err(invalid_config(format!(
+                "keychain.service_prefix doit finir par un point et ne contenir que des lettres, \
+                 chiffres, points, tirets ou soulignes ({} caracteres au plus)",
+                MAX_PREFIX_CHARS
+            )));
+        }
+        manager.check_service_prefix(prefix)?;
+        let profiles = &self.profiles;
+        if let Some(name) = profiles
+            .names
+            .iter()
+            .find(|name| !cli::is_valid_profile(name))
+        {
+            return Err(invalid_config(format!(
+                "profiles.names : profil {} invalide",
+                name
+            )));
+        }
+        if let Some(active) = profiles
+            .active
+            .as_ref()
+            .filter(|active| !profiles.names.contains(active))
+        {
+            return Err(invalid_config(format!(
+                "profiles.active : profil {} absent de profiles.names",
+                active
+            )));
+        }
+        if let Some(pattern) = self
+            .links
+            .allowed_hosts
+            .iter()
+            .find(|pattern| !links::is_valid_pattern(pattern))
+        {
+            return Err(invalid_config(format!(
+                "links.allowed_hosts : hote {} invalide",
+                pattern
+            )));
+        }
+        let backup = &self.backup;
+        if !(1..=MAX_BACKUP_RETENTION).contains(&backup.retention) {
+            return Err(invalid_config(format!(
+                "backup.retention doit etre compris entre 1 et {}",
+                MAX_BACKUP_RETENTION
+            )));
+        }
+        if backup
+            .directory
+            .as_ref()
+            .is_some_and(|dir| dir.is_relative())
+        {
+            return Err(invalid_config(
+                "backup.directory doit etre un chemin absolu",
+            ));
+        }
+        if backup.enabled && backup.services.is_empty() {
+            return Err(invalid_config(
+                "backup.services doit contenir au moins un service",
+            ));
+        }
+        let expiry = &self.expiry;
+        if !(1..=MAX_EXPIRY_SCAN_MINUTES).contains(&expiry.scan_minutes) {
+            return Err(invalid_config(format!(
+                "expiry.scan_minutes doit etre compris entre 1 et {}",
+                MAX_EXPIRY_SCAN_MINUTES
+            )));
+        }
+        if !(1..=MAX_EXPIRY_WARN_HOURS).contains(&expiry.warn_hours) {
+            return Err(invalid_config(format!(
+                "expiry.warn_hours doit etre compris entre 1 et {}",
+                MAX_EXPIRY_WARN_HOURS
+            )));
+        }
+        for service in &backup.services {
+            manager.check_service(service)?;
+        }
+        Ok(())
+    }
+
+    /// Transmet les reglages aux sous-systemes qui les lisent.
+    fn apply(&self, manager: &KeychainManager) {
+        manager.set_service_prefix(&self.keychain.service_prefix);
+        manager.set_idle_minutes(self.session.idle_minutes);
+        manager.set_lock_triggers(
+            self.session.lock_on_suspend,
+            self.session.lock_on_user_switch,
+        );
+        messages::set_locale(self.locale);
+        manager.set_active_profile(self.profiles.active.clone());
+        telemetry::set_enabled(self.telemetry.enabled);
+    }
+}
+
+fn invalid_config(detail: impl Into<String>) -> KeychainError {
+    KeychainError::rejected(KeychainErrorCode::InvalidArgument, detail)
+}
+
+/// Fusionne `patch` dans `base`, objet par objet ; toute autre valeur
+/// remplace celle de `base`.
+fn merge(base: &mut Value, patch: Value) {
+    match (base, patch) {
+        (Value::Object(base), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, patch) => *base = patch,
+    }
+}
+
+/// `defaults` complete par `patch`. Les champs inconnus de `patch` sont
+/// refuses par `deny_unknown_fields`.
+fn merged(defaults: &Config, patch: Value) -> Result<Config, KeychainError> {
+    if !patch.is_object() {
+        return Err(invalid_config("la configuration doit etre un objet JSON"));
+    }
+    let mut value = serde_json::to_value(defaults)
+        .map_err(|e| invalid_config(format!("configuration invalide : {}", e)))?;
+    merge(&mut value, patch);
+    serde_json::from_value(value)
+        .map_err(|e| invalid_config(format!("configuration invalide : {}", e)))
+}
+
+/// Ecrit dans un fichier temporaire puis le renomme : une coupure en pleine
+/// ecriture laisse l'ancien fichier intact.
+pub(crate) fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let written = std::fs::File::create(&tmp)
+        .and_then(|mut file| file.write_all(data).and_then(|()| file.sync_all()))
+        .and_then(|()| std::fs::rename(&tmp, path));
+    if written.is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+    written
+}
+
+/// Reglages charges une fois au demarrage, enregistres dans l'etat Tauri.
+pub(crate) struct ConfigStore {
+    path: Option<PathBuf>,
+    defaults: Config,
+    current: RwLock<Config>,
+}
+
+impl ConfigStore {
+    /// Un fichier absent, corrompu ou hors limites donne les valeurs par
+    /// defaut ; les deux derniers cas sont rapportes dans `report`.
+    pub(crate) fn load(
+        app: &tauri::App,
+        defaults: Config,
+        profile: Option<&str>,
+        manager: &KeychainManager,
+        report: &StartupReport,
+    ) -> Self {
+        let file = match profile {
+            Some(profile) => format!("config-{}.json", profile),
+            None => SETTINGS_FILE.to_string(),
+        };
+        let path = app
+            .path_resolver()
+            .app_data_dir()
+            .map(|dir| dir.join(&file));
+        let current = match path.as_ref().map(std::fs::read) {
+            Some(Ok(data)) => serde_json::from_slice(&data)
+                .map_err(|e| invalid_config(e.to_string()))
+                .and_then(|patch| merged(&defaults, patch))
+                .and_then(|config| config.validate(manager).map(|()| config))
+                .unwrap_or_else(|e| {
+                    report.record(StartupFeature::Config, format!("{} ignore : {}", file, e));
+                    defaults.clone()
+                }),
+            _ => defaults.clone(),
+        };
+        current.apply(manager);
+        ConfigStore {
+            path,
+            defaults,
+            current: RwLock::new(current),
+        }
+    }
+
+    pub(crate) fn get(&self) -> RwLockReadGuard<'_, Config> {
+        self.current.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn current_mut(&self) -> RwLockWriteGuard<'_, Config> {
+        self.current.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Modifie la configuration en cours avec `change`, puis l'enregistre
+    /// comme `config_set`.
+    pub(crate) fn update(
+        &self,
+        app: &AppHandle,
+        manager: &KeychainManager,
+        change: impl FnOnce(&mut Config) -> Result<(), KeychainError>,
+    ) -> Result<Config, KeychainError> {
+        let current = self.current_mut();
+        let mut config = current.clone();
+        change(&mut config)?;
+        self.replace(app, manager, current, config)
+    }
+
+    /// Valide, ecrit puis applique `config`. En cas d'erreur, la
+    /// configuration en cours et le fichier restent inchanges.
+    fn replace(
+        &self,
+        app: &AppHandle,
+        manager: &KeychainManager,
+        mut current: RwLockWriteGuard<'_, Config>,
+        config: Config,
+    ) -> Result<Config, KeychainError> {
+        config.validate(manager)?;
+        let Some(path) = &self.path else {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::IoError,
+                "dossier de donnees introuvable",
+            ));
+        };
+        let data = serde_json::to_vec_pretty(&config)
+            .map_err(|e| invalid_config(format!("configuration invalide : {}", e)))?;
+        write_atomic(path, &data).map_err(|e| io_error(&path.display().to_string(), e))?;
+        config.apply(manager);
+        *current = config.clone();
+        drop(current);
+        let _ = app.emit_all(CONFIG_CHANGED, config.clone());
+        Ok(config)
+    }
+}
+
+#[tauri::command]
+pub(crate) fn config_get(store: State<'_, ConfigStore>) -> Config {
+    store.get().clone()
+}
+
+/// Fusionne `partial` dans la configuration en cours, par exemple
+/// `{ "session": { "idle_minutes": 5 } }`, puis l'enregistre.
+#[tauri::command]
+pub(crate) fn config_set(
+    app: AppHandle,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+    partial: Value,
+) -> Result<Config, KeychainError> {
+    let current = store.current_mut();
+    let config = merged(&current, partial)?;
+    store.replace(&app, &manager, current, config)
+}
+
+/// Change la langue des messages d'erreur et l'enregistre. Une region est
+/// acceptee et ignoree : `fr-CA` donne `fr`.
+#[tauri::command]
+pub(crate) fn set_locale(
+    app: AppHandle,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+    locale: String,
+) -> Result<Config, KeychainError> {
+    let locale = Locale::parse(&locale)
+        .ok_or_else(|| invalid_config("langue non prise en charge : fr ou en"))?;
+    store.update(&app, &manager, |config| {
+        config.locale = locale;
+        Ok(())
+    })
+}
+
+/// Revient aux valeurs par defaut d'`argos.json`.
+#[tauri::command]
+pub(crate) fn config_reset(
+    app: AppHandle,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+) -> Result<Config, KeychainError> {
+    let current = store.current_mut();
+    store.replace(&app, &manager, current, store.defaults.clone())
+}
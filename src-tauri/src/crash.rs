@@ -0,0 +1,154 @@
+//! Rapport d'arret inattendu : une panique ecrit un rapport dans `crashes/`
+//! du dossier de donnees, et le lancement suivant le signale a l'utilisateur.
+//! Le rapport ne contient que le message de la panique et la pile d'appels,
+//! jamais l'etat de l'application.
+
+use std::backtrace::Backtrace;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use tauri::{App, Manager};
+
+use crate::appinfo::AppInfo;
+use crate::instance::MAIN_WINDOW;
+
+pub(crate) const CRASH_DIR: &str = "crashes";
+const REPORT_PREFIX: &str = "crash-";
+const REPORT_SUFFIX: &str = ".txt";
+/// Suffixe d'un rapport deja signale.
+const SEEN_SUFFIX: &str = ".seen.txt";
+
+/// Installe le rapport de panique, avant la creation de l'application. Le
+/// message habituel reste ecrit sur la sortie d'erreur.
+pub(crate) fn install(data_dir: Option<PathBuf>, version: String) {
+    let dir = data_dir.map(|dir| dir.join(CRASH_DIR));
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if let Some(dir) = &dir {
+            if let Err(e) = write_report(dir, &version, info) {
+                tracing::error!(error = %e, "rapport de panique non enregistre");
+            }
+        }
+        previous(info);
+    }));
+}
+
+fn write_report(dir: &Path, version: &str, info: &PanicHookInfo<'_>) -> std::io::Result<()> {
+    let payload = info.payload();
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("panique sans message");
+    let location = info
+        .location()
+        .map(|location| location.to_string())
+        .unwrap_or_default();
+    let thread = std::thread::current();
+    tracing::error!(%location, "panique : {}", message);
+    let now = chrono::Local::now();
+    let report = format!(
+        "ARGOS {}\ncompilation : {}\ndate : {}\nthread : {}\npanique : {}\nemplacement : {}\n\n{}\n",
+        version,
+        serde_json::to_string(&AppInfo::current()).unwrap_or_default(),
+        now.to_rfc3339(),
+        thread.name().unwrap_or("sans nom"),
+        message,
+        location,
+        Backtrace::force_capture()
+    );
+    std::fs::create_dir_all(dir)?;
+    let name = format!(
+        "{}{}{}",
+        REPORT_PREFIX,
+        now.format("%Y%m%d-%H%M%S%.3f"),
+        REPORT_SUFFIX
+    );
+    std::fs::write(dir.join(name), report)
+}
+
+fn pending_reports(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| {
+                    name.starts_with(REPORT_PREFIX)
+                        && name.ends_with(REPORT_SUFFIX)
+                        && !name.ends_with(SEEN_SUFFIX)
+                })
+        })
+        .collect()
+}
+
+/// Un rapport est marque comme vu avant la boite de dialogue : une nouvelle
+/// panique ne le signale pas une seconde fois.
+fn acknowledge(report: &Path) {
+    let name = report
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let seen = report.with_file_name(format!(
+        "{}{}",
+        name.trim_end_matches(REPORT_SUFFIX),
+        SEEN_SUFFIX
+    ));
+    if let Err(e) = std::fs::rename(report, seen) {
+        tracing::warn!(report = %report.display(), error = %e, "rapport non marque comme vu");
+    }
+}
+
+/// Signale les rapports du lancement precedent et propose d'ouvrir leur
+/// dossier.
+pub(crate) fn report_previous(app: &App) {
+    let Some(dir) = app
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(CRASH_DIR))
+    else {
+        return;
+    };
+    let reports = pending_reports(&dir);
+    if reports.is_empty() {
+        return;
+    }
+    reports.iter().for_each(|report| acknowledge(report));
+    let window = app.get_window(MAIN_WINDOW);
+    tauri::api::dialog::ask(
+        window.as_ref(),
+        "ARGOS",
+        "ARGOS s'est arrete de maniere inattendue. Un rapport a ete enregistre : \
+         ouvrir son dossier ?",
+        move |open| {
+            if open {
+                if let Err(e) = open_dir(&dir) {
+                    tracing::warn!(dir = %dir.display(), error = %e, "dossier non ouvert");
+                }
+            }
+        },
+    );
+}
+
+fn open_dir(dir: &Path) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    Command::new(program).arg(dir).spawn().map(drop)
+}
+
+/// Declenche une panique pour verifier le rapport ; absent des versions
+/// publiees.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub(crate) fn debug_trigger_panic() {
+    panic!("panique declenchee par debug_trigger_panic");
+}
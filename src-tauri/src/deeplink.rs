@@ -0,0 +1,392 @@
+//! Liens `argos://` ouverts depuis le portail web. Seules quelques actions
+//! sont acceptees, avec des parametres verifies ; un lien qui ajoute un
+//! jeton est transmis au frontend, qui demande confirmation a
+//! l'utilisateur avant tout enregistrement.
+//!
+//! Sous Windows et Linux, le lien arrive en argument : au lancement, ou
+//! transmis par une seconde instance. Sous macOS, il arrive par un
+//! evenement Apple.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+use crate::keychain::{session, Secret};
+
+pub(crate) const SCHEME: &str = "argos";
+
+pub(crate) const DEEP_LINK: &str = "argos://deep-link";
+
+const MAX_URL_CHARS: usize = 8_192;
+const MAX_PARAM_CHARS: usize = 512;
+const MAX_TOKEN_CHARS: usize = 4_096;
+/// Liens gardes en attente du frontend ; au-dela, les plus recents sont
+/// refuses.
+const MAX_PENDING: usize = 16;
+
+/// Action demandee par un lien, telle qu'emise vers le frontend.
+#[derive(Serialize, Clone)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub(crate) enum DeepLink {
+    /// Proposer l'ajout d'un jeton : jamais enregistre sans confirmation de
+    /// l'utilisateur dans l'interface.
+    AddToken {
+        service: String,
+        label: Option<String>,
+        key: Option<String>,
+        token: Option<Secret>,
+    },
+    OpenProfile {
+        id: String,
+    },
+    /// Traite en Rust, jamais emis.
+    Lock,
+}
+
+/// Liens recus avant que l'application, puis le frontend, soient prets.
+struct Inbox {
+    app: Option<AppHandle>,
+    /// Le frontend a appele `deep_link_take` et ecoute `argos://deep-link`.
+    ready: bool,
+    pending: Vec<DeepLink>,
+    lock_pending: bool,
+}
+
+static INBOX: Mutex<Inbox> = Mutex::new(Inbox {
+    app: None,
+    ready: false,
+    pending: Vec::new(),
+    lock_pending: false,
+});
+
+fn inbox() -> MutexGuard<'static, Inbox> {
+    INBOX.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn param(
+    params: &mut HashMap<String, String>,
+    name: &str,
+    max_chars: usize,
+) -> Result<Option<String>, String> {
+    match params.remove(name) {
+        None => Ok(None),
+        Some(value) if value.is_empty() => Err(format!("parametre {} vide", name)),
+        Some(value) if value.chars().count() > max_chars => {
+            Err(format!("parametre {} trop long", name))
+        }
+        Some(value) if value.chars().any(char::is_control) => {
+            Err(format!("parametre {} invalide", name))
+        }
+        Some(value) => Ok(Some(value)),
+    }
+}
+
+fn required(params: &mut HashMap<String, String>, name: &str) -> Result<String, String> {
+    param(params, name, MAX_PARAM_CHARS)?.ok_or_else(|| format!("parametre {} manquant", name))
+}
+
+/// Les messages d'erreur ne reprennent jamais les valeurs du lien, qui peut
+/// contenir un jeton.
+fn parse(raw: &str) -> Result<DeepLink, String> {
+    if raw.len() > MAX_URL_CHARS {
+        return Err("lien trop long".to_string());
+    }
+    let url = Url::parse(raw).map_err(|e| format!("lien illisible : {}", e))?;
+    if url.scheme() != SCHEME
+        || !url.username().is_empty()
+        || url.password().is_some()
+        || url.port().is_some()
+        || url.fragment().is_some()
+        || !matches!(url.path(), "" | "/")
+    {
+        return Err("forme de lien inattendue".to_string());
+    }
+    let mut params = HashMap::new();
+    for (name, value) in url.query_pairs() {
+        if params
+            .insert(name.to_string(), value.into_owned())
+            .is_some()
+        {
+            return Err(format!("parametre {} repete", name));
+        }
+    }
+    let action = url.host_str().unwrap_or_default();
+    let link = match action {
+        "add-token" => DeepLink::AddToken {
+            service: required(&mut params, "service")?,
+            label: param(&mut params, "label", MAX_PARAM_CHARS)?,
+            key: param(&mut params, "key", MAX_PARAM_CHARS)?,
+            token: param(&mut params, "token", MAX_TOKEN_CHARS)?.map(Secret::new),
+        },
+        "open-profile" => DeepLink::OpenProfile {
+            id: required(&mut params, "id")?,
+        },
+        "lock" => DeepLink::Lock,
+        _ => return Err(format!("action {} inconnue", action)),
+    };
+    if let Some(name) = params.keys().next() {
+        return Err(format!("parametre {} inattendu pour {}", name, action));
+    }
+    Ok(link)
+}
+
+/// Traite un lien recu : `lock` verrouille aussitot la session, les autres
+/// actions sont emises vers le frontend ou gardees jusqu'a ce qu'il soit
+/// pret. Un lien refuse est seulement journalise.
+pub(crate) fn receive(raw: &str) {
+    let link = match parse(raw) {
+        Ok(link) => link,
+        Err(reason) => {
+            tracing::warn!(scheme = SCHEME, %reason, "lien refuse");
+            return;
+        }
+    };
+    let mut inbox = inbox();
+    let app = inbox.app.clone();
+    match (link, app) {
+        (DeepLink::Lock, Some(app)) => {
+            drop(inbox);
+            session::lock_app(&app);
+        }
+        (DeepLink::Lock, None) => inbox.lock_pending = true,
+        (link, Some(app)) if inbox.ready => {
+            drop(inbox);
+            let _ = app.emit_all(DEEP_LINK, link);
+        }
+        (_, _) if inbox.pending.len() >= MAX_PENDING => {
+            tracing::warn!(scheme = SCHEME, "lien refuse : trop de liens en attente");
+        }
+        (link, _) => inbox.pending.push(link),
+    }
+}
+
+/// Cherche des liens parmi des arguments de ligne de commande.
+pub(crate) fn receive_args<S: AsRef<str>>(args: impl IntoIterator<Item = S>) {
+    for arg in args {
+        if is_deep_link(arg.as_ref()) {
+            receive(arg.as_ref());
+        }
+    }
+}
+
+/// `argos:` en tete, quelle que soit la suite.
+pub(crate) fn is_deep_link(arg: &str) -> bool {
+    arg.strip_prefix(SCHEME)
+        .is_some_and(|rest| rest.starts_with(':'))
+}
+
+/// Rattache les liens a l'application une fois le `KeychainManager` en
+/// place ; un `lock` recu au lancement est applique maintenant.
+pub(crate) fn attach(app: &AppHandle) {
+    let lock_pending = {
+        let mut inbox = inbox();
+        inbox.app = Some(app.clone());
+        std::mem::take(&mut inbox.lock_pending)
+    };
+    if lock_pending {
+        session::lock_app(app);
+    }
+}
+
+/// Renvoie les liens recus avant que le frontend ecoute, puis emet les
+/// suivants en direct sur `argos://deep-link`.
+#[tauri::command]
+pub(crate) fn deep_link_take() -> Vec<DeepLink> {
+    let mut inbox = inbox();
+    inbox.ready = true;
+    std::mem::take(&mut inbox.pending)
+}
+
+/// Associe le schema `argos://` a l'executable en cours, pour l'utilisateur
+/// courant : en mode portable, le chemin change d'un poste a l'autre.
+pub(crate) fn register_scheme() {
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    if let Err(e) = platform::register(&exe) {
+        tracing::warn!(scheme = SCHEME, error = %e, "schema non enregistre");
+    }
+}
+
+/// A appeler avant le demarrage de Tauri : un lien qui lance l'application
+/// arrive avant la fin de son initialisation.
+pub(crate) fn install() {
+    platform::install();
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::path::Path;
+
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::System::Registry::{
+        RegCloseKey, RegCreateKeyExW, RegSetValueExW, HKEY, HKEY_CURRENT_USER, KEY_WRITE,
+        REG_OPTION_NON_VOLATILE, REG_SZ,
+    };
+
+    use super::SCHEME;
+
+    /// Cree la cle s'il le faut et y ecrit une valeur texte ; `None` designe
+    /// la valeur par defaut.
+    fn set_value(path: &str, name: Option<&str>, value: &str) -> windows::core::Result<()> {
+        let mut key = HKEY::default();
+        let data: Vec<u8> = value
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let name = name.map(HSTRING::from);
+        unsafe {
+            RegCreateKeyExW(
+                HKEY_CURRENT_USER,
+                &HSTRING::from(path),
+                0,
+                PCWSTR::null(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_WRITE,
+                None,
+                &mut key,
+                None,
+            )
+            .ok()?;
+            let written = RegSetValueExW(
+                key,
+                name.as_ref()
+                    .map_or(PCWSTR::null(), |name| PCWSTR(name.as_ptr())),
+                0,
+                REG_SZ,
+                Some(&data),
+            )
+            .ok();
+            let _ = RegCloseKey(key);
+            written
+        }
+    }
+
+    pub(super) fn register(exe: &Path) -> windows::core::Result<()> {
+        let root = format!("Software\\Classes\\{}", SCHEME);
+        set_value(&root, None, "URL:ARGOS")?;
+        set_value(&root, Some("URL Protocol"), "")?;
+        set_value(
+            &format!("{}\\shell\\open\\command", root),
+            None,
+            &format!("\"{}\" \"%1\"", exe.display()),
+        )
+    }
+
+    pub(super) fn install() {}
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::path::Path;
+
+    use objc2::rc::Id;
+    use objc2::runtime::{AnyObject, NSObject};
+    use objc2::{declare_class, msg_send_id, mutability, sel, ClassType, DeclaredClass};
+    use objc2_foundation::{NSAppleEventDescriptor, NSAppleEventManager};
+
+    /// `kInternetEventClass` et `kAEGetURL` : 'GURL'.
+    const GET_URL: u32 = u32::from_be_bytes(*b"GURL");
+    /// `keyDirectObject` : '----'.
+    const DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+
+    declare_class!(
+        struct UrlHandler;
+
+        unsafe impl ClassType for UrlHandler {
+            type Super = NSObject;
+            type Mutability = mutability::InteriorMutable;
+            const NAME: &'static str = "ArgosUrlHandler";
+        }
+
+        impl DeclaredClass for UrlHandler {}
+
+        unsafe impl UrlHandler {
+            #[method(handleGetURLEvent:withReplyEvent:)]
+            fn handle_get_url(
+                &self,
+                event: &NSAppleEventDescriptor,
+                _reply: &NSAppleEventDescriptor,
+            ) {
+                let url = unsafe { event.paramDescriptorForKeyword(DIRECT_OBJECT) }
+                    .and_then(|descriptor| unsafe { descriptor.stringValue() });
+                if let Some(url) = url {
+                    super::receive(&url.to_string());
+                }
+            }
+        }
+    );
+
+    /// Le schema est declare dans `Info.plist`.
+    pub(super) fn register(_exe: &Path) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    pub(super) fn install() {
+        let handler: Id<UrlHandler> = unsafe { msg_send_id![UrlHandler::alloc(), init] };
+        let target: &AnyObject = &handler;
+        unsafe {
+            NSAppleEventManager::sharedAppleEventManager()
+                .setEventHandler_andSelector_forEventClass_andEventID(
+                    target,
+                    sel!(handleGetURLEvent:withReplyEvent:),
+                    GET_URL,
+                    GET_URL,
+                );
+        }
+        // Le gestionnaire d'evenements ne retient pas l'objet.
+        std::mem::forget(handler);
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use super::SCHEME;
+
+    const DESKTOP_FILE: &str = "fr.bbrain.argos-url.desktop";
+
+    fn applications_dir() -> Option<PathBuf> {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })
+            .map(|dir| dir.join("applications"))
+    }
+
+    /// Ecrit un lanceur cache qui declare le schema, puis le designe comme
+    /// gestionnaire par defaut. Rien n'est refait si le lanceur est a jour.
+    pub(super) fn register(exe: &Path) -> std::io::Result<()> {
+        let dir = applications_dir().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "dossier personnel introuvable",
+            )
+        })?;
+        let path = dir.join(DESKTOP_FILE);
+        let desktop = format!(
+            "[Desktop Entry]\nType=Application\nName=ARGOS\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+            exe.display(),
+            SCHEME
+        );
+        if std::fs::read_to_string(&path).is_ok_and(|current| current == desktop) {
+            return Ok(());
+        }
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(&path, desktop)?;
+        Command::new("xdg-mime")
+            .args(["default", DESKTOP_FILE])
+            .arg(format!("x-scheme-handler/{}", SCHEME))
+            .status()?;
+        Ok(())
+    }
+
+    pub(super) fn install() {}
+}
@@ -0,0 +1,319 @@
+//! Archive de diagnostic a joindre a un ticket : journaux recents, reglages,
+//! etat du keychain, versions et systeme. Aucun secret n'y entre, et les noms
+//! de service et de cle n'y figurent que masques.
+
+use std::io::{Cursor, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use tauri::api::dialog::blocking::FileDialogBuilder;
+use tauri::{AppHandle, State, Window};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::appinfo::AppInfo;
+use crate::config::{write_atomic, ConfigStore};
+use crate::keychain::backup::io_error;
+use crate::keychain::{commands, metrics, KeychainError, KeychainErrorCode, KeychainManager};
+use crate::logging::Logging;
+
+/// Journaux joints : les trois derniers jours suffisent a un ticket.
+const LOG_FILES: usize = 3;
+/// Fin de chaque journal gardee, en octets.
+const MAX_LOG_BYTES: usize = 2 * 1024 * 1024;
+
+/// Champs du journal qui portent un nom de service ou de cle.
+const NAME_FIELDS: [&str; 2] = ["service=\"", "key=\""];
+
+#[derive(Serialize)]
+pub(crate) struct DiagnosticsBundle {
+    path: PathBuf,
+    size_bytes: u64,
+}
+
+/// Noms de service et de cle releves dans le journal et les reglages, masques
+/// partout ou ils apparaissent, messages d'erreur compris.
+#[derive(Default)]
+struct Redactor {
+    names: Vec<String>,
+}
+
+impl Redactor {
+    fn collect_fields(&mut self, text: &str) {
+        for field in NAME_FIELDS {
+            for (start, _) in text.match_indices(field) {
+                let preceded = text[..start]
+                    .chars()
+                    .next_back()
+                    .is_none_or(|c| c == ' ' || c == '{');
+                let value = &text[start + field.len()..];
+                if let (true, Some(end)) = (preceded, value.find('"')) {
+                    self.add(&value[..end]);
+                }
+            }
+        }
+    }
+
+    fn collect_strings(&mut self, value: &Value) {
+        match value {
+            Value::String(s) => self.add(s),
+            Value::Array(items) => items.iter().for_each(|item| self.collect_strings(item)),
+            Value::Object(fields) => fields
+                .values()
+                .for_each(|field| self.collect_strings(field)),
+            _ => {}
+        }
+    }
+
+    fn add(&mut self, name: &str) {
+        if !name.is_empty() && !self.names.iter().any(|known| known == name) {
+            self.names.push(name.to_string());
+        }
+    }
+
+    /// Les noms longs d'abord : un service ne doit pas etre masque morceau
+    /// par morceau a cause d'un prefixe plus court.
+    fn finish(mut self) -> Self {
+        self.names.sort_by_key(|name| std::cmp::Reverse(name.len()));
+        self
+    }
+
+    fn redact(&self, text: &str) -> String {
+        self.names.iter().fold(text.to_string(), |text, name| {
+            replace_word(&text, name, &mask(name))
+        })
+    }
+}
+
+/// Deux caracteres gardes au plus, et la longueur : assez pour distinguer
+/// deux noms dans un ticket, pas pour les retrouver.
+fn mask(name: &str) -> String {
+    let chars = name.chars().count();
+    let kept: String = name.chars().take(if chars > 6 { 2 } else { 0 }).collect();
+    format!("{}***({})", kept, chars)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '-'
+}
+
+/// Remplace `name` la ou il n'est pas le morceau d'un mot plus long.
+fn replace_word(text: &str, name: &str, replacement: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (start, _) in text.match_indices(name) {
+        let end = start + name.len();
+        let before = text[..start].chars().next_back().is_some_and(is_word_char);
+        let after = text[end..].chars().next().is_some_and(is_word_char);
+        if start < last || before || after {
+            continue;
+        }
+        out.push_str(&text[last..start]);
+        out.push_str(replacement);
+        last = end;
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// Fin du journal, coupee au debut d'une ligne.
+fn read_log_tail(path: &Path) -> Result<String, KeychainError> {
+    let data = std::fs::read(path).map_err(|e| io_error(&path.display().to_string(), e))?;
+    let mut start = data.len().saturating_sub(MAX_LOG_BYTES);
+    if start > 0 {
+        start += data[start..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(0, |newline| newline + 1);
+    }
+    Ok(String::from_utf8_lossy(&data[start..]).into_owned())
+}
+
+fn system_info(app: &AppHandle) -> Value {
+    let package = app.package_info();
+    let os = os_info::get();
+    json!({
+        "application": package.name,
+        "version": package.version.to_string(),
+        "tauri": tauri::VERSION,
+        "build": if cfg!(debug_assertions) { "debug" } else { "release" },
+        "mock_store": cfg!(feature = "mock-store"),
+        "os": os.os_type().to_string(),
+        "os_version": os.version().to_string(),
+        "arch": std::env::consts::ARCH,
+        "generated_at": chrono::Utc::now().to_rfc3339(),
+        "app_info": AppInfo::current(),
+    })
+}
+
+fn build_archive(entries: &[(String, String)]) -> zip::result::ZipResult<Vec<u8>> {
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+    for (name, content) in entries {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(content.as_bytes())?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+fn platform_error(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::PlatformError,
+        format!("diagnostic impossible : {}", detail),
+    )
+}
+
+/// Demande ou enregistrer l'archive puis l'ecrit ; `None` si l'utilisateur
+/// annule la boite de dialogue.
+#[tauri::command]
+pub(crate) async fn diagnostics_collect(
+    app: AppHandle,
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    logging: State<'_, Logging>,
+    store: State<'_, ConfigStore>,
+) -> Result<Option<DiagnosticsBundle>, KeychainError> {
+    let status = commands::probe_status(manager.inner().clone()).await;
+    let status = serde_json::to_value(&status).map_err(platform_error)?;
+    let config = serde_json::to_value(&*store.get()).map_err(platform_error)?;
+    let log_files = logging.recent_files(LOG_FILES);
+    let system = system_info(&app);
+    let metrics = serde_json::to_value(metrics::snapshot()).map_err(platform_error)?;
+
+    let file_name = format!(
+        "argos-diagnostic-{}.zip",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let mut dialog = FileDialogBuilder::new()
+        .set_parent(&window)
+        .set_file_name(&file_name)
+        .add_filter("Archive zip", &["zip"]);
+    if let Some(dir) = tauri::api::path::download_dir() {
+        dialog = dialog.set_directory(dir);
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(path) = dialog.save_file() else {
+            return Ok(None);
+        };
+        let mut logs = Vec::new();
+        for file in &log_files {
+            let name = file.file_name().unwrap_or_default().to_string_lossy();
+            logs.push((format!("logs/{}", name), read_log_tail(file)?));
+        }
+        let mut redactor = Redactor::default();
+        redactor.collect_strings(&config);
+        logs.iter()
+            .for_each(|(_, content)| redactor.collect_fields(content));
+        let redactor = redactor.finish();
+
+        let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap_or_default();
+        let mut entries = vec![
+            ("system.json".to_string(), pretty(&system)),
+            ("config.json".to_string(), redactor.redact(&pretty(&config))),
+            (
+                "keychain-status.json".to_string(),
+                redactor.redact(&pretty(&status)),
+            ),
+            ("metrics.json".to_string(), pretty(&metrics)),
+        ];
+        entries.extend(
+            logs.into_iter()
+                .map(|(name, content)| (name, redactor.redact(&content))),
+        );
+
+        let archive = build_archive(&entries).map_err(platform_error)?;
+        let display = path.display().to_string();
+        write_atomic(&path, &archive).map_err(|e| io_error(&display, e))?;
+        Ok(Some(DiagnosticsBundle {
+            path,
+            size_bytes: archive.len() as u64,
+        }))
+    })
+    .await
+    .map_err(platform_error)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LOG: &str = concat!(
+        "2026-01-02T10:00:00Z  INFO keychain_get{service=\"fr.bbrain.argos.prod\" key=\"db_password\"}: lecture\n",
+        "2026-01-02T10:00:01Z  WARN keychain_set{service=\"fr.bbrain.argos\" key=\"api\"}: ",
+        "erreur sur db_password (fr.bbrain.argos.prod)\n",
+        "2026-01-02T10:00:02Z  INFO autre{myservice=\"ignore\" key=\"\"}: fin\n",
+    );
+
+    fn redactor() -> Redactor {
+        let mut redactor = Redactor::default();
+        redactor.collect_fields(LOG);
+        redactor.collect_strings(&json!({
+            "service_prefixes": ["fr.client.vault"],
+            "profiles": { "active": "staging" },
+            "retry": 3,
+        }));
+        redactor.finish()
+    }
+
+    #[test]
+    fn names_are_collected_from_fields_and_settings() {
+        let mut names = redactor().names;
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "api",
+                "db_password",
+                "fr.bbrain.argos",
+                "fr.bbrain.argos.prod",
+                "fr.client.vault",
+                "staging",
+            ]
+        );
+    }
+
+    #[test]
+    fn names_are_masked_everywhere() {
+        let redacted = redactor().redact(LOG);
+        for name in ["db_password", "fr.bbrain", "api\"", "staging"] {
+            assert!(!redacted.contains(name), "{} dans {}", name, redacted);
+        }
+        // Le nom le plus long passe d'abord et reste entier.
+        assert!(redacted.contains("service=\"fr***(20)\""));
+        assert!(redacted.contains("service=\"fr***(15)\""));
+        assert!(redacted.contains("erreur sur db***(11) (fr***(20))"));
+        assert!(redacted.contains("key=\"***(3)\""));
+        assert!(redacted.contains("myservice=\"ignore\""));
+    }
+
+    #[test]
+    fn only_whole_words_are_replaced() {
+        assert_eq!(
+            replace_word("api apis rapi api_2 (api)", "api", "X"),
+            "X apis rapi api_2 (X)"
+        );
+        assert_eq!(replace_word("aaaa", "aa", "X"), "aaaa");
+        assert_eq!(mask("token"), "***(5)");
+        assert_eq!(mask("été-2026"), "ét***(8)");
+    }
+
+    #[test]
+    fn log_tail_starts_on_a_full_line() {
+        let dir = crate::keychain::testing::scratch_dir("diagnostics");
+        let path = dir.join("argos.log");
+        let line = format!("{}\n", "x".repeat(99));
+        let mut log = String::from("premiere ligne coupee\n");
+        log.push_str(&line.repeat(MAX_LOG_BYTES / line.len() + 1));
+        std::fs::write(&path, &log).unwrap();
+
+        let tail = read_log_tail(&path).unwrap();
+        assert!(tail.len() <= MAX_LOG_BYTES);
+        assert!(tail.starts_with('x') && tail.ends_with('\n'));
+        assert_eq!(tail.len() % line.len(), 0);
+
+        std::fs::write(&path, "court\n").unwrap();
+        assert_eq!(read_log_tail(&path).unwrap(), "court\n");
+    }
+}
@@ -0,0 +1,143 @@
+//! Raccourci global de verrouillage d'urgence : ou que soit le focus, il
+//! verrouille la session et vide le presse-papiers.
+
+use std::sync::{Mutex, MutexGuard};
+
+use serde::Serialize;
+use tauri::{AppHandle, GlobalShortcutManager, Manager, State};
+
+use crate::keychain::{clipboard, session, KeychainError, KeychainErrorCode};
+
+pub(crate) const DEFAULT_LOCK_HOTKEY: &str = "CmdOrCtrl+Shift+L";
+
+pub(crate) const HOTKEY_FAILED: &str = "argos://hotkey-failed";
+
+#[derive(Serialize, Clone, Default)]
+pub(crate) struct HotkeyStatus {
+    /// Raccourci enregistre, `None` si aucun ne l'est.
+    accelerator: Option<String>,
+    /// Echec du dernier enregistrement, par exemple un raccourci deja pris
+    /// par une autre application.
+    error: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct HotkeyFailed {
+    accelerator: String,
+    message: String,
+}
+
+/// Etat du raccourci, enregistre dans l'etat Tauri.
+#[derive(Default)]
+pub(crate) struct Hotkey(Mutex<HotkeyStatus>);
+
+impl Hotkey {
+    fn status(&self) -> MutexGuard<'_, HotkeyStatus> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn shortcut_error(accelerator: &str, detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::ShortcutUnavailable,
+        format!("raccourci {} indisponible : {}", accelerator, detail),
+    )
+}
+
+fn register(app: &AppHandle, accelerator: &str) -> Result<(), KeychainError> {
+    let handle = app.clone();
+    app.global_shortcut_manager()
+        .register(accelerator, move || lock_now(&handle))
+        .map_err(|e| shortcut_error(accelerator, e))
+}
+
+/// Vide le presse-papiers meme si la session etait deja verrouillee.
+fn lock_now(app: &AppHandle) {
+    session::lock_app(app);
+    let _ = clipboard::clear_now(app);
+}
+
+fn record_failure(
+    app: &AppHandle,
+    status: &mut HotkeyStatus,
+    accelerator: &str,
+    error: &KeychainError,
+) {
+    status.error = Some(error.to_string());
+    let _ = app.emit_all(
+        HOTKEY_FAILED,
+        HotkeyFailed {
+            accelerator: accelerator.to_string(),
+            message: error.to_string(),
+        },
+    );
+}
+
+/// Enregistre au demarrage le raccourci d'`argos.json`. Un echec n'empeche
+/// pas ARGOS de demarrer : il est rapporte par `hotkey_status`,
+/// l'evenement `argos://hotkey-failed` et l'erreur renvoyee.
+pub(crate) fn register_startup(
+    app: &AppHandle,
+    accelerator: Option<String>,
+) -> Result<(), KeychainError> {
+    let accelerator = accelerator.unwrap_or_else(|| DEFAULT_LOCK_HOTKEY.to_string());
+    let accelerator = accelerator.trim();
+    if accelerator.is_empty() {
+        return Ok(());
+    }
+    let hotkey = app.state::<Hotkey>();
+    let mut status = hotkey.status();
+    register(app, accelerator)
+        .map(|()| status.accelerator = Some(accelerator.to_string()))
+        .inspect_err(|e| record_failure(app, &mut status, accelerator, e))
+}
+
+/// Remplace le raccourci de verrouillage ; une chaine vide le retire.
+/// L'ancien raccourci est libere d'abord, et repris si le nouveau ne peut
+/// pas etre enregistre.
+#[tauri::command]
+pub(crate) fn hotkey_set(
+    app: AppHandle,
+    hotkey: State<'_, Hotkey>,
+    accelerator: String,
+) -> Result<HotkeyStatus, KeychainError> {
+    let accelerator = accelerator.trim();
+    let mut status = hotkey.status();
+    if status.accelerator.as_deref() == Some(accelerator) {
+        return Ok(status.clone());
+    }
+    let previous = status.accelerator.take();
+    if let Some(previous) = &previous {
+        if let Err(e) = app.global_shortcut_manager().unregister(previous) {
+            status.accelerator = Some(previous.clone());
+            return Err(shortcut_error(previous, e));
+        }
+    }
+    if accelerator.is_empty() {
+        status.error = None;
+        return Ok(status.clone());
+    }
+    match register(&app, accelerator) {
+        Ok(()) => {
+            status.accelerator = Some(accelerator.to_string());
+            status.error = None;
+            Ok(status.clone())
+        }
+        Err(e) => {
+            if let Some(previous) = previous {
+                if register(&app, &previous).is_ok() {
+                    status.accelerator = Some(previous);
+                }
+            }
+            record_failure(&app, &mut status, accelerator, &e);
+            Err(e)
+        }
+    }
+}
+
+/// Raccourci enregistre et dernier echec, par exemple au demarrage avant
+/// le chargement des fenetres.
+#[tauri::command]
+pub(crate) fn hotkey_status(hotkey: State<'_, Hotkey>) -> HotkeyStatus {
+    hotkey.status().clone()
+}
@@ -0,0 +1,176 @@
+//! Instance unique d'ARGOS : un second lancement transmet ses arguments a
+//! l'instance en cours, qui revient au premier plan, puis s'arrete.
+//!
+//! L'instance principale detient un verrou exclusif sur `argos.lock`. Le
+//! systeme le libere a la fin du processus, meme apres un plantage : un
+//! verrou ne reste jamais perime. Les arguments passent par une connexion
+//! locale dont le port et le jeton sont ecrits dans `argos.port`.
+
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{self, Read, Write};
+use std::net::{Ipv4Addr, TcpListener, TcpStream};
+use std::path::Path;
+use std::time::Duration;
+
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::keychain::session;
+use crate::{cli, deeplink};
+
+pub(crate) const SECOND_INSTANCE: &str = "argos://second-instance";
+
+pub(crate) const LOCK_FILE: &str = "argos.lock";
+pub(crate) const ENDPOINT_FILE: &str = "argos.port";
+pub(crate) const MAIN_WINDOW: &str = "main";
+
+/// L'instance principale peut etre en train de demarrer : son port n'est
+/// pas encore publie.
+const CONNECT_ATTEMPTS: u32 = 20;
+const CONNECT_DELAY: Duration = Duration::from_millis(100);
+const IO_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_MESSAGE_BYTES: u64 = 64 * 1024;
+
+/// Lancement transmis a l'instance principale.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct Launch {
+    /// Arguments de ligne de commande, sans le nom de l'executable.
+    args: Vec<String>,
+    cwd: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Endpoint {
+    port: u16,
+    token: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Forwarded {
+    token: String,
+    launch: Launch,
+}
+
+/// Instance principale, qui garde le verrou jusqu'a la fermeture d'ARGOS.
+pub(crate) struct Instance {
+    _lock: File,
+    listener: TcpListener,
+    token: String,
+}
+
+fn open_private(path: &Path) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create(true).truncate(false);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(path)
+}
+
+/// Prend le verrou d'instance dans `dir`. Si une autre instance le detient,
+/// lui transmet les arguments du lancement et renvoie `None` : l'appelant
+/// doit s'arreter.
+pub(crate) fn acquire(dir: &Path) -> io::Result<Option<Instance>> {
+    std::fs::create_dir_all(dir)?;
+    let lock = open_private(&dir.join(LOCK_FILE))?;
+    match lock.try_lock() {
+        Ok(()) => {}
+        Err(TryLockError::WouldBlock) => {
+            if let Err(e) = forward(dir) {
+                eprintln!("ARGOS est deja lance, transmission impossible : {}", e);
+            }
+            return Ok(None);
+        }
+        Err(TryLockError::Error(e)) => return Err(e),
+    }
+
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    let mut token = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut token);
+    let endpoint = Endpoint {
+        port: listener.local_addr()?.port(),
+        token: token.iter().map(|b| format!("{:02x}", b)).collect(),
+    };
+    let mut file = open_private(&dir.join(ENDPOINT_FILE))?;
+    file.set_len(0)?;
+    file.write_all(&serde_json::to_vec(&endpoint)?)?;
+    Ok(Some(Instance {
+        _lock: lock,
+        listener,
+        token: endpoint.token,
+    }))
+}
+
+fn forward(dir: &Path) -> io::Result<()> {
+    let launch = Launch {
+        args: std::env::args().skip(1).collect(),
+        cwd: std::env::current_dir()
+            .ok()
+            .map(|dir| dir.to_string_lossy().into_owned()),
+    };
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "port d'instance introuvable");
+    for _ in 0..CONNECT_ATTEMPTS {
+        match send(dir, &launch) {
+            Ok(()) => return Ok(()),
+            Err(e) => last_error = e,
+        }
+        std::thread::sleep(CONNECT_DELAY);
+    }
+    Err(last_error)
+}
+
+fn send(dir: &Path, launch: &Launch) -> io::Result<()> {
+    let endpoint: Endpoint = serde_json::from_slice(&std::fs::read(dir.join(ENDPOINT_FILE))?)?;
+    let mut stream = TcpStream::connect((Ipv4Addr::LOCALHOST, endpoint.port))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let message = Forwarded {
+        token: endpoint.token,
+        launch: launch.clone(),
+    };
+    stream.write_all(&serde_json::to_vec(&message)?)
+}
+
+impl Instance {
+    /// Recoit les lancements suivants pendant toute la vie de l'application :
+    /// chacun ramene la fenetre principale au premier plan, sauf avec
+    /// `--minimized`, et emet `argos://second-instance` avec ses arguments.
+    /// `--locked` verrouille la session en cours ; `--profile` et
+    /// `--portable` ne s'appliquent qu'au demarrage.
+    pub(crate) fn listen(self, app: AppHandle) {
+        std::thread::spawn(move || {
+            for stream in self.listener.incoming().flatten() {
+                let Some(launch) = receive(stream, &self.token) else {
+                    continue;
+                };
+                let args = cli::parse(launch.args.iter().cloned()).unwrap_or_default();
+                if args.locked {
+                    session::lock_app(&app);
+                }
+                if !args.minimized {
+                    show_main_window(&app);
+                }
+                deeplink::receive_args(&launch.args);
+                let _ = app.emit_all(SECOND_INSTANCE, launch);
+            }
+        });
+    }
+}
+
+/// Affiche la fenetre principale, meme reduite ou cachee, au premier plan.
+pub(crate) fn show_main_window(app: &AppHandle) {
+    if let Some(window) = app.get_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+/// Un message sans le jeton d'`argos.port` vient d'un autre programme et
+/// est ignore.
+fn receive(stream: TcpStream, token: &str) -> Option<Launch> {
+    stream.set_read_timeout(Some(IO_TIMEOUT)).ok()?;
+    let mut data = Vec::new();
+    stream.take(MAX_MESSAGE_BYTES).read_to_end(&mut data).ok()?;
+    let message: Forwarded = serde_json::from_slice(&data).ok()?;
+    (message.token == token).then_some(message.launch)
+}
@@ -0,0 +1,162 @@
+//! Protections propres au trousseau macOS, demandees par `keychain_set` :
+//! accessibilite de l'entree (`kSecAttrAccessible...`) et presence de
+//! l'utilisateur. Les autres backends les ignorent et le signalent.
+
+use serde::{Deserialize, Serialize};
+
+/// Quand l'entree est lisible ; les variantes `this_device_only` ne sont ni
+/// sauvegardees ni migrees vers un nouvel appareil.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MacosAccessibility {
+    WhenUnlocked,
+    WhenUnlockedThisDeviceOnly,
+    AfterFirstUnlock,
+    AfterFirstUnlockThisDeviceOnly,
+    WhenPasscodeSetThisDeviceOnly,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug)]
+#[serde(default)]
+pub(crate) struct MacosAccess {
+    pub(crate) accessibility: Option<MacosAccessibility>,
+    /// Touch ID ou mot de passe de session a chaque lecture.
+    pub(crate) require_user_presence: bool,
+}
+
+impl MacosAccess {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.accessibility.is_none() && !self.require_user_presence
+    }
+}
+
+/// Options reellement appliquees, renvoyees par `keychain_set`.
+#[derive(Serialize)]
+pub(crate) struct MacosReport {
+    accessibility: Option<MacosAccessibility>,
+    require_user_presence: bool,
+    /// `true` si le backend ne les prend pas en charge : rien n'a ete
+    /// applique.
+    ignored: bool,
+}
+
+impl MacosReport {
+    pub(crate) fn applied(access: MacosAccess) -> Self {
+        MacosReport {
+            accessibility: access.accessibility,
+            require_user_presence: access.require_user_presence,
+            ignored: false,
+        }
+    }
+
+    pub(crate) fn ignored() -> Self {
+        MacosReport {
+            accessibility: None,
+            require_user_presence: false,
+            ignored: true,
+        }
+    }
+}
+
+/// Recree l'entree `(service, key)` avec `access`. Le contenu est relu puis
+/// l'entree supprimee et ajoutee avec ses protections ; si l'ajout echoue,
+/// l'entree d'origine est restauree.
+#[cfg(target_os = "macos")]
+pub(super) fn protect(
+    service: &str,
+    key: &str,
+    access: &MacosAccess,
+) -> Result<(), super::KeychainError> {
+    use security_framework::access_control::{ProtectionMode, SecAccessControl};
+    use security_framework::passwords::{
+        delete_generic_password, get_generic_password, set_generic_password,
+        set_generic_password_options, PasswordOptions,
+    };
+    use security_framework::passwords_options::AccessControlOptions;
+    use zeroize::Zeroizing;
+
+    use super::{KeychainError, KeychainErrorCode};
+
+    let platform = |e: security_framework::base::Error| {
+        KeychainError::rejected(
+            KeychainErrorCode::PlatformError,
+            format!("protections macOS non appliquees : {}", e),
+        )
+    };
+    let protection = access
+        .accessibility
+        .map(|accessibility| match accessibility {
+            MacosAccessibility::WhenUnlocked => ProtectionMode::AccessibleWhenUnlocked,
+            MacosAccessibility::WhenUnlockedThisDeviceOnly => {
+                ProtectionMode::AccessibleWhenUnlockedThisDeviceOnly
+            }
+            MacosAccessibility::AfterFirstUnlock => ProtectionMode::AccessibleAfterFirstUnlock,
+            MacosAccessibility::AfterFirstUnlockThisDeviceOnly => {
+                ProtectionMode::AccessibleAfterFirstUnlockThisDeviceOnly
+            }
+            MacosAccessibility::WhenPasscodeSetThisDeviceOnly => {
+                ProtectionMode::AccessibleWhenPasscodeSetThisDeviceOnly
+            }
+        });
+    let flags = if access.require_user_presence {
+        AccessControlOptions::USER_PRESENCE.bits()
+    } else {
+        0
+    };
+    let control = SecAccessControl::create_with_protection(protection, flags).map_err(platform)?;
+
+    let value = Zeroizing::new(get_generic_password(service, key).map_err(platform)?);
+    delete_generic_password(service, key).map_err(platform)?;
+    let mut options = PasswordOptions::new_generic_password(service, key);
+    options.set_access_control(control);
+    if let Err(e) = set_generic_password_options(&value, options) {
+        if let Err(restore) = set_generic_password(service, key, &value) {
+            tracing::warn!(service, key, error = %restore, "entree macOS non restauree");
+        }
+        return Err(platform(e));
+    }
+    Ok(())
+}
+
+/// `errSecInteractionNotAllowed` : trousseau verrouille, sans demande
+/// possible a l'utilisateur.
+#[cfg(target_os = "macos")]
+const ERR_SEC_INTERACTION_NOT_ALLOWED: i32 = -25308;
+/// `errSecUserCanceled`.
+#[cfg(target_os = "macos")]
+const ERR_SEC_USER_CANCELED: i32 = -128;
+
+/// Echec du crate keyring du a un trousseau verrouille.
+#[cfg(target_os = "macos")]
+pub(super) fn is_locked_error(e: &keyring::Error) -> bool {
+    match e {
+        keyring::Error::PlatformFailure(inner) | keyring::Error::NoStorageAccess(inner) => inner
+            .downcast_ref::<security_framework::base::Error>()
+            .is_some_and(|e| e.code() == ERR_SEC_INTERACTION_NOT_ALLOWED),
+        _ => false,
+    }
+}
+
+/// `SecKeychainUnlock` sur le trousseau par defaut, sans mot de passe : le
+/// systeme affiche sa propre demande.
+#[cfg(target_os = "macos")]
+pub(super) fn unlock_keychain() -> Result<(), super::KeychainError> {
+    use security_framework::os::macos::keychain::SecKeychain;
+
+    use super::{KeychainError, KeychainErrorCode};
+
+    let platform = |e: security_framework::base::Error| {
+        KeychainError::rejected(
+            KeychainErrorCode::PlatformError,
+            format!("trousseau macOS : {}", e),
+        )
+    };
+    let mut keychain = SecKeychain::default().map_err(platform)?;
+    keychain.unlock(None).map_err(|e| match e.code() {
+        ERR_SEC_USER_CANCELED => KeychainError::rejected(
+            KeychainErrorCode::BackendLocked,
+            "deverrouillage du trousseau annule",
+        ),
+        _ => platform(e),
+    })
+}
@@ -0,0 +1,600 @@
+//! Journal d'audit des operations keychain : une ligne JSON par operation,
+//! ajoutee a un fichier du dossier de donnees. Les valeurs des secrets n'y
+//! figurent jamais, ni dans ses exports CSV ou JSON lines.
+
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+
+use super::{
+    now_rfc3339, worker_interrupted, KeychainBatchResult, KeychainError, KeychainErrorCode,
+    KeychainManager, ARGOS_SERVICE,
+};
+use crate::logging::CommandSpan;
+
+/// Nom du journal dans le dossier de donnees de l'application. L'ancien
+/// journal, apres rotation, porte le suffixe `.1`.
+pub(crate) const AUDIT_FILE: &str = "audit.log";
+
+/// Taille a partir de laquelle le journal est archive (5 Mio).
+pub(crate) const DEFAULT_AUDIT_MAX_BYTES: u64 = 5 * 1024 * 1024;
+
+const DEFAULT_READ_LIMIT: usize = 100;
+const MAX_READ_LIMIT: usize = 1_000;
+
+/// Marque d'ordre des octets UTF-8 en tete des exports CSV : sans elle,
+/// Excel lit le fichier en Windows-1252 et abime les accents.
+const CSV_BOM: &[u8] = b"\xEF\xBB\xBF";
+
+const CSV_HEADER: &str = "timestamp,operation,service,key,detail,outcome,error_code,window\r\n";
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AuditExportFormat {
+    Csv,
+    Jsonl,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+/// Ligne du journal. `key` est absent pour les operations sur tout un
+/// service ; `detail` precise la cible d'un renommage, d'une migration ou
+/// d'une sauvegarde.
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct AuditRecord {
+    timestamp: String,
+    operation: String,
+    service: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    outcome: AuditOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error_code: Option<KeychainErrorCode>,
+    /// Libelle de la fenetre a l'origine de l'appel, ou de la tache de fond.
+    window: String,
+}
+
+/// Nom serde d'une valeur, par exemple `NotFound` pour un code d'erreur.
+fn serde_name<T: Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+/// Ajoute un champ CSV (RFC 4180) : entre guillemets, guillemets doubles,
+/// s'il contient une virgule, un guillemet ou un saut de ligne. Un champ
+/// qui commence par `=`, `+`, `-` ou `@` est precede d'une apostrophe, pour
+/// qu'un tableur ne l'evalue pas comme une formule.
+fn push_csv_field(line: &mut String, value: &str) {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+    if value.contains([',', '"', '\n', '\r']) {
+        line.push('"');
+        line.push_str(&value.replace('"', "\"\""));
+        line.push('"');
+    } else {
+        line.push_str(&value);
+    }
+}
+
+impl AuditRecord {
+    fn csv_line(&self) -> String {
+        let fields = [
+            self.timestamp.clone(),
+            self.operation.clone(),
+            self.service.clone(),
+            self.key.clone().unwrap_or_default(),
+            self.detail.clone().unwrap_or_default(),
+            serde_name(&self.outcome),
+            self.error_code.as_ref().map(serde_name).unwrap_or_default(),
+            self.window.clone(),
+        ];
+        let mut line = String::new();
+        for (i, field) in fields.iter().enumerate() {
+            if i > 0 {
+                line.push(',');
+            }
+            push_csv_field(&mut line, field);
+        }
+        line.push_str("\r\n");
+        line
+    }
+
+    /// Horodatage compris dans `[from, to]`, bornes incluses ; une ligne a
+    /// l'horodatage illisible est exclue.
+    fn within(&self, from: Option<&Timestamp>, to: Option<&Timestamp>) -> bool {
+        let Ok(at) = chrono::DateTime::parse_from_rfc3339(&self.timestamp) else {
+            return false;
+        };
+        from.is_none_or(|from| at >= *from) && to.is_none_or(|to| at <= *to)
+    }
+}
+
+type Timestamp = chrono::DateTime<chrono::FixedOffset>;
+
+fn parse_bound(name: &str, value: Option<&str>) -> Result<Option<Timestamp>, KeychainError> {
+    value
+        .map(|value| {
+            chrono::DateTime::parse_from_rfc3339(value).map_err(|_| {
+                KeychainError::rejected(
+                    KeychainErrorCode::InvalidArgument,
+                    format!("{} doit etre une date RFC 3339", name),
+                )
+            })
+        })
+        .transpose()
+}
+
+#[derive(Serialize)]
+pub(crate) struct AuditExport {
+    count: usize,
+    path: String,
+}
+
+/// Journal partage par toutes les commandes. Sans dossier de donnees, chaque
+/// ecriture compte comme un echec.
+pub(crate) struct AuditLog {
+    path: Option<PathBuf>,
+    max_bytes: u64,
+    failures: AtomicU64,
+    /// Serialise les ecritures et la rotation.
+    lock: Mutex<()>,
+}
+
+impl AuditLog {
+    pub(crate) fn new(path: Option<PathBuf>, max_bytes: u64) -> Self {
+        AuditLog {
+            path,
+            max_bytes,
+            failures: AtomicU64::new(0),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Ecritures perdues depuis le demarrage.
+    pub(crate) fn failures(&self) -> u64 {
+        self.failures.load(Ordering::Relaxed)
+    }
+
+    /// Ajoute une ligne au journal. Un echec est compte, jamais remonte :
+    /// il ne doit pas faire echouer l'operation journalisee.
+    pub(crate) fn append(&self, record: &AuditRecord) {
+        if self.try_append(record).is_err() {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn try_append(&self, record: &AuditRecord) -> std::io::Result<()> {
+        let path = self.path()?;
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let size = std::fs::metadata(path).map_or(0, |m| m.len());
+        if size > 0 && size + line.len() as u64 > self.max_bytes {
+            std::fs::rename(path, rotated(path))?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        file.write_all(&line)
+    }
+
+    fn path(&self) -> std::io::Result<&Path> {
+        self.path.as_deref().ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "dossier de donnees de l'application introuvable",
+            )
+        })
+    }
+
+    /// Lignes du journal, la plus recente en tete, archive comprise. Les
+    /// lignes illisibles sont ignorees.
+    pub(crate) fn read(
+        &self,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<Vec<AuditRecord>, KeychainError> {
+        let path = self.path().map_err(audit_error)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut lines = Vec::new();
+        for file in [rotated(path), path.to_path_buf()] {
+            match std::fs::read_to_string(&file) {
+                Ok(data) => lines.extend(data.lines().map(str::to_string)),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(audit_error(e)),
+            }
+        }
+        Ok(lines
+            .iter()
+            .rev()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(DEFAULT_READ_LIMIT).min(MAX_READ_LIMIT))
+            .collect())
+    }
+
+    /// Ecrit dans `out` les lignes de l'archive puis du journal comprises
+    /// entre `from` et `to`, dans l'ordre chronologique. Les fichiers sont
+    /// lus ligne a ligne ; seuls les champs connus d'une ligne sont
+    /// recopies. Le fichier n'apparait qu'une fois complet. Renvoie le
+    /// nombre de lignes exportees.
+    fn export(
+        &self,
+        out: &Path,
+        from: Option<&Timestamp>,
+        to: Option<&Timestamp>,
+        format: AuditExportFormat,
+    ) -> std::io::Result<usize> {
+        let path = self.path()?;
+        let mut partial = out.as_os_str().to_owned();
+        partial.push(".part");
+        let partial = PathBuf::from(partial);
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let written = (|| {
+            let mut writer = BufWriter::new(std::fs::File::create(&partial)?);
+            if format == AuditExportFormat::Csv {
+                writer.write_all(CSV_BOM)?;
+                writer.write_all(CSV_HEADER.as_bytes())?;
+            }
+            let mut count = 0;
+            for file in [rotated(path), path.to_path_buf()] {
+                let input = match std::fs::File::open(&file) {
+                    Ok(input) => input,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e),
+                };
+                for line in BufReader::new(input).split(b'\n') {
+                    let Ok(record) = serde_json::from_slice::<AuditRecord>(&line?) else {
+                        continue;
+                    };
+                    if !record.within(from, to) {
+                        continue;
+                    }
+                    match format {
+                        AuditExportFormat::Csv => writer.write_all(record.csv_line().as_bytes())?,
+                        AuditExportFormat::Jsonl => {
+                            serde_json::to_writer(&mut writer, &record)?;
+                            writer.write_all(b"\n")?;
+                        }
+                    }
+                    count += 1;
+                }
+            }
+            writer
+                .into_inner()
+                .map_err(|e| e.into_error())?
+                .sync_all()?;
+            Ok(count)
+        })();
+        match written {
+            Ok(count) => std::fs::rename(&partial, out).map(|()| count),
+            Err(e) => {
+                let _ = std::fs::remove_file(&partial);
+                Err(e)
+            }
+        }
+    }
+
+    /// Vide le journal et son archive.
+    pub(crate) fn clear(&self) -> Result<(), KeychainError> {
+        let path = self.path().map_err(audit_error)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        for file in [rotated(path), path.to_path_buf()] {
+            match std::fs::remove_file(&file) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(audit_error(e)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+fn rotated(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+fn audit_error(e: std::io::Error) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::IoError,
+        format!("journal d'audit inaccessible : {}", e),
+    )
+}
+
+/// Ligne preparee avant l'operation et ecrite avec son resultat.
+pub(crate) struct PendingAudit {
+    log: Arc<AuditLog>,
+    record: AuditRecord,
+    /// La meme operation, dans le journal de diagnostic.
+    span: CommandSpan,
+}
+
+impl PendingAudit {
+    pub(crate) fn new(
+        log: Arc<AuditLog>,
+        origin: &str,
+        operation: &str,
+        service: &str,
+        key: Option<&str>,
+    ) -> Self {
+        PendingAudit {
+            log,
+            span: CommandSpan::new(operation, service, key),
+            record: AuditRecord {
+                timestamp: String::new(),
+                operation: operation.to_string(),
+                service: service.to_string(),
+                key: key.map(str::to_string),
+                detail: None,
+                outcome: AuditOutcome::Success,
+                error_code: None,
+                window: origin.to_string(),
+            },
+        }
+    }
+
+    pub(crate) fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.record.detail = Some(detail.into());
+        self
+    }
+
+    /// Journalise le resultat puis le renvoie tel quel.
+    pub(crate) fn finish<T>(
+        mut self,
+        result: Result<T, KeychainError>,
+    ) -> Result<T, KeychainError> {
+        self.record.timestamp = now_rfc3339();
+        if let Err(e) = &result {
+            self.record.outcome = AuditOutcome::Failure;
+            self.record.error_code = Some(e.code());
+        }
+        self.log.append(&self.record);
+        self.span.finish(&result);
+        result
+    }
+
+    /// Comme `finish`, avec une ligne par cle d'une operation groupee.
+    pub(crate) fn finish_batch(
+        mut self,
+        result: Result<Vec<KeychainBatchResult>, KeychainError>,
+    ) -> Result<Vec<KeychainBatchResult>, KeychainError> {
+        let Ok(results) = &result else {
+            return self.finish(result);
+        };
+        self.record.timestamp = now_rfc3339();
+        for item in results {
+            let mut record = self.record.clone();
+            record.key = Some(item.key.clone());
+            if let Some(e) = &item.error {
+                record.outcome = AuditOutcome::Failure;
+                record.error_code = Some(e.code());
+            }
+            self.log.append(&record);
+        }
+        self.span.finish(&result);
+        result
+    }
+}
+
+/// Lignes du journal d'audit, la plus recente en tete (100 par defaut).
+#[tauri::command]
+pub(crate) async fn audit_read(
+    manager: State<'_, KeychainManager>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<AuditRecord>, KeychainError> {
+    let log = manager.audit.clone();
+    tauri::async_runtime::spawn_blocking(move || log.read(limit, offset))
+        .await
+        .map_err(worker_interrupted)?
+}
+
+/// Vide le journal d'audit. L'effacement lui-meme y est consigne.
+#[tauri::command]
+pub(crate) async fn audit_clear(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+) -> Result<(), KeychainError> {
+    let log = manager.audit.clone();
+    let pending = PendingAudit::new(
+        log.clone(),
+        window.label(),
+        "audit_clear",
+        ARGOS_SERVICE,
+        None,
+    );
+    let result = tauri::async_runtime::spawn_blocking(move || log.clear())
+        .await
+        .map_err(worker_interrupted)?;
+    pending.finish(result)
+}
+
+/// Exporte le journal d'audit en `csv` ou `jsonl` entre `from` et `to`
+/// (RFC 3339, bornes incluses, facultatives). Le CSV est en UTF-8 avec BOM ;
+/// une periode sans ligne donne un fichier avec le seul en-tete. Refuse d'ecraser un fichier existant
+/// sauf avec `overwrite`.
+#[tauri::command]
+pub(crate) async fn audit_export(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    path: String,
+    from: Option<String>,
+    to: Option<String>,
+    format: AuditExportFormat,
+    overwrite: Option<bool>,
+) -> Result<AuditExport, KeychainError> {
+    let log = manager.audit.clone();
+    let pending = PendingAudit::new(
+        log.clone(),
+        window.label(),
+        "audit_export",
+        ARGOS_SERVICE,
+        None,
+    )
+    .detail(path.as_str());
+    let bounds = parse_bound("from", from.as_deref())
+        .and_then(|from| Ok((from, parse_bound("to", to.as_deref())?)));
+    let (from, to) = match bounds {
+        Ok(bounds) => bounds,
+        Err(e) => return pending.finish(Err(e)),
+    };
+    if !overwrite.unwrap_or(false) && Path::new(&path).exists() {
+        return pending.finish(Err(KeychainError::rejected(
+            KeychainErrorCode::AlreadyExists,
+            format!("le fichier {} existe deja", path),
+        )));
+    }
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        log.export(Path::new(&path), from.as_ref(), to.as_ref(), format)
+            .map(|count| AuditExport { count, path })
+            .map_err(audit_error)
+    })
+    .await
+    .map_err(worker_interrupted)?;
+    pending.finish(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::scratch_dir;
+    use super::*;
+
+    fn record(timestamp: &str, key: Option<&str>, window: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: timestamp.to_string(),
+            operation: "get".to_string(),
+            service: "argos".to_string(),
+            key: key.map(str::to_string),
+            detail: None,
+            outcome: AuditOutcome::Success,
+            error_code: None,
+            window: window.to_string(),
+        }
+    }
+
+    fn export(
+        log: &AuditLog,
+        out: &Path,
+        from: Option<&str>,
+        to: Option<&str>,
+        format: AuditExportFormat,
+    ) -> (usize, String) {
+        let from = parse_bound("from", from).unwrap();
+        let to = parse_bound("to", to).unwrap();
+        let count = log.export(out, from.as_ref(), to.as_ref(), format).unwrap();
+        (count, std::fs::read_to_string(out).unwrap())
+    }
+
+    #[test]
+    fn csv_fields_are_escaped() {
+        let mut failed = record(
+            "2026-03-01T10:00:00Z",
+            Some("cle, \"prod\""),
+            "fenetre \"principale\",\nligne 2",
+        );
+        failed.detail = Some("=HYPERLINK(\"x\")".to_string());
+        failed.outcome = AuditOutcome::Failure;
+        failed.error_code = Some(KeychainErrorCode::NotFound);
+        assert_eq!(
+            failed.csv_line(),
+            "2026-03-01T10:00:00Z,get,argos,\"cle, \"\"prod\"\"\",\
+             \"'=HYPERLINK(\"\"x\"\")\",failure,NotFound,\
+             \"fenetre \"\"principale\"\",\nligne 2\"\r\n"
+        );
+        let plain = record("2026-03-01T10:00:00Z", None, "main");
+        assert_eq!(
+            plain.csv_line(),
+            "2026-03-01T10:00:00Z,get,argos,,,success,,main\r\n"
+        );
+        let mut line = String::new();
+        push_csv_field(&mut line, "-1\r");
+        assert_eq!(line, "\"'-1\r\"");
+    }
+
+    #[test]
+    fn csv_export_filters_by_range() {
+        let dir = scratch_dir("audit-csv");
+        let log = AuditLog::new(Some(dir.join(AUDIT_FILE)), DEFAULT_AUDIT_MAX_BYTES);
+        log.append(&record("2026-03-01T10:00:00Z", Some("a"), "main"));
+        log.append(&record("2026-03-02T10:00:00Z", Some("b"), "a,\"b\"\nc"));
+        log.append(&record("2026-03-03T10:00:00+01:00", Some("c"), "main"));
+        // Ligne illisible : ignoree.
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join(AUDIT_FILE))
+            .and_then(|mut file| file.write_all(b"{pas du json\n"))
+            .unwrap();
+
+        let out = dir.join("export.csv");
+        let (count, data) = export(
+            &log,
+            &out,
+            Some("2026-03-02T10:00:00Z"),
+            Some("2026-03-03T09:00:00Z"),
+            AuditExportFormat::Csv,
+        );
+        assert_eq!(count, 2);
+        assert!(data.starts_with('\u{feff}'));
+        assert_eq!(
+            &data[CSV_BOM.len()..],
+            format!(
+                "{}2026-03-02T10:00:00Z,get,argos,b,,success,,\"a,\"\"b\"\"\nc\"\r\n\
+                 2026-03-03T10:00:00+01:00,get,argos,c,,success,,main\r\n",
+                CSV_HEADER
+            )
+        );
+        assert!(!dir.join("export.csv.part").exists());
+
+        let (count, data) = export(
+            &log,
+            &out,
+            Some("2027-01-01T00:00:00Z"),
+            None,
+            AuditExportFormat::Csv,
+        );
+        assert_eq!(count, 0);
+        assert_eq!(data.as_bytes(), [CSV_BOM, CSV_HEADER.as_bytes()].concat());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn jsonl_export_reads_the_rotated_log_first() {
+        let dir = scratch_dir("audit-jsonl");
+        // Assez petit pour archiver le journal a la deuxieme ligne.
+        let log = AuditLog::new(Some(dir.join(AUDIT_FILE)), 200);
+        log.append(&record("2026-03-01T10:00:00Z", Some("a"), "main"));
+        log.append(&record("2026-03-02T10:00:00Z", Some("b"), "main"));
+        assert!(rotated(&dir.join(AUDIT_FILE)).exists());
+
+        let out = dir.join("export.jsonl");
+        let (count, data) = export(&log, &out, None, None, AuditExportFormat::Jsonl);
+        assert_eq!(count, 2);
+        let keys: Vec<Option<String>> = data
+            .lines()
+            .map(|line| serde_json::from_str::<AuditRecord>(line).unwrap().key)
+            .collect();
+        assert_eq!(keys, [Some("a".to_string()), Some("b".to_string())]);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,240 @@
+//! Verification de l'utilisateur par le systeme (Windows Hello, Touch ID,
+//! polkit) avant de renvoyer un secret marque `require_auth`.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{worker_interrupted, KeychainError, KeychainErrorCode};
+
+/// Delai pendant lequel une verification reussie vaut pour les lectures
+/// suivantes, reglable par `keychain_configure` : l'allonger demande une
+/// verification.
+pub(crate) const DEFAULT_AUTH_GRACE_SECS: u64 = 60;
+pub(crate) const MAX_AUTH_GRACE_SECS: u64 = 3_600;
+
+pub(crate) static AUTH_GRACE_SECS: AtomicU64 = AtomicU64::new(DEFAULT_AUTH_GRACE_SECS);
+
+/// Valeur lue qui n'est remise au webview qu'apres `UserAuth::release`.
+/// `reason`, affiche par le systeme, est renseigne quand une verification
+/// est exigee.
+pub(crate) struct Gated<T> {
+    value: T,
+    reason: Option<String>,
+}
+
+impl<T> Gated<T> {
+    pub(crate) fn new(value: T, reason: Option<String>) -> Self {
+        Gated { value, reason }
+    }
+
+    /// Valeur sans verification, pour un traitement qui ne la remet jamais
+    /// au webview.
+    pub(crate) fn into_unverified(self) -> T {
+        self.value
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct UserAuth {
+    /// Derniere verification reussie.
+    verified_at: Mutex<Option<Instant>>,
+    /// Une seule demande a la fois : les lectures simultanees attendent la
+    /// premiere puis profitent du delai de grace.
+    prompt: tokio::sync::Mutex<()>,
+}
+
+impl UserAuth {
+    fn within_grace(&self) -> bool {
+        let grace = Duration::from_secs(AUTH_GRACE_SECS.load(Ordering::Relaxed));
+        let verified_at = self.verified_at.lock().unwrap_or_else(|e| e.into_inner());
+        matches!(*verified_at, Some(at) if at.elapsed() < grace)
+    }
+
+    /// Exige une nouvelle verification a la prochaine lecture protegee.
+    pub(crate) fn forget(&self) {
+        *self.verified_at.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Renvoie la valeur lue, apres verification de l'utilisateur si elle
+    /// est exigee et qu'aucune n'a reussi dans le delai de grace. La
+    /// verification n'est pas bornee par `TIMEOUT_MS` : l'utilisateur peut
+    /// prendre son temps.
+    pub(crate) async fn release<T>(
+        &self,
+        read: Result<Gated<T>, KeychainError>,
+    ) -> Result<T, KeychainError> {
+        let Gated { value, reason } = read?;
+        let Some(reason) = reason else {
+            return Ok(value);
+        };
+        let _prompt = self.prompt.lock().await;
+        if self.within_grace() {
+            return Ok(value);
+        }
+        self.prompt_user(reason).await?;
+        Ok(value)
+    }
+
+    /// Verifie l'utilisateur meme dans le delai de grace, avant un reglage
+    /// qui affaiblit la protection des lectures.
+    pub(crate) async fn verify(&self, reason: String) -> Result<(), KeychainError> {
+        let _prompt = self.prompt.lock().await;
+        self.prompt_user(reason).await
+    }
+
+    async fn prompt_user(&self, reason: String) -> Result<(), KeychainError> {
+        tauri::async_runtime::spawn_blocking(move || platform::verify(&reason))
+            .await
+            .map_err(worker_interrupted)??;
+        *self.verified_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        Ok(())
+    }
+}
+
+fn cancelled() -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::AuthCancelled,
+        "verification annulee par l'utilisateur",
+    )
+}
+
+fn unavailable(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::AuthUnavailable,
+        format!(
+            "aucune verification de l'utilisateur disponible : {}",
+            detail
+        ),
+    )
+}
+
+fn failed(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::AuthFailed,
+        format!("verification de l'utilisateur refusee : {}", detail),
+    )
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::HSTRING;
+    use windows::Security::Credentials::UI::{
+        UserConsentVerificationResult as Verification, UserConsentVerifier,
+        UserConsentVerifierAvailability as Availability,
+    };
+
+    use super::{cancelled, failed, unavailable};
+    use crate::keychain::KeychainError;
+
+    /// Windows Hello (visage, empreinte ou code PIN).
+    pub(super) fn verify(reason: &str) -> Result<(), KeychainError> {
+        let availability = UserConsentVerifier::CheckAvailabilityAsync()
+            .and_then(|op| op.get())
+            .map_err(|e| unavailable(e.message()))?;
+        if availability != Availability::Available {
+            return Err(unavailable(format!("Windows Hello : {:?}", availability)));
+        }
+        let result = UserConsentVerifier::RequestVerificationAsync(&HSTRING::from(reason))
+            .and_then(|op| op.get())
+            .map_err(|e| failed(e.message()))?;
+        match result {
+            Verification::Verified => Ok(()),
+            Verification::Canceled => Err(cancelled()),
+            Verification::DeviceNotPresent
+            | Verification::NotConfiguredForUser
+            | Verification::DisabledByPolicy => {
+                Err(unavailable(format!("Windows Hello : {:?}", result)))
+            }
+            _ => Err(failed(format!("Windows Hello : {:?}", result))),
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::sync::mpsc;
+
+    use block2::RcBlock;
+    use objc2::runtime::Bool;
+    use objc2_foundation::{NSError, NSString};
+    use objc2_local_authentication::{LAContext, LAPolicy};
+
+    use super::{cancelled, failed, unavailable};
+    use crate::keychain::KeychainError;
+
+    // Codes de `LAError`.
+    const USER_CANCEL: isize = -2;
+    const SYSTEM_CANCEL: isize = -4;
+    const PASSCODE_NOT_SET: isize = -5;
+    const BIOMETRY_NOT_AVAILABLE: isize = -6;
+    const BIOMETRY_NOT_ENROLLED: isize = -7;
+    const APP_CANCEL: isize = -9;
+    const NOT_INTERACTIVE: isize = -1004;
+
+    /// Touch ID, ou a defaut le mot de passe de la session.
+    pub(super) fn verify(reason: &str) -> Result<(), KeychainError> {
+        let policy = LAPolicy::DeviceOwnerAuthentication;
+        let context = unsafe { LAContext::new() };
+        if let Err(e) = unsafe { context.canEvaluatePolicy_error(policy) } {
+            return Err(unavailable(e.localizedDescription()));
+        }
+        let (tx, rx) = mpsc::channel();
+        let reply = RcBlock::new(move |success: Bool, error: *mut NSError| {
+            let code = unsafe { error.as_ref() }.map(|e| e.code());
+            let _ = tx.send((success.as_bool(), code));
+        });
+        unsafe {
+            context.evaluatePolicy_localizedReason_reply(
+                policy,
+                &NSString::from_str(reason),
+                &reply,
+            )
+        };
+        match rx.recv() {
+            Ok((true, _)) => Ok(()),
+            Ok((false, Some(USER_CANCEL | SYSTEM_CANCEL | APP_CANCEL))) => Err(cancelled()),
+            Ok((
+                false,
+                Some(
+                    PASSCODE_NOT_SET
+                    | BIOMETRY_NOT_AVAILABLE
+                    | BIOMETRY_NOT_ENROLLED
+                    | NOT_INTERACTIVE,
+                ),
+            )) => Err(unavailable("Touch ID et mot de passe indisponibles")),
+            Ok((false, code)) => Err(failed(format!("LAError {:?}", code))),
+            Err(e) => Err(failed(e)),
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::process::Command;
+
+    use super::{cancelled, failed, unavailable};
+    use crate::keychain::KeychainError;
+
+    /// Action declaree par `polkit/fr.bbrain.argos.policy`, installe avec le
+    /// paquet.
+    const POLKIT_ACTION: &str = "fr.bbrain.argos.reveal-secret";
+
+    /// Demande le mot de passe de la session via l'agent polkit. Le message
+    /// affiche est celui de l'action polkit, pas `reason`.
+    pub(super) fn verify(_reason: &str) -> Result<(), KeychainError> {
+        let status = Command::new("pkcheck")
+            .args(["--action-id", POLKIT_ACTION, "--process"])
+            .arg(std::process::id().to_string())
+            .arg("--allow-user-interaction")
+            .status()
+            .map_err(|e| unavailable(format!("pkcheck : {}", e)))?;
+        match status.code() {
+            Some(0) => Ok(()),
+            Some(1) => Err(failed("authentification polkit refusee")),
+            Some(3) => Err(cancelled()),
+            // 4 : action non installee ou pas d'agent polkit dans la session.
+            _ => Err(unavailable(format!("pkcheck a echoue ({})", status))),
+        }
+    }
+}
@@ -0,0 +1,415 @@
+//! Sauvegardes automatiques : a chaque echeance, les services de
+//! `backup.services` sont exportes comme par `keychain_export`, dans un
+//! fichier par service. La phrase de passe est une cle aleatoire gardee
+//! dans le keychain OS sous `BACKUP_KEY` : aucune saisie n'est demandee, et
+//! les fichiers ne s'ouvrent qu'avec cette cle.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, Window};
+
+use super::audit::PendingAudit;
+use super::backup::{self, io_error};
+use super::{
+    found, keyring_call, now_rfc3339, run_blocking, KeychainError, KeychainErrorCode,
+    KeychainManager, RetryPolicy, Secret, ARGOS_SERVICE,
+};
+use crate::config::{write_atomic, BackupConfig, ConfigStore};
+use crate::notifications::{self, Notice, NoticeKind, Target};
+
+/// Cle du service ARGOS qui garde la phrase de passe des sauvegardes
+/// automatiques.
+pub(crate) const BACKUP_KEY: &str = "__argos_backup_key__";
+
+/// Bilan de la derniere sauvegarde, dans le dossier de donnees.
+pub(crate) const STATUS_FILE: &str = "backup-status.json";
+/// Dossier des sauvegardes si `backup.directory` n'est pas renseigne.
+pub(crate) const DEFAULT_DIR: &str = "backups";
+const EXTENSION: &str = ".argosbak";
+/// Horodatage des noms de fichier, triable comme du texte.
+const STAMP_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const STAMP_LEN: usize = 16;
+
+/// Premiere verification apres le demarrage, puis intervalle entre deux.
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Delai avant une nouvelle tentative apres un echec.
+const RETRY_AFTER_MINUTES: i64 = 60;
+
+/// Origine des lignes d'audit d'une sauvegarde programmee.
+const SCHEDULER_ORIGIN: &str = "backup-scheduler";
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum BackupInterval {
+    Daily,
+    Weekly,
+}
+
+impl BackupInterval {
+    fn duration(self) -> chrono::Duration {
+        match self {
+            BackupInterval::Daily => chrono::Duration::days(1),
+            BackupInterval::Weekly => chrono::Duration::weeks(1),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct BackupFile {
+    service: String,
+    path: String,
+    count: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct BackupFailure {
+    service: String,
+    code: KeychainErrorCode,
+    error: String,
+}
+
+/// Bilan de `backup_last_status`.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub(crate) struct BackupStatus {
+    /// Debut de la derniere sauvegarde, reussie ou non.
+    last_run: Option<String>,
+    last_success: Option<String>,
+    files: Vec<BackupFile>,
+    failed: Vec<BackupFailure>,
+    /// Anciennes sauvegardes supprimees au-dela de `backup.retention`.
+    removed: Vec<String>,
+    /// Prochaine echeance ; `None` si les sauvegardes sont desactivees.
+    #[serde(skip_deserializing)]
+    next_run: Option<String>,
+}
+
+fn parse_time(at: &Option<String>) -> Option<chrono::DateTime<chrono::Utc>> {
+    let at = chrono::DateTime::parse_from_rfc3339(at.as_deref()?).ok()?;
+    Some(at.with_timezone(&chrono::Utc))
+}
+
+impl BackupStatus {
+    /// Echeance suivante : un intervalle apres la derniere reussite, et pas
+    /// avant `RETRY_AFTER_MINUTES` apres un echec.
+    fn next_run(&self, config: &BackupConfig) -> Option<chrono::DateTime<chrono::Utc>> {
+        if !config.enabled || config.services.is_empty() {
+            return None;
+        }
+        let now = chrono::Utc::now();
+        let mut next = parse_time(&self.last_success)
+            .map(|at| at + config.interval.duration())
+            .unwrap_or(now);
+        if !self.failed.is_empty() {
+            if let Some(at) = parse_time(&self.last_run) {
+                next = next.max(at + chrono::Duration::minutes(RETRY_AFTER_MINUTES));
+            }
+        }
+        Some(next)
+    }
+
+    fn with_next_run(mut self, config: &BackupConfig) -> Self {
+        self.next_run = self
+            .next_run(config)
+            .map(|at| at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true));
+        self
+    }
+}
+
+/// Etat des sauvegardes automatiques, enregistre dans l'etat Tauri.
+pub(crate) struct BackupScheduler {
+    data_dir: Option<PathBuf>,
+    status: Mutex<BackupStatus>,
+}
+
+impl BackupScheduler {
+    /// Reprend le bilan enregistre ; absent ou illisible, aucune sauvegarde
+    /// n'a encore eu lieu.
+    pub(crate) fn load(app: &tauri::App) -> Self {
+        let data_dir = app.path_resolver().app_data_dir();
+        let status = data_dir
+            .as_ref()
+            .and_then(|dir| std::fs::read(dir.join(STATUS_FILE)).ok())
+            .and_then(|data| serde_json::from_slice(&data).ok())
+            .unwrap_or_default();
+        BackupScheduler {
+            data_dir,
+            status: Mutex::new(status),
+        }
+    }
+
+    fn status(&self) -> BackupStatus {
+        self.status
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    fn record(&self, status: BackupStatus) {
+        if let Some(dir) = &self.data_dir {
+            let written = serde_json::to_vec_pretty(&status)
+                .map_err(std::io::Error::other)
+                .and_then(|data| write_atomic(&dir.join(STATUS_FILE), &data));
+            if let Err(e) = written {
+                tracing::warn!(error = %e, "bilan de sauvegarde non enregistre");
+            }
+        }
+        *self.status.lock().unwrap_or_else(|e| e.into_inner()) = status;
+    }
+
+    fn directory(&self, config: &BackupConfig) -> Result<PathBuf, KeychainError> {
+        match (&config.directory, &self.data_dir) {
+            (Some(dir), _) => Ok(dir.clone()),
+            (None, Some(data_dir)) => Ok(data_dir.join(DEFAULT_DIR)),
+            (None, None) => Err(KeychainError::rejected(
+                KeychainErrorCode::IoError,
+                "dossier de donnees introuvable",
+            )),
+        }
+    }
+}
+
+/// Phrase de passe des sauvegardes, creee a la premiere sauvegarde.
+fn backup_key(manager: &KeychainManager) -> Result<Secret, KeychainError> {
+    let store = &manager.store;
+    let retry = RetryPolicy::default();
+    if let Some(key) = found(keyring_call(
+        store,
+        retry,
+        ARGOS_SERVICE,
+        BACKUP_KEY,
+        |entry| entry.get_password(),
+    ))? {
+        return Ok(Secret::from(key));
+    }
+    let mut bytes = zeroize::Zeroizing::new([0u8; 32]);
+    rand::thread_rng().fill_bytes(&mut bytes[..]);
+    let key = Secret::new(base64::engine::general_purpose::STANDARD.encode(&bytes[..]));
+    keyring_call(store, retry, ARGOS_SERVICE, BACKUP_KEY, |entry| {
+        entry.set_password(&key)
+    })?;
+    Ok(key)
+}
+
+/// Nom de fichier sans separateur de chemin, quel que soit le service.
+fn file_prefix(service: &str) -> String {
+    let safe: String = service
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}-", safe)
+}
+
+/// Fichier ecrit par `run`, quel qu'en soit le service : `<service>-<date>`
+/// suivi de l'extension des sauvegardes.
+pub(super) fn is_backup_file(name: &str) -> bool {
+    name.strip_suffix(EXTENSION)
+        .and_then(|rest| rest.rsplit_once('-'))
+        .is_some_and(|(_, stamp)| {
+            stamp.len() == STAMP_LEN
+                && chrono::NaiveDateTime::parse_from_str(stamp, STAMP_FORMAT).is_ok()
+        })
+}
+
+/// Supprime les sauvegardes de `service` au-dela des `retention` plus
+/// recentes, et renvoie les fichiers supprimes.
+fn rotate(dir: &Path, service: &str, retention: usize) -> Vec<String> {
+    let prefix = file_prefix(service);
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter(|name| {
+            name.strip_prefix(prefix.as_str())
+                .and_then(|rest| rest.strip_suffix(EXTENSION))
+                .is_some_and(|stamp| stamp.len() == STAMP_LEN)
+        })
+        .collect();
+    names.sort_unstable_by(|a, b| b.cmp(a));
+    let mut removed = Vec::new();
+    for name in names.into_iter().skip(retention) {
+        let path = dir.join(&name);
+        match std::fs::remove_file(&path) {
+            Ok(()) => removed.push(path.display().to_string()),
+            Err(e) => tracing::warn!(file = %name, error = %e, "ancienne sauvegarde conservee"),
+        }
+    }
+    removed
+}
+
+/// Sauvegarde chaque service de `config`, sous le verrou des exports. Un
+/// service en echec n'arrete pas les suivants. La session verrouillee
+/// n'empeche rien, les valeurs ne quittant pas Rust ; un coffre portable
+/// verrouille fait en revanche echouer chaque service.
+async fn run(
+    manager: &KeychainManager,
+    scheduler: &BackupScheduler,
+    config: &BackupConfig,
+    origin: &str,
+) -> BackupStatus {
+    let _exclusive = manager.bulk.exclusive().await;
+    let mut status = BackupStatus {
+        last_run: Some(now_rfc3339()),
+        last_success: scheduler.status().last_success,
+        ..BackupStatus::default()
+    };
+    let prepared = scheduler.directory(config).and_then(|dir| {
+        std::fs::create_dir_all(&dir).map_err(|e| io_error(&dir.display().to_string(), e))?;
+        Ok(dir)
+    });
+    let prepared = match prepared {
+        Ok(dir) => {
+            let reader = manager.clone();
+            run_blocking(move || backup_key(&reader))
+                .await
+                .map(|key| (dir, key))
+        }
+        Err(e) => Err(e),
+    };
+    let (dir, key) = match prepared {
+        Ok(prepared) => prepared,
+        Err(e) => {
+            status.failed = config
+                .services
+                .iter()
+                .map(|service| BackupFailure {
+                    service: service.clone(),
+                    code: e.code(),
+                    error: e.to_string(),
+                })
+                .collect();
+            return status;
+        }
+    };
+    let stamp = chrono::Utc::now().format(STAMP_FORMAT).to_string();
+    for service in &config.services {
+        let path = dir.join(format!("{}{}{}", file_prefix(service), stamp, EXTENSION));
+        let path = path.display().to_string();
+        let audit = PendingAudit::new(manager.audit.clone(), origin, "backup", service, None)
+            .detail(path.as_str());
+        let exported = audit
+            .finish(backup::export_unattended(manager, service, key.clone(), path.clone()).await);
+        match exported {
+            Ok(report) => {
+                status.files.push(BackupFile {
+                    service: service.clone(),
+                    path,
+                    count: report.count,
+                });
+                status
+                    .removed
+                    .extend(rotate(&dir, service, config.retention as usize));
+            }
+            Err(e) => status.failed.push(BackupFailure {
+                service: service.clone(),
+                code: e.code(),
+                error: e.to_string(),
+            }),
+        }
+    }
+    if status.failed.is_empty() {
+        status.last_success = status.last_run.clone();
+    }
+    status
+}
+
+/// Lance une sauvegarde, enregistre son bilan et previent l'utilisateur
+/// d'un echec.
+async fn run_and_record(app: &AppHandle, origin: &str) -> Option<BackupStatus> {
+    let manager = app.try_state::<KeychainManager>()?;
+    let scheduler = app.try_state::<BackupScheduler>()?;
+    let config = app.try_state::<ConfigStore>()?.get().backup.clone();
+    let status = run(&manager, &scheduler, &config, origin).await;
+    if !status.failed.is_empty() {
+        let services: Vec<&str> = status.failed.iter().map(|f| f.service.as_str()).collect();
+        notifications::send(
+            app,
+            Notice {
+                title: "Sauvegarde automatique echouee".to_string(),
+                body: format!("Services non sauvegardes : {}", services.join(", ")),
+                kind: NoticeKind::Error,
+                target: Some(Target {
+                    page: "backup",
+                    service: None,
+                    key: None,
+                }),
+            },
+        );
+    }
+    scheduler.record(status.clone());
+    Some(status.with_next_run(&config))
+}
+
+/// Verifie l'echeance toutes les `CHECK_INTERVAL`, pendant toute la vie de
+/// l'application : une echeance passee pendant l'arret ou la veille est
+/// rattrapee a la verification suivante.
+pub(crate) fn spawn_scheduler(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        loop {
+            let due = match (
+                app.try_state::<BackupScheduler>(),
+                app.try_state::<ConfigStore>(),
+            ) {
+                (Some(scheduler), Some(store)) => scheduler
+                    .status()
+                    .next_run(&store.get().backup)
+                    .is_some_and(|next| next <= chrono::Utc::now()),
+                _ => false,
+            };
+            if due {
+                run_and_record(&app, SCHEDULER_ORIGIN).await;
+            }
+            tokio::time::sleep(CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// Sauvegarde tout de suite les services de `backup.services`, meme si les
+/// sauvegardes automatiques sont desactivees, et renvoie le bilan. Attend
+/// la fin d'un export en cours.
+#[tauri::command]
+pub(crate) async fn backup_run_now(
+    app: AppHandle,
+    window: Window,
+) -> Result<BackupStatus, KeychainError> {
+    let configured = app
+        .try_state::<ConfigStore>()
+        .is_some_and(|store| !store.get().backup.services.is_empty());
+    if !configured {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            "aucun service dans backup.services",
+        ));
+    }
+    run_and_record(&app, window.label()).await.ok_or_else(|| {
+        KeychainError::rejected(
+            KeychainErrorCode::BackendUnavailable,
+            "ARGOS n'a pas fini de demarrer",
+        )
+    })
+}
+
+/// Bilan de la derniere sauvegarde, manuelle ou programmee, et prochaine
+/// echeance.
+#[tauri::command]
+pub(crate) fn backup_last_status(
+    scheduler: State<'_, BackupScheduler>,
+    store: State<'_, ConfigStore>,
+) -> BackupStatus {
+    scheduler.status().with_next_run(&store.get().backup)
+}
@@ -0,0 +1,907 @@
+//! Sauvegarde chiffree des secrets d'un service, pour changer de poste.
+
+use std::io::Write;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::auth::Gated;
+use super::bulk::{self, BulkPhase, BulkStarted, Progress};
+use super::store::Store;
+use super::{
+    check_user_key, decode_base64, found, keyring_call, now_rfc3339, raw_is_encryption_key,
+    raw_requires_auth, read_index, read_raw, update_index, worker_interrupted, write_raw,
+    KeyIndexLock, KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy, Secret,
+    SecretOperation,
+};
+
+const MAGIC: &[u8; 8] = b"ARGOSBAK";
+const FORMAT_VERSION: u8 = 1;
+
+pub(crate) const SALT_LEN: usize = 16;
+pub(crate) const NONCE_LEN: usize = 12;
+pub(crate) const KEY_LEN: usize = 32;
+
+/// Parametres Argon2id des nouvelles sauvegardes (64 Mio, 3 passes).
+pub(crate) const KDF_M_COST: u32 = 64 * 1024;
+pub(crate) const KDF_T_COST: u32 = 3;
+pub(crate) const KDF_P_COST: u32 = 1;
+
+/// Bornes acceptees a la lecture, pour qu'un fichier forge ne puisse pas
+/// demander une derivation demesuree.
+pub(crate) const MAX_KDF_M_COST: u32 = 1024 * 1024;
+pub(crate) const MAX_KDF_T_COST: u32 = 16;
+pub(crate) const MAX_KDF_P_COST: u32 = 16;
+
+const HEADER_LEN: usize = 8 + 1 + 12 + SALT_LEN + NONCE_LEN;
+
+const MIN_PASSPHRASE_CHARS: usize = 12;
+
+/// En-tete en clair du fichier, authentifie comme donnee associee du GCM.
+struct Header {
+    version: u8,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: [u8; SALT_LEN],
+    nonce: [u8; NONCE_LEN],
+}
+
+impl Header {
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN);
+        out.extend_from_slice(MAGIC);
+        out.push(self.version);
+        out.extend_from_slice(&self.m_cost.to_le_bytes());
+        out.extend_from_slice(&self.t_cost.to_le_bytes());
+        out.extend_from_slice(&self.p_cost.to_le_bytes());
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&self.nonce);
+        out
+    }
+
+    /// Verifie le format et la version avant toute tentative de dechiffrement.
+    fn parse(data: &[u8]) -> Result<Header, KeychainError> {
+        if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+            return Err(invalid_backup("ce fichier n'est pas une sauvegarde ARGOS"));
+        }
+        let version = data[MAGIC.len()];
+        if version > FORMAT_VERSION {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::UnsupportedVersion,
+                format!(
+                    "sauvegarde au format {} creee par une version plus recente d'ARGOS, \
+                     mettez ARGOS a jour pour l'importer",
+                    version
+                ),
+            ));
+        }
+        if version == 0 {
+            return Err(invalid_backup("version de sauvegarde invalide"));
+        }
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+        let header = Header {
+            version,
+            m_cost: u32_at(9),
+            t_cost: u32_at(13),
+            p_cost: u32_at(17),
+            salt: data[21..21 + SALT_LEN].try_into().unwrap_or_default(),
+            nonce: data[21 + SALT_LEN..HEADER_LEN]
+                .try_into()
+                .unwrap_or_default(),
+        };
+        if header.m_cost > MAX_KDF_M_COST
+            || header.t_cost > MAX_KDF_T_COST
+            || header.p_cost > MAX_KDF_P_COST
+        {
+            return Err(invalid_backup("parametres de derivation hors limites"));
+        }
+        Ok(header)
+    }
+}
+
+/// Contenu chiffre : les secrets texte sont repris tels qu'ils sont stockes
+/// (enveloppe comprise), les secrets binaires en base64.
+#[derive(Serialize, Deserialize)]
+struct Backup {
+    service: String,
+    exported_at: String,
+    entries: Vec<BackupEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct BackupEntry {
+    key: String,
+    #[serde(flatten)]
+    value: BackupValue,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+enum BackupValue {
+    Text(String),
+    Bytes(String),
+}
+
+impl Drop for BackupValue {
+    fn drop(&mut self) {
+        match self {
+            BackupValue::Text(data) | BackupValue::Bytes(data) => data.zeroize(),
+        }
+    }
+}
+
+pub(crate) fn io_error(path: &str, e: std::io::Error) -> KeychainError {
+    KeychainError::rejected(KeychainErrorCode::IoError, format!("{} : {}", path, e))
+}
+
+fn invalid_backup(detail: &str) -> KeychainError {
+    KeychainError::rejected(KeychainErrorCode::InvalidBackup, detail)
+}
+
+pub(crate) fn crypto_error(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::PlatformError,
+        format!("erreur de chiffrement : {}", detail),
+    )
+}
+
+/// Controle minimal de la phrase de passe : au moins 12 caracteres, et
+/// plusieurs familles de caracteres sauf pour une phrase longue.
+pub(crate) fn check_passphrase(passphrase: &str) -> Result<(), KeychainError> {
+    let chars = passphrase.chars().count();
+    let classes = [
+        passphrase.chars().any(|c| c.is_lowercase()),
+        passphrase.chars().any(|c| c.is_uppercase()),
+        passphrase.chars().any(|c| c.is_ascii_digit()),
+        passphrase.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|present| **present)
+    .count();
+    let distinct = {
+        let mut seen: Vec<char> = passphrase.chars().collect();
+        seen.sort_unstable();
+        seen.dedup();
+        seen.len()
+    };
+    if chars < MIN_PASSPHRASE_CHARS || distinct < 6 || (chars < 20 && classes < 3) {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::WeakPassphrase,
+            format!(
+                "phrase de passe trop faible : au moins {} caracteres et 3 types \
+                 (minuscules, majuscules, chiffres, symboles), ou 20 caracteres",
+                MIN_PASSPHRASE_CHARS
+            ),
+        ));
+    }
+    Ok(())
+}
+
+fn derive_key(
+    passphrase: &str,
+    header: &Header,
+) -> Result<Zeroizing<[u8; KEY_LEN]>, KeychainError> {
+    argon2id(
+        passphrase,
+        [header.m_cost, header.t_cost, header.p_cost],
+        &header.salt,
+    )
+}
+
+/// Derivation Argon2id d'une cle AES-256, `cost` valant `[m, t, p]`. Aussi
+/// utilisee par le coffre portable.
+pub(crate) fn argon2id(
+    passphrase: &str,
+    cost: [u32; 3],
+    salt: &[u8],
+) -> Result<Zeroizing<[u8; KEY_LEN]>, KeychainError> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    argon2id_into(passphrase, cost, salt, &mut key[..])?;
+    Ok(key)
+}
+
+/// Comme `argon2id`, avec une sortie de la longueur de `out`.
+pub(crate) fn argon2id_into(
+    passphrase: &str,
+    cost: [u32; 3],
+    salt: &[u8],
+    out: &mut [u8],
+) -> Result<(), KeychainError> {
+    let [m_cost, t_cost, p_cost] = cost;
+    let params = Params::new(m_cost, t_cost, p_cost, Some(out.len())).map_err(crypto_error)?;
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, out)
+        .map_err(crypto_error)
+}
+
+/// Chiffre la sauvegarde et renvoie le fichier complet, en-tete compris.
+fn seal(backup: &Backup, passphrase: &str) -> Result<Vec<u8>, KeychainError> {
+    let mut header = Header {
+        version: FORMAT_VERSION,
+        m_cost: KDF_M_COST,
+        t_cost: KDF_T_COST,
+        p_cost: KDF_P_COST,
+        salt: [0u8; SALT_LEN],
+        nonce: [0u8; NONCE_LEN],
+    };
+    rand::thread_rng().fill_bytes(&mut header.salt);
+    rand::thread_rng().fill_bytes(&mut header.nonce);
+    let key = derive_key(passphrase, &header)?;
+    let cipher = Aes256Gcm::new_from_slice(&key[..]).map_err(crypto_error)?;
+
+    let plaintext = plaintext(backup)?;
+    let mut file = header.to_bytes();
+    let ciphertext = cipher
+        .encrypt(
+            Nonce::from_slice(&header.nonce),
+            Payload {
+                msg: &plaintext,
+                aad: &file,
+            },
+        )
+        .map_err(crypto_error)?;
+    file.extend_from_slice(&ciphertext);
+    Ok(file)
+}
+
+/// Sauvegarde serialisee, effacee une fois chiffree.
+fn plaintext(backup: &Backup) -> Result<Zeroizing<Vec<u8>>, KeychainError> {
+    serde_json::to_vec(backup)
+        .map(Zeroizing::new)
+        .map_err(crypto_error)
+}
+
+/// Dechiffre un fichier de sauvegarde. Un tag GCM invalide signifie une
+/// phrase de passe fausse ou un fichier altere : les deux sont indiscernables.
+fn open(data: &[u8], passphrase: &str) -> Result<Backup, KeychainError> {
+    let plaintext = decrypt(data, passphrase)?;
+    serde_json::from_slice(&plaintext).map_err(|e| invalid_backup(&e.to_string()))
+}
+
+fn decrypt(data: &[u8], passphrase: &str) -> Result<Zeroizing<Vec<u8>>, KeychainError> {
+    let header = Header::parse(data)?;
+    let key = derive_key(passphrase, &header)?;
+    let cipher = Aes256Gcm::new_from_slice(&key[..]).map_err(crypto_error)?;
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&header.nonce),
+            Payload {
+                msg: &data[HEADER_LEN..],
+                aad: &data[..HEADER_LEN],
+            },
+        )
+        .map_err(|_| {
+            KeychainError::rejected(
+                KeychainErrorCode::BadMac,
+                "phrase de passe incorrecte ou sauvegarde alteree",
+            )
+        })?;
+    Ok(Zeroizing::new(plaintext))
+}
+
+fn write_file(path: &str, data: &[u8], overwrite: bool) -> Result<(), KeychainError> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true);
+    if overwrite {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options.open(path).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::AlreadyExists {
+            KeychainError::rejected(
+                KeychainErrorCode::AlreadyExists,
+                format!("le fichier {} existe deja", path),
+            )
+        } else {
+            io_error(path, e)
+        }
+    })?;
+    file.write_all(data)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| io_error(path, e))
+}
+
+/// Lit toutes les entrees indexees d'un service. L'export exige une
+/// verification de l'utilisateur si l'une d'elles est marquee
+/// `require_auth`. Les cles de chiffrement restent dans le keychain.
+fn collect_entries(
+    store: &Store,
+    index: &KeyIndexLock,
+    retry: RetryPolicy,
+    service: &str,
+    progress: &Progress,
+) -> Result<Gated<Vec<BackupEntry>>, KeychainError> {
+    let keys = {
+        let _guard = index.0.lock().unwrap_or_else(|e| e.into_inner());
+        read_index(store, service)?
+    };
+    let total = keys.len();
+    let mut entries = Vec::with_capacity(total);
+    let mut protected = false;
+    for (done, key) in keys.into_iter().enumerate() {
+        if !progress.step(BulkPhase::Reading, done, total, Some(&key)) {
+            return Err(cancelled(progress, "export annule : aucun fichier ecrit"));
+        }
+        let value = match read_raw(store, retry, service, &key) {
+            Ok(Some(raw)) if raw_is_encryption_key(raw.as_bytes()) => continue,
+            Ok(Some(mut raw)) => {
+                protected |= raw_requires_auth(&raw);
+                BackupValue::Text(std::mem::take(&mut *raw))
+            }
+            // Entree indexee mais deja supprimee.
+            Ok(None) => continue,
+            Err(e) if e.code() == KeychainErrorCode::BadEncoding => {
+                match found(keyring_call(store, retry, service, &key, |entry| {
+                    entry.get_secret()
+                }))? {
+                    Some(bytes) => BackupValue::Bytes(
+                        base64::engine::general_purpose::STANDARD.encode(&bytes[..]),
+                    ),
+                    None => continue,
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        entries.push(BackupEntry { key, value });
+    }
+    let reason = protected.then(|| format!("ARGOS demande l'export des secrets de {}", service));
+    Ok(Gated::new(entries, reason))
+}
+
+/// Erreur d'une operation arretee par `bulk_cancel`, avec sa derniere cle.
+fn cancelled(progress: &Progress, detail: &str) -> KeychainError {
+    let detail = match progress.stopped_at() {
+        Some(key) => format!("{} (arret avant {})", detail, key),
+        None => detail.to_string(),
+    };
+    KeychainError::rejected(KeychainErrorCode::OperationCancelled, detail)
+}
+
+#[derive(Serialize)]
+pub(crate) struct ExportReport {
+    pub(crate) count: usize,
+    path: String,
+}
+
+/// Exporte tous les secrets d'un service, hors cles de chiffrement, dans un
+/// fichier chiffre (Argon2id + AES-256-GCM). Refuse d'ecraser un fichier existant sauf avec
+/// `overwrite`. Renvoie aussitot l'identifiant de l'operation ; le bilan
+/// arrive sur `argos://bulk-progress`, et une annulation n'ecrit rien. Une
+/// sauvegarde automatique en cours est attendue.
+#[tauri::command]
+pub(crate) async fn keychain_export(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    passphrase: Secret,
+    path: String,
+    overwrite: Option<bool>,
+    retry: Option<RetryPolicy>,
+) -> Result<BulkStarted, KeychainError> {
+    let audit = manager
+        .audit(&window, "export", &service, None)
+        .detail(path.as_str());
+    let manager = manager.inner().clone();
+    let progress = manager.start_bulk("export");
+    let tracked = progress.clone();
+    Ok(bulk::spawn(progress, async move {
+        audit.finish(
+            export(
+                manager, service, passphrase, path, overwrite, retry, tracked,
+            )
+            .await,
+        )
+    }))
+}
+
+async fn export(
+    manager: KeychainManager,
+    service: String,
+    passphrase: Secret,
+    path: String,
+    overwrite: Option<bool>,
+    retry: Option<RetryPolicy>,
+    progress: Progress,
+) -> Result<ExportReport, KeychainError> {
+    manager.check_unlocked()?;
+    let service = manager.resolve_service(&service)?;
+    check_passphrase(&passphrase)?;
+    let overwrite = overwrite.unwrap_or(false);
+    if !overwrite && std::path::Path::new(&path).exists() {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::AlreadyExists,
+            format!("le fichier {} existe deja", path),
+        ));
+    }
+
+    let _exclusive = manager.bulk.exclusive().await;
+    let retry = retry.unwrap_or_default();
+    let auth = manager.auth.clone();
+    let entries = auth
+        .release(read_entries(&manager, &service, retry, &progress).await)
+        .await?;
+    let total = entries.len();
+    if !progress.step(BulkPhase::Sealing, total, total, None) {
+        return Err(cancelled(&progress, "export annule : aucun fichier ecrit"));
+    }
+    write_backup(service, entries, passphrase, path, overwrite).await
+}
+
+/// Meme export que `keychain_export`, sans progression ni verification de
+/// l'utilisateur : les valeurs protegees ne quittent pas Rust, elles sont
+/// chiffrees avec `passphrase` dans un nouveau fichier. Pour les
+/// sauvegardes automatiques, qui tiennent deja le verrou des exports.
+pub(crate) async fn export_unattended(
+    manager: &KeychainManager,
+    service: &str,
+    passphrase: Secret,
+    path: String,
+) -> Result<ExportReport, KeychainError> {
+    let service = manager.resolve_service(service)?;
+    let entries = read_entries(manager, &service, RetryPolicy::default(), &Progress::none())
+        .await?
+        .into_unverified();
+    write_backup(service, entries, passphrase, path, false).await
+}
+
+async fn read_entries(
+    manager: &KeychainManager,
+    service: &str,
+    retry: RetryPolicy,
+    progress: &Progress,
+) -> Result<Gated<Vec<BackupEntry>>, KeychainError> {
+    let manager = manager.clone();
+    let service = service.to_string();
+    let progress = progress.clone();
+    bulk::blocking(move || {
+        collect_entries(&manager.store, &manager.index, retry, &service, &progress)
+    })
+    .await
+}
+
+async fn write_backup(
+    service: String,
+    entries: Vec<BackupEntry>,
+    passphrase: Secret,
+    path: String,
+    overwrite: bool,
+) -> Result<ExportReport, KeychainError> {
+    let backup = Backup {
+        service,
+        exported_at: now_rfc3339(),
+        entries,
+    };
+    // La derivation Argon2 prend du temps : hors du delai des appels keychain.
+    tauri::async_runtime::spawn_blocking(move || {
+        let data = seal(&backup, &passphrase)?;
+        write_file(&path, &data, overwrite)?;
+        Ok(ExportReport {
+            count: backup.entries.len(),
+            path,
+        })
+    })
+    .await
+    .map_err(worker_interrupted)?
+}
+
+/// Traitement des cles deja presentes dans le keychain.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ConflictPolicy {
+    /// Conserve l'entree existante.
+    Skip,
+    /// Remplace l'entree existante.
+    Overwrite,
+    /// N'importe rien si une seule cle existe deja.
+    Fail,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(super) enum ImportStatus {
+    Imported,
+    Skipped,
+    Failed,
+}
+
+#[derive(Serialize)]
+struct ImportOutcome {
+    key: String,
+    status: ImportStatus,
+    error: Option<KeychainError>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ImportReport {
+    imported: usize,
+    skipped: usize,
+    failed: usize,
+    results: Vec<ImportOutcome>,
+    /// Premiere cle non importee si `bulk_cancel` a interrompu l'import.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped_at: Option<String>,
+}
+
+fn entry_exists(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+) -> Result<bool, KeychainError> {
+    Ok(found(keyring_call(store, retry, service, key, |entry| {
+        entry.probe()
+    }))?
+    .is_some())
+}
+
+fn restore_entry(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    entry: &BackupEntry,
+) -> Result<(), KeychainError> {
+    match &entry.value {
+        BackupValue::Text(raw) => write_raw(store, retry, service, key, raw),
+        BackupValue::Bytes(encoded) => {
+            check_user_key(key)?;
+            let bytes = decode_base64(encoded).map_err(|e| invalid_backup(&e.to_string()))?;
+            keyring_call(store, retry, service, key, |item| item.set_secret(&bytes))
+        }
+    }
+}
+
+/// Les cles de la sauvegarde sont normalisees comme par `keychain_set` : une
+/// cle en collision avec une orthographe existante echoue seule, avec
+/// `NameCollision`.
+fn restore(
+    manager: &KeychainManager,
+    retry: RetryPolicy,
+    service: &str,
+    backup: &Backup,
+    policy: ConflictPolicy,
+    progress: &Progress,
+) -> Result<ImportReport, KeychainError> {
+    let store = &manager.store;
+    let total = backup.entries.len();
+    let names: Vec<Result<String, KeychainError>> = backup
+        .entries
+        .iter()
+        .map(|entry| manager.claim_key(service, &entry.key, retry))
+        .collect();
+    let _guard = manager.locks.lock(
+        service,
+        names.iter().filter_map(|name| name.as_deref().ok()),
+    );
+    let mut existing = Vec::new();
+    for (done, name) in names.iter().enumerate() {
+        let Ok(name) = name else {
+            continue;
+        };
+        if !progress.step(BulkPhase::Checking, done, total, Some(name)) {
+            return Err(cancelled(progress, "import annule : aucune cle ecrite"));
+        }
+        if entry_exists(store, retry, service, name)? {
+            existing.push(name.clone());
+        }
+    }
+    if policy == ConflictPolicy::Fail && !existing.is_empty() {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::AlreadyExists,
+            format!("cles deja presentes : {}", existing.join(", ")),
+        ));
+    }
+
+    let mut report = ImportReport {
+        imported: 0,
+        skipped: 0,
+        failed: 0,
+        results: Vec::with_capacity(total),
+        stopped_at: None,
+    };
+    for (done, (entry, name)) in backup.entries.iter().zip(names).enumerate() {
+        let key = name.as_deref().unwrap_or(&entry.key).to_string();
+        if !progress.step(BulkPhase::Writing, done, total, Some(&key)) {
+            report.stopped_at = Some(key);
+            break;
+        }
+        let written = match name {
+            Ok(name) if policy == ConflictPolicy::Skip && existing.contains(&name) => Ok(false),
+            Ok(name) => restore_entry(store, retry, service, &name, entry).map(|()| true),
+            Err(e) => Err(e),
+        };
+        let (status, error) = match written {
+            Ok(true) => {
+                report.imported += 1;
+                (ImportStatus::Imported, None)
+            }
+            Ok(false) => {
+                report.skipped += 1;
+                (ImportStatus::Skipped, None)
+            }
+            Err(e) => {
+                report.failed += 1;
+                (ImportStatus::Failed, Some(e))
+            }
+        };
+        report.results.push(ImportOutcome { key, status, error });
+    }
+    let imported: Vec<&str> = report
+        .results
+        .iter()
+        .filter(|r| matches!(r.status, ImportStatus::Imported))
+        .map(|r| r.key.as_str())
+        .collect();
+    update_index(store, &manager.index, service, &imported, true);
+    Ok(report)
+}
+
+/// Importe une sauvegarde chiffree dans le keychain du service. Le format
+/// et le tag GCM sont verifies avant toute ecriture. En tache de fond comme
+/// `keychain_export` ; une annulation garde les cles deja importees.
+#[tauri::command]
+pub(crate) async fn keychain_import(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    passphrase: Secret,
+    path: String,
+    conflict_policy: ConflictPolicy,
+    retry: Option<RetryPolicy>,
+) -> Result<BulkStarted, KeychainError> {
+    let audit = manager
+        .audit(&window, "import", &service, None)
+        .detail(path.as_str());
+    let manager = manager.inner().clone();
+    let progress = manager.start_bulk("import");
+    let tracked = progress.clone();
+    Ok(bulk::spawn(progress, async move {
+        audit.finish(
+            import(
+                manager,
+                service,
+                passphrase,
+                path,
+                conflict_policy,
+                retry,
+                tracked,
+            )
+            .await,
+        )
+    }))
+}
+
+async fn import(
+    manager: KeychainManager,
+    service: String,
+    passphrase: Secret,
+    path: String,
+    conflict_policy: ConflictPolicy,
+    retry: Option<RetryPolicy>,
+    progress: Progress,
+) -> Result<ImportReport, KeychainError> {
+    let service = manager.resolve_service(&service)?;
+    if !progress.step(BulkPhase::Opening, 0, 0, None) {
+        return Err(cancelled(&progress, "import annule : aucune cle ecrite"));
+    }
+    let backup = tauri::async_runtime::spawn_blocking(move || {
+        let data = std::fs::read(&path).map_err(|e| io_error(&path, e))?;
+        open(&data, &passphrase)
+    })
+    .await
+    .map_err(worker_interrupted)??;
+
+    let retry = retry.unwrap_or_default();
+    bulk::blocking(move || {
+        let report = restore(
+            &manager,
+            retry,
+            &service,
+            &backup,
+            conflict_policy,
+            &progress,
+        );
+        manager.cache.invalidate_service(&service);
+        let report = report?;
+        manager.events.emit_all(
+            &service,
+            report
+                .results
+                .iter()
+                .filter(|r| matches!(r.status, ImportStatus::Imported))
+                .map(|r| r.key.as_str()),
+            SecretOperation::Import,
+        );
+        Ok(report)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{get, memory_manager, NO_RETRY, SERVICE};
+    use super::super::write_index;
+    use super::*;
+
+    #[test]
+    fn export_buffers_are_zeroizing() {
+        // Assertions de type : ne compile plus si un buffer en clair cesse
+        // d'etre efface a sa liberation.
+        type Cleared<T> = Result<Zeroizing<T>, KeychainError>;
+        let _: fn(&Backup) -> Cleared<Vec<u8>> = plaintext;
+        let _: fn(&[u8], &str) -> Cleared<Vec<u8>> = decrypt;
+        let _: fn(&str, &Header) -> Cleared<[u8; KEY_LEN]> = derive_key;
+    }
+
+    const PASSPHRASE: &str = "Correct-Horse-42";
+
+    fn sample() -> Backup {
+        Backup {
+            service: SERVICE.to_string(),
+            exported_at: now_rfc3339(),
+            entries: vec![
+                BackupEntry {
+                    key: "token".to_string(),
+                    value: BackupValue::Text("v1".to_string()),
+                },
+                BackupEntry {
+                    key: "cle".to_string(),
+                    value: BackupValue::Bytes("AP8A".to_string()),
+                },
+            ],
+        }
+    }
+
+    fn open_code(data: &[u8], passphrase: &str) -> Option<KeychainErrorCode> {
+        open(data, passphrase).err().map(|e| e.code())
+    }
+
+    #[test]
+    fn sealed_backup_opens_with_its_passphrase() {
+        let file = seal(&sample(), PASSPHRASE).unwrap();
+        assert_eq!(&file[..MAGIC.len()], MAGIC);
+        assert_eq!(file[MAGIC.len()], FORMAT_VERSION);
+        let backup = open(&file, PASSPHRASE).unwrap();
+        assert_eq!(backup.service, SERVICE);
+        let entries: Vec<_> = backup
+            .entries
+            .iter()
+            .map(|entry| match &entry.value {
+                BackupValue::Text(data) => (entry.key.as_str(), "text", data.as_str()),
+                BackupValue::Bytes(data) => (entry.key.as_str(), "bytes", data.as_str()),
+            })
+            .collect();
+        assert_eq!(entries, [("token", "text", "v1"), ("cle", "bytes", "AP8A")]);
+    }
+
+    #[test]
+    fn altered_backup_fails_authentication() {
+        let file = seal(&sample(), PASSPHRASE).unwrap();
+        // Sel et nonce de l'en-tete, premier octet chiffre, dernier octet du tag.
+        for offset in [21, 21 + SALT_LEN, HEADER_LEN, file.len() - 1] {
+            let mut altered = file.clone();
+            altered[offset] ^= 0x01;
+            assert_eq!(
+                open_code(&altered, PASSPHRASE),
+                Some(KeychainErrorCode::BadMac),
+                "octet {}",
+                offset
+            );
+        }
+        assert_eq!(
+            open_code(&file, "Wrong-Horse-42"),
+            Some(KeychainErrorCode::BadMac)
+        );
+    }
+
+    #[test]
+    fn header_is_checked_before_decryption() {
+        let file = seal(&sample(), PASSPHRASE).unwrap();
+        let mut newer = file.clone();
+        newer[MAGIC.len()] = FORMAT_VERSION + 1;
+        assert_eq!(
+            open_code(&newer, PASSPHRASE),
+            Some(KeychainErrorCode::UnsupportedVersion)
+        );
+        let mut zero = file.clone();
+        zero[MAGIC.len()] = 0;
+        assert_eq!(
+            open_code(&zero, PASSPHRASE),
+            Some(KeychainErrorCode::InvalidBackup)
+        );
+        let mut costly = file.clone();
+        costly[9..13].copy_from_slice(&(MAX_KDF_M_COST + 1).to_le_bytes());
+        assert_eq!(
+            open_code(&costly, PASSPHRASE),
+            Some(KeychainErrorCode::InvalidBackup)
+        );
+        assert_eq!(
+            open_code(b"PKZIP", PASSPHRASE),
+            Some(KeychainErrorCode::InvalidBackup)
+        );
+        assert_eq!(
+            open_code(&file[..HEADER_LEN - 1], PASSPHRASE),
+            Some(KeychainErrorCode::InvalidBackup)
+        );
+    }
+
+    #[test]
+    fn restore_normalizes_keys_like_keychain_set() {
+        let manager = memory_manager();
+        write_index(&manager.store, SERVICE, &["Cle\u{301}".to_string()]).unwrap();
+        let backup = Backup {
+            service: SERVICE.to_string(),
+            exported_at: now_rfc3339(),
+            entries: vec![
+                BackupEntry {
+                    key: " token ".to_string(),
+                    value: BackupValue::Text("v1".to_string()),
+                },
+                BackupEntry {
+                    key: "Clé".to_string(),
+                    value: BackupValue::Text("v2".to_string()),
+                },
+            ],
+        };
+        let report = restore(
+            &manager,
+            NO_RETRY,
+            SERVICE,
+            &backup,
+            ConflictPolicy::Overwrite,
+            &Progress::none(),
+        )
+        .unwrap();
+        assert_eq!((report.imported, report.failed), (1, 1));
+        assert_eq!(report.results[0].key, "token");
+        assert!(matches!(report.results[1].status, ImportStatus::Failed));
+        assert_eq!(
+            report.results[1].error.as_ref().map(|e| e.code()),
+            Some(KeychainErrorCode::NameCollision)
+        );
+        assert_eq!(get(&manager, "token").as_deref(), Some("v1"));
+        assert_eq!(
+            read_index(&manager.store, SERVICE).unwrap(),
+            ["Cle\u{301}", "token"]
+        );
+    }
+
+    #[test]
+    fn encryption_keys_stay_out_of_exports() {
+        let manager = memory_manager();
+        let key = super::super::SetOptions {
+            encryption_key: true,
+            ..Default::default()
+        };
+        manager.set(SERVICE, "cle", "AAAA", key, NO_RETRY).unwrap();
+        manager
+            .set(SERVICE, "token", "v1", Default::default(), NO_RETRY)
+            .unwrap();
+        let entries = collect_entries(
+            &manager.store,
+            &manager.index,
+            NO_RETRY,
+            SERVICE,
+            &Progress::none(),
+        )
+        .unwrap()
+        .into_unverified();
+        let keys: Vec<&str> = entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["token"]);
+    }
+}
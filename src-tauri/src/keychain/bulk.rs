@@ -0,0 +1,276 @@
+//! Operations en masse : export, import, import d'un autre gestionnaire,
+//! migration, vidage d'un service et recherche de doublons.
+//! La commande renvoie aussitot un identifiant ; la progression est emise
+//! sur `argos://bulk-progress` et `bulk_cancel` interrompt l'operation entre
+//! deux cles. Les cles deja traitees le restent, et le bilan final indique
+//! la cle ou l'operation s'est arretee.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use rand::RngCore;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use super::{worker_interrupted, KeychainError, KeychainManager};
+
+pub(crate) const BULK_PROGRESS: &str = "argos://bulk-progress";
+
+/// Etape en cours ; `done`, `cancelled` et `failed` terminent l'operation.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum BulkPhase {
+    Reading,
+    Checking,
+    Writing,
+    Migrating,
+    Deleting,
+    Sealing,
+    Opening,
+    Done,
+    Cancelled,
+    Failed,
+}
+
+#[derive(Serialize, Clone)]
+struct BulkProgress<'a> {
+    operation_id: &'a str,
+    operation: &'static str,
+    done: usize,
+    total: usize,
+    current_key: Option<&'a str>,
+    phase: BulkPhase,
+    /// Bilan de la commande, sur l'evenement final.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<serde_json::Value>,
+}
+
+/// Reponse immediate des commandes en masse.
+#[derive(Serialize)]
+pub(crate) struct BulkStarted {
+    operation_id: String,
+}
+
+/// Operations en cours, par identifiant, avec leur demande d'annulation.
+#[derive(Clone, Default)]
+pub(crate) struct BulkOperations {
+    running: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+    /// Tenu pendant un export, manuel ou automatique : deux exports ne
+    /// s'entrelacent pas.
+    exclusive: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl BulkOperations {
+    /// Attend la fin de l'export en cours s'il y en a un.
+    pub(crate) async fn exclusive(&self) -> tokio::sync::OwnedMutexGuard<()> {
+        Arc::clone(&self.exclusive).lock_owned().await
+    }
+
+    pub(crate) fn start(&self, app: Option<&AppHandle>, operation: &'static str) -> Progress {
+        let mut bytes = [0u8; 8];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let id: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(id.clone(), Arc::clone(&cancel));
+        Progress(Some(Arc::new(Tracked {
+            id,
+            operation,
+            app: app.cloned(),
+            cancel,
+            operations: self.clone(),
+            position: Mutex::new(Position::default()),
+        })))
+    }
+
+    /// `false` si l'operation est inconnue ou deja terminee.
+    fn cancel(&self, id: &str) -> bool {
+        match self
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(id)
+        {
+            Some(cancel) => {
+                cancel.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Position {
+    done: usize,
+    total: usize,
+    key: Option<String>,
+    /// Cle non traitee sur laquelle l'annulation a ete constatee.
+    stopped_at: Option<String>,
+}
+
+struct Tracked {
+    id: String,
+    operation: &'static str,
+    app: Option<AppHandle>,
+    cancel: Arc<AtomicBool>,
+    operations: BulkOperations,
+    position: Mutex<Position>,
+}
+
+impl Tracked {
+    fn emit(
+        &self,
+        position: &Position,
+        phase: BulkPhase,
+        result: Option<serde_json::Value>,
+        error: Option<serde_json::Value>,
+    ) {
+        let Some(app) = &self.app else {
+            return;
+        };
+        let _ = app.emit_all(
+            BULK_PROGRESS,
+            BulkProgress {
+                operation_id: &self.id,
+                operation: self.operation,
+                done: position.done,
+                total: position.total,
+                current_key: position.key.as_deref(),
+                phase,
+                result,
+                error,
+            },
+        );
+    }
+}
+
+/// Suivi d'une operation en masse, partage avec sa tache bloquante.
+/// `Progress::none()` ne suit rien : migrations de demarrage, suppression
+/// d'un profil.
+#[derive(Clone)]
+pub(crate) struct Progress(Option<Arc<Tracked>>);
+
+impl Progress {
+    pub(crate) fn none() -> Self {
+        Progress(None)
+    }
+
+    /// Annonce `key`, la `done + 1`-ieme sur `total`, avant son traitement.
+    /// Renvoie `false` si l'annulation est demandee : `key` n'est alors pas
+    /// traitee et l'appelant s'arrete.
+    pub(crate) fn step(
+        &self,
+        phase: BulkPhase,
+        done: usize,
+        total: usize,
+        key: Option<&str>,
+    ) -> bool {
+        let Some(tracked) = &self.0 else {
+            return true;
+        };
+        let mut position = tracked.position.lock().unwrap_or_else(|e| e.into_inner());
+        position.done = done;
+        position.total = total;
+        position.key = key.map(str::to_string);
+        if tracked.cancel.load(Ordering::SeqCst) {
+            position.stopped_at = position.key.clone();
+            return false;
+        }
+        tracked.emit(&position, phase, None, None);
+        true
+    }
+
+    /// Cle a laquelle l'operation s'est arretee sur annulation.
+    pub(crate) fn stopped_at(&self) -> Option<String> {
+        let tracked = self.0.as_ref()?;
+        let position = tracked.position.lock().unwrap_or_else(|e| e.into_inner());
+        position.stopped_at.clone()
+    }
+
+    fn id(&self) -> String {
+        self.0
+            .as_ref()
+            .map(|tracked| tracked.id.clone())
+            .unwrap_or_default()
+    }
+
+    /// Emet l'evenement final avec le bilan ou l'erreur, puis oublie
+    /// l'operation.
+    fn finish<T: Serialize>(&self, result: &Result<T, KeychainError>) {
+        let Some(tracked) = &self.0 else {
+            return;
+        };
+        tracked
+            .operations
+            .running
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&tracked.id);
+        let mut position = tracked.position.lock().unwrap_or_else(|e| e.into_inner());
+        let phase = match result {
+            _ if position.stopped_at.is_some() => BulkPhase::Cancelled,
+            Ok(_) => BulkPhase::Done,
+            Err(_) => BulkPhase::Failed,
+        };
+        if phase == BulkPhase::Done {
+            position.done = position.total;
+            position.key = None;
+        }
+        let (result, error) = match result {
+            Ok(report) => (serde_json::to_value(report).ok(), None),
+            Err(e) => (None, serde_json::to_value(e).ok()),
+        };
+        tracked.emit(&position, phase, result, error);
+    }
+}
+
+impl KeychainManager {
+    /// Nouvelle operation suivie, emise vers toutes les fenetres.
+    pub(crate) fn start_bulk(&self, operation: &'static str) -> Progress {
+        self.bulk.start(self.events.app(), operation)
+    }
+}
+
+/// Lance `work` en tache de fond et renvoie aussitot l'identifiant de
+/// `progress`. Le resultat n'est connu que par l'evenement final.
+pub(crate) fn spawn<T, F>(progress: Progress, work: F) -> BulkStarted
+where
+    T: Serialize,
+    F: Future<Output = Result<T, KeychainError>> + Send + 'static,
+{
+    let started = BulkStarted {
+        operation_id: progress.id(),
+    };
+    tauri::async_runtime::spawn(async move {
+        let result = work.await;
+        progress.finish(&result);
+    });
+    started
+}
+
+/// Comme `run_blocking`, sans delai maximal, comme les migrations de
+/// demarrage : la progression montre que l'operation avance, et
+/// `bulk_cancel` l'arrete.
+pub(crate) async fn blocking<T, F>(f: F) -> Result<T, KeychainError>
+where
+    F: FnOnce() -> Result<T, KeychainError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(worker_interrupted)?
+}
+
+/// Demande l'arret de l'operation `operation_id` avant sa prochaine cle.
+/// `false` si elle est inconnue ou deja terminee.
+#[tauri::command]
+pub(crate) fn bulk_cancel(manager: State<'_, KeychainManager>, operation_id: String) -> bool {
+    manager.bulk.cancel(&operation_id)
+}
@@ -0,0 +1,181 @@
+//! Cache memoire des lectures de `keychain_get`, sur demande : certains
+//! backends Secret Service mettent plusieurs centaines de millisecondes a
+//! repondre a chaque lecture.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::State;
+
+use super::{KeychainManager, Secret};
+
+pub(crate) const DEFAULT_CACHE_TTL_SECS: u64 = 300;
+pub(crate) const DEFAULT_CACHE_MAX_ENTRIES: usize = 256;
+
+struct CachedSecret {
+    value: Secret,
+    require_auth: bool,
+    /// Expiration de l'enveloppe, verifiee a chaque lecture du cache.
+    expires_at: Option<String>,
+    /// Le secret est mis en cache par sa politique, meme sans `cache: true`.
+    pinned: bool,
+    cached_until: Instant,
+}
+
+/// Valeur servie par le cache.
+pub(crate) struct CacheHit {
+    pub(crate) value: Secret,
+    pub(crate) require_auth: bool,
+}
+
+/// Cache des secrets texte, par service et cle. Les valeurs sont effacees
+/// de la memoire a leur eviction.
+pub(crate) struct SecretCache {
+    entries: RwLock<HashMap<(String, String), CachedSecret>>,
+    /// `None` desactive le cache.
+    ttl: Option<Duration>,
+    max_entries: usize,
+    /// Incremente a chaque invalidation : une lecture du backend commencee
+    /// avant n'est pas mise en cache.
+    generation: AtomicU64,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SecretCache {
+    pub(crate) fn new(ttl_seconds: u64, max_entries: usize) -> Self {
+        SecretCache {
+            entries: RwLock::new(HashMap::new()),
+            ttl: (ttl_seconds > 0 && max_entries > 0).then(|| Duration::from_secs(ttl_seconds)),
+            max_entries,
+            generation: AtomicU64::new(0),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Sert `key` depuis le cache si l'appelant l'a demande ou si le secret
+    /// y a ete mis par sa politique. Un secret expire n'est jamais servi.
+    pub(crate) fn lookup(&self, service: &str, key: &str, requested: bool) -> Option<CacheHit> {
+        self.ttl?;
+        let entries = self.entries.read().unwrap_or_else(|e| e.into_inner());
+        let hit = entries
+            .get(&(service.to_string(), key.to_string()))
+            .filter(|entry| requested || entry.pinned)
+            .filter(|entry| {
+                entry.cached_until > Instant::now() && !super::is_past(entry.expires_at.as_deref())
+            })
+            .map(|entry| CacheHit {
+                value: entry.value.clone(),
+                require_auth: entry.require_auth,
+            });
+        if hit.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        } else if requested {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        hit
+    }
+
+    /// Garde une valeur lue du backend, sauf si une invalidation a eu lieu
+    /// depuis `generation`. Au-dela de `max_entries`, les entrees perimees
+    /// puis les plus anciennes sont evincees.
+    pub(crate) fn insert(
+        &self,
+        generation: u64,
+        service: &str,
+        key: &str,
+        hit: CacheHit,
+        expires_at: Option<String>,
+        pinned: bool,
+    ) {
+        let Some(ttl) = self.ttl else {
+            return;
+        };
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        if self.generation() != generation {
+            return;
+        }
+        let id = (service.to_string(), key.to_string());
+        if !entries.contains_key(&id) && entries.len() >= self.max_entries {
+            let now = Instant::now();
+            entries.retain(|_, entry| entry.cached_until > now);
+            if entries.len() >= self.max_entries {
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.cached_until)
+                    .map(|(id, _)| id.clone())
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(
+            id,
+            CachedSecret {
+                value: hit.value,
+                require_auth: hit.require_auth,
+                expires_at,
+                pinned,
+                cached_until: Instant::now() + ttl,
+            },
+        );
+    }
+
+    /// A appeler apres chaque ecriture ou suppression de `keys`.
+    pub(crate) fn invalidate<'a>(&self, service: &str, keys: impl IntoIterator<Item = &'a str>) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        for key in keys {
+            entries.remove(&(service.to_string(), key.to_string()));
+        }
+    }
+
+    pub(crate) fn invalidate_service(&self, service: &str) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.retain(|(cached_service, _), _| cached_service != service);
+    }
+
+    /// Vide tout le cache, au verrouillage de la session ou du coffre.
+    pub(crate) fn flush(&self) {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        self.generation.fetch_add(1, Ordering::SeqCst);
+        entries.clear();
+    }
+
+    fn stats(&self) -> CacheStats {
+        CacheStats {
+            enabled: self.ttl.is_some(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.read().unwrap_or_else(|e| e.into_inner()).len(),
+            ttl_seconds: self.ttl.map_or(0, |ttl| ttl.as_secs()),
+            max_entries: self.max_entries,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct CacheStats {
+    enabled: bool,
+    hits: u64,
+    /// Lectures `cache: true` servies par le backend.
+    misses: u64,
+    entries: usize,
+    ttl_seconds: u64,
+    max_entries: usize,
+}
+
+/// Compteurs du cache depuis le demarrage.
+#[tauri::command]
+pub(crate) fn keychain_cache_stats(manager: State<'_, KeychainManager>) -> CacheStats {
+    manager.cache.stats()
+}
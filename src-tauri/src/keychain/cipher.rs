@@ -0,0 +1,399 @@
+//! Chiffrement AES-256-GCM avec une cle gardee dans le keychain : le
+//! webview envoie et recoit des donnees, jamais la cle.
+
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use rand::RngCore;
+use serde::Serialize;
+use tauri::{State, Window};
+use zeroize::Zeroizing;
+
+use super::backup::{KEY_LEN, NONCE_LEN};
+use super::{
+    fingerprint, read_raw, run_blocking, KeychainError, KeychainErrorCode, KeychainManager,
+    RetryPolicy, Secret, SetOptions, Stored,
+};
+
+/// Longueur du tag GCM ajoute au chiffre.
+const TAG_LEN: usize = 16;
+
+#[derive(Serialize)]
+pub(crate) struct EncryptionKeyReport {
+    persistent: bool,
+    /// Empreinte de la cle, pour verifier deux postes sans l'exposer.
+    fingerprint: String,
+}
+
+fn decode(name: &str, data: &str) -> Result<Zeroizing<Vec<u8>>, KeychainError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(data.trim())
+        .map(Zeroizing::new)
+        .map_err(|e| {
+            KeychainError::rejected(
+                KeychainErrorCode::InvalidBase64,
+                format!("{} : base64 invalide : {}", name, e),
+            )
+        })
+}
+
+fn encode(data: &[u8]) -> Secret {
+    Secret::new(base64::engine::general_purpose::STANDARD.encode(data))
+}
+
+fn wrong_length(key: &str, len: usize) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::InvalidArgument,
+        format!(
+            "la cle de chiffrement {} fait {} octets au lieu de {}",
+            key, len, KEY_LEN
+        ),
+    )
+}
+
+fn not_a_key(key: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::InvalidArgument,
+        format!("{} n'a pas ete ecrit par keychain_set_encryption_key", key),
+    )
+}
+
+fn authentication_failed() -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::AuthenticationFailed,
+        "dechiffrement impossible : cle incorrecte ou donnees alterees",
+    )
+}
+
+/// Lit une cle ecrite par `keychain_set_encryption_key` ; tout autre secret
+/// est refuse.
+fn encryption_key(
+    manager: &KeychainManager,
+    service: &str,
+    key: &str,
+    retry: RetryPolicy,
+) -> Result<Aes256Gcm, KeychainError> {
+    manager.check_unlocked()?;
+    let service = &manager.resolve_service(service)?;
+    let name = manager.resolve_key(service, key, retry)?;
+    let key = name.as_str();
+    let stored = read_raw(&manager.store, retry, service, key)
+        .map_err(|e| match e.code() {
+            KeychainErrorCode::BadEncoding => not_a_key(key),
+            _ => e,
+        })?
+        .map(Stored::parse)
+        .ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::NotFound,
+                format!("aucune cle de chiffrement {} dans {}", key, service),
+            )
+        })?;
+    if !stored.is_encryption_key() {
+        return Err(not_a_key(key));
+    }
+    let bytes = decode(key, &stored.into_value())?;
+    if bytes.len() != KEY_LEN {
+        return Err(wrong_length(key, bytes.len()));
+    }
+    Aes256Gcm::new_from_slice(&bytes).map_err(|_| wrong_length(key, bytes.len()))
+}
+
+fn encrypt(
+    manager: &KeychainManager,
+    service: &str,
+    key: &str,
+    plaintext_base64: &str,
+    retry: RetryPolicy,
+) -> Result<String, KeychainError> {
+    let plaintext = decode("plaintext_base64", plaintext_base64)?;
+    let cipher = encryption_key(manager, service, key, retry)?;
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), &plaintext[..])
+        .map_err(super::backup::crypto_error)?;
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(base64::engine::general_purpose::STANDARD.encode(out))
+}
+
+fn decrypt(
+    manager: &KeychainManager,
+    service: &str,
+    key: &str,
+    ciphertext_base64: &str,
+    retry: RetryPolicy,
+) -> Result<Secret, KeychainError> {
+    let data = decode("ciphertext_base64", ciphertext_base64)?;
+    let cipher = encryption_key(manager, service, key, retry)?;
+    if data.len() < NONCE_LEN + TAG_LEN {
+        return Err(authentication_failed());
+    }
+    let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map(Zeroizing::new)
+        .map_err(|_| authentication_failed())?;
+    Ok(encode(&plaintext))
+}
+
+fn set_encryption_key(
+    manager: &KeychainManager,
+    service: &str,
+    key: &str,
+    key_base64: Option<&str>,
+    retry: RetryPolicy,
+) -> Result<EncryptionKeyReport, KeychainError> {
+    let bytes = match key_base64 {
+        Some(data) => decode("key_base64", data)?,
+        None => {
+            let mut bytes = Zeroizing::new(vec![0u8; KEY_LEN]);
+            rand::thread_rng().fill_bytes(&mut bytes);
+            bytes
+        }
+    };
+    if bytes.len() != KEY_LEN {
+        return Err(wrong_length(key, bytes.len()));
+    }
+    let encoded = encode(&bytes);
+    let options = SetOptions {
+        history: Some(0),
+        encryption_key: true,
+        ..SetOptions::default()
+    };
+    let report = manager.set(service, key, &encoded, options, retry)?;
+    Ok(EncryptionKeyReport {
+        persistent: report.persistent,
+        fingerprint: fingerprint(&encoded),
+    })
+}
+
+/// Ecrit une cle AES-256 de 32 octets, fournie en base64 ou, a defaut,
+/// tiree au hasard sans quitter Rust. Toute autre longueur est refusee. Les
+/// lectures, empreintes et exports refusent ensuite l'entree
+/// (`EncryptionKeyProtected`), et elle n'a pas d'historique.
+#[tauri::command]
+pub(crate) async fn keychain_set_encryption_key(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    key_base64: Option<Secret>,
+    retry: Option<RetryPolicy>,
+) -> Result<EncryptionKeyReport, KeychainError> {
+    let audit = manager.audit(&window, "set_encryption_key", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        run_blocking(move || {
+            set_encryption_key(
+                &manager,
+                &service,
+                &key,
+                key_base64.as_ref().map(|k| k.as_str()),
+                retry,
+            )
+        })
+        .await,
+    )
+}
+
+/// Chiffre des donnees (base64) avec la cle `key`. Le resultat, en base64,
+/// commence par le nonce aleatoire de 12 octets.
+#[tauri::command]
+pub(crate) async fn keychain_encrypt(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    plaintext_base64: Secret,
+    retry: Option<RetryPolicy>,
+) -> Result<String, KeychainError> {
+    let audit = manager.audit(&window, "encrypt", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        run_blocking(move || encrypt(&manager, &service, &key, &plaintext_base64, retry)).await,
+    )
+}
+
+/// Dechiffre un resultat de `keychain_encrypt`. Une cle incorrecte ou des
+/// donnees alterees donnent `AuthenticationFailed`.
+#[tauri::command]
+pub(crate) async fn keychain_decrypt(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    ciphertext_base64: String,
+    retry: Option<RetryPolicy>,
+) -> Result<Secret, KeychainError> {
+    let audit = manager.audit(&window, "decrypt", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        run_blocking(move || decrypt(&manager, &service, &key, &ciphertext_base64, retry)).await,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{memory_manager, NO_RETRY, SERVICE};
+    use super::*;
+
+    /// Octets 0 a 31, en base64.
+    const KEY: &str = "AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8=";
+    /// "ARGOS" chiffre avec `KEY` par une autre implementation (nonce 100 a
+    /// 111), pour verifier le format nonce || chiffre || tag.
+    const SEALED: &str = "ZGVmZ2hpamtsbW5vCUmZKSr1k20h7UB9sw3YHADjaoGW";
+
+    fn code<T>(result: Result<T, KeychainError>) -> Option<KeychainErrorCode> {
+        result.err().map(|e| e.code())
+    }
+
+    fn flip(data: &str, offset: usize) -> String {
+        let mut bytes = decode("test", data).unwrap();
+        bytes[offset] ^= 0x01;
+        base64::engine::general_purpose::STANDARD.encode(&bytes[..])
+    }
+
+    #[test]
+    fn encrypted_data_round_trips() {
+        let manager = memory_manager();
+        set_encryption_key(&manager, SERVICE, "cache", None, NO_RETRY).unwrap();
+        let plaintext = base64::engine::general_purpose::STANDARD.encode(b"\0donnees\xff");
+        let first = encrypt(&manager, SERVICE, "cache", &plaintext, NO_RETRY).unwrap();
+        let second = encrypt(&manager, SERVICE, "cache", &plaintext, NO_RETRY).unwrap();
+        // Nonce aleatoire : deux chiffrements du meme texte different.
+        assert_ne!(first, second);
+        assert_eq!(
+            decode("first", &first).unwrap().len(),
+            NONCE_LEN + 9 + TAG_LEN
+        );
+        for ciphertext in [first, second] {
+            let decrypted = decrypt(&manager, SERVICE, "cache", &ciphertext, NO_RETRY).unwrap();
+            assert_eq!(&*decrypted, &plaintext);
+        }
+    }
+
+    #[test]
+    fn known_ciphertext_decrypts() {
+        let manager = memory_manager();
+        set_encryption_key(&manager, SERVICE, "cache", Some(KEY), NO_RETRY).unwrap();
+        let decrypted = decrypt(&manager, SERVICE, "cache", SEALED, NO_RETRY).unwrap();
+        assert_eq!(&*decrypted, "QVJHT1M=");
+    }
+
+    #[test]
+    fn tampered_data_fails_authentication() {
+        let manager = memory_manager();
+        set_encryption_key(&manager, SERVICE, "cache", Some(KEY), NO_RETRY).unwrap();
+        // Nonce, premier octet chiffre, dernier octet du tag.
+        for offset in [0, NONCE_LEN, NONCE_LEN + 5 + TAG_LEN - 1] {
+            let tampered = flip(SEALED, offset);
+            assert_eq!(
+                code(decrypt(&manager, SERVICE, "cache", &tampered, NO_RETRY)),
+                Some(KeychainErrorCode::AuthenticationFailed),
+                "octet {}",
+                offset
+            );
+        }
+        let truncated =
+            base64::engine::general_purpose::STANDARD.encode([0u8; NONCE_LEN + TAG_LEN - 1]);
+        assert_eq!(
+            code(decrypt(&manager, SERVICE, "cache", &truncated, NO_RETRY)),
+            Some(KeychainErrorCode::AuthenticationFailed)
+        );
+        assert_eq!(
+            code(decrypt(
+                &manager,
+                SERVICE,
+                "cache",
+                "pas du base64",
+                NO_RETRY
+            )),
+            Some(KeychainErrorCode::InvalidBase64)
+        );
+    }
+
+    #[test]
+    fn wrong_key_fails_authentication() {
+        let manager = memory_manager();
+        set_encryption_key(&manager, SERVICE, "cache", Some(KEY), NO_RETRY).unwrap();
+        set_encryption_key(&manager, SERVICE, "other", None, NO_RETRY).unwrap();
+        assert_eq!(
+            code(decrypt(&manager, SERVICE, "other", SEALED, NO_RETRY)),
+            Some(KeychainErrorCode::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn keys_of_the_wrong_length_are_refused() {
+        let manager = memory_manager();
+        let short = base64::engine::general_purpose::STANDARD.encode([7u8; 16]);
+        assert_eq!(
+            code(set_encryption_key(
+                &manager,
+                SERVICE,
+                "cache",
+                Some(&short),
+                NO_RETRY
+            )),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+        assert_eq!(
+            code(encrypt(&manager, SERVICE, "cache", "QVJHT1M=", NO_RETRY)),
+            Some(KeychainErrorCode::NotFound)
+        );
+        // Une cle ecrite par un autre moyen est controlee a la lecture.
+        manager
+            .set_bytes(SERVICE, "cache", &short, NO_RETRY)
+            .unwrap();
+        assert_eq!(
+            code(encrypt(&manager, SERVICE, "cache", "QVJHT1M=", NO_RETRY)),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn keys_are_never_returned() {
+        let manager = memory_manager();
+        set_encryption_key(&manager, SERVICE, "cache", Some(KEY), NO_RETRY).unwrap();
+        let protected = Some(KeychainErrorCode::EncryptionKeyProtected);
+        assert_eq!(
+            code(manager.get_bytes(SERVICE, "cache", NO_RETRY)),
+            protected
+        );
+        assert_eq!(
+            code(manager.get(SERVICE, "cache", false, NO_RETRY).map(drop)),
+            protected
+        );
+        let fingerprint = manager.fingerprint(
+            SERVICE,
+            "cache",
+            super::super::fingerprint::FingerprintAlgo::default(),
+            None,
+            NO_RETRY,
+        );
+        assert_eq!(code(fingerprint.map(drop)), protected);
+
+        // Remplacee, l'ancienne cle n'entre pas dans l'historique.
+        set_encryption_key(&manager, SERVICE, "cache", None, NO_RETRY).unwrap();
+        let history = manager.history(SERVICE, "cache", NO_RETRY).unwrap();
+        assert!(history.is_empty());
+    }
+
+    #[test]
+    fn ordinary_secrets_are_not_keys() {
+        let manager = memory_manager();
+        manager
+            .set(SERVICE, "cache", KEY, SetOptions::default(), NO_RETRY)
+            .unwrap();
+        assert_eq!(
+            code(encrypt(&manager, SERVICE, "cache", "QVJHT1M=", NO_RETRY)),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+    }
+}
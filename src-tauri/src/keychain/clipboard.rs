@@ -0,0 +1,199 @@
+//! Copie d'un secret dans le presse-papiers, efface automatiquement apres un
+//! delai s'il contient encore la valeur copiee.
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State, Window};
+use zeroize::Zeroizing;
+
+use super::{
+    run_blocking, sha256_hex, KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy,
+};
+
+pub(crate) const CLIPBOARD_CLEARED: &str = "argos://clipboard-cleared";
+
+const DEFAULT_CLEAR_AFTER_MS: u64 = 30_000;
+const MIN_CLEAR_AFTER_MS: u64 = 1_000;
+const MAX_CLEAR_AFTER_MS: u64 = 10 * 60_000;
+
+#[derive(Serialize, Clone)]
+struct ClipboardCleared {
+    service: String,
+    key: String,
+}
+
+fn clipboard_error(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::PlatformError,
+        format!("presse-papiers inaccessible : {}", detail),
+    )
+}
+
+/// Copie le secret `key` dans le presse-papiers et l'efface apres
+/// `clear_after_ms` (30 s par defaut), sauf si l'utilisateur a copie autre
+/// chose entre-temps. L'effacement emet `argos://clipboard-cleared`.
+#[tauri::command]
+pub(crate) async fn clipboard_copy_secret(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    clear_after_ms: Option<u64>,
+    retry: Option<RetryPolicy>,
+) -> Result<(), KeychainError> {
+    let clear_after_ms = clear_after_ms.unwrap_or(DEFAULT_CLEAR_AFTER_MS);
+    if !(MIN_CLEAR_AFTER_MS..=MAX_CLEAR_AFTER_MS).contains(&clear_after_ms) {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!(
+                "clear_after_ms doit etre compris entre {} et {}",
+                MIN_CLEAR_AFTER_MS, MAX_CLEAR_AFTER_MS
+            ),
+        ));
+    }
+    let audit = manager.audit(&window, "clipboard_copy", &service, Some(&key));
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let app = window.app_handle();
+    let reader = manager.inner().clone();
+    let result = {
+        let (service, key) = (service.clone(), key.clone());
+        auth.release(
+            run_blocking(move || reader.get(&service, &key, false, retry.unwrap_or_default()))
+                .await,
+        )
+        .await
+    };
+    let copied = result.and_then(|value| {
+        let value = value.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::NotFound,
+                format!("aucun secret {} dans {}", key, service),
+            )
+        })?;
+        platform::write(&app, &value)?;
+        Ok(sha256_hex(&value))
+    });
+    let digest = audit.finish(copied)?;
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(clear_after_ms)).await;
+        if clear_if_unchanged(&app, &digest) {
+            let _ = app.emit_all(CLIPBOARD_CLEARED, ClipboardCleared { service, key });
+        }
+    });
+    Ok(())
+}
+
+/// Efface le presse-papiers quel que soit son contenu, pour le raccourci de
+/// verrouillage d'urgence.
+pub(crate) fn clear_now(app: &AppHandle) -> Result<(), KeychainError> {
+    platform::clear(app)
+}
+
+/// Efface le presse-papiers s'il contient toujours la valeur d'empreinte
+/// `digest`. Seule l'empreinte est gardee pendant le delai.
+fn clear_if_unchanged(app: &AppHandle, digest: &str) -> bool {
+    use tauri::ClipboardManager;
+
+    let Ok(Some(current)) = app.clipboard_manager().read_text() else {
+        return false;
+    };
+    let current = Zeroizing::new(current);
+    sha256_hex(&current) == digest && platform::clear(app).is_ok()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use tauri::AppHandle;
+    use windows::core::{w, PCWSTR};
+    use windows::Win32::Foundation::{HANDLE, HWND};
+    use windows::Win32::System::DataExchange::{
+        CloseClipboard, EmptyClipboard, OpenClipboard, RegisterClipboardFormatW, SetClipboardData,
+    };
+    use windows::Win32::System::Memory::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+
+    use super::clipboard_error;
+    use crate::keychain::KeychainError;
+
+    const CF_UNICODETEXT: u32 = 13;
+
+    /// Formats lus par l'historique du presse-papiers et la synchronisation
+    /// cloud de Windows : la valeur qui les accompagne en est exclue.
+    const EXCLUSION_FORMATS: [(PCWSTR, u32); 3] = [
+        (w!("ExcludeClipboardContentFromMonitorProcessing"), 0),
+        (w!("CanIncludeInClipboardHistory"), 0),
+        (w!("CanUploadToCloudClipboard"), 0),
+    ];
+
+    /// Copie `data` dans un bloc global et le confie au presse-papiers.
+    unsafe fn set_data(format: u32, data: &[u8]) -> windows::core::Result<()> {
+        let global = GlobalAlloc(GMEM_MOVEABLE, data.len())?;
+        let target = GlobalLock(global) as *mut u8;
+        std::ptr::copy_nonoverlapping(data.as_ptr(), target, data.len());
+        let _ = GlobalUnlock(global);
+        SetClipboardData(format, HANDLE(global.0))?;
+        Ok(())
+    }
+
+    pub(super) fn write(_app: &AppHandle, value: &str) -> Result<(), KeychainError> {
+        let mut text: Vec<u8> = value
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .flat_map(u16::to_le_bytes)
+            .collect();
+        let result = unsafe {
+            OpenClipboard(HWND::default()).and_then(|()| {
+                let written = EmptyClipboard().and_then(|()| {
+                    set_data(CF_UNICODETEXT, &text)?;
+                    for (name, value) in EXCLUSION_FORMATS {
+                        let format = RegisterClipboardFormatW(name);
+                        if format != 0 {
+                            let _ = set_data(format, &value.to_le_bytes());
+                        }
+                    }
+                    Ok(())
+                });
+                let _ = CloseClipboard();
+                written
+            })
+        };
+        zeroize::Zeroize::zeroize(&mut text);
+        result.map_err(|e| clipboard_error(e.message()))
+    }
+
+    pub(super) fn clear(_app: &AppHandle) -> Result<(), KeychainError> {
+        unsafe {
+            OpenClipboard(HWND::default())
+                .and_then(|()| {
+                    let emptied = EmptyClipboard();
+                    let _ = CloseClipboard();
+                    emptied
+                })
+                .map_err(|e| clipboard_error(e.message()))
+        }
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+mod platform {
+    use tauri::{AppHandle, ClipboardManager};
+
+    use super::clipboard_error;
+    use crate::keychain::KeychainError;
+
+    pub(super) fn write(app: &AppHandle, value: &str) -> Result<(), KeychainError> {
+        app.clipboard_manager()
+            .write_text(value)
+            .map_err(clipboard_error)
+    }
+
+    pub(super) fn clear(app: &AppHandle) -> Result<(), KeychainError> {
+        app.clipboard_manager()
+            .write_text(String::new())
+            .map_err(clipboard_error)
+    }
+}
@@ -0,0 +1,187 @@
+//! Collection Secret Service ou ARGOS range ses secrets sous Linux. La
+//! collection par defaut est souvent le trousseau de session ; une collection
+//! nommee, `ARGOS` par exemple, se verrouille independamment. Le crate keyring
+//! y route les entrees par son libelle, passe comme `target`.
+
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_os = "linux")]
+use super::{KeychainError, KeychainErrorCode};
+
+const MAX_LABEL_CHARS: usize = 64;
+
+/// `"default"`, `"session"` ou `{ "named": "ARGOS" }`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LinuxCollection {
+    #[default]
+    Default,
+    Session,
+    Named(String),
+}
+
+/// Collection utilisee, rapportee par `keychain_status`.
+#[derive(Serialize, Clone)]
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+pub(crate) struct CollectionStatus {
+    collection: LinuxCollection,
+    label: String,
+    /// `None` si le Secret Service ne repond pas.
+    locked: Option<bool>,
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use dbus_secret_service::{Collection, EncryptionType, Error, SecretService};
+
+    use super::{CollectionStatus, KeychainError, KeychainErrorCode, LinuxCollection};
+
+    /// Libelle reconnu par le crate keyring pour la collection par defaut.
+    const DEFAULT_TARGET: &str = "default";
+
+    fn platform(e: Error) -> KeychainError {
+        KeychainError::rejected(
+            KeychainErrorCode::PlatformError,
+            format!("Secret Service : {}", e),
+        )
+    }
+
+    pub(crate) fn locked_error(collection: &LinuxCollection) -> KeychainError {
+        KeychainError::rejected(
+            KeychainErrorCode::BackendLocked,
+            format!("collection {} verrouillee", collection.describe()),
+        )
+    }
+
+    /// Aucun secret ne transite : le chiffrement de session est inutile.
+    fn connect() -> Result<SecretService, KeychainError> {
+        SecretService::connect(EncryptionType::Plain).map_err(platform)
+    }
+
+    fn find<'a>(
+        ss: &'a SecretService,
+        collection: &LinuxCollection,
+    ) -> Result<Option<Collection<'a>>, Error> {
+        let found = match collection {
+            LinuxCollection::Default => ss.get_default_collection(),
+            LinuxCollection::Session => ss.get_collection_by_alias("session"),
+            LinuxCollection::Named(label) => {
+                return Ok(ss
+                    .get_all_collections()?
+                    .into_iter()
+                    .find(|c| c.get_label().is_ok_and(|l| l == *label)))
+            }
+        };
+        match found {
+            Ok(found) => Ok(Some(found)),
+            Err(Error::NoResult) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Cree la collection nommee si besoin, le Secret Service demandant son
+    /// mot de passe, puis la deverrouille. Renvoie la cible a passer au crate
+    /// keyring.
+    pub(crate) fn prepare(collection: &LinuxCollection) -> Result<String, KeychainError> {
+        if *collection == LinuxCollection::Default {
+            return Ok(DEFAULT_TARGET.to_string());
+        }
+        let ss = connect()?;
+        let found = match (find(&ss, collection).map_err(platform)?, collection) {
+            (Some(found), _) => found,
+            (None, LinuxCollection::Named(label)) => match ss.create_collection(label, "") {
+                Ok(created) => created,
+                Err(Error::Prompt) => return Err(locked_error(collection)),
+                Err(e) => return Err(platform(e)),
+            },
+            (None, _) => {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::BackendUnavailable,
+                    format!("collection {} introuvable", collection.describe()),
+                ))
+            }
+        };
+        unlock(collection, &found)?;
+        match found.get_label().map_err(platform)? {
+            label if label.is_empty() => Err(KeychainError::rejected(
+                KeychainErrorCode::PlatformError,
+                format!("collection {} sans libelle", collection.describe()),
+            )),
+            label => Ok(label),
+        }
+    }
+
+    fn unlock(collection: &LinuxCollection, found: &Collection) -> Result<(), KeychainError> {
+        if !found.is_locked().map_err(platform)? {
+            return Ok(());
+        }
+        match found.unlock() {
+            Ok(()) => Ok(()),
+            Err(Error::Prompt | Error::Locked) => Err(locked_error(collection)),
+            Err(e) => Err(platform(e)),
+        }
+    }
+
+    /// Demande au Secret Service de deverrouiller la collection, ce qui
+    /// affiche sa demande de mot de passe.
+    pub(crate) fn unlock_collection(collection: &LinuxCollection) -> Result<(), KeychainError> {
+        let ss = connect()?;
+        let found = find(&ss, collection).map_err(platform)?.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::BackendUnavailable,
+                format!("collection {} introuvable", collection.describe()),
+            )
+        })?;
+        unlock(collection, &found)
+    }
+
+    /// `None` si l'etat ne peut pas etre lu.
+    pub(crate) fn is_locked(collection: &LinuxCollection) -> Option<bool> {
+        let ss = connect().ok()?;
+        let found = find(&ss, collection).ok()??;
+        found.is_locked().ok()
+    }
+
+    pub(crate) fn status(collection: &LinuxCollection, target: &str) -> CollectionStatus {
+        CollectionStatus {
+            collection: collection.clone(),
+            label: target.to_string(),
+            locked: is_locked(collection),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+pub(super) use linux::{is_locked, locked_error, prepare, status, unlock_collection};
+
+impl LinuxCollection {
+    /// Libelle de repli quand la collection n'a pas pu etre preparee au
+    /// demarrage : le crate keyring la recherchera, ou la creera, a la
+    /// premiere ecriture.
+    #[cfg(target_os = "linux")]
+    pub(super) fn fallback_target(&self) -> String {
+        match self {
+            LinuxCollection::Default => "default".to_string(),
+            LinuxCollection::Session => "session".to_string(),
+            LinuxCollection::Named(label) => label.clone(),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn describe(&self) -> String {
+        match self {
+            LinuxCollection::Named(label) => format!("\"{}\"", label),
+            other => other.fallback_target(),
+        }
+    }
+
+    /// Nom vide ou trop long refuse a la lecture de la configuration.
+    pub(crate) fn is_valid(&self) -> bool {
+        match self {
+            LinuxCollection::Named(label) => {
+                !label.trim().is_empty() && label.chars().count() <= MAX_LABEL_CHARS
+            }
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,606 @@
+//! Commandes Tauri du keychain. Chaque commande lit ses arguments, puis
+//! delegue a `KeychainManager` dans `run_blocking`.
+
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use zeroize::Zeroizing;
+
+use super::auth::{AUTH_GRACE_SECS, MAX_AUTH_GRACE_SECS};
+use super::{
+    bulk, bulk::BulkStarted, run_blocking, GetOrSetResult, HistoryVersion, KeychainBatchResult,
+    KeychainError, KeychainErrorCode, KeychainItem, KeychainManager, KeychainSettings,
+    KeychainStatus, ListedKey, RenameResult, RetryPolicy, Secret, SecretMetadata, SetOptions,
+    SetReport, ARGOS_SERVICE, EXPIRY_GRACE_SECS, HUNG_WORKERS, MAX_EXPIRY_GRACE_SECS,
+    MAX_TIMEOUT_MS, MIN_TIMEOUT_MS, RETRIED_CALLS, RETRY_RECOVERED, TIMEOUT_MS,
+};
+use crate::logging::CommandSpan;
+
+/// Enveloppe historique des commandes `_v1`. Deprecie : a retirer a la
+/// prochaine version, les nouvelles commandes rejettent la promesse en cas
+/// d'erreur.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct KeychainResult {
+    success: bool,
+    value: Option<Secret>,
+    error: Option<String>,
+    error_code: Option<KeychainErrorCode>,
+    /// Renseigne par get et delete : `false` si l'entree n'existait pas, ce
+    /// qui n'est pas un echec.
+    found: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct KeychainExistsResult {
+    success: bool,
+    exists: bool,
+    error: Option<String>,
+    error_code: Option<KeychainErrorCode>,
+}
+
+/// Modifie le delai maximal des appels keychain, la tolerance d'expiration et
+/// le delai de grace des verifications de l'utilisateur, et renvoie les
+/// reglages courants avec le nombre de threads restes bloques et les
+/// compteurs de nouvelles tentatives. Allonger le delai de grace exige une
+/// verification de l'utilisateur ; le raccourcir, non.
+#[tauri::command]
+pub(crate) async fn keychain_configure(
+    manager: State<'_, KeychainManager>,
+    timeout_ms: Option<u64>,
+    expiry_grace_seconds: Option<u64>,
+    auth_grace_seconds: Option<u64>,
+) -> Result<KeychainSettings, KeychainError> {
+    if let Some(grace) = auth_grace_seconds {
+        if grace > MAX_AUTH_GRACE_SECS {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                format!(
+                    "auth_grace_seconds ne doit pas depasser {}",
+                    MAX_AUTH_GRACE_SECS
+                ),
+            ));
+        }
+        if grace > AUTH_GRACE_SECS.load(Ordering::Relaxed) {
+            manager
+                .auth
+                .verify(format!(
+                    "Allonger a {} secondes le delai sans nouvelle verification",
+                    grace
+                ))
+                .await?;
+        }
+        AUTH_GRACE_SECS.store(grace, Ordering::Relaxed);
+    }
+    if let Some(timeout_ms) = timeout_ms {
+        if !(MIN_TIMEOUT_MS..=MAX_TIMEOUT_MS).contains(&timeout_ms) {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                format!(
+                    "timeout_ms doit etre compris entre {} et {}",
+                    MIN_TIMEOUT_MS, MAX_TIMEOUT_MS
+                ),
+            ));
+        }
+        TIMEOUT_MS.store(timeout_ms, Ordering::SeqCst);
+    }
+    if let Some(grace) = expiry_grace_seconds {
+        if grace > MAX_EXPIRY_GRACE_SECS {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                format!(
+                    "expiry_grace_seconds ne doit pas depasser {}",
+                    MAX_EXPIRY_GRACE_SECS
+                ),
+            ));
+        }
+        EXPIRY_GRACE_SECS.store(grace, Ordering::Relaxed);
+    }
+    Ok(KeychainSettings {
+        timeout_ms: TIMEOUT_MS.load(Ordering::SeqCst),
+        expiry_grace_seconds: EXPIRY_GRACE_SECS.load(Ordering::Relaxed),
+        auth_grace_seconds: AUTH_GRACE_SECS.load(Ordering::Relaxed),
+        hung_workers: HUNG_WORKERS.load(Ordering::SeqCst),
+        retried_calls: RETRIED_CALLS.load(Ordering::Relaxed),
+        retry_recovered: RETRY_RECOVERED.load(Ordering::Relaxed),
+    })
+}
+
+/// Indique si les secrets sont stockes dans le keychain OS, dans le coffre
+/// portable ou dans le store de repli en memoire, et verifie que le backend
+/// repond par une ecriture, une relecture et une suppression jetables.
+#[tauri::command]
+pub(crate) async fn keychain_status(
+    manager: State<'_, KeychainManager>,
+) -> Result<KeychainStatus, KeychainError> {
+    Ok(probe_status(manager.inner().clone()).await)
+}
+
+/// Corps de `keychain_status`, partage avec la zone de notification.
+pub(crate) async fn probe_status(manager: KeychainManager) -> KeychainStatus {
+    let started = Instant::now();
+    let probe = {
+        let manager = manager.clone();
+        run_blocking(move || manager.probe_health()).await
+    };
+    let latency = started.elapsed();
+    let collection = {
+        let manager = manager.clone();
+        run_blocking(move || Ok(manager.collection_status()))
+            .await
+            .ok()
+            .flatten()
+    };
+    manager.status(probe, latency, collection)
+}
+
+/// Deverrouille le keychain apres un `BackendLocked` : Secret Service
+/// `Unlock` sur la collection sous Linux, `SecKeychainUnlock` sous macOS.
+/// `Unsupported` sous Windows et pour les backends d'ARGOS. Sans delai
+/// maximal : la demande attend l'utilisateur.
+#[tauri::command]
+pub(crate) async fn keychain_unlock(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+) -> Result<(), KeychainError> {
+    let audit = manager.audit(&window, "unlock_backend", ARGOS_SERVICE, None);
+    let manager = manager.inner().clone();
+    audit.finish(bulk::blocking(move || manager.unlock_backend()).await)
+}
+
+/// Stocke un secret dans le keychain OS (Windows Credential Manager,
+/// macOS Keychain, Linux Secret Service). Avec `options.if_matches`,
+/// l'ecriture echoue avec `Conflict` si un autre acteur a change la valeur
+/// entre-temps, par exemple un jeton deja renouvele.
+#[tauri::command]
+pub(crate) async fn keychain_set(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    value: Secret,
+    options: Option<SetOptions>,
+    retry: Option<RetryPolicy>,
+) -> Result<SetReport, KeychainError> {
+    let audit = manager.audit(&window, "set", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let options = options.unwrap_or_default();
+    let retry = retry.unwrap_or_default();
+    audit.finish(run_blocking(move || manager.set(&service, &key, &value, options, retry)).await)
+}
+
+/// Recupere un secret depuis le keychain OS. Renvoie `None` si l'entree
+/// n'existe pas. Un secret marque `require_auth` n'est renvoye qu'apres
+/// verification de l'utilisateur par le systeme. Au-dela du debit autorise,
+/// la commande echoue avec `RateLimited` et `retry_after_ms`. Avec `cache`,
+/// la valeur est servie par le cache memoire tant qu'elle n'a pas change.
+#[tauri::command]
+pub(crate) async fn keychain_get(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    cache: Option<bool>,
+    retry: Option<RetryPolicy>,
+) -> Result<Option<Secret>, KeychainError> {
+    let audit = manager.audit(&window, "get", &service, Some(&key));
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        auth.release(
+            run_blocking(move || manager.get(&service, &key, cache.unwrap_or(false), retry)).await,
+        )
+        .await,
+    )
+}
+
+/// Lit un secret et, s'il n'existe pas, stocke `default_value`. Deux
+/// fenetres qui appellent en meme temps obtiennent la meme valeur, une seule
+/// la cree.
+#[tauri::command]
+pub(crate) async fn keychain_get_or_set(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    default_value: Secret,
+    retry: Option<RetryPolicy>,
+) -> Result<GetOrSetResult, KeychainError> {
+    let audit = manager.audit(&window, "get_or_set", &service, Some(&key));
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        auth.release(
+            run_blocking(move || manager.get_or_set(&service, &key, default_value, retry)).await,
+        )
+        .await,
+    )
+}
+
+/// Renvoie les metadonnees d'un secret (jamais la valeur), ou `None` si
+/// l'entree n'existe pas.
+#[tauri::command]
+pub(crate) async fn keychain_get_metadata(
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    retry: Option<RetryPolicy>,
+) -> Result<Option<SecretMetadata>, KeychainError> {
+    let span = CommandSpan::new("get_metadata", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let result = run_blocking(move || manager.get_metadata(&service, &key, retry)).await;
+    span.finish(&result);
+    result
+}
+
+/// Supprime un secret du keychain OS, historique compris. Un secret avec
+/// historique demande la confirmation de l'utilisateur. Renvoie `false` si
+/// l'entree n'existait deja plus : la suppression est idempotente.
+#[tauri::command]
+pub(crate) async fn keychain_delete(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    retry: Option<RetryPolicy>,
+) -> Result<bool, KeychainError> {
+    let audit = manager.audit(&window, "delete", &service, Some(&key));
+    let retry = retry.unwrap_or_default();
+    if let Err(e) = manager
+        .confirm_delete(Some(&window), &service, &key, retry)
+        .await
+    {
+        return audit.finish(Err(e));
+    }
+    let manager = manager.inner().clone();
+    audit.finish(run_blocking(move || manager.delete(&service, &key, retry)).await)
+}
+
+/// Liste les versions precedentes d'un secret, la plus recente en tete.
+#[tauri::command]
+pub(crate) async fn keychain_history(
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    retry: Option<RetryPolicy>,
+) -> Result<Vec<HistoryVersion>, KeychainError> {
+    let span = CommandSpan::new("history", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let result = run_blocking(move || manager.history(&service, &key, retry)).await;
+    span.finish(&result);
+    result
+}
+
+/// Restaure une version de l'historique. La valeur courante prend sa place
+/// dans l'historique ; une version inconnue ne modifie rien.
+#[tauri::command]
+pub(crate) async fn keychain_rollback(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    version_id: u64,
+    retry: Option<RetryPolicy>,
+) -> Result<(), KeychainError> {
+    let audit = manager.audit(&window, "rollback", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(run_blocking(move || manager.rollback(&service, &key, version_id, retry)).await)
+}
+
+/// Deplace un secret vers une nouvelle cle sans le faire transiter par le
+/// webview : lecture, ecriture, relecture de controle, puis suppression de
+/// l'ancienne entree.
+#[tauri::command]
+pub(crate) async fn keychain_rename(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    old_key: String,
+    new_key: String,
+    overwrite: bool,
+    retry: Option<RetryPolicy>,
+) -> Result<RenameResult, KeychainError> {
+    let audit = manager
+        .audit(&window, "rename", &service, Some(&old_key))
+        .detail(new_key.as_str());
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        run_blocking(move || manager.rename(&service, &old_key, &new_key, overwrite, retry)).await,
+    )
+}
+
+/// Indique si un secret existe sans jamais le renvoyer au webview.
+/// Une entree absente donne `false`, toute autre erreur (keychain
+/// verrouille, backend indisponible) est remontee comme un echec.
+#[tauri::command]
+pub(crate) async fn keychain_exists(
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    retry: Option<RetryPolicy>,
+) -> Result<bool, KeychainError> {
+    let span = CommandSpan::new("exists", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let result = run_blocking(move || manager.exists(&service, &key, retry)).await;
+    span.finish(&result);
+    result
+}
+
+/// Nom exact de l'entree dans le gestionnaire d'identifiants Windows, pour
+/// qu'un administrateur la retrouve. `Unsupported` sur les autres systemes.
+#[tauri::command]
+pub(crate) async fn keychain_target_name(
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+) -> Result<String, KeychainError> {
+    let manager = manager.inner().clone();
+    run_blocking(move || manager.target_name(&service, &key)).await
+}
+
+/// Stocke un secret binaire transmis en base64. Les octets decodes sont
+/// ecrits tels quels via `set_secret`, sans passer par une chaine UTF-8.
+#[tauri::command]
+pub(crate) async fn keychain_set_bytes(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    value_base64: String,
+    retry: Option<RetryPolicy>,
+) -> Result<SetReport, KeychainError> {
+    let audit = manager.audit(&window, "set_bytes", &service, Some(&key));
+    let manager = manager.inner().clone();
+    let value_base64 = Zeroizing::new(value_base64);
+    let retry = retry.unwrap_or_default();
+    audit
+        .finish(run_blocking(move || manager.set_bytes(&service, &key, &value_base64, retry)).await)
+}
+
+/// Recupere un secret binaire, encode en base64. Renvoie `None` si l'entree
+/// n'existe pas.
+#[tauri::command]
+pub(crate) async fn keychain_get_bytes(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    retry: Option<RetryPolicy>,
+) -> Result<Option<Secret>, KeychainError> {
+    let audit = manager.audit(&window, "get_bytes", &service, Some(&key));
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(run_blocking(move || manager.get_bytes(&service, &key, retry)).await)
+}
+
+/// Lit plusieurs secrets d'un service en un seul appel IPC. Chaque cle
+/// compte comme une lecture pour la limitation de debit.
+#[tauri::command]
+pub(crate) async fn keychain_get_many(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    keys: Vec<String>,
+    retry: Option<RetryPolicy>,
+) -> Result<Vec<KeychainBatchResult>, KeychainError> {
+    let audit = manager.audit(&window, "get_many", &service, None);
+    if let Err(e) = manager
+        .reads
+        .acquire(&service, keys.iter().map(String::as_str))
+    {
+        return audit.finish_batch(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish_batch(
+        auth.release(run_blocking(move || manager.get_many(&service, keys, retry)).await)
+            .await,
+    )
+}
+
+/// Ecrit plusieurs secrets d'un service. Chaque element est tente ; ceux qui
+/// reussissent restent ecrits meme si d'autres echouent.
+#[tauri::command]
+pub(crate) async fn keychain_set_many(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    items: Vec<KeychainItem>,
+    retry: Option<RetryPolicy>,
+) -> Result<Vec<KeychainBatchResult>, KeychainError> {
+    let audit = manager.audit(&window, "set_many", &service, None);
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish_batch(run_blocking(move || manager.set_many(&service, items, retry)).await)
+}
+
+/// Supprime toutes les entrees connues d'un service ARGOS, apres la
+/// confirmation de l'utilisateur. Renvoie alors l'identifiant de
+/// l'operation ; le bilan arrive sur `argos://bulk-progress`.
+#[tauri::command]
+pub(crate) async fn keychain_clear_service(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    retry: Option<RetryPolicy>,
+) -> Result<BulkStarted, KeychainError> {
+    let audit = manager.audit(&window, "clear_service", &service, None);
+    let question = format!("Supprimer toutes les entrees du service {} ?", service);
+    if let Err(e) = manager.confirm(Some(&window), question).await {
+        return audit.finish(Err(e));
+    }
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let progress = manager.start_bulk("clear_service");
+    let tracked = progress.clone();
+    Ok(bulk::spawn(progress, async move {
+        audit.finish(bulk::blocking(move || manager.clear_service(&service, retry, &tracked)).await)
+    }))
+}
+
+/// Deplace toutes les entrees de `old_service` vers `new_service`, en tache
+/// de fond comme `keychain_clear_service`.
+#[tauri::command]
+pub(crate) async fn keychain_migrate_service(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    old_service: String,
+    new_service: String,
+    delete_old: bool,
+    legacy_keys: Option<Vec<String>>,
+    retry: Option<RetryPolicy>,
+) -> Result<BulkStarted, KeychainError> {
+    let audit = manager
+        .audit(&window, "migrate_service", &old_service, None)
+        .detail(new_service.as_str());
+    let manager = manager.inner().clone();
+    let legacy_keys = legacy_keys.unwrap_or_default();
+    let retry = retry.unwrap_or_default();
+    let progress = manager.start_bulk("migrate_service");
+    let tracked = progress.clone();
+    Ok(bulk::spawn(progress, async move {
+        audit.finish(
+            bulk::blocking(move || {
+                manager.migrate_service(
+                    &old_service,
+                    &new_service,
+                    delete_old,
+                    &legacy_keys,
+                    retry,
+                    &tracked,
+                )
+            })
+            .await,
+        )
+    }))
+}
+
+/// Liste les cles connues d'un service (jamais les valeurs), en signalant
+/// celles dont le TTL est depasse.
+#[tauri::command]
+pub(crate) async fn keychain_list(
+    manager: State<'_, KeychainManager>,
+    service: String,
+) -> Result<Vec<ListedKey>, KeychainError> {
+    let span = CommandSpan::new("list", &service, None);
+    let manager = manager.inner().clone();
+    let result = run_blocking(move || manager.list(&service)).await;
+    span.finish(&result);
+    result
+}
+
+impl KeychainResult {
+    fn from_result(result: Result<Option<Secret>, KeychainError>) -> Self {
+        match result {
+            Ok(value) => KeychainResult {
+                success: true,
+                value,
+                error: None,
+                error_code: None,
+                found: None,
+            },
+            Err(e) => KeychainResult {
+                success: false,
+                value: None,
+                error: Some(e.to_string()),
+                error_code: Some(e.code()),
+                found: None,
+            },
+        }
+    }
+
+    fn with_found(mut self, found: bool) -> Self {
+        if self.success {
+            self.found = Some(found);
+        }
+        self
+    }
+}
+
+// Les alias `_v1` ne renvoient jamais `Err` : le `Result` n'est la que parce
+// qu'une commande async qui emprunte un `State` doit en renvoyer un. Sur le
+// fil, `Ok(x)` est serialise comme `x`, l'enveloppe historique est intacte.
+
+/// Deprecie : utiliser `keychain_set`.
+#[tauri::command]
+pub(crate) async fn keychain_set_v1(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    value: Secret,
+) -> Result<KeychainResult, KeychainError> {
+    Ok(KeychainResult::from_result(
+        keychain_set(window, manager, service, key, value, None, None)
+            .await
+            .map(|_| None),
+    ))
+}
+
+/// Deprecie : utiliser `keychain_get`. Une entree absente donne
+/// `success: true, found: false`.
+#[tauri::command]
+pub(crate) async fn keychain_get_v1(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+) -> Result<KeychainResult, KeychainError> {
+    let result = keychain_get(window, manager, service, key, None, None).await;
+    let found = matches!(result, Ok(Some(_)));
+    Ok(KeychainResult::from_result(result).with_found(found))
+}
+
+/// Deprecie : utiliser `keychain_delete`.
+#[tauri::command]
+pub(crate) async fn keychain_delete_v1(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+) -> Result<KeychainResult, KeychainError> {
+    let result = keychain_delete(window, manager, service, key, None).await;
+    let found = matches!(result, Ok(true));
+    Ok(KeychainResult::from_result(result.map(|_| None)).with_found(found))
+}
+
+/// Deprecie : utiliser `keychain_exists`.
+#[tauri::command]
+pub(crate) async fn keychain_exists_v1(
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+) -> Result<KeychainExistsResult, KeychainError> {
+    Ok(match keychain_exists(manager, service, key, None).await {
+        Ok(exists) => KeychainExistsResult {
+            success: true,
+            exists,
+            error: None,
+            error_code: None,
+        },
+        Err(e) => KeychainExistsResult {
+            success: false,
+            exists: false,
+            error: Some(e.to_string()),
+            error_code: Some(e.code()),
+        },
+    })
+}
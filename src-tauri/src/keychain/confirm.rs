@@ -0,0 +1,98 @@
+//! Confirmation native des operations destructrices, demandee par la
+//! commande elle-meme : un webview defaillant ou compromis ne peut pas
+//! effacer d'entrees sans l'accord de l'utilisateur. Le reglage
+//! `confirm_destructive` d'`argos.json`, hors de portee du webview, la
+//! desactive.
+
+use tauri::Window;
+
+use super::{KeychainError, KeychainErrorCode};
+
+enum Mode {
+    Off,
+    Dialog,
+    /// Reponse fixee par `ARGOS_MOCK_CONFIRM` avec le store de test, pour
+    /// que la CI n'attende pas une boite de dialogue.
+    #[cfg(any(test, feature = "mock-store"))]
+    Preset(bool),
+}
+
+pub(crate) struct Confirmation {
+    mode: Mode,
+    /// Confirme aussi la suppression des secrets sans historique.
+    every_delete: bool,
+    /// Une seule boite a la fois : les demandes suivantes attendent.
+    prompt: tokio::sync::Mutex<()>,
+}
+
+impl Confirmation {
+    /// Avec `mock`, les confirmations sont acceptees sauf si
+    /// `ARGOS_MOCK_CONFIRM` vaut `cancel`.
+    pub(crate) fn new(enabled: bool, every_delete: bool, mock: bool) -> Self {
+        let mode = match (enabled, mock) {
+            (false, _) => Mode::Off,
+            #[cfg(any(test, feature = "mock-store"))]
+            (true, true) => {
+                return Self::preset(
+                    std::env::var("ARGOS_MOCK_CONFIRM").map_or(true, |answer| answer != "cancel"),
+                    every_delete,
+                )
+            }
+            _ => Mode::Dialog,
+        };
+        Confirmation {
+            mode,
+            every_delete,
+            prompt: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Repond `answer` a toutes les demandes, sans boite de dialogue.
+    #[cfg(any(test, feature = "mock-store"))]
+    pub(crate) fn preset(answer: bool, every_delete: bool) -> Self {
+        Confirmation {
+            mode: Mode::Preset(answer),
+            every_delete,
+            prompt: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        !matches!(self.mode, Mode::Off)
+    }
+
+    pub(crate) fn every_delete(&self) -> bool {
+        self.every_delete
+    }
+
+    /// Pose `question` dans une boite OK / Annuler rattachee a `window`.
+    /// La boite tourne sur la boucle d'evenements : la tache ne fait
+    /// qu'attendre sa reponse. Un refus donne `CancelledByUser`.
+    pub(crate) async fn ask(
+        &self,
+        window: Option<&Window>,
+        question: String,
+    ) -> Result<(), KeychainError> {
+        let accepted = match self.mode {
+            Mode::Off => return Ok(()),
+            #[cfg(any(test, feature = "mock-store"))]
+            Mode::Preset(answer) => answer,
+            Mode::Dialog => {
+                let _prompt = self.prompt.lock().await;
+                let (tx, rx) = tokio::sync::oneshot::channel();
+                tauri::api::dialog::confirm(window, "ARGOS", &question, move |ok| {
+                    let _ = tx.send(ok);
+                });
+                rx.await.unwrap_or(false)
+            }
+        };
+        tracing::info!(accepted, "confirmation d'une operation destructrice");
+        if accepted {
+            return Ok(());
+        }
+        Err(KeychainError::rejected(
+            KeychainErrorCode::CancelledByUser,
+            "operation refusee par l'utilisateur",
+        ))
+    }
+}
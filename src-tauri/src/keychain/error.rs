@@ -0,0 +1,319 @@
+//! Erreurs des commandes keychain et leur traduction en codes stables.
+
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+
+/// Codes d'erreur stables exposes au frontend. Le frontend ne doit brancher
+/// que sur ce code, `error` reste un message lisible pour les logs.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KeychainErrorCode {
+    NotFound,
+    AccessDenied,
+    BackendUnavailable,
+    Ambiguous,
+    BadEncoding,
+    InvalidArgument,
+    InvalidBase64,
+    Timeout,
+    AlreadyExists,
+    /// `if_matches` ne correspond plus a la valeur stockee.
+    Conflict,
+    /// Nom de cle qui, normalise, designe une entree d'orthographe
+    /// differente.
+    NameCollision,
+    VerificationFailed,
+    Corrupt,
+    VersionNotFound,
+    WeakPassphrase,
+    IoError,
+    InvalidBackup,
+    UnsupportedVersion,
+    BadMac,
+    ServiceNotAllowed,
+    /// Commande ou service hors des droits de la fenetre appelante.
+    PermissionDenied,
+    /// Requete reseau refusee d'avance : le reseau est coupe.
+    Offline,
+    /// Programme absent de `allowed_programs`.
+    ProgramNotAllowed,
+    /// Lien refuse par `open_external` : schema, identifiants ou hote.
+    UrlNotAllowed,
+    /// Valeur trop longue pour tenir dans un QR code.
+    QrCapacityExceeded,
+    Expired,
+    VaultLocked,
+    VaultNotFound,
+    VaultCorrupt,
+    WrongPassphrase,
+    EmptyKey,
+    InvalidKeyCharacters,
+    KeyTooLong,
+    AuthCancelled,
+    AuthUnavailable,
+    AuthFailed,
+    RateLimited,
+    InvalidBase32,
+    /// Secret qui n'est pas un objet JSON, pour `keychain_get_field`.
+    NotJson,
+    /// JSON Pointer qui ne mene a aucun champ.
+    PointerNotFound,
+    AuthenticationFailed,
+    /// Cle de `keychain_set_encryption_key` : seuls `keychain_encrypt` et
+    /// `keychain_decrypt` s'en servent, aucune commande ne la renvoie.
+    EncryptionKeyProtected,
+    Unsupported,
+    SessionLocked,
+    ShortcutUnavailable,
+    UpdateFailed,
+    UpdateSignatureInvalid,
+    /// Entree de demarrage refusee par les droits ou le bac a sable.
+    AutostartDenied,
+    AutostartFailed,
+    /// Keychain verrouille (collection Secret Service, trousseau macOS) et
+    /// deverrouillage refuse ou impossible sans l'utilisateur : a proposer
+    /// via `keychain_unlock`. Anciennement `CollectionLocked`.
+    #[serde(alias = "CollectionLocked")]
+    BackendLocked,
+    /// Operation en masse interrompue par `bulk_cancel`.
+    OperationCancelled,
+    /// Operation destructrice refusee dans la boite de confirmation.
+    CancelledByUser,
+    PlatformError,
+}
+
+/// Erreur des commandes keychain. Serialisee en `{ code, message, detail,
+/// backend, kind, attempts, retry_after_ms }` pour que `invoke()` rejette avec
+/// un objet exploitable : `message` est traduit, `detail` reste brut.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum KeychainError {
+    /// Erreur remontee par le crate keyring, apres `attempts` tentatives.
+    #[error("{detail}")]
+    Keyring {
+        code: KeychainErrorCode,
+        kind: &'static str,
+        detail: String,
+        attempts: u32,
+    },
+    /// Erreur detectee par ARGOS lui-meme (requete refusee, secret expire...).
+    #[error("{detail}")]
+    Rejected {
+        code: KeychainErrorCode,
+        detail: String,
+    },
+    /// Lecture refusee par le limiteur de debit, a retenter apres
+    /// `retry_after_ms`.
+    #[error("trop de lectures, reessayer dans {retry_after_ms} ms")]
+    RateLimited { retry_after_ms: u64 },
+}
+
+impl KeychainError {
+    pub(crate) fn code(&self) -> KeychainErrorCode {
+        match self {
+            KeychainError::Keyring { code, .. } | KeychainError::Rejected { code, .. } => *code,
+            KeychainError::RateLimited { .. } => KeychainErrorCode::RateLimited,
+        }
+    }
+
+    fn kind(&self) -> Option<&'static str> {
+        match self {
+            KeychainError::Keyring { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    fn attempts(&self) -> Option<u32> {
+        match self {
+            KeychainError::Keyring { attempts, .. } => Some(*attempts),
+            _ => None,
+        }
+    }
+
+    fn retry_after_ms(&self) -> Option<u64> {
+        match self {
+            KeychainError::RateLimited { retry_after_ms } => Some(*retry_after_ms),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn with_attempts(mut self, made: u32) -> Self {
+        if let KeychainError::Keyring { attempts, .. } = &mut self {
+            *attempts = made;
+        }
+        self
+    }
+
+    pub(crate) fn rejected(code: KeychainErrorCode, detail: impl Into<String>) -> Self {
+        KeychainError::Rejected {
+            code,
+            detail: detail.into(),
+        }
+    }
+
+    pub(crate) fn rate_limited(retry_after_ms: u64) -> Self {
+        KeychainError::RateLimited { retry_after_ms }
+    }
+
+    pub(crate) fn from_keyring(e: &keyring::Error) -> Self {
+        KeychainError::Keyring {
+            code: error_code(e),
+            kind: keyring_error_kind(e),
+            detail: format!("{}", e),
+            attempts: 1,
+        }
+    }
+
+    pub(crate) fn from_entry_creation(e: &keyring::Error) -> Self {
+        KeychainError::Keyring {
+            code: entry_error_code(e),
+            kind: keyring_error_kind(e),
+            detail: format!("{}", e),
+            attempts: 1,
+        }
+    }
+}
+
+impl Serialize for KeychainError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let detail = self.to_string();
+        let mut s = serializer.serialize_struct("KeychainError", 7)?;
+        s.serialize_field("code", &self.code())?;
+        s.serialize_field("message", &super::messages::localized(self.code(), &detail))?;
+        s.serialize_field("detail", &detail)?;
+        s.serialize_field("backend", backend_name())?;
+        s.serialize_field("kind", &self.kind())?;
+        s.serialize_field("attempts", &self.attempts())?;
+        s.serialize_field("retry_after_ms", &self.retry_after_ms())?;
+        s.end()
+    }
+}
+
+/// Nom du backend keychain utilise, repris dans les erreurs.
+fn backend_name() -> &'static str {
+    super::store::active().name()
+}
+
+/// Nom du variant `keyring::Error`, conserve pour les diagnostics.
+fn keyring_error_kind(e: &keyring::Error) -> &'static str {
+    match e {
+        keyring::Error::PlatformFailure(_) => "PlatformFailure",
+        keyring::Error::NoStorageAccess(_) => "NoStorageAccess",
+        keyring::Error::NoEntry => "NoEntry",
+        keyring::Error::BadEncoding(_) => "BadEncoding",
+        keyring::Error::TooLong(_, _) => "TooLong",
+        keyring::Error::Invalid(_, _) => "Invalid",
+        keyring::Error::Ambiguous(_) => "Ambiguous",
+        _ => "Unknown",
+    }
+}
+
+/// Traduit une erreur keyring en code stable. Toutes les commandes passent
+/// par cette fonction pour que les codes restent coherents.
+fn error_code(e: &keyring::Error) -> KeychainErrorCode {
+    match e {
+        keyring::Error::NoEntry => KeychainErrorCode::NotFound,
+        keyring::Error::NoStorageAccess(_) => KeychainErrorCode::AccessDenied,
+        keyring::Error::Ambiguous(_) => KeychainErrorCode::Ambiguous,
+        keyring::Error::BadEncoding(_) => KeychainErrorCode::BadEncoding,
+        keyring::Error::TooLong(_, _) | keyring::Error::Invalid(_, _) => {
+            KeychainErrorCode::InvalidArgument
+        }
+        _ => KeychainErrorCode::PlatformError,
+    }
+}
+
+/// Variante de `error_code` pour les echecs de `Entry::new` : une erreur
+/// plateforme a ce stade signifie que le backend lui-meme est injoignable.
+fn entry_error_code(e: &keyring::Error) -> KeychainErrorCode {
+    match e {
+        keyring::Error::PlatformFailure(_) => KeychainErrorCode::BackendUnavailable,
+        _ => error_code(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn platform() -> Box<dyn std::error::Error + Send + Sync> {
+        "service dbus injoignable".into()
+    }
+
+    #[test]
+    fn keyring_errors_map_to_stable_codes() {
+        let cases = [
+            (
+                keyring::Error::NoEntry,
+                KeychainErrorCode::NotFound,
+                "NoEntry",
+            ),
+            (
+                keyring::Error::Ambiguous(Vec::new()),
+                KeychainErrorCode::Ambiguous,
+                "Ambiguous",
+            ),
+            (
+                keyring::Error::NoStorageAccess(platform()),
+                KeychainErrorCode::AccessDenied,
+                "NoStorageAccess",
+            ),
+            (
+                keyring::Error::BadEncoding(vec![0xff]),
+                KeychainErrorCode::BadEncoding,
+                "BadEncoding",
+            ),
+            (
+                keyring::Error::TooLong("service".to_string(), 255),
+                KeychainErrorCode::InvalidArgument,
+                "TooLong",
+            ),
+            (
+                keyring::Error::Invalid("user".to_string(), "vide".to_string()),
+                KeychainErrorCode::InvalidArgument,
+                "Invalid",
+            ),
+            (
+                keyring::Error::PlatformFailure(platform()),
+                KeychainErrorCode::PlatformError,
+                "PlatformFailure",
+            ),
+        ];
+        for (e, code, kind) in cases {
+            let error = KeychainError::from_keyring(&e);
+            assert_eq!(error.code(), code, "{}", kind);
+            assert_eq!(error.kind(), Some(kind));
+            assert_eq!(error.attempts(), Some(1));
+            assert_eq!(error.to_string(), e.to_string());
+        }
+    }
+
+    #[test]
+    fn entry_creation_failures_mean_an_unavailable_backend() {
+        let unavailable =
+            KeychainError::from_entry_creation(&keyring::Error::PlatformFailure(platform()));
+        assert_eq!(unavailable.code(), KeychainErrorCode::BackendUnavailable);
+        let missing = KeychainError::from_entry_creation(&keyring::Error::NoEntry);
+        assert_eq!(missing.code(), KeychainErrorCode::NotFound);
+    }
+
+    #[test]
+    fn errors_serialize_with_their_code_and_context() {
+        let error =
+            KeychainError::from_keyring(&keyring::Error::Ambiguous(Vec::new())).with_attempts(3);
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], "Ambiguous");
+        assert_eq!(json["kind"], "Ambiguous");
+        assert_eq!(json["attempts"], 3);
+        assert!(json["retry_after_ms"].is_null());
+        assert!(json["message"].is_string());
+        assert!(json["backend"].is_string());
+
+        let json = serde_json::to_value(KeychainError::rate_limited(250)).unwrap();
+        assert_eq!(json["code"], "RateLimited");
+        assert_eq!(json["retry_after_ms"], 250);
+        assert!(json["kind"].is_null());
+        // Seules les erreurs keyring comptent leurs tentatives.
+        let rejected =
+            KeychainError::rejected(KeychainErrorCode::NotFound, "absent").with_attempts(3);
+        assert_eq!(rejected.attempts(), None);
+    }
+}
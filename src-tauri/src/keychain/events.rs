@@ -0,0 +1,87 @@
+//! Evenement `argos://secret-changed`, emis vers toutes les fenetres quand un
+//! secret est modifie, pour qu'elles rafraichissent leur copie. La valeur du
+//! secret n'y figure jamais.
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+pub(crate) const SECRET_CHANGED: &str = "argos://secret-changed";
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SecretOperation {
+    Set,
+    Delete,
+    Rename,
+    Rollback,
+    Import,
+    Migrate,
+}
+
+#[derive(Serialize, Clone)]
+struct SecretChanged<'a> {
+    service: &'a str,
+    key: &'a str,
+    operation: SecretOperation,
+    /// Ancienne cle d'un secret renomme.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_key: Option<&'a str>,
+}
+
+/// Poignee d'emission gardee dans l'etat partage, utilisable depuis une
+/// tache de fond sans fenetre appelante. Sans application Tauri, rien n'est
+/// emis.
+#[derive(Clone, Default)]
+pub(crate) struct SecretEvents(Option<AppHandle>);
+
+impl SecretEvents {
+    pub(crate) fn new(app: AppHandle) -> Self {
+        SecretEvents(Some(app))
+    }
+
+    /// Application Tauri, pour les autres evenements d'ARGOS.
+    pub(crate) fn app(&self) -> Option<&AppHandle> {
+        self.0.as_ref()
+    }
+
+    pub(crate) fn emit(&self, service: &str, key: &str, operation: SecretOperation) {
+        self.send(service, key, operation, None);
+    }
+
+    pub(crate) fn emit_all<'a>(
+        &self,
+        service: &str,
+        keys: impl IntoIterator<Item = &'a str>,
+        operation: SecretOperation,
+    ) {
+        for key in keys {
+            self.emit(service, key, operation);
+        }
+    }
+
+    pub(crate) fn emit_rename(&self, service: &str, old_key: &str, new_key: &str) {
+        self.send(service, new_key, SecretOperation::Rename, Some(old_key));
+    }
+
+    // Un echec d'emission ne remet pas en cause l'ecriture : il est ignore.
+    fn send(
+        &self,
+        service: &str,
+        key: &str,
+        operation: SecretOperation,
+        previous_key: Option<&str>,
+    ) {
+        let Some(app) = &self.0 else {
+            return;
+        };
+        let _ = app.emit_all(
+            SECRET_CHANGED,
+            SecretChanged {
+                service,
+                key,
+                operation,
+                previous_key,
+            },
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Avertissement avant l'expiration des secrets a TTL. Une tache de fond
+//! parcourt l'index des services du profil actif et ne lit que la date
+//! d'expiration et le libelle des enveloppes, jamais la valeur. Chaque
+//! echeance n'est signalee qu'une fois : `expiry-notified.json` garde la
+//! date deja annoncee pour chaque cle.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::profiles::read_services;
+use super::{read_index, read_raw, run_blocking, KeychainManager, RetryPolicy, ENVELOPE_VERSION};
+use crate::config::{write_atomic, ConfigStore, ExpiryConfig};
+use crate::notifications::{self, Notice, NoticeKind, Target};
+use crate::status::StatusManager;
+
+/// Emis pour chaque secret qui entre dans la fenetre d'avertissement.
+pub(crate) const SECRET_EXPIRING: &str = "argos://secret-expiring";
+
+const NOTIFIED_FILE: &str = "expiry-notified.json";
+/// Premiere analyse apres le demarrage, puis verification de l'echeance et
+/// du verrouillage.
+const STARTUP_DELAY: Duration = Duration::from_secs(60);
+const TICK: Duration = Duration::from_secs(60);
+/// Secrets nommes dans une notification ; les autres sont comptes.
+const MAX_LISTED: usize = 5;
+
+/// Champs lus dans une enveloppe : `value` est ignore par serde sans etre
+/// conserve.
+#[derive(Deserialize)]
+struct ExpiryFields {
+    v: u32,
+    label: Option<String>,
+    expires_at: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct ExpiringSecret {
+    service: String,
+    key: String,
+    label: Option<String>,
+    expires_at: String,
+    remaining_secs: i64,
+}
+
+/// Date d'expiration deja annoncee, par service puis par cle.
+type Notified = BTreeMap<String, BTreeMap<String, String>>;
+
+impl KeychainManager {
+    /// Secrets des services du profil actif qui expirent dans moins de
+    /// `within`, sans ceux deja expires. Une entree illisible est ignoree.
+    fn expiring(&self, within: chrono::Duration) -> Vec<ExpiringSecret> {
+        let now = chrono::Utc::now();
+        let profile = self.profiles.active();
+        let services = read_services(&self.store, profile.as_deref()).unwrap_or_default();
+        let mut expiring = Vec::new();
+        for service in services {
+            let keys = {
+                let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+                read_index(&self.store, &service).unwrap_or_default()
+            };
+            for key in keys {
+                let Ok(Some(raw)) = read_raw(&self.store, RetryPolicy::default(), &service, &key)
+                else {
+                    continue;
+                };
+                let Ok(fields) = serde_json::from_str::<ExpiryFields>(&raw) else {
+                    continue;
+                };
+                let Some(expires_at) = fields.expires_at.filter(|_| fields.v == ENVELOPE_VERSION)
+                else {
+                    continue;
+                };
+                let Ok(at) = chrono::DateTime::parse_from_rfc3339(&expires_at) else {
+                    continue;
+                };
+                let remaining = at.signed_duration_since(now);
+                if remaining > chrono::Duration::zero() && remaining <= within {
+                    expiring.push(ExpiringSecret {
+                        service: service.clone(),
+                        key,
+                        label: fields.label,
+                        expires_at,
+                        remaining_secs: remaining.num_seconds(),
+                    });
+                }
+            }
+        }
+        expiring
+    }
+}
+
+fn load_notified(path: Option<&PathBuf>) -> Notified {
+    path.and_then(|path| std::fs::read(path).ok())
+        .and_then(|data| serde_json::from_slice(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_notified(path: Option<&PathBuf>, notified: &Notified) {
+    let Some(path) = path else {
+        return;
+    };
+    let written = serde_json::to_vec(notified)
+        .map_err(std::io::Error::other)
+        .and_then(|data| write_atomic(path, &data));
+    if let Err(e) = written {
+        tracing::warn!(error = %e, "suivi des expirations non enregistre");
+    }
+}
+
+/// "45 min", "5 h" ou "3 j".
+fn remaining_text(secs: i64) -> String {
+    match secs {
+        s if s < 3600 => format!("{} min", (s / 60).max(1)),
+        s if s < 48 * 3600 => format!("{} h", s / 3600),
+        s => format!("{} j", s / 86400),
+    }
+}
+
+fn notify(app: &AppHandle, fresh: &[ExpiringSecret]) {
+    let mut lines: Vec<String> = fresh
+        .iter()
+        .take(MAX_LISTED)
+        .map(|secret| {
+            format!(
+                "{} ({}) : dans {}",
+                secret.label.as_deref().unwrap_or(&secret.key),
+                secret.service,
+                remaining_text(secret.remaining_secs)
+            )
+        })
+        .collect();
+    if fresh.len() > MAX_LISTED {
+        lines.push(format!("et {} autres", fresh.len() - MAX_LISTED));
+    }
+    let single = (fresh.len() == 1).then(|| &fresh[0]);
+    notifications::send(
+        app,
+        Notice {
+            title: "Secrets bientot expires".to_string(),
+            body: lines.join("\n"),
+            kind: NoticeKind::Warning,
+            target: Some(Target {
+                page: "secrets",
+                service: single.map(|secret| secret.service.clone()),
+                key: single.map(|secret| secret.key.clone()),
+            }),
+        },
+    );
+}
+
+/// Analyse les secrets et annonce ceux qui n'ont pas encore ete signales
+/// pour leur date d'expiration actuelle. Les cles sorties de la fenetre
+/// sont oubliees, pour qu'un TTL renouvele soit de nouveau signale.
+async fn scan(
+    app: &AppHandle,
+    manager: KeychainManager,
+    config: ExpiryConfig,
+    path: Option<&PathBuf>,
+) {
+    let within = chrono::Duration::hours(config.warn_hours as i64);
+    let Ok(expiring) = run_blocking(move || Ok(manager.expiring(within))).await else {
+        return;
+    };
+    if let Some(status) = app.try_state::<StatusManager>() {
+        status.set_warnings(app, expiring.len());
+    }
+    let previous = load_notified(path);
+    let mut notified = Notified::new();
+    let mut fresh = Vec::new();
+    for secret in expiring {
+        let known = previous
+            .get(&secret.service)
+            .and_then(|keys| keys.get(&secret.key))
+            .is_some_and(|at| *at == secret.expires_at);
+        notified
+            .entry(secret.service.clone())
+            .or_default()
+            .insert(secret.key.clone(), secret.expires_at.clone());
+        if !known {
+            fresh.push(secret);
+        }
+    }
+    for secret in &fresh {
+        let _ = app.emit_all(SECRET_EXPIRING, secret);
+    }
+    if config.notify && !fresh.is_empty() {
+        notify(app, &fresh);
+    }
+    if notified != previous {
+        save_notified(path, &notified);
+    }
+}
+
+/// Analyse les secrets toutes les `expiry.scan_minutes` pendant toute la
+/// vie de l'application. Session verrouillee, l'analyse attend : elle est
+/// faite a la verification qui suit le deverrouillage.
+pub(crate) fn spawn_scanner(app: &AppHandle) {
+    let app = app.clone();
+    let path = app
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(NOTIFIED_FILE));
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(STARTUP_DELAY).await;
+        let mut next = Instant::now();
+        loop {
+            if let (Some(manager), Some(store)) = (
+                app.try_state::<KeychainManager>(),
+                app.try_state::<ConfigStore>(),
+            ) {
+                let config = store.get().expiry.clone();
+                if Instant::now() >= next && !manager.is_session_locked() {
+                    scan(&app, manager.inner().clone(), config.clone(), path.as_ref()).await;
+                    next = Instant::now() + Duration::from_secs(config.scan_minutes * 60);
+                }
+            }
+            tokio::time::sleep(TICK).await;
+        }
+    });
+}
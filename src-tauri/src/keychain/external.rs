@@ -0,0 +1,764 @@
+//! Import des identifiants exportes par un gestionnaire de mots de passe :
+//! CSV quelconque avec correspondance des colonnes, CSV de 1Password et
+//! JSON non chiffre de Bitwarden. Le fichier est lu cote Rust, chaque ligne
+//! est validee puis ecrite comme par `keychain_set` (enveloppe, index,
+//! historique, evenements). Une ligne invalide est rapportee sans arreter
+//! l'import.
+//!
+//! Les valeurs lues ne passent que par des tampons effaces a leur
+//! liberation : le contenu du fichier, les champs CSV et les `Secret`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use zeroize::Zeroizing;
+
+use super::backup::{io_error, ConflictPolicy, ImportStatus};
+use super::bulk::{self, BulkPhase, BulkStarted, Progress};
+use super::wipe::overwrite_and_remove;
+use super::{
+    read_index, KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy, Secret, SetOptions,
+};
+
+/// Taille maximale du fichier lu, tres au-dela d'un export reel.
+const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExternalFormat {
+    /// CSV avec une ligne d'en-tete, lu d'apres `mapping`.
+    Csv,
+    /// Export CSV de 1Password : `Title`, `Username`, `Password`...
+    #[serde(rename = "1password")]
+    OnePassword,
+    /// Export JSON non chiffre de Bitwarden.
+    Bitwarden,
+}
+
+/// Colonnes d'un CSV quelconque, d'apres les noms de sa ligne d'en-tete
+/// (sans tenir compte de la casse).
+#[derive(Deserialize, Clone)]
+pub(crate) struct ColumnMapping {
+    /// Colonne du nom de la cle.
+    name: String,
+    value: String,
+    label: Option<String>,
+}
+
+fn invalid(detail: impl Into<String>) -> KeychainError {
+    KeychainError::rejected(KeychainErrorCode::InvalidArgument, detail)
+}
+
+/// Entree lue dans le fichier, avant validation du nom.
+struct ExternalEntry {
+    key: String,
+    value: Secret,
+    label: Option<String>,
+}
+
+/// Ligne du CSV ou rang de l'element Bitwarden, et son contenu ou la raison
+/// de son rejet.
+struct ExternalRow {
+    row: usize,
+    entry: Result<ExternalEntry, KeychainError>,
+}
+
+/// Enregistrement CSV et la ligne ou il commence.
+struct CsvRecord {
+    line: usize,
+    fields: Vec<Zeroizing<String>>,
+    error: Option<&'static str>,
+}
+
+/// Champ qui commence a `start`.
+struct CsvField {
+    value: Zeroizing<String>,
+    /// Position du separateur ou de la fin de ligne qui suit le champ.
+    end: usize,
+    newlines: usize,
+    error: Option<&'static str>,
+}
+
+fn is_separator(byte: u8) -> bool {
+    matches!(byte, b',' | b'\n' | b'\r')
+}
+
+/// Les tampons sont alloues a leur taille finale : aucune copie de la
+/// valeur n'est laissee par une reallocation.
+fn read_field(text: &str, start: usize) -> CsvField {
+    let bytes = text.as_bytes();
+    let next_separator = |from: usize| {
+        bytes[from..]
+            .iter()
+            .position(|&b| is_separator(b))
+            .map_or(bytes.len(), |i| from + i)
+    };
+    if bytes.get(start) != Some(&b'"') {
+        let end = next_separator(start);
+        return CsvField {
+            value: Zeroizing::new(text[start..end].to_string()),
+            end,
+            newlines: 0,
+            error: None,
+        };
+    }
+    // Guillemet fermant : le premier qui n'est pas double.
+    let mut close = None;
+    let mut i = start + 1;
+    while i < bytes.len() {
+        if bytes[i] == b'"' {
+            if bytes.get(i + 1) == Some(&b'"') {
+                i += 2;
+                continue;
+            }
+            close = Some(i);
+            break;
+        }
+        i += 1;
+    }
+    let raw = &text[start + 1..close.unwrap_or(bytes.len())];
+    let mut value = Zeroizing::new(String::with_capacity(raw.len()));
+    for (n, part) in raw.split("\"\"").enumerate() {
+        if n > 0 {
+            value.push('"');
+        }
+        value.push_str(part);
+    }
+    let newlines = raw.matches('\n').count();
+    let Some(close) = close else {
+        return CsvField {
+            value,
+            end: bytes.len(),
+            newlines,
+            error: Some("guillemet non ferme"),
+        };
+    };
+    let end = next_separator(close + 1);
+    CsvField {
+        value,
+        end,
+        newlines,
+        error: (end != close + 1).then_some("texte apres un guillemet fermant"),
+    }
+}
+
+/// CSV RFC 4180 : virgules, guillemets doubles, fins de ligne CRLF ou LF.
+/// Les lignes vides sont ignorees.
+fn parse_csv(text: &str) -> Vec<CsvRecord> {
+    let bytes = text.as_bytes();
+    let mut records = Vec::new();
+    let (mut pos, mut line) = (0, 1);
+    while pos < bytes.len() {
+        if matches!(bytes[pos], b'\n' | b'\r') {
+            pos += if bytes[pos..].starts_with(b"\r\n") {
+                2
+            } else {
+                1
+            };
+            line += 1;
+            continue;
+        }
+        let mut record = CsvRecord {
+            line,
+            fields: Vec::new(),
+            error: None,
+        };
+        loop {
+            let field = read_field(text, pos);
+            record.fields.push(field.value);
+            record.error = record.error.or(field.error);
+            line += field.newlines;
+            pos = field.end;
+            if bytes.get(pos) != Some(&b',') {
+                break;
+            }
+            pos += 1;
+        }
+        if bytes[pos..].starts_with(b"\r\n") {
+            pos += 2;
+        } else if pos < bytes.len() {
+            pos += 1;
+        }
+        line += 1;
+        records.push(record);
+    }
+    records
+}
+
+/// Rang des colonnes du nom, de la valeur et du libelle dans l'en-tete.
+fn columns(
+    header: &CsvRecord,
+    mapping: &ColumnMapping,
+) -> Result<(usize, usize, Option<usize>), KeychainError> {
+    let find = |name: &str| {
+        header
+            .fields
+            .iter()
+            .position(|field| field.trim().eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| invalid(format!("colonne {} absente de l'en-tete", name)))
+    };
+    Ok((
+        find(&mapping.name)?,
+        find(&mapping.value)?,
+        mapping.label.as_deref().map(find).transpose()?,
+    ))
+}
+
+fn csv_rows(text: &str, mapping: &ColumnMapping) -> Result<Vec<ExternalRow>, KeychainError> {
+    let mut records = parse_csv(text).into_iter();
+    let header = records.next().ok_or_else(|| invalid("fichier CSV vide"))?;
+    let (name, value, label) = columns(&header, mapping)?;
+    Ok(records
+        .map(|record| {
+            let entry = match record.error {
+                Some(error) => Err(invalid(error)),
+                None => match (record.fields.get(name), record.fields.get(value)) {
+                    (Some(key), Some(value)) if !value.is_empty() => Ok(ExternalEntry {
+                        key: key.trim().to_string(),
+                        value: Secret::new(value.to_string()),
+                        label: label
+                            .and_then(|label| record.fields.get(label))
+                            .map(|label| label.trim().to_string())
+                            .filter(|label| !label.is_empty()),
+                    }),
+                    (Some(_), Some(_)) => Err(invalid("valeur vide")),
+                    _ => Err(invalid("colonnes manquantes")),
+                },
+            };
+            ExternalRow {
+                row: record.line,
+                entry,
+            }
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct BitwardenExport {
+    #[serde(default)]
+    encrypted: bool,
+    #[serde(default)]
+    items: Vec<BitwardenItem>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenItem {
+    name: Option<String>,
+    notes: Option<Secret>,
+    login: Option<BitwardenLogin>,
+}
+
+#[derive(Deserialize)]
+struct BitwardenLogin {
+    username: Option<String>,
+    password: Option<Secret>,
+}
+
+/// Le mot de passe d'un identifiant, a defaut les notes : une note
+/// securisee garde souvent un jeton d'API.
+fn bitwarden_rows(data: &[u8]) -> Result<Vec<ExternalRow>, KeychainError> {
+    let export: BitwardenExport = serde_json::from_slice(data)
+        .map_err(|e| invalid(format!("export Bitwarden illisible : {}", e)))?;
+    if export.encrypted {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::Unsupported,
+            "export Bitwarden chiffre : exporter au format JSON non chiffre",
+        ));
+    }
+    Ok(export
+        .items
+        .into_iter()
+        .enumerate()
+        .map(|(n, item)| {
+            let (username, password) = match item.login {
+                Some(login) => (login.username, login.password),
+                None => (None, None),
+            };
+            let value = password
+                .filter(|password| !password.is_empty())
+                .or(item.notes.filter(|notes| !notes.is_empty()));
+            let entry = match (item.name, value) {
+                (Some(key), Some(value)) => Ok(ExternalEntry {
+                    key: key.trim().to_string(),
+                    value,
+                    label: username.filter(|username| !username.is_empty()),
+                }),
+                (None, _) => Err(invalid("element sans nom")),
+                (_, None) => Err(invalid("ni mot de passe ni notes")),
+            };
+            ExternalRow { row: n + 1, entry }
+        })
+        .collect())
+}
+
+fn parse(
+    data: &[u8],
+    format: ExternalFormat,
+    mapping: Option<ColumnMapping>,
+) -> Result<Vec<ExternalRow>, KeychainError> {
+    if format == ExternalFormat::Bitwarden {
+        return bitwarden_rows(data);
+    }
+    let text = std::str::from_utf8(data).map_err(|_| {
+        KeychainError::rejected(KeychainErrorCode::BadEncoding, "fichier CSV hors UTF-8")
+    })?;
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let mapping = match (format, mapping) {
+        (ExternalFormat::Csv, Some(mapping)) => mapping,
+        (ExternalFormat::Csv, None) => return Err(invalid("mapping requis pour un CSV")),
+        _ => ColumnMapping {
+            name: "title".to_string(),
+            value: "password".to_string(),
+            label: Some("username".to_string()),
+        },
+    };
+    csv_rows(text, &mapping)
+}
+
+#[derive(Serialize)]
+struct ExternalOutcome {
+    row: usize,
+    key: Option<String>,
+    status: ImportStatus,
+    error: Option<KeychainError>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct ExternalImportReport {
+    imported: usize,
+    skipped: usize,
+    failed: usize,
+    results: Vec<ExternalOutcome>,
+    /// Premiere cle non importee si `bulk_cancel` a interrompu l'import.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped_at: Option<String>,
+    /// Fichier source efface apres l'import, avec `delete_source`.
+    source_deleted: bool,
+}
+
+impl ExternalImportReport {
+    fn push(&mut self, row: usize, key: Option<String>, result: Result<bool, KeychainError>) {
+        let (status, error) = match result {
+            Ok(true) => {
+                self.imported += 1;
+                (ImportStatus::Imported, None)
+            }
+            Ok(false) => {
+                self.skipped += 1;
+                (ImportStatus::Skipped, None)
+            }
+            Err(e) => {
+                self.failed += 1;
+                (ImportStatus::Failed, Some(e))
+            }
+        };
+        self.results.push(ExternalOutcome {
+            row,
+            key,
+            status,
+            error,
+        });
+    }
+}
+
+impl KeychainManager {
+    /// Valide les noms, applique `policy` aux cles deja presentes et aux
+    /// doublons du fichier, puis ecrit chaque entree. Avec `Fail`, rien
+    /// n'est ecrit si une seule cle est en conflit.
+    fn import_external(
+        &self,
+        service: &str,
+        rows: Vec<ExternalRow>,
+        policy: ConflictPolicy,
+        retry: RetryPolicy,
+        progress: &Progress,
+    ) -> Result<ExternalImportReport, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let existing: HashSet<String> = {
+            let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+            read_index(&self.store, service)?.into_iter().collect()
+        };
+        let mut report = ExternalImportReport {
+            imported: 0,
+            skipped: 0,
+            failed: 0,
+            results: Vec::with_capacity(rows.len()),
+            stopped_at: None,
+            source_deleted: false,
+        };
+        let total = rows.len();
+        let mut planned = Vec::with_capacity(total);
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let mut conflicts = Vec::new();
+        for (done, row) in rows.into_iter().enumerate() {
+            if !progress.step(BulkPhase::Checking, done, total, None) {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::OperationCancelled,
+                    "import annule : aucune cle ecrite",
+                ));
+            }
+            let named = row
+                .entry
+                .and_then(|entry| Ok((self.claim_key(service, &entry.key, retry)?, entry)));
+            let (name, entry) = match named {
+                Ok(named) => named,
+                Err(e) => {
+                    report.push(row.row, None, Err(e));
+                    continue;
+                }
+            };
+            let duplicate = seen.insert(name.clone(), row.row).is_some();
+            if duplicate || existing.contains(&name) {
+                conflicts.push(name.clone());
+            }
+            let skip = policy == ConflictPolicy::Skip && (duplicate || existing.contains(&name));
+            planned.push((row.row, name, entry, skip));
+        }
+        if policy == ConflictPolicy::Fail && !conflicts.is_empty() {
+            conflicts.sort();
+            conflicts.dedup();
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::AlreadyExists,
+                format!("cles deja presentes : {}", conflicts.join(", ")),
+            ));
+        }
+        let total = planned.len();
+        for (done, (row, name, entry, skip)) in planned.into_iter().enumerate() {
+            if !progress.step(BulkPhase::Writing, done, total, Some(&name)) {
+                report.stopped_at = Some(name);
+                break;
+            }
+            let written = if skip {
+                Ok(false)
+            } else {
+                let options = SetOptions {
+                    label: entry.label,
+                    ..SetOptions::default()
+                };
+                self.set(service, &name, &entry.value, options, retry)
+                    .map(|_| true)
+            };
+            report.push(row, Some(name), written);
+        }
+        Ok(report)
+    }
+}
+
+/// Importe les identifiants d'un export de gestionnaire de mots de passe
+/// dans `service`. `mapping` est requis pour `csv`, ignore sinon. Avec
+/// `delete_source`, le fichier est ecrase puis supprime une fois l'import
+/// termine sans echec ; `source_deleted` dit s'il l'a ete. En tache de fond
+/// comme `keychain_import`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn keychain_import_external(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    path: String,
+    format: ExternalFormat,
+    mapping: Option<ColumnMapping>,
+    conflict_policy: ConflictPolicy,
+    delete_source: Option<bool>,
+    retry: Option<RetryPolicy>,
+) -> Result<BulkStarted, KeychainError> {
+    let audit = manager
+        .audit(&window, "import_external", &service, None)
+        .detail(path.as_str());
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let progress = manager.start_bulk("import_external");
+    let tracked = progress.clone();
+    Ok(bulk::spawn(progress, async move {
+        audit.finish(
+            bulk::blocking(move || {
+                if !tracked.step(BulkPhase::Opening, 0, 0, None) {
+                    return Err(KeychainError::rejected(
+                        KeychainErrorCode::OperationCancelled,
+                        "import annule : aucune cle ecrite",
+                    ));
+                }
+                let size = std::fs::metadata(&path)
+                    .map_err(|e| io_error(&path, e))?
+                    .len();
+                if size > MAX_FILE_BYTES {
+                    return Err(invalid(format!(
+                        "fichier de plus de {} Mio",
+                        MAX_FILE_BYTES / (1024 * 1024)
+                    )));
+                }
+                let rows = {
+                    let data =
+                        Zeroizing::new(std::fs::read(&path).map_err(|e| io_error(&path, e))?);
+                    parse(&data, format, mapping)?
+                };
+                let mut report =
+                    manager.import_external(&service, rows, conflict_policy, retry, &tracked)?;
+                if delete_source.unwrap_or(false)
+                    && report.failed == 0
+                    && report.stopped_at.is_none()
+                {
+                    match overwrite_and_remove(Path::new(&path)) {
+                        Ok(()) => report.source_deleted = true,
+                        Err(e) => tracing::warn!(error = %e, "fichier source non efface"),
+                    }
+                }
+                Ok(report)
+            })
+            .await,
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bulk::Progress;
+    use super::super::testing::{get, memory_manager, set, NO_RETRY, SERVICE};
+    use super::*;
+
+    /// Export CSV de 1Password 8, BOM compris.
+    const ONE_PASSWORD: &str = "\u{feff}Title,Website,Username,Password,OTPAuth,Favorite,Archived,Tags,Notes\r\n\
+        AWS prod,https://console.aws.amazon.com,ops@exemple.fr,\"AKIA,\"\"42\"\"\",,false,false,cloud,\r\n\
+        GitHub,https://github.com,,ghp_abc,,true,false,,\"jeton\r\nsur deux lignes\"\r\n\
+        \r\n\
+        Vide,https://exemple.fr,moi,,,false,false,,\r\n\
+        Cassee,https://exemple.fr,moi,\"ouvert,,,\r\n";
+
+    /// Export JSON non chiffre de Bitwarden, reduit aux champs lus.
+    const BITWARDEN: &str = r#"{
+        "encrypted": false,
+        "folders": [],
+        "items": [
+            {"type": 1, "name": "Stripe", "notes": null,
+             "login": {"username": "billing", "password": "sk_live_1", "uris": []}},
+            {"type": 2, "name": "cle API", "notes": "tok\"en", "secureNote": {"type": 0}},
+            {"type": 1, "name": "Sans valeur", "login": {"username": "x", "password": ""}},
+            {"type": 1, "login": {"password": "orphelin"}}
+        ]
+    }"#;
+
+    fn entries(rows: &[ExternalRow]) -> Vec<(usize, &str, &str, Option<&str>)> {
+        rows.iter()
+            .filter_map(|row| {
+                let entry = row.entry.as_ref().ok()?;
+                Some((
+                    row.row,
+                    entry.key.as_str(),
+                    entry.value.as_str(),
+                    entry.label.as_deref(),
+                ))
+            })
+            .collect()
+    }
+
+    fn errors(rows: &[ExternalRow]) -> Vec<(usize, KeychainErrorCode)> {
+        rows.iter()
+            .filter_map(|row| Some((row.row, row.entry.as_ref().err()?.code())))
+            .collect()
+    }
+
+    fn mapping(name: &str, value: &str, label: Option<&str>) -> ColumnMapping {
+        ColumnMapping {
+            name: name.to_string(),
+            value: value.to_string(),
+            label: label.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn csv_quoting_follows_rfc_4180() {
+        let records = parse_csv("a,\"b,c\",\"d \"\"e\"\"\"\n\n\"f\ng\",,h\r\n\"i\"j,k\n\"l");
+        let fields: Vec<Vec<&str>> = records
+            .iter()
+            .map(|record| record.fields.iter().map(|field| field.as_str()).collect())
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                vec!["a", "b,c", "d \"e\""],
+                vec!["f\ng", "", "h"],
+                vec!["i", "k"],
+                vec!["l"],
+            ]
+        );
+        let lines: Vec<usize> = records.iter().map(|record| record.line).collect();
+        assert_eq!(lines, [1, 3, 5, 6]);
+        let errors: Vec<_> = records.iter().map(|record| record.error).collect();
+        assert_eq!(
+            errors,
+            [
+                None,
+                None,
+                Some("texte apres un guillemet fermant"),
+                Some("guillemet non ferme")
+            ]
+        );
+    }
+
+    #[test]
+    fn one_password_export_is_parsed() {
+        let rows = parse(ONE_PASSWORD.as_bytes(), ExternalFormat::OnePassword, None).unwrap();
+        assert_eq!(
+            entries(&rows),
+            [
+                (2, "AWS prod", "AKIA,\"42\"", Some("ops@exemple.fr")),
+                (3, "GitHub", "ghp_abc", None),
+            ]
+        );
+        assert_eq!(
+            errors(&rows),
+            [
+                (6, KeychainErrorCode::InvalidArgument),
+                (7, KeychainErrorCode::InvalidArgument)
+            ]
+        );
+    }
+
+    #[test]
+    fn generic_csv_uses_the_mapping() {
+        let csv = "Nom,Secret,Note\n token ,abc, prod \nautre,def,\ncourte\n";
+        let rows = parse(
+            csv.as_bytes(),
+            ExternalFormat::Csv,
+            Some(mapping("nom", " SECRET ", Some("note"))),
+        )
+        .unwrap();
+        assert_eq!(
+            entries(&rows),
+            [(2, "token", "abc", Some("prod")), (3, "autre", "def", None)]
+        );
+        assert_eq!(errors(&rows), [(4, KeychainErrorCode::InvalidArgument)]);
+
+        let code = |data: &[u8], mapping: Option<ColumnMapping>| {
+            parse(data, ExternalFormat::Csv, mapping)
+                .err()
+                .map(|e| e.code())
+        };
+        let some = || Some(mapping("nom", "valeur", None));
+        assert_eq!(
+            code(csv.as_bytes(), some()),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+        assert_eq!(
+            code(csv.as_bytes(), None),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+        assert_eq!(code(b"", some()), Some(KeychainErrorCode::InvalidArgument));
+        assert_eq!(
+            code(b"nom,valeur\n\xff,x\n", some()),
+            Some(KeychainErrorCode::BadEncoding)
+        );
+    }
+
+    #[test]
+    fn bitwarden_export_is_parsed() {
+        let rows = parse(BITWARDEN.as_bytes(), ExternalFormat::Bitwarden, None).unwrap();
+        assert_eq!(
+            entries(&rows),
+            [
+                (1, "Stripe", "sk_live_1", Some("billing")),
+                (2, "cle API", "tok\"en", None),
+            ]
+        );
+        assert_eq!(
+            errors(&rows),
+            [
+                (3, KeychainErrorCode::InvalidArgument),
+                (4, KeychainErrorCode::InvalidArgument)
+            ]
+        );
+        let encrypted = parse(
+            br#"{"encrypted": true, "items": []}"#,
+            ExternalFormat::Bitwarden,
+            None,
+        );
+        assert_eq!(
+            encrypted.err().map(|e| e.code()),
+            Some(KeychainErrorCode::Unsupported)
+        );
+        let broken = parse(b"{\"items\": [", ExternalFormat::Bitwarden, None);
+        assert_eq!(
+            broken.err().map(|e| e.code()),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+    }
+
+    fn import(
+        manager: &KeychainManager,
+        csv: &str,
+        policy: ConflictPolicy,
+    ) -> Result<ExternalImportReport, KeychainError> {
+        let rows = parse(
+            csv.as_bytes(),
+            ExternalFormat::Csv,
+            Some(mapping("name", "value", Some("label"))),
+        )?;
+        manager.import_external(SERVICE, rows, policy, NO_RETRY, &Progress::none())
+    }
+
+    const DUPLICATES: &str =
+        "name,value,label\nexistant,nouveau,\nneuf,1,premier\nneuf,2,\nCle\u{301},x,\n";
+
+    #[test]
+    fn import_writes_rows_like_keychain_set() {
+        let manager = memory_manager();
+        set(&manager, "existant", "ancien");
+        let report = import(&manager, DUPLICATES, ConflictPolicy::Skip).unwrap();
+        assert_eq!((report.imported, report.skipped, report.failed), (2, 2, 0));
+        let keys: Vec<_> = report.results.iter().map(|r| r.key.as_deref()).collect();
+        assert_eq!(
+            keys,
+            [Some("existant"), Some("neuf"), Some("neuf"), Some("Clé")]
+        );
+        assert_eq!(get(&manager, "existant").as_deref(), Some("ancien"));
+        assert_eq!(get(&manager, "neuf").as_deref(), Some("1"));
+        let metadata = manager.get_metadata(SERVICE, "neuf", NO_RETRY).unwrap();
+        assert_eq!(metadata.and_then(|m| m.label).as_deref(), Some("premier"));
+        assert_eq!(
+            read_index(&manager.store, SERVICE).unwrap(),
+            ["Clé", "existant", "neuf"]
+        );
+    }
+
+    #[test]
+    fn conflict_policy_decides_duplicates() {
+        let manager = memory_manager();
+        set(&manager, "existant", "ancien");
+        let report = import(&manager, DUPLICATES, ConflictPolicy::Overwrite).unwrap();
+        assert_eq!((report.imported, report.skipped), (4, 0));
+        assert_eq!(get(&manager, "existant").as_deref(), Some("nouveau"));
+        // La derniere ligne d'une cle en double l'emporte.
+        assert_eq!(get(&manager, "neuf").as_deref(), Some("2"));
+
+        let manager = memory_manager();
+        set(&manager, "existant", "ancien");
+        let refused = import(&manager, DUPLICATES, ConflictPolicy::Fail);
+        assert_eq!(
+            refused.err().map(|e| e.code()),
+            Some(KeychainErrorCode::AlreadyExists)
+        );
+        assert_eq!(read_index(&manager.store, SERVICE).unwrap(), ["existant"]);
+    }
+
+    #[test]
+    fn invalid_names_are_reported_per_row() {
+        let manager = memory_manager();
+        let report = import(
+            &manager,
+            "name,value,label\n,vide,\nbon,1,\n\"a\u{7}b\",2,\n",
+            ConflictPolicy::Skip,
+        )
+        .unwrap();
+        assert_eq!((report.imported, report.failed), (1, 2));
+        let failed: Vec<_> = report
+            .results
+            .iter()
+            .filter(|r| r.error.is_some())
+            .map(|r| r.row)
+            .collect();
+        assert_eq!(failed, [2, 4]);
+        assert_eq!(get(&manager, "bon").as_deref(), Some("1"));
+    }
+}
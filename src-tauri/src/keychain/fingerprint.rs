@@ -0,0 +1,416 @@
+//! Empreinte d'un secret calculee cote Rust : le frontend peut verifier
+//! qu'une valeur locale correspond a celle d'un serveur, afficher "se
+//! termine par ...a9f2" ou reperer les cles qui gardent la meme valeur,
+//! sans que la valeur n'atteigne le webview.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{State, Window};
+use zeroize::Zeroizing;
+
+use super::auth::Gated;
+use super::bulk::{self, BulkPhase, BulkStarted, Progress};
+use super::{
+    auth_reason, encryption_key_protected, expired, read_index, read_raw_secret, run_blocking,
+    KeychainBatchResult, KeychainError, KeychainErrorCode, KeychainManager, RawSecret, RetryPolicy,
+    SecretMetadata, Stored,
+};
+
+/// Caracteres de fin renvoyes avec l'empreinte.
+const TAIL_CHARS: usize = 4;
+/// En dessous, la fin du secret en revelerait une trop grande part : elle
+/// n'est pas renvoyee.
+const MIN_CHARS_FOR_TAIL: usize = 12;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum FingerprintAlgo {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl FingerprintAlgo {
+    fn digest(self, data: &[u8]) -> String {
+        match self {
+            FingerprintAlgo::Sha256 => hex(&Sha256::digest(data)),
+            FingerprintAlgo::Blake3 => hex(blake3::hash(data).as_bytes()),
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Empreinte et fin du secret, ou seulement le resultat de la comparaison
+/// quand `compare_to` est fourni.
+#[derive(Serialize)]
+#[serde(untagged)]
+pub(crate) enum Fingerprint {
+    Digest {
+        algo: FingerprintAlgo,
+        digest: String,
+        /// Quatre derniers caracteres d'un secret texte, ou quatre derniers
+        /// chiffres hexadecimaux d'un secret binaire.
+        tail: Option<String>,
+    },
+    Comparison {
+        matches: bool,
+    },
+}
+
+/// Fin de `value`, si elle est assez longue pour en reveler peu.
+fn tail(value: &[u8], text: bool) -> Option<String> {
+    if text {
+        let value = std::str::from_utf8(value).ok()?;
+        let count = value.chars().count();
+        (count >= MIN_CHARS_FOR_TAIL).then(|| value.chars().skip(count - TAIL_CHARS).collect())
+    } else {
+        (value.len() >= MIN_CHARS_FOR_TAIL).then(|| hex(&value[value.len() - TAIL_CHARS / 2..]))
+    }
+}
+
+/// Cle d'un groupe de doublons, avec ses metadonnees.
+#[derive(Serialize)]
+pub(crate) struct DuplicateKey {
+    key: String,
+    #[serde(flatten)]
+    metadata: SecretMetadata,
+}
+
+/// Bilan de `keychain_find_duplicates` : les groupes d'au moins deux cles
+/// de meme valeur, sans empreinte ni valeur.
+#[derive(Serialize)]
+pub(crate) struct DuplicateReport {
+    groups: Vec<Vec<DuplicateKey>>,
+    scanned: usize,
+    failed: Vec<KeychainBatchResult>,
+    /// Premiere cle non lue si `bulk_cancel` a interrompu l'analyse.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped_at: Option<String>,
+}
+
+/// Octets de la valeur et metadonnees d'une entree, expiree ou non : une
+/// copie expiree reste une copie du meme identifiant.
+fn contents(secret: RawSecret) -> (Zeroizing<Vec<u8>>, SecretMetadata) {
+    match secret {
+        RawSecret::Bytes(bytes) => (bytes, SecretMetadata::legacy()),
+        RawSecret::Text(raw) => {
+            let stored = Stored::parse(raw);
+            let metadata = SecretMetadata::of(&stored);
+            let value = stored.into_value();
+            (Zeroizing::new(value.as_bytes().to_vec()), metadata)
+        }
+    }
+}
+
+impl KeychainManager {
+    /// Empreinte des octets du secret : la valeur d'une enveloppe pour un
+    /// secret texte, les octets stockes pour un secret binaire.
+    pub(crate) fn fingerprint(
+        &self,
+        service: &str,
+        key: &str,
+        algo: FingerprintAlgo,
+        compare_to: Option<&str>,
+        retry: RetryPolicy,
+    ) -> Result<Gated<Fingerprint>, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let secret = {
+            let _guard = self.locks.lock(service, [key]);
+            read_raw_secret(&self.store, retry, service, key)?
+        };
+        let (bytes, text, reason) = match secret {
+            None => {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::NotFound,
+                    format!("aucun secret {} dans {}", key, service),
+                ))
+            }
+            Some(RawSecret::Bytes(bytes)) => (bytes, false, None),
+            Some(RawSecret::Text(raw)) => {
+                let stored = Stored::parse(raw);
+                if stored.is_encryption_key() {
+                    return Err(encryption_key_protected(key));
+                }
+                if stored.is_expired() {
+                    return Err(expired(key));
+                }
+                let reason = stored.requires_auth().then(|| auth_reason(key));
+                let value = stored.into_value();
+                (Zeroizing::new(value.as_bytes().to_vec()), true, reason)
+            }
+        };
+        let digest = algo.digest(&bytes);
+        let fingerprint = match compare_to {
+            Some(expected) => Fingerprint::Comparison {
+                matches: digest.eq_ignore_ascii_case(expected.trim()),
+            },
+            None => Fingerprint::Digest {
+                algo,
+                digest,
+                tail: tail(&bytes, text),
+            },
+        };
+        Ok(Gated::new(fingerprint, reason))
+    }
+
+    /// Lit chaque cle de l'index de `service` et regroupe celles dont la
+    /// valeur a la meme empreinte SHA-256. Comme les autres balayages
+    /// internes, la lecture echappe a la limitation de debit.
+    pub(crate) fn find_duplicates(
+        &self,
+        service: &str,
+        retry: RetryPolicy,
+        progress: &Progress,
+    ) -> Result<DuplicateReport, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let keys = {
+            let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+            read_index(&self.store, service)?
+        };
+        let total = keys.len();
+        let mut report = DuplicateReport {
+            groups: Vec::new(),
+            scanned: 0,
+            failed: Vec::new(),
+            stopped_at: None,
+        };
+        let mut by_digest: HashMap<[u8; 32], Vec<DuplicateKey>> = HashMap::new();
+        for (done, key) in keys.into_iter().enumerate() {
+            if !progress.step(BulkPhase::Reading, done, total, Some(&key)) {
+                report.stopped_at = Some(key);
+                break;
+            }
+            let secret = {
+                let _guard = self.locks.lock(service, [key.as_str()]);
+                read_raw_secret(&self.store, retry, service, &key)
+            };
+            match secret {
+                Ok(Some(secret)) => {
+                    let (bytes, metadata) = contents(secret);
+                    let digest: [u8; 32] = Sha256::digest(&bytes[..]).into();
+                    by_digest
+                        .entry(digest)
+                        .or_default()
+                        .push(DuplicateKey { key, metadata });
+                    report.scanned += 1;
+                }
+                // Supprimee depuis la lecture de l'index.
+                Ok(None) => {}
+                Err(e) => report.failed.push(KeychainBatchResult {
+                    key,
+                    value: None,
+                    error: Some(e),
+                }),
+            }
+        }
+        report.groups = by_digest
+            .into_values()
+            .filter(|group| group.len() > 1)
+            .collect();
+        report.groups.sort_by(|a, b| a[0].key.cmp(&b[0].key));
+        Ok(report)
+    }
+}
+
+/// Empreinte SHA-256 (par defaut) ou BLAKE3 d'un secret, en hexadecimal,
+/// avec ses derniers caracteres. Avec `compare_to`, seul le resultat de la
+/// comparaison a cette empreinte est renvoye. Une entree absente donne
+/// `NotFound`.
+#[tauri::command]
+pub(crate) async fn keychain_fingerprint(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    algo: Option<FingerprintAlgo>,
+    compare_to: Option<String>,
+    retry: Option<RetryPolicy>,
+) -> Result<Fingerprint, KeychainError> {
+    let audit = manager.audit(&window, "fingerprint", &service, Some(&key));
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let manager = manager.inner().clone();
+    let algo = algo.unwrap_or_default();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        auth.release(
+            run_blocking(move || {
+                manager.fingerprint(&service, &key, algo, compare_to.as_deref(), retry)
+            })
+            .await,
+        )
+        .await,
+    )
+}
+
+/// Cherche les cles de `service` qui gardent la meme valeur. Renvoie
+/// aussitot l'identifiant de l'operation ; les groupes arrivent sur
+/// `argos://bulk-progress`. Les doublons se suppriment ensuite cle par cle
+/// avec `keychain_delete`.
+#[tauri::command]
+pub(crate) async fn keychain_find_duplicates(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    retry: Option<RetryPolicy>,
+) -> Result<BulkStarted, KeychainError> {
+    let audit = manager.audit(&window, "find_duplicates", &service, None);
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let progress = manager.start_bulk("find_duplicates");
+    let tracked = progress.clone();
+    Ok(bulk::spawn(progress, async move {
+        audit.finish(
+            bulk::blocking(move || manager.find_duplicates(&service, retry, &tracked)).await,
+        )
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{memory_manager, set, NO_RETRY, SERVICE};
+    use super::*;
+
+    fn fingerprint(
+        manager: &KeychainManager,
+        key: &str,
+        algo: FingerprintAlgo,
+        compare_to: Option<&str>,
+    ) -> Fingerprint {
+        manager
+            .fingerprint(SERVICE, key, algo, compare_to, NO_RETRY)
+            .unwrap()
+            .into_unverified()
+    }
+
+    fn digest(fingerprint: Fingerprint) -> (String, Option<String>) {
+        match fingerprint {
+            Fingerprint::Digest { digest, tail, .. } => (digest, tail),
+            Fingerprint::Comparison { .. } => panic!("empreinte attendue"),
+        }
+    }
+
+    fn matches(fingerprint: Fingerprint) -> bool {
+        match fingerprint {
+            Fingerprint::Comparison { matches } => matches,
+            Fingerprint::Digest { .. } => panic!("comparaison attendue"),
+        }
+    }
+
+    #[test]
+    fn digests_match_known_answers() {
+        let vectors = [
+            (
+                FingerprintAlgo::Sha256,
+                "",
+                "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+            ),
+            (
+                FingerprintAlgo::Sha256,
+                "abc",
+                "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            ),
+            (
+                FingerprintAlgo::Blake3,
+                "",
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            ),
+            (
+                FingerprintAlgo::Blake3,
+                "abc",
+                "6437b3ac38465133ffb63b75273a8db548c558465d79db03fd359c6cd5bd9d85",
+            ),
+        ];
+        for (algo, input, expected) in vectors {
+            assert_eq!(algo.digest(input.as_bytes()), expected, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn tail_keeps_the_last_four_characters_of_long_secrets() {
+        assert_eq!(
+            tail(b"correct horse battery", true).as_deref(),
+            Some("tery")
+        );
+        assert_eq!(
+            tail("mot de passe é€∑☃".as_bytes(), true).as_deref(),
+            Some("é€∑☃")
+        );
+        assert_eq!(tail(b"eleven char", true), None);
+        let bytes: Vec<u8> = (0xf0..=0xff).collect();
+        assert_eq!(tail(&bytes, false).as_deref(), Some("feff"));
+        assert_eq!(tail(&bytes[..11], false), None);
+    }
+
+    #[test]
+    fn fingerprint_hashes_the_stored_value() {
+        let manager = memory_manager();
+        set(&manager, "token", "correct horse battery");
+        let expected = "9028ea0d15decaa35b2da21c0290af3b1a5ba0a30a591906f89b5074e209ea72";
+        let (digest_hex, tail) = digest(fingerprint(
+            &manager,
+            "token",
+            FingerprintAlgo::Sha256,
+            None,
+        ));
+        assert_eq!(digest_hex, expected);
+        assert_eq!(tail.as_deref(), Some("tery"));
+        let (digest_hex, _) = digest(fingerprint(
+            &manager,
+            "token",
+            FingerprintAlgo::Blake3,
+            None,
+        ));
+        assert_eq!(
+            digest_hex,
+            FingerprintAlgo::Blake3.digest(b"correct horse battery")
+        );
+
+        let upper = format!(" {} ", expected.to_uppercase());
+        assert!(matches(fingerprint(
+            &manager,
+            "token",
+            FingerprintAlgo::Sha256,
+            Some(&upper)
+        )));
+        assert!(!matches(fingerprint(
+            &manager,
+            "token",
+            FingerprintAlgo::Blake3,
+            Some(expected)
+        )));
+
+        manager
+            .set_bytes(SERVICE, "binary", "8PHy8/T19vf4+fr7/P3+/w==", NO_RETRY)
+            .unwrap();
+        let (digest_hex, tail) = digest(fingerprint(
+            &manager,
+            "binary",
+            FingerprintAlgo::Sha256,
+            None,
+        ));
+        assert_eq!(
+            digest_hex,
+            "96053d1a0f5e0b02950c81282738484c5d28c6e250e8ad0315fe1d38cf0473a5"
+        );
+        assert_eq!(tail.as_deref(), Some("feff"));
+
+        let missing =
+            manager.fingerprint(SERVICE, "absent", FingerprintAlgo::Sha256, None, NO_RETRY);
+        assert_eq!(
+            missing.err().map(|e| e.code()),
+            Some(KeychainErrorCode::NotFound)
+        );
+    }
+}
@@ -0,0 +1,313 @@
+//! Generation de secrets aleatoires (jetons, mots de passe, phrases de
+//! passe) a partir du generateur du systeme, a la place de `Math.random()`
+//! cote webview.
+
+use base64::Engine;
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use zeroize::Zeroizing;
+
+use super::{
+    fingerprint, run_blocking, KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy,
+    Secret, SetOptions,
+};
+
+/// Liste de 1024 mots courts, sans accents : 10 bits d'entropie par mot.
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+const MAX_BYTES: usize = 1_024;
+const MAX_PASSWORD_CHARS: usize = 256;
+const MAX_WORDS: usize = 32;
+
+const LOWER: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPER: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SYMBOLS: &[u8] = b"!#$%&()*+,-./:;<=>?@[]^_{|}~";
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ByteEncoding {
+    Hex,
+    #[default]
+    Base64,
+}
+
+/// Classes de caracteres d'un mot de passe, toutes retenues par defaut.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub(crate) struct CharClasses {
+    lower: bool,
+    upper: bool,
+    digits: bool,
+    symbols: bool,
+}
+
+impl Default for CharClasses {
+    fn default() -> Self {
+        CharClasses {
+            lower: true,
+            upper: true,
+            digits: true,
+            symbols: true,
+        }
+    }
+}
+
+impl CharClasses {
+    fn selected(&self) -> Vec<&'static [u8]> {
+        [
+            (self.lower, LOWER),
+            (self.upper, UPPER),
+            (self.digits, DIGITS),
+            (self.symbols, SYMBOLS),
+        ]
+        .into_iter()
+        .filter_map(|(selected, class)| selected.then_some(class))
+        .collect()
+    }
+}
+
+fn default_separator() -> String {
+    "-".to_string()
+}
+
+/// Secret a generer, distingue par `kind`.
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub(crate) enum SecretSpec {
+    /// `length` octets aleatoires, encodes en hexadecimal ou en base64.
+    Bytes {
+        length: usize,
+        #[serde(default)]
+        encoding: ByteEncoding,
+    },
+    /// Mot de passe de `length` caracteres contenant au moins un caractere
+    /// de chaque classe retenue.
+    Password {
+        length: usize,
+        #[serde(default)]
+        classes: CharClasses,
+    },
+    /// Phrase de passe de `words` mots de la liste embarquee.
+    Passphrase {
+        words: usize,
+        #[serde(default = "default_separator")]
+        separator: String,
+    },
+}
+
+/// Emplacement ou ecrire directement le secret genere.
+#[derive(Deserialize)]
+pub(crate) struct StoreTarget {
+    service: String,
+    key: String,
+}
+
+/// Secret genere. `value` est absent quand il a ete ecrit dans le keychain :
+/// seule son empreinte est renvoyee.
+#[derive(Serialize)]
+pub(crate) struct GeneratedSecret {
+    value: Option<Secret>,
+    fingerprint: String,
+    /// Renseigne apres ecriture, comme pour `keychain_set`.
+    persistent: Option<bool>,
+}
+
+fn out_of_range(name: &str, min: usize, max: usize) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::InvalidArgument,
+        format!("{} doit etre compris entre {} et {}", name, min, max),
+    )
+}
+
+/// Les tirages passent par `gen_range`, qui rejette les valeurs hors de la
+/// plage uniforme plutot que de reduire modulo : aucun biais.
+pub(crate) fn generate(spec: &SecretSpec) -> Result<Secret, KeychainError> {
+    match spec {
+        SecretSpec::Bytes { length, encoding } => {
+            if !(1..=MAX_BYTES).contains(length) {
+                return Err(out_of_range("length", 1, MAX_BYTES));
+            }
+            let mut bytes = Zeroizing::new(vec![0u8; *length]);
+            OsRng.fill_bytes(&mut bytes);
+            Ok(Secret::new(match encoding {
+                ByteEncoding::Hex => bytes.iter().map(|b| format!("{:02x}", b)).collect(),
+                ByteEncoding::Base64 => {
+                    base64::engine::general_purpose::STANDARD.encode(&bytes[..])
+                }
+            }))
+        }
+        SecretSpec::Password { length, classes } => {
+            let classes = classes.selected();
+            if classes.is_empty() {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::InvalidArgument,
+                    "au moins une classe de caracteres doit etre retenue",
+                ));
+            }
+            if !(classes.len()..=MAX_PASSWORD_CHARS).contains(length) {
+                return Err(out_of_range("length", classes.len(), MAX_PASSWORD_CHARS));
+            }
+            let alphabet: Vec<u8> = classes.concat();
+            // Un caractere de chaque classe, le reste dans l'alphabet complet,
+            // puis melange pour que les premiers ne soient pas previsibles.
+            let mut chars = Zeroizing::new(Vec::with_capacity(*length));
+            for class in &classes {
+                chars.push(class[OsRng.gen_range(0..class.len())]);
+            }
+            while chars.len() < *length {
+                chars.push(alphabet[OsRng.gen_range(0..alphabet.len())]);
+            }
+            chars.shuffle(&mut OsRng);
+            Ok(Secret::new(chars.iter().map(|&c| c as char).collect()))
+        }
+        SecretSpec::Passphrase { words, separator } => {
+            if !(1..=MAX_WORDS).contains(words) {
+                return Err(out_of_range("words", 1, MAX_WORDS));
+            }
+            let list: Vec<&str> = WORDLIST.lines().collect();
+            let picked: Vec<&str> = (0..*words)
+                .map(|_| list[OsRng.gen_range(0..list.len())])
+                .collect();
+            Ok(Secret::new(picked.join(separator)))
+        }
+    }
+}
+
+/// Genere un secret aleatoire. Avec `store_as`, il est ecrit dans le
+/// keychain et seule son empreinte est renvoyee.
+#[tauri::command]
+pub(crate) async fn secret_generate(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    spec: SecretSpec,
+    store_as: Option<StoreTarget>,
+    retry: Option<RetryPolicy>,
+) -> Result<GeneratedSecret, KeychainError> {
+    let value = generate(&spec)?;
+    let fingerprint = fingerprint(&value);
+    let Some(target) = store_as else {
+        return Ok(GeneratedSecret {
+            value: Some(value),
+            fingerprint,
+            persistent: None,
+        });
+    };
+    let audit = manager
+        .audit(&window, "generate", &target.service, Some(&target.key))
+        .detail(fingerprint.as_str());
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let report = audit.finish(
+        run_blocking(move || {
+            manager.set(
+                &target.service,
+                &target.key,
+                &value,
+                SetOptions::default(),
+                retry,
+            )
+        })
+        .await,
+    )?;
+    Ok(GeneratedSecret {
+        value: None,
+        fingerprint,
+        persistent: Some(report.persistent),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn password(length: usize, classes: CharClasses) -> Result<Secret, KeychainError> {
+        generate(&SecretSpec::Password { length, classes })
+    }
+
+    #[test]
+    fn passwords_contain_every_selected_class() {
+        let only = |lower, upper, digits, symbols| CharClasses {
+            lower,
+            upper,
+            digits,
+            symbols,
+        };
+        let cases = [
+            CharClasses::default(),
+            only(true, false, false, false),
+            only(false, true, true, false),
+            only(false, false, false, true),
+            only(true, false, true, true),
+        ];
+        for classes in cases {
+            let selected = classes.selected();
+            let alphabet = selected.concat();
+            // A la longueur minimale, chaque classe n'a qu'une place.
+            for length in [selected.len(), 12, MAX_PASSWORD_CHARS] {
+                for _ in 0..50 {
+                    let value = password(length, classes).unwrap();
+                    assert_eq!(value.len(), length);
+                    assert!(value.bytes().all(|c| alphabet.contains(&c)));
+                    for class in &selected {
+                        assert!(value.bytes().any(|c| class.contains(&c)), "{}", &*value);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn password_bounds_depend_on_the_classes() {
+        let code = |result: Result<Secret, KeychainError>| result.err().map(|e| e.code());
+        let none = CharClasses {
+            lower: false,
+            upper: false,
+            digits: false,
+            symbols: false,
+        };
+        for length in [3, MAX_PASSWORD_CHARS + 1] {
+            assert_eq!(
+                code(password(length, CharClasses::default())),
+                Some(KeychainErrorCode::InvalidArgument)
+            );
+        }
+        assert_eq!(
+            code(password(12, none)),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+    }
+
+    #[test]
+    fn bytes_and_passphrases_follow_their_spec() {
+        let hex = generate(&SecretSpec::Bytes {
+            length: 16,
+            encoding: ByteEncoding::Hex,
+        })
+        .unwrap();
+        assert_eq!(hex.len(), 32);
+        assert!(hex.bytes().all(|c| c.is_ascii_hexdigit()));
+        let base64 = generate(&SecretSpec::Bytes {
+            length: 32,
+            encoding: ByteEncoding::Base64,
+        })
+        .unwrap();
+        let decoded = base64::engine::general_purpose::STANDARD.decode(&*base64);
+        assert_eq!(decoded.map(|bytes| bytes.len()).ok(), Some(32));
+
+        let phrase = generate(&SecretSpec::Passphrase {
+            words: 6,
+            separator: " ".to_string(),
+        })
+        .unwrap();
+        let words: Vec<&str> = phrase.split(' ').collect();
+        assert_eq!(words.len(), 6);
+        assert!(words
+            .iter()
+            .all(|word| WORDLIST.lines().any(|w| w == *word)));
+        assert_eq!(WORDLIST.lines().count(), 1_024);
+    }
+}
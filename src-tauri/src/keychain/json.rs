@@ -0,0 +1,293 @@
+//! Secrets structures : un petit objet JSON (client_id, client_secret,
+//! tenant...) stocke comme valeur de l'enveloppe. `keychain_get_field`
+//! applique un JSON Pointer (RFC 6901) cote Rust pour ne renvoyer au webview
+//! que le champ demande.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tauri::{State, Window};
+use zeroize::{Zeroize, Zeroizing};
+
+use super::{
+    auth_reason, read_stored, record_history, run_blocking, write_password, EnvelopeMeta, Gated,
+    KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy, SecretOperation, SetOptions,
+    SetReport, DEFAULT_HISTORY_DEPTH,
+};
+
+/// Valeur JSON dont les chaines sont effacees de la memoire a la
+/// liberation ; les noms de champs ne sont pas secrets.
+#[derive(Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct JsonSecret(Value);
+
+fn wipe(value: &mut Value) {
+    match value {
+        Value::String(s) => s.zeroize(),
+        Value::Array(items) => items.iter_mut().for_each(wipe),
+        Value::Object(fields) => fields.values_mut().for_each(wipe),
+        _ => {}
+    }
+}
+
+impl Drop for JsonSecret {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+impl JsonSecret {
+    fn parse(key: &str, raw: &str) -> Result<Self, KeychainError> {
+        match serde_json::from_str::<Value>(raw) {
+            Ok(value) if value.is_object() => Ok(JsonSecret(value)),
+            Ok(mut value) => {
+                wipe(&mut value);
+                Err(not_json(key))
+            }
+            Err(_) => Err(not_json(key)),
+        }
+    }
+
+    fn to_raw(&self) -> Result<Zeroizing<String>, KeychainError> {
+        serde_json::to_string(&self.0)
+            .map(Zeroizing::new)
+            .map_err(|e| {
+                KeychainError::rejected(
+                    KeychainErrorCode::PlatformError,
+                    format!("serialisation JSON impossible : {}", e),
+                )
+            })
+    }
+}
+
+fn not_json(key: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::NotJson,
+        format!("le secret {} n'est pas un objet JSON", key),
+    )
+}
+
+fn not_found(pointer: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::PointerNotFound,
+        format!("aucun champ {}", pointer),
+    )
+}
+
+/// `""` designe l'objet entier, sinon le pointeur commence par `/`.
+fn check_pointer(pointer: &str) -> Result<(), KeychainError> {
+    if pointer.is_empty() || pointer.starts_with('/') {
+        return Ok(());
+    }
+    Err(KeychainError::rejected(
+        KeychainErrorCode::InvalidArgument,
+        format!(
+            "pointeur JSON invalide : {} (doit commencer par /)",
+            pointer
+        ),
+    ))
+}
+
+/// Remplace ou ajoute la cible de `pointer`. Son parent doit exister : un
+/// objet recoit le champ, un tableau l'element a l'index donne, ou en fin
+/// pour `-`. `""` remplace l'objet entier.
+fn set_pointer(root: &mut Value, pointer: &str, value: Value) -> Result<(), KeychainError> {
+    if pointer.is_empty() {
+        if !value.is_object() {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                "la racine d'un secret JSON doit rester un objet",
+            ));
+        }
+        let mut previous = std::mem::replace(root, value);
+        wipe(&mut previous);
+        return Ok(());
+    }
+    let (parent, token) = pointer.rsplit_once('/').unwrap_or(("", pointer));
+    let token = token.replace("~1", "/").replace("~0", "~");
+    match root.pointer_mut(parent) {
+        Some(Value::Object(fields)) => {
+            if let Some(mut previous) = fields.insert(token, value) {
+                wipe(&mut previous);
+            }
+            Ok(())
+        }
+        Some(Value::Array(items)) => {
+            let index = if token == "-" {
+                items.len()
+            } else {
+                token.parse::<usize>().map_err(|_| not_found(pointer))?
+            };
+            match index.cmp(&items.len()) {
+                std::cmp::Ordering::Less => {
+                    let mut previous = std::mem::replace(&mut items[index], value);
+                    wipe(&mut previous);
+                }
+                std::cmp::Ordering::Equal => items.push(value),
+                std::cmp::Ordering::Greater => return Err(not_found(pointer)),
+            }
+            Ok(())
+        }
+        _ => Err(not_found(pointer)),
+    }
+}
+
+impl KeychainManager {
+    /// Champ `pointer` du secret JSON `key`.
+    pub(crate) fn get_field(
+        &self,
+        service: &str,
+        key: &str,
+        pointer: &str,
+        retry: RetryPolicy,
+    ) -> Result<Gated<JsonSecret>, KeychainError> {
+        check_pointer(pointer)?;
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let stored = {
+            let _guard = self.locks.lock(service, [key]);
+            read_stored(&self.store, retry, service, key)?
+        };
+        let stored = stored.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::NotFound,
+                format!("aucun secret {} dans {}", key, service),
+            )
+        })?;
+        let reason = stored.requires_auth().then(|| auth_reason(key));
+        let mut document = JsonSecret::parse(key, &stored.into_value())?;
+        let field = document
+            .0
+            .pointer_mut(pointer)
+            .map(Value::take)
+            .ok_or_else(|| not_found(pointer))?;
+        Ok(Gated::new(JsonSecret(field), reason))
+    }
+
+    /// Lecture, modification et ecriture sous le verrou de la cle.
+    /// L'expiration et les autres reglages de l'enveloppe sont conserves,
+    /// l'ancienne valeur passe dans l'historique.
+    pub(crate) fn set_field(
+        &self,
+        service: &str,
+        key: &str,
+        pointer: &str,
+        mut value: JsonSecret,
+        retry: RetryPolicy,
+    ) -> Result<(), KeychainError> {
+        check_pointer(pointer)?;
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let store = &self.store;
+        let _guard = self.locks.lock(service, [key]);
+        let current = read_stored(store, retry, service, key)?.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::NotFound,
+                format!("aucun secret {} dans {}", key, service),
+            )
+        })?;
+        let (expires_at, _) = current.cache_hint();
+        let mut document = JsonSecret::parse(key, &current.into_value())?;
+        set_pointer(&mut document.0, pointer, value.0.take())?;
+        let raw = document.to_raw()?;
+        let previous = write_password(
+            store,
+            retry,
+            service,
+            key,
+            &raw,
+            EnvelopeMeta {
+                expires_at,
+                ..EnvelopeMeta::default()
+            },
+        )?;
+        self.cache.invalidate(service, [key]);
+        record_history(store, retry, service, key, previous, DEFAULT_HISTORY_DEPTH);
+        self.events.emit(service, key, SecretOperation::Set);
+        Ok(())
+    }
+}
+
+/// Stocke `object`, qui doit etre un objet JSON, avec les memes options que
+/// `keychain_set`.
+#[tauri::command]
+pub(crate) async fn keychain_set_json(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    object: JsonSecret,
+    options: Option<SetOptions>,
+    retry: Option<RetryPolicy>,
+) -> Result<SetReport, KeychainError> {
+    let audit = manager.audit(&window, "set_json", &service, Some(&key));
+    if !object.0.is_object() {
+        return audit.finish(Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            "object doit etre un objet JSON",
+        )));
+    }
+    let raw = match object.to_raw() {
+        Ok(raw) => raw,
+        Err(e) => return audit.finish(Err(e)),
+    };
+    drop(object);
+    let manager = manager.inner().clone();
+    let options = options.unwrap_or_default();
+    let retry = retry.unwrap_or_default();
+    audit.finish(run_blocking(move || manager.set(&service, &key, &raw, options, retry)).await)
+}
+
+/// Renvoie le seul champ `pointer` (RFC 6901, `""` pour l'objet entier).
+/// Un secret qui n'est pas un objet JSON donne `NotJson`, un pointeur qui
+/// ne mene a rien `PointerNotFound`.
+#[tauri::command]
+pub(crate) async fn keychain_get_field(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    pointer: String,
+    retry: Option<RetryPolicy>,
+) -> Result<JsonSecret, KeychainError> {
+    let audit = manager
+        .audit(&window, "get_field", &service, Some(&key))
+        .detail(pointer.as_str());
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        auth.release(
+            run_blocking(move || manager.get_field(&service, &key, &pointer, retry)).await,
+        )
+        .await,
+    )
+}
+
+/// Remplace ou ajoute le champ `pointer` d'un secret JSON existant, sans
+/// renvoyer les autres champs au webview.
+#[tauri::command]
+pub(crate) async fn keychain_set_field(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    pointer: String,
+    value: JsonSecret,
+    retry: Option<RetryPolicy>,
+) -> Result<(), KeychainError> {
+    let audit = manager
+        .audit(&window, "set_field", &service, Some(&key))
+        .detail(pointer.as_str());
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        run_blocking(move || manager.set_field(&service, &key, &pointer, value, retry)).await,
+    )
+}
@@ -0,0 +1,255 @@
+//! Derivation Argon2id exposee au frontend pour le chiffrement des donnees
+//! synchronisees : plus rapide qu'en JavaScript, et hors du thread principal.
+
+use std::sync::Mutex;
+
+use base64::Engine;
+use rand::RngCore;
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+use super::backup::{
+    argon2id_into, KDF_M_COST, KDF_P_COST, KDF_T_COST, KEY_LEN, MAX_KDF_M_COST, MAX_KDF_P_COST,
+    MAX_KDF_T_COST, SALT_LEN,
+};
+use super::{worker_interrupted, KeychainError, KeychainErrorCode, Secret};
+
+const MIN_OUTPUT_LEN: usize = 16;
+const MAX_OUTPUT_LEN: usize = 64;
+/// Argon2 exige au moins 8 octets de sel.
+const MIN_SALT_LEN: usize = 8;
+const MAX_SALT_LEN: usize = 64;
+
+/// Une derivation a la fois : une page ne peut pas multiplier les appels
+/// pour reserver plusieurs fois `MAX_KDF_M_COST` de memoire.
+static DERIVATION: Mutex<()> = Mutex::new(());
+
+/// Parametres Argon2id, par defaut ceux des sauvegardes.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub(crate) struct KdfParams {
+    /// Memoire, en Kio.
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    /// Longueur de la cle derivee, en octets.
+    output_len: usize,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        KdfParams {
+            memory_kib: KDF_M_COST,
+            iterations: KDF_T_COST,
+            parallelism: KDF_P_COST,
+            output_len: KEY_LEN,
+        }
+    }
+}
+
+impl KdfParams {
+    fn validate(&self) -> Result<(), KeychainError> {
+        let check = |name: &str, value: usize, min: usize, max: usize| {
+            if (min..=max).contains(&value) {
+                Ok(())
+            } else {
+                Err(KeychainError::rejected(
+                    KeychainErrorCode::InvalidArgument,
+                    format!("{} doit etre compris entre {} et {}", name, min, max),
+                ))
+            }
+        };
+        check(
+            "parallelism",
+            self.parallelism as usize,
+            1,
+            MAX_KDF_P_COST as usize,
+        )?;
+        // Argon2 impose 8 Kio par voie.
+        check(
+            "memory_kib",
+            self.memory_kib as usize,
+            8 * self.parallelism as usize,
+            MAX_KDF_M_COST as usize,
+        )?;
+        check(
+            "iterations",
+            self.iterations as usize,
+            1,
+            MAX_KDF_T_COST as usize,
+        )?;
+        check(
+            "output_len",
+            self.output_len,
+            MIN_OUTPUT_LEN,
+            MAX_OUTPUT_LEN,
+        )
+    }
+}
+
+/// Derive une cle Argon2id de `passphrase` et du sel fourni en base64, et la
+/// renvoie en base64. La phrase de passe est effacee apres la derivation.
+#[tauri::command]
+pub(crate) async fn kdf_derive(
+    passphrase: Secret,
+    salt_base64: String,
+    params: Option<KdfParams>,
+) -> Result<Secret, KeychainError> {
+    let params = params.unwrap_or_default();
+    params.validate()?;
+    let salt = base64::engine::general_purpose::STANDARD
+        .decode(salt_base64.trim())
+        .map_err(|e| {
+            KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                format!("sel base64 invalide : {}", e),
+            )
+        })?;
+    if !(MIN_SALT_LEN..=MAX_SALT_LEN).contains(&salt.len()) {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!(
+                "le sel doit faire entre {} et {} octets",
+                MIN_SALT_LEN, MAX_SALT_LEN
+            ),
+        ));
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        let _slot = DERIVATION.lock().unwrap_or_else(|e| e.into_inner());
+        let mut key = Zeroizing::new(vec![0u8; params.output_len]);
+        argon2id_into(
+            &passphrase,
+            [params.memory_kib, params.iterations, params.parallelism],
+            &salt,
+            &mut key,
+        )?;
+        Ok(Secret::new(
+            base64::engine::general_purpose::STANDARD.encode(&key[..]),
+        ))
+    })
+    .await
+    .map_err(worker_interrupted)?
+}
+
+/// Sel aleatoire de 16 octets, en base64, pour `kdf_derive`.
+#[tauri::command]
+pub(crate) fn kdf_generate_salt() -> String {
+    let mut salt = [0u8; SALT_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    base64::engine::general_purpose::STANDARD.encode(salt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn derive(
+        passphrase: &str,
+        salt_base64: &str,
+        params: KdfParams,
+    ) -> Result<Secret, KeychainError> {
+        tauri::async_runtime::block_on(kdf_derive(
+            Secret::new(passphrase.to_string()),
+            salt_base64.to_string(),
+            Some(params),
+        ))
+    }
+
+    fn params(memory_kib: u32, iterations: u32, parallelism: u32) -> KdfParams {
+        KdfParams {
+            memory_kib,
+            iterations,
+            parallelism,
+            output_len: 32,
+        }
+    }
+
+    #[test]
+    fn derive_matches_the_reference_implementation() {
+        // Vecteurs Argon2id v1.3 de l'implementation de reference
+        // (phc-winner-argon2, src/test.c), sel "somesalt" ou "diffsalt".
+        let vectors = [
+            (
+                params(1 << 16, 2, 1),
+                "password",
+                "c29tZXNhbHQ=",
+                "CTFhFdXPJO1aFaMaO6Mm5c8y7cJHAph8ArZWb2GRPPc=",
+            ),
+            (
+                params(1 << 8, 2, 1),
+                "password",
+                "c29tZXNhbHQ=",
+                "nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4=",
+            ),
+            (
+                params(1 << 8, 2, 2),
+                "password",
+                "c29tZXNhbHQ=",
+                "bQk8UB/VmZZF4Oo79iDXuL5/0ttZwg2f/5U52iv1cDc=",
+            ),
+            (
+                params(1 << 16, 1, 1),
+                "password",
+                "c29tZXNhbHQ=",
+                "9qWtwbpyPd3vm1rB1GThgPzZ3/ydHL92zKL+15XZypg=",
+            ),
+            (
+                params(1 << 16, 2, 1),
+                "differentpassword",
+                "c29tZXNhbHQ=",
+                "C4TWUs9rDEvq7w3+J4umqA32aWKB1+DSiRuBfYxFj94=",
+            ),
+            (
+                params(1 << 16, 2, 1),
+                "password",
+                "ZGlmZnNhbHQ=",
+                "vfMrBczELrFdWP0ZsfhWsRPaHppYdP3MVEMIVlqoFBw=",
+            ),
+        ];
+        for (params, passphrase, salt, expected) in vectors {
+            let key = derive(passphrase, salt, params).unwrap();
+            assert_eq!(&*key, expected, "{} / {}", passphrase, salt);
+        }
+    }
+
+    #[test]
+    fn output_len_sets_the_key_length() {
+        let mut params = params(1 << 8, 1, 1);
+        params.output_len = MAX_OUTPUT_LEN;
+        let key = derive("password", "c29tZXNhbHQ=", params).unwrap();
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(&*key)
+            .unwrap();
+        assert_eq!(bytes.len(), MAX_OUTPUT_LEN);
+    }
+
+    #[test]
+    fn out_of_bounds_requests_are_rejected() {
+        let too_much_output = KdfParams {
+            output_len: MAX_OUTPUT_LEN + 1,
+            ..params(1 << 8, 1, 1)
+        };
+        let cases = [
+            (params(MAX_KDF_M_COST + 1, 1, 1), "c29tZXNhbHQ="),
+            (params(15, 1, 2), "c29tZXNhbHQ="),
+            (params(1 << 8, 0, 1), "c29tZXNhbHQ="),
+            (params(1 << 8, MAX_KDF_T_COST + 1, 1), "c29tZXNhbHQ="),
+            (params(1 << 8, 1, MAX_KDF_P_COST + 1), "c29tZXNhbHQ="),
+            (too_much_output, "c29tZXNhbHQ="),
+            // Sel de 7 octets, puis base64 invalide.
+            (params(1 << 8, 1, 1), "c29tZXNhbA=="),
+            (params(1 << 8, 1, 1), "pas du base64"),
+        ];
+        for (params, salt) in cases {
+            let code = derive("password", salt, params).err().map(|e| e.code());
+            assert_eq!(code, Some(KeychainErrorCode::InvalidArgument), "{}", salt);
+        }
+    }
+
+    #[test]
+    fn generated_salts_are_random_and_accepted() {
+        let salt = kdf_generate_salt();
+        assert_ne!(salt, kdf_generate_salt());
+        assert!(derive("password", &salt, params(1 << 8, 1, 1)).is_ok());
+    }
+}
@@ -0,0 +1,243 @@
+//! Limitation du debit des lectures de secrets demandees par le webview,
+//! pour ralentir un script qui tenterait de tout lire d'un coup. Les
+//! traitements internes (export, migrations) ne passent pas par ici.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use super::{KeychainError, KeychainErrorCode};
+
+/// Lectures autorisees par minute, par cle et toutes cles confondues.
+pub(crate) const DEFAULT_READS_PER_KEY_PER_MINUTE: u32 = 30;
+pub(crate) const DEFAULT_READS_PER_MINUTE: u32 = 120;
+
+/// Au-dela, les seaux pleins sont oublies.
+const MAX_TRACKED_KEYS: usize = 1_024;
+
+/// Seau a jetons rempli en continu : `capacity` jetons par minute.
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(capacity: u32, now: Instant) -> Self {
+        Bucket {
+            capacity: capacity as f64,
+            tokens: capacity as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.updated = now;
+    }
+
+    fn is_full(&self) -> bool {
+        self.tokens >= self.capacity
+    }
+
+    /// Delai avant que `count` jetons soient disponibles, 0 s'ils le sont.
+    fn wait_ms(&self, count: f64) -> u64 {
+        let missing = count - self.tokens;
+        if missing <= 0.0 {
+            0
+        } else {
+            (missing * 60_000.0 / self.capacity).ceil() as u64
+        }
+    }
+}
+
+struct Buckets {
+    total: Option<Bucket>,
+    per_key: HashMap<(String, String), Bucket>,
+}
+
+/// Limiteur partage par les commandes de lecture. Une limite a 0 est
+/// desactivee.
+pub(crate) struct ReadLimiter {
+    per_key: u32,
+    buckets: Mutex<Buckets>,
+}
+
+impl ReadLimiter {
+    pub(crate) fn new(per_key_per_minute: u32, per_minute: u32) -> Self {
+        let now = Instant::now();
+        ReadLimiter {
+            per_key: per_key_per_minute,
+            buckets: Mutex::new(Buckets {
+                total: (per_minute > 0).then(|| Bucket::new(per_minute, now)),
+                per_key: HashMap::new(),
+            }),
+        }
+    }
+
+    /// Consomme une lecture pour chacune des `keys`, ou aucune si l'une des
+    /// limites serait depassee : l'erreur `RateLimited` indique alors le
+    /// delai a attendre.
+    pub(crate) fn acquire<'a>(
+        &self,
+        service: &str,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), KeychainError> {
+        self.acquire_at(Instant::now(), service, keys)
+    }
+
+    fn acquire_at<'a>(
+        &self,
+        now: Instant,
+        service: &str,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> Result<(), KeychainError> {
+        let mut counts: HashMap<&str, u32> = HashMap::new();
+        for key in keys {
+            *counts.entry(key).or_default() += 1;
+        }
+        let requested: u32 = counts.values().sum();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut wait_ms = 0;
+        if let Some(total) = &mut buckets.total {
+            total.refill(now);
+            if requested as f64 > total.capacity {
+                return Err(too_many(requested, total.capacity));
+            }
+            wait_ms = total.wait_ms(requested as f64);
+        }
+        if self.per_key > 0 {
+            for (key, count) in &counts {
+                if *count > self.per_key {
+                    return Err(too_many(*count, self.per_key as f64));
+                }
+                if let Some(bucket) = buckets
+                    .per_key
+                    .get_mut(&(service.to_string(), key.to_string()))
+                {
+                    bucket.refill(now);
+                    wait_ms = wait_ms.max(bucket.wait_ms(*count as f64));
+                }
+            }
+        }
+        if wait_ms > 0 {
+            return Err(KeychainError::rate_limited(wait_ms));
+        }
+
+        if let Some(total) = &mut buckets.total {
+            total.tokens -= requested as f64;
+        }
+        if self.per_key > 0 {
+            if buckets.per_key.len() > MAX_TRACKED_KEYS {
+                buckets.per_key.retain(|_, bucket| {
+                    bucket.refill(now);
+                    !bucket.is_full()
+                });
+            }
+            for (key, count) in counts {
+                buckets
+                    .per_key
+                    .entry((service.to_string(), key.to_string()))
+                    .or_insert_with(|| Bucket::new(self.per_key, now))
+                    .tokens -= count as f64;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn too_many(requested: u32, capacity: f64) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::InvalidArgument,
+        format!(
+            "{} lectures demandees, la limite est de {} par minute",
+            requested, capacity
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn wait_ms(result: Result<(), KeychainError>) -> Option<u64> {
+        match result {
+            Err(KeychainError::RateLimited { retry_after_ms }) => Some(retry_after_ms),
+            Err(e) => panic!("erreur inattendue : {:?}", e.code()),
+            Ok(()) => None,
+        }
+    }
+
+    #[test]
+    fn per_key_limit_trips_and_recovers() {
+        let limiter = ReadLimiter::new(2, 0);
+        let start = Instant::now();
+        assert!(limiter.acquire_at(start, "svc", ["a", "a"]).is_ok());
+        // Un jeton revient toutes les 30 s avec 2 lectures par minute.
+        assert_eq!(
+            wait_ms(limiter.acquire_at(start, "svc", ["a"])),
+            Some(30_000)
+        );
+        assert!(limiter.acquire_at(start, "svc", ["b"]).is_ok());
+        assert!(limiter.acquire_at(start, "other", ["a"]).is_ok());
+
+        let later = start + Duration::from_secs(15);
+        assert_eq!(
+            wait_ms(limiter.acquire_at(later, "svc", ["a"])),
+            Some(15_000)
+        );
+        let later = start + Duration::from_secs(30);
+        assert!(limiter.acquire_at(later, "svc", ["a"]).is_ok());
+        assert!(limiter.acquire_at(later, "svc", ["a"]).is_err());
+
+        // Le seau se remplit sans depasser sa capacite.
+        let much_later = start + Duration::from_secs(3_600);
+        assert!(limiter.acquire_at(much_later, "svc", ["a", "a"]).is_ok());
+        assert!(limiter.acquire_at(much_later, "svc", ["a"]).is_err());
+    }
+
+    #[test]
+    fn total_limit_is_all_or_nothing() {
+        let limiter = ReadLimiter::new(0, 3);
+        let start = Instant::now();
+        assert!(limiter.acquire_at(start, "svc", ["a", "b"]).is_ok());
+        assert_eq!(
+            wait_ms(limiter.acquire_at(start, "svc", ["c", "d"])),
+            Some(20_000)
+        );
+        // Le lot refuse n'a rien consomme.
+        assert!(limiter.acquire_at(start, "svc", ["c"]).is_ok());
+        assert!(limiter.acquire_at(start, "svc", ["d"]).is_err());
+        let later = start + Duration::from_secs(20);
+        assert!(limiter.acquire_at(later, "svc", ["d"]).is_ok());
+    }
+
+    #[test]
+    fn oversized_requests_are_invalid() {
+        let limiter = ReadLimiter::new(2, 3);
+        let start = Instant::now();
+        let code = |result: Result<(), KeychainError>| result.err().map(|e| e.code());
+        assert_eq!(
+            code(limiter.acquire_at(start, "svc", ["a", "b", "c", "d"])),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+        assert_eq!(
+            code(limiter.acquire_at(start, "svc", ["a", "a", "a"])),
+            Some(KeychainErrorCode::InvalidArgument)
+        );
+        assert!(limiter.acquire_at(start, "svc", ["a", "a", "b"]).is_ok());
+    }
+
+    #[test]
+    fn zero_disables_a_limit() {
+        let limiter = ReadLimiter::new(0, 0);
+        let start = Instant::now();
+        for _ in 0..1_000 {
+            assert!(limiter.acquire_at(start, "svc", ["a"]).is_ok());
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Messages d'erreur traduits, indexes par code. Le code reste le contrat
+//! avec le frontend, qui peut afficher sa propre traduction ; le message est
+//! une commodite dans la langue choisie par `set_locale`.
+
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use super::KeychainErrorCode;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Locale {
+    #[default]
+    Fr,
+    En,
+}
+
+impl Locale {
+    /// Accepte aussi une region, `fr-CA` ou `en_US` : seule la langue compte.
+    pub(crate) fn parse(tag: &str) -> Option<Self> {
+        let language = tag.trim().split(['-', '_']).next()?;
+        match language.to_ascii_lowercase().as_str() {
+            "fr" => Some(Locale::Fr),
+            "en" => Some(Locale::En),
+            _ => None,
+        }
+    }
+}
+
+/// Langue des messages, lue par la serialisation des erreurs qui n'a pas
+/// acces a l'etat Tauri.
+static LOCALE: AtomicU8 = AtomicU8::new(Locale::Fr as u8);
+
+pub(crate) fn set_locale(locale: Locale) {
+    LOCALE.store(locale as u8, Ordering::Relaxed);
+}
+
+pub(crate) fn locale() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        x if x == Locale::En as u8 => Locale::En,
+        _ => Locale::Fr,
+    }
+}
+
+/// `(code, fr, en)`.
+const MESSAGES: &[(KeychainErrorCode, &str, &str)] = &[
+    (
+        KeychainErrorCode::NotFound,
+        "Secret introuvable",
+        "Secret not found",
+    ),
+    (
+        KeychainErrorCode::AccessDenied,
+        "Acces au keychain refuse",
+        "Keychain access denied",
+    ),
+    (
+        KeychainErrorCode::BackendUnavailable,
+        "Keychain du systeme indisponible",
+        "System keychain unavailable",
+    ),
+    (
+        KeychainErrorCode::Ambiguous,
+        "Plusieurs secrets correspondent",
+        "Several secrets match",
+    ),
+    (
+        KeychainErrorCode::BadEncoding,
+        "Le secret n'est pas du texte valide",
+        "The secret is not valid text",
+    ),
+    (
+        KeychainErrorCode::InvalidArgument,
+        "Parametre invalide",
+        "Invalid parameter",
+    ),
+    (
+        KeychainErrorCode::InvalidBase64,
+        "Base64 invalide",
+        "Invalid base64",
+    ),
+    (
+        KeychainErrorCode::Timeout,
+        "Le keychain ne repond pas",
+        "The keychain is not responding",
+    ),
+    (
+        KeychainErrorCode::AlreadyExists,
+        "Le secret existe deja",
+        "The secret already exists",
+    ),
+    (
+        KeychainErrorCode::Conflict,
+        "Le secret a ete modifie entre-temps",
+        "The secret was changed in the meantime",
+    ),
+    (
+        KeychainErrorCode::NameCollision,
+        "Une cle de meme nom existe avec une autre orthographe",
+        "A key with the same name exists with a different spelling",
+    ),
+    (
+        KeychainErrorCode::VerificationFailed,
+        "Le secret relu ne correspond pas",
+        "The secret read back does not match",
+    ),
+    (
+        KeychainErrorCode::Corrupt,
+        "Secret altere",
+        "Corrupted secret",
+    ),
+    (
+        KeychainErrorCode::VersionNotFound,
+        "Version du secret introuvable",
+        "Secret version not found",
+    ),
+    (
+        KeychainErrorCode::WeakPassphrase,
+        "Phrase de passe trop faible",
+        "Passphrase too weak",
+    ),
+    (
+        KeychainErrorCode::IoError,
+        "Erreur de lecture ou d'ecriture",
+        "Read or write error",
+    ),
+    (
+        KeychainErrorCode::InvalidBackup,
+        "Sauvegarde invalide",
+        "Invalid backup",
+    ),
+    (
+        KeychainErrorCode::UnsupportedVersion,
+        "Version de fichier non prise en charge",
+        "Unsupported file version",
+    ),
+    (
+        KeychainErrorCode::BadMac,
+        "Mauvaise phrase de passe ou fichier altere",
+        "Wrong passphrase or tampered file",
+    ),
+    (
+        KeychainErrorCode::ServiceNotAllowed,
+        "Service non autorise",
+        "Service not allowed",
+    ),
+    (
+        KeychainErrorCode::PermissionDenied,
+        "Action non autorisee pour cette fenetre",
+        "Action not allowed for this window",
+    ),
+    (
+        KeychainErrorCode::Offline,
+        "Pas de connexion reseau",
+        "No network connection",
+    ),
+    (
+        KeychainErrorCode::ProgramNotAllowed,
+        "Programme non autorise",
+        "Program not allowed",
+    ),
+    (
+        KeychainErrorCode::UrlNotAllowed,
+        "Lien non autorise",
+        "Link not allowed",
+    ),
+    (
+        KeychainErrorCode::QrCapacityExceeded,
+        "Secret trop long pour un QR code",
+        "Secret too long for a QR code",
+    ),
+    (
+        KeychainErrorCode::Expired,
+        "Le secret a expire",
+        "The secret has expired",
+    ),
+    (
+        KeychainErrorCode::VaultLocked,
+        "Coffre verrouille",
+        "Vault locked",
+    ),
+    (
+        KeychainErrorCode::VaultNotFound,
+        "Coffre introuvable",
+        "Vault not found",
+    ),
+    (
+        KeychainErrorCode::VaultCorrupt,
+        "Coffre altere",
+        "Corrupted vault",
+    ),
+    (
+        KeychainErrorCode::WrongPassphrase,
+        "Phrase de passe incorrecte",
+        "Wrong passphrase",
+    ),
+    (
+        KeychainErrorCode::EmptyKey,
+        "Nom de cle vide",
+        "Empty key name",
+    ),
+    (
+        KeychainErrorCode::InvalidKeyCharacters,
+        "Caracteres interdits dans le nom de cle",
+        "Forbidden characters in key name",
+    ),
+    (
+        KeychainErrorCode::KeyTooLong,
+        "Nom de cle trop long",
+        "Key name too long",
+    ),
+    (
+        KeychainErrorCode::AuthCancelled,
+        "Verification annulee",
+        "Verification cancelled",
+    ),
+    (
+        KeychainErrorCode::AuthUnavailable,
+        "Verification de l'utilisateur indisponible",
+        "User verification unavailable",
+    ),
+    (
+        KeychainErrorCode::AuthFailed,
+        "Verification refusee",
+        "Verification failed",
+    ),
+    (
+        KeychainErrorCode::RateLimited,
+        "Trop de lectures, reessayer plus tard",
+        "Too many reads, try again later",
+    ),
+    (
+        KeychainErrorCode::InvalidBase32,
+        "Base32 invalide",
+        "Invalid base32",
+    ),
+    (
+        KeychainErrorCode::NotJson,
+        "Le secret n'est pas un objet JSON",
+        "The secret is not a JSON object",
+    ),
+    (
+        KeychainErrorCode::PointerNotFound,
+        "Champ introuvable dans le secret",
+        "Field not found in the secret",
+    ),
+    (
+        KeychainErrorCode::AuthenticationFailed,
+        "Authentification echouee",
+        "Authentication failed",
+    ),
+    (
+        KeychainErrorCode::EncryptionKeyProtected,
+        "Cle de chiffrement non lisible",
+        "Encryption keys cannot be read",
+    ),
+    (
+        KeychainErrorCode::Unsupported,
+        "Operation non prise en charge",
+        "Unsupported operation",
+    ),
+    (
+        KeychainErrorCode::SessionLocked,
+        "Session verrouillee",
+        "Session locked",
+    ),
+    (
+        KeychainErrorCode::ShortcutUnavailable,
+        "Raccourci clavier indisponible",
+        "Keyboard shortcut unavailable",
+    ),
+    (
+        KeychainErrorCode::UpdateFailed,
+        "Mise a jour echouee",
+        "Update failed",
+    ),
+    (
+        KeychainErrorCode::UpdateSignatureInvalid,
+        "Signature de la mise a jour invalide",
+        "Invalid update signature",
+    ),
+    (
+        KeychainErrorCode::AutostartDenied,
+        "Demarrage automatique refuse par le systeme",
+        "Autostart denied by the system",
+    ),
+    (
+        KeychainErrorCode::AutostartFailed,
+        "Demarrage automatique impossible a modifier",
+        "Autostart could not be changed",
+    ),
+    (
+        KeychainErrorCode::BackendLocked,
+        "Keychain verrouille",
+        "Keychain locked",
+    ),
+    (
+        KeychainErrorCode::OperationCancelled,
+        "Operation annulee",
+        "Operation cancelled",
+    ),
+    (
+        KeychainErrorCode::CancelledByUser,
+        "Operation refusee",
+        "Cancelled by user",
+    ),
+    (
+        KeychainErrorCode::PlatformError,
+        "Erreur du systeme d'exploitation",
+        "Operating system error",
+    ),
+];
+
+/// Codes du catalogue, pour `metrics_snapshot`.
+pub(crate) fn codes() -> impl Iterator<Item = KeychainErrorCode> {
+    MESSAGES.iter().map(|(code, _, _)| *code)
+}
+
+/// Message de `code` dans la langue en cours. Un code absent du catalogue
+/// donne le modele anglais suivi du detail brut.
+pub(crate) fn localized(code: KeychainErrorCode, detail: &str) -> String {
+    match MESSAGES.iter().find(|(known, _, _)| *known == code) {
+        Some((_, fr, en)) => match locale() {
+            Locale::Fr => fr.to_string(),
+            Locale::En => en.to_string(),
+        },
+        None => format!("Unexpected error: {}", detail),
+    }
+}
@@ -0,0 +1,241 @@
+//! Compteurs de latence et d'erreurs par operation, alimentes par
+//! `CommandSpan::finish`. Tout est en compteurs atomiques dans un tableau
+//! statique : une mesure ne prend aucun verrou, depuis une commande comme
+//! depuis une tache de fond.
+
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::{messages, KeychainErrorCode};
+
+/// Bornes superieures des tranches de latence, en millisecondes ; une
+/// derniere tranche recoit le reste.
+const BUCKETS_MS: [u64; 12] = [1, 5, 10, 25, 50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+const BUCKET_COUNT: usize = BUCKETS_MS.len() + 1;
+/// `PlatformError` reste la derniere variante de `KeychainErrorCode`.
+const CODE_COUNT: usize = KeychainErrorCode::PlatformError as usize + 1;
+/// Operations distinctes suivies ; au-dela, les mesures sont comptees dans
+/// `dropped`.
+const MAX_OPERATIONS: usize = 128;
+
+struct Slot {
+    claimed: AtomicBool,
+    name: OnceLock<String>,
+    count: AtomicU64,
+    errors: AtomicU64,
+    total_us: AtomicU64,
+    max_us: AtomicU64,
+    buckets: [AtomicU64; BUCKET_COUNT],
+    codes: [AtomicU64; CODE_COUNT],
+}
+
+impl Slot {
+    const fn new() -> Self {
+        Slot {
+            claimed: AtomicBool::new(false),
+            name: OnceLock::new(),
+            count: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            total_us: AtomicU64::new(0),
+            max_us: AtomicU64::new(0),
+            buckets: [const { AtomicU64::new(0) }; BUCKET_COUNT],
+            codes: [const { AtomicU64::new(0) }; CODE_COUNT],
+        }
+    }
+
+    fn reset(&self) {
+        for counter in [&self.count, &self.errors, &self.total_us, &self.max_us]
+            .into_iter()
+            .chain(&self.buckets)
+            .chain(&self.codes)
+        {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+static SLOTS: [Slot; MAX_OPERATIONS] = [const { Slot::new() }; MAX_OPERATIONS];
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+/// Derniere remise a zero, en secondes Unix ; 0 si aucune.
+static SINCE: AtomicI64 = AtomicI64::new(0);
+
+/// Emplacement de `operation`, reserve a sa premiere mesure. Seule cette
+/// premiere mesure attend, le temps que le nom soit ecrit.
+fn slot(operation: &str) -> Option<&'static Slot> {
+    for slot in &SLOTS {
+        if !slot.claimed.load(Ordering::Acquire)
+            && slot
+                .claimed
+                .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            let _ = slot.name.set(operation.to_string());
+            return Some(slot);
+        }
+        let name = loop {
+            match slot.name.get() {
+                Some(name) => break name,
+                None => std::hint::spin_loop(),
+            }
+        };
+        if name == operation {
+            return Some(slot);
+        }
+    }
+    None
+}
+
+/// Mesure d'une operation terminee.
+pub(crate) fn record(operation: &str, elapsed: Duration, error: Option<KeychainErrorCode>) {
+    let Some(slot) = slot(operation) else {
+        DROPPED.fetch_add(1, Ordering::Relaxed);
+        return;
+    };
+    let us = elapsed.as_micros().min(u64::MAX as u128) as u64;
+    let ms = us / 1_000;
+    let bucket = BUCKETS_MS
+        .iter()
+        .position(|&bound| ms < bound)
+        .unwrap_or(BUCKETS_MS.len());
+    slot.count.fetch_add(1, Ordering::Relaxed);
+    slot.total_us.fetch_add(us, Ordering::Relaxed);
+    slot.max_us.fetch_max(us, Ordering::Relaxed);
+    slot.buckets[bucket].fetch_add(1, Ordering::Relaxed);
+    if let Some(code) = error {
+        slot.errors.fetch_add(1, Ordering::Relaxed);
+        if let Some(counter) = slot.codes.get(code as usize) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Bucket {
+    /// Borne exclue ; `None` pour la derniere tranche.
+    below_ms: Option<u64>,
+    count: u64,
+}
+
+#[derive(Serialize)]
+struct CodeCount {
+    code: KeychainErrorCode,
+    count: u64,
+}
+
+#[derive(Serialize)]
+struct OperationMetrics {
+    operation: String,
+    count: u64,
+    errors: u64,
+    errors_by_code: Vec<CodeCount>,
+    buckets: Vec<Bucket>,
+    /// Borne superieure de la tranche qui contient le centile : une
+    /// estimation par exces, `None` dans la derniere tranche.
+    p50_ms: Option<u64>,
+    p95_ms: Option<u64>,
+    mean_ms: f64,
+    max_ms: f64,
+}
+
+#[derive(Serialize)]
+pub(crate) struct MetricsSnapshot {
+    /// Derniere remise a zero ; `None` depuis le demarrage d'ARGOS.
+    since: Option<String>,
+    operations: Vec<OperationMetrics>,
+    /// Mesures ignorees faute d'emplacement libre.
+    dropped: u64,
+}
+
+fn percentile(buckets: &[u64], total: u64, rank: f64) -> Option<u64> {
+    if total == 0 {
+        return None;
+    }
+    let target = ((total as f64) * rank).ceil() as u64;
+    let mut seen = 0;
+    for (i, count) in buckets.iter().enumerate() {
+        seen += count;
+        if seen >= target {
+            return BUCKETS_MS.get(i).copied();
+        }
+    }
+    None
+}
+
+/// Lecture des compteurs. Une mesure concurrente peut n'etre que
+/// partiellement visible ; l'ecart disparait a la lecture suivante.
+pub(crate) fn snapshot() -> MetricsSnapshot {
+    let since = match SINCE.load(Ordering::Relaxed) {
+        0 => None,
+        since => chrono::DateTime::from_timestamp(since, 0)
+            .map(|at| at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)),
+    };
+    let operations = SLOTS
+        .iter()
+        .filter_map(|slot| Some((slot.name.get()?, slot)))
+        .filter(|(_, slot)| slot.count.load(Ordering::Relaxed) > 0)
+        .map(|(name, slot)| {
+            let buckets: Vec<u64> = slot
+                .buckets
+                .iter()
+                .map(|b| b.load(Ordering::Relaxed))
+                .collect();
+            let count: u64 = buckets.iter().sum();
+            let total_us = slot.total_us.load(Ordering::Relaxed);
+            OperationMetrics {
+                operation: name.clone(),
+                count,
+                errors: slot.errors.load(Ordering::Relaxed),
+                errors_by_code: messages::codes()
+                    .filter_map(|code| {
+                        let count = slot.codes.get(code as usize)?.load(Ordering::Relaxed);
+                        (count > 0).then_some(CodeCount { code, count })
+                    })
+                    .collect(),
+                p50_ms: percentile(&buckets, count, 0.50),
+                p95_ms: percentile(&buckets, count, 0.95),
+                mean_ms: if count == 0 {
+                    0.0
+                } else {
+                    total_us as f64 / count as f64 / 1_000.0
+                },
+                max_ms: slot.max_us.load(Ordering::Relaxed) as f64 / 1_000.0,
+                buckets: buckets
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &count)| Bucket {
+                        below_ms: BUCKETS_MS.get(i).copied(),
+                        count,
+                    })
+                    .collect(),
+            }
+        })
+        .collect();
+    MetricsSnapshot {
+        since,
+        operations,
+        dropped: DROPPED.load(Ordering::Relaxed),
+    }
+}
+
+/// Remet les compteurs a zero ; les operations deja vues gardent leur
+/// emplacement.
+pub(crate) fn reset() {
+    SLOTS.iter().for_each(Slot::reset);
+    DROPPED.store(0, Ordering::Relaxed);
+    SINCE.store(chrono::Utc::now().timestamp(), Ordering::Relaxed);
+}
+
+/// Nombre d'appels, erreurs par code et repartition des latences par
+/// operation, depuis le demarrage ou le dernier `metrics_reset`.
+#[tauri::command]
+pub(crate) fn metrics_snapshot() -> MetricsSnapshot {
+    snapshot()
+}
+
+#[tauri::command]
+pub(crate) fn metrics_reset() {
+    reset();
+}
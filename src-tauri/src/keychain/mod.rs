@@ -0,0 +1,3767 @@
+//! Stockage des secrets d'ARGOS. `KeychainManager`, enregistre dans l'etat
+//! Tauri, porte le backend et les verrous ; les commandes de `commands`,
+//! `backup` et `vault` lui deleguent le travail.
+
+mod access;
+pub(crate) mod audit;
+mod auth;
+pub(crate) mod autobackup;
+pub(crate) mod backup;
+pub(crate) mod bulk;
+pub(crate) mod cache;
+pub(crate) mod cipher;
+pub(crate) mod clipboard;
+pub(crate) mod collection;
+pub(crate) mod commands;
+mod confirm;
+mod error;
+mod events;
+pub(crate) mod expiry;
+pub(crate) mod external;
+pub(crate) mod fingerprint;
+pub(crate) mod generate;
+pub(crate) mod json;
+pub(crate) mod kdf;
+mod limiter;
+pub(crate) mod messages;
+pub(crate) mod metrics;
+mod names;
+mod persistence;
+pub(crate) mod process;
+pub(crate) mod profiles;
+pub(crate) mod qr;
+pub(crate) mod rotation;
+pub(crate) mod session;
+mod store;
+pub(crate) mod strength;
+#[cfg(test)]
+pub(crate) mod testing;
+pub(crate) mod totp;
+pub(crate) mod vault;
+pub(crate) mod wipe;
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, RwLock};
+use std::time::Duration;
+
+use base64::Engine;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::config::AppConfig;
+use access::{MacosAccess, MacosReport};
+use audit::{AuditLog, PendingAudit};
+use auth::{Gated, UserAuth};
+use bulk::{BulkOperations, BulkPhase, Progress};
+use cache::{CacheHit, SecretCache};
+use collection::{CollectionStatus, LinuxCollection};
+use confirm::Confirmation;
+pub(crate) use error::{KeychainError, KeychainErrorCode};
+use events::{SecretEvents, SecretOperation};
+use limiter::ReadLimiter;
+use persistence::{WindowsOptions, WindowsPersistence, WindowsReport};
+use profiles::Profiles;
+use rotation::RotationState;
+use session::SessionState;
+pub(crate) use store::LinuxBackend;
+use store::{MemoryStore, SecretStore, Store};
+use totp::{TotpParams, TotpSeed};
+use vault::FileVault;
+
+/// Cle reservee qui contient, pour chaque service, la liste JSON des cles
+/// stockees. Le crate keyring ne sait pas enumerer sur toutes les plateformes.
+const INDEX_KEY: &str = "__argos_index__";
+
+/// Toutes les cles internes d'ARGOS commencent par ce prefixe.
+const RESERVED_KEY_PREFIX: &str = "__argos_";
+
+/// Service ARGOS et prefixe de ses sous-services. Les commandes refusent
+/// tout autre service, sauf les prefixes ajoutes par `argos.json`.
+const ARGOS_SERVICE: &str = "fr.bbrain.argos";
+pub(crate) const SERVICE_PREFIX: &str = "fr.bbrain.argos.";
+
+/// Longueur maximale d'un nom de cle, comptee par `names::length`.
+const MAX_KEY_CHARS: usize = 255;
+
+/// Services renommes, migres une fois au demarrage.
+const STARTUP_MIGRATIONS: &[(&str, &str)] = &[("argos-dev", ARGOS_SERVICE)];
+
+/// Cle du service cible qui liste les anciens services deja migres.
+const MIGRATION_MARKER_KEY: &str = "__argos_migrations__";
+
+/// Les versions precedentes d'un secret sont stockees sous `<cle>#history`.
+const HISTORY_SUFFIX: &str = "#history";
+const DEFAULT_HISTORY_DEPTH: u32 = 3;
+const MAX_HISTORY_DEPTH: u32 = 10;
+/// Taille maximale de l'historique serialise, en unites UTF-16 comme
+/// `CHUNK_UNITS`.
+const MAX_HISTORY_UNITS: usize = CHUNK_UNITS;
+
+/// Windows Credential Manager refuse les secrets de plus de 2560 octets, et
+/// `set_password` y stocke de l'UTF-16 : au-dela de `CHUNK_UNITS` unites, la
+/// valeur est repartie sur des entrees `<cle>#0`, `<cle>#1`...
+const CHUNK_UNITS: usize = 1_200;
+const MAX_CHUNKS: usize = 64;
+const CHUNK_FORMAT_VERSION: u32 = 1;
+
+/// Delai maximal d'un appel au backend keychain (hors macOS, ou l'appel peut
+/// attendre une reponse de l'utilisateur).
+const DEFAULT_TIMEOUT_MS: u64 = 10_000;
+const MIN_TIMEOUT_MS: u64 = 500;
+const MAX_TIMEOUT_MS: u64 = 300_000;
+
+/// Nombre maximal de threads bloques dans un appel qui a expire. Au-dela on
+/// refuse les nouveaux appels plutot que d'empiler des threads.
+const MAX_HUNG_WORKERS: usize = 4;
+
+static TIMEOUT_MS: AtomicU64 = AtomicU64::new(DEFAULT_TIMEOUT_MS);
+static HUNG_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+const MAX_RETRY_ATTEMPTS: u32 = 5;
+const MAX_RETRY_BASE_DELAY_MS: u64 = 2_000;
+
+/// Appels qui ont eu besoin d'au moins une nouvelle tentative, et parmi eux
+/// ceux qui ont fini par reussir.
+static RETRIED_CALLS: AtomicU64 = AtomicU64::new(0);
+static RETRY_RECOVERED: AtomicU64 = AtomicU64::new(0);
+
+/// Tolerance appliquee a `expires_at`, pour absorber un decalage d'horloge.
+const DEFAULT_EXPIRY_GRACE_SECS: u64 = 30;
+const MAX_EXPIRY_GRACE_SECS: u64 = 3_600;
+
+/// TTL maximal accepte par `keychain_set` (dix ans).
+const MAX_TTL_SECS: u64 = 10 * 365 * 24 * 3_600;
+
+static EXPIRY_GRACE_SECS: AtomicU64 = AtomicU64::new(DEFAULT_EXPIRY_GRACE_SECS);
+
+/// Verrou du read-modify-write de l'index, partage par toutes les fenetres.
+#[derive(Default, Clone)]
+pub(crate) struct KeyIndexLock(Arc<Mutex<()>>);
+
+type HeldKeys = HashSet<(String, String)>;
+
+/// Verrous par couple (service, cle), pris par toute ecriture et par les
+/// lectures en plusieurs etapes (fragments, `get_or_set`) : un manifeste de
+/// fragments ou l'index ne voit jamais deux operations entrelacees. Seules
+/// les cles verrouillees sont dans la table, qui ne grossit donc pas avec
+/// le nombre de secrets.
+#[derive(Default, Clone)]
+pub(crate) struct KeyLocks(Arc<(Mutex<HeldKeys>, Condvar)>);
+
+/// Libere ses cles au drop.
+pub(crate) struct KeyGuard {
+    locks: KeyLocks,
+    held: Vec<(String, String)>,
+}
+
+impl KeyLocks {
+    fn held(&self) -> std::sync::MutexGuard<'_, HeldKeys> {
+        self.0 .0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Nombre d'entrees actuellement verrouillees.
+    fn held_count(&self) -> usize {
+        self.held().len()
+    }
+
+    pub(crate) fn lock<'a>(
+        &self,
+        service: &str,
+        keys: impl IntoIterator<Item = &'a str>,
+    ) -> KeyGuard {
+        self.lock_entries(
+            keys.into_iter()
+                .map(|key| (service.to_string(), key.to_string())),
+        )
+    }
+
+    /// Attend que toutes les entrees soient libres puis les prend ensemble :
+    /// deux operations aux cles communes ne peuvent pas s'interbloquer, quel
+    /// que soit l'ordre de leurs cles.
+    pub(crate) fn lock_entries(
+        &self,
+        entries: impl IntoIterator<Item = (String, String)>,
+    ) -> KeyGuard {
+        let mut wanted: Vec<(String, String)> = entries.into_iter().collect();
+        wanted.sort();
+        wanted.dedup();
+        let mut held = self.held();
+        while wanted.iter().any(|entry| held.contains(entry)) {
+            held = self.0 .1.wait(held).unwrap_or_else(|e| e.into_inner());
+        }
+        held.extend(wanted.iter().cloned());
+        KeyGuard {
+            locks: self.clone(),
+            held: wanted,
+        }
+    }
+}
+
+impl Drop for KeyGuard {
+    fn drop(&mut self) {
+        let mut held = self.locks.held();
+        for entry in &self.held {
+            held.remove(entry);
+        }
+        drop(held);
+        self.locks.0 .1.notify_all();
+    }
+}
+
+/// Cle lue une fois au demarrage pour verifier que le backend repond.
+const PROBE_KEY: &str = "__argos_probe__";
+
+/// Entree jetable ecrite, relue puis supprimee par `keychain_status`.
+const HEALTH_KEY: &str = "__argos_health__";
+
+/// Interroge le backend OS une seule fois. S'il est injoignable (erreur
+/// plateforme ou pas de reponse dans le delai), les secrets sont gardes
+/// par keyutils sous Linux, sinon dans `memory` ; la raison du repli est
+/// renvoyee avec le store.
+fn detect_backend(
+    memory: &MemoryStore,
+    collection: &LinuxCollection,
+    linux_backend: LinuxBackend,
+) -> (Store, Option<String>) {
+    let forced = cfg!(target_os = "linux") && linux_backend == LinuxBackend::Keyutils;
+    let (store, failure) = select_backend(
+        memory,
+        forced,
+        || match probe_backend(Arc::new(store::KeyringStore::default())) {
+            None => Ok(Arc::new(keyring_store(collection))),
+            Some(reason) => Err(reason),
+        },
+        || keyutils_store(linux_backend),
+    );
+    tracing::info!(
+        backend = store.name(),
+        fallback_reason = failure.as_deref(),
+        "backend des secrets retenu"
+    );
+    (store, failure)
+}
+
+/// Retient le backend OS s'il repond, puis keyutils, puis `memory`. Les
+/// sondes ne sont appelees qu'au besoin ; `forced` saute celle du backend OS.
+fn select_backend(
+    memory: &MemoryStore,
+    forced: bool,
+    os: impl FnOnce() -> Result<Store, String>,
+    keyutils: impl FnOnce() -> Result<Store, Option<String>>,
+) -> (Store, Option<String>) {
+    let os_failure = if forced {
+        None
+    } else {
+        match os() {
+            Ok(store) => return (store, None),
+            Err(reason) => Some(reason),
+        }
+    };
+    match (keyutils(), os_failure) {
+        (Ok(keyutils), reason) => (keyutils, reason),
+        (Err(Some(keyutils)), Some(reason)) => (
+            Arc::new(memory.clone()),
+            Some(format!("{} ; {}", reason, keyutils)),
+        ),
+        (Err(keyutils), reason) => (Arc::new(memory.clone()), reason.or(keyutils)),
+    }
+}
+
+/// Lit `PROBE_KEY` dans un thread, dans la limite de `DEFAULT_TIMEOUT_MS`.
+/// Renvoie la raison de l'echec si le backend ne repond pas.
+fn probe_backend(store: Store) -> Option<String> {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let probe = std::thread::Builder::new()
+        .name("keychain-probe".into())
+        .spawn(move || {
+            let _ = tx.send(found(store.probe(ARGOS_SERVICE, PROBE_KEY)));
+        });
+    match probe {
+        Err(e) => Some(format!("sonde keychain impossible : {}", e)),
+        Ok(_) => match rx.recv_timeout(Duration::from_millis(DEFAULT_TIMEOUT_MS)) {
+            Ok(Err(e)) if is_transient(e.code()) => Some(e.to_string()),
+            Ok(_) => None,
+            Err(_) => Some(format!(
+                "le backend keychain n'a pas repondu en {} ms",
+                DEFAULT_TIMEOUT_MS
+            )),
+        },
+    }
+}
+
+/// keyutils, s'il repond et que `linux_backend` ne l'exclut pas. L'erreur
+/// porte la raison de son absence, `None` s'il n'a pas ete essaye.
+#[cfg(target_os = "linux")]
+fn keyutils_store(linux_backend: LinuxBackend) -> Result<Store, Option<String>> {
+    if linux_backend == LinuxBackend::SecretService {
+        return Err(None);
+    }
+    let keyutils: Store = Arc::new(store::KeyutilsStore);
+    match probe_backend(Arc::clone(&keyutils)) {
+        None => Ok(keyutils),
+        Some(reason) => Err(Some(format!("keyutils indisponible : {}", reason))),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn keyutils_store(_linux_backend: LinuxBackend) -> Result<Store, Option<String>> {
+    Err(None)
+}
+
+/// Sous Linux, prepare la collection choisie hors du delai de la sonde : sa
+/// creation ou son deverrouillage attend la reponse de l'utilisateur.
+#[cfg(target_os = "linux")]
+fn keyring_store(collection: &LinuxCollection) -> store::KeyringStore {
+    let target = collection::prepare(collection).unwrap_or_else(|e| {
+        tracing::warn!(error = %e, "collection Secret Service non preparee");
+        collection.fallback_target()
+    });
+    store::KeyringStore::in_collection(collection.clone(), target)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn keyring_store(_collection: &LinuxCollection) -> store::KeyringStore {
+    store::KeyringStore::default()
+}
+
+/// Politique de nouvelle tentative des erreurs transitoires, surchargeable
+/// par appel via le parametre optionnel `retry`.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub(crate) struct RetryPolicy {
+    attempts: u32,
+    base_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            attempts: 3,
+            base_delay_ms: 200,
+        }
+    }
+}
+
+impl RetryPolicy {
+    fn max_attempts(&self) -> u32 {
+        self.attempts.clamp(1, MAX_RETRY_ATTEMPTS)
+    }
+
+    /// Backoff exponentiel avec jitter, pour que deux fenetres qui echouent
+    /// ensemble ne reessaient pas au meme instant.
+    fn delay(&self, attempt: u32) -> Duration {
+        let base = self.base_delay_ms.min(MAX_RETRY_BASE_DELAY_MS);
+        let backoff = base.saturating_mul(1 << (attempt - 1).min(4));
+        let jitter = rand::thread_rng().gen_range(0..=backoff / 2);
+        Duration::from_millis(backoff + jitter)
+    }
+}
+
+/// Seules les pannes du backend sont reessayees ; `NotFound` et
+/// `AccessDenied` sont des reponses definitives.
+fn is_transient(code: KeychainErrorCode) -> bool {
+    matches!(
+        code,
+        KeychainErrorCode::PlatformError | KeychainErrorCode::BackendUnavailable
+    )
+}
+
+/// Entree (service, cle) d'un `SecretStore`, passee aux operations de
+/// `keyring_call`.
+struct StoreEntry<'a> {
+    store: &'a dyn SecretStore,
+    service: &'a str,
+    key: &'a str,
+}
+
+impl StoreEntry<'_> {
+    fn get_password(&self) -> Result<Zeroizing<String>, KeychainError> {
+        self.store
+            .get_password(self.service, self.key)
+            .map(Zeroizing::new)
+    }
+
+    fn set_password(&self, value: &str) -> Result<(), KeychainError> {
+        self.store.set_password(self.service, self.key, value)
+    }
+
+    fn get_secret(&self) -> Result<Zeroizing<Vec<u8>>, KeychainError> {
+        self.store.get(self.service, self.key).map(Zeroizing::new)
+    }
+
+    fn set_secret(&self, value: &[u8]) -> Result<(), KeychainError> {
+        self.store.set(self.service, self.key, value)
+    }
+
+    fn delete_credential(&self) -> Result<(), KeychainError> {
+        self.store.delete(self.service, self.key)
+    }
+
+    fn probe(&self) -> Result<(), KeychainError> {
+        self.store.probe(self.service, self.key)
+    }
+}
+
+/// Applique `op` a l'entree, en reessayant les erreurs transitoires : juste
+/// apres l'ouverture de session, le Secret Service n'est pas toujours pret.
+fn keyring_call<T>(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    mut op: impl FnMut(&StoreEntry) -> Result<T, KeychainError>,
+) -> Result<T, KeychainError> {
+    let entry = StoreEntry {
+        store: store.as_ref(),
+        service,
+        key,
+    };
+    let max_attempts = retry.max_attempts();
+    let mut attempt = 1;
+    loop {
+        let result = op(&entry);
+        match result {
+            Ok(value) => {
+                if attempt > 1 {
+                    RETRY_RECOVERED.fetch_add(1, Ordering::Relaxed);
+                }
+                return Ok(value);
+            }
+            Err(e) if attempt < max_attempts && is_transient(e.code()) => {
+                if attempt == 1 {
+                    RETRIED_CALLS.fetch_add(1, Ordering::Relaxed);
+                }
+                tracing::debug!(service, key, attempt, code = ?e.code(), "nouvelle tentative");
+                std::thread::sleep(retry.delay(attempt));
+                attempt += 1;
+            }
+            Err(e) => return Err(e.with_attempts(attempt)),
+        }
+    }
+}
+
+/// Transforme un `NotFound` en `Ok(None)`.
+fn found<T>(result: Result<T, KeychainError>) -> Result<Option<T>, KeychainError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == KeychainErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Refuse les noms de cle vides, trop longs ou avec des caracteres de
+/// controle, ainsi que les cles reservees a ARGOS pour que le webview ne
+/// puisse pas ecraser l'index ou les marqueurs internes.
+fn check_user_key(key: &str) -> Result<(), KeychainError> {
+    if key.is_empty() {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::EmptyKey,
+            "le nom de cle est vide",
+        ));
+    }
+    if key.chars().any(char::is_control) {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidKeyCharacters,
+            "le nom de cle contient des caracteres de controle",
+        ));
+    }
+    if names::length(key) > MAX_KEY_CHARS {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::KeyTooLong,
+            format!("le nom de cle depasse {} caracteres", MAX_KEY_CHARS),
+        ));
+    }
+    if key.starts_with(RESERVED_KEY_PREFIX) {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!(
+                "les cles commencant par {} sont reservees",
+                RESERVED_KEY_PREFIX
+            ),
+        ));
+    }
+    if key.ends_with(HISTORY_SUFFIX) || is_chunk_key(key) {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!("les suffixes {} et #<numero> sont reserves", HISTORY_SUFFIX),
+        ));
+    }
+    Ok(())
+}
+
+/// Lit l'index d'un service. Un index absent ou corrompu n'est pas une
+/// erreur : on recupere les noms encore lisibles et on repart de la. Une
+/// erreur du backend est en revanche remontee, pour ne pas reecrire un index
+/// vide par-dessus un index simplement illisible pour l'instant.
+fn read_index(store: &Store, service: &str) -> Result<Vec<String>, KeychainError> {
+    read_index_with(store, service, RetryPolicy::default())
+}
+
+/// `read_index` avec la politique de nouvelles tentatives de l'appelant.
+fn read_index_with(
+    store: &Store,
+    service: &str,
+    retry: RetryPolicy,
+) -> Result<Vec<String>, KeychainError> {
+    let raw = match keyring_call(store, retry, service, INDEX_KEY, |entry| {
+        entry.get_password()
+    }) {
+        Ok(raw) => raw,
+        Err(e) if e.code() == KeychainErrorCode::NotFound => return Ok(Vec::new()),
+        Err(e) if e.code() == KeychainErrorCode::BadEncoding => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut keys: Vec<String> = match serde_json::from_str::<serde_json::Value>(&raw) {
+        Ok(serde_json::Value::Array(items)) => items
+            .into_iter()
+            .filter_map(|item| match item {
+                serde_json::Value::String(key) if !key.starts_with(RESERVED_KEY_PREFIX) => {
+                    Some(key)
+                }
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    keys.sort();
+    keys.dedup();
+    Ok(keys)
+}
+
+fn write_index(store: &Store, service: &str, keys: &[String]) -> Result<(), KeychainError> {
+    let raw = serde_json::to_string(keys).unwrap_or_else(|_| "[]".to_string());
+    keyring_call(store, RetryPolicy::default(), service, INDEX_KEY, |entry| {
+        entry.set_password(&raw)
+    })
+}
+
+/// Met a jour l'index apres un set ou un delete. Un echec ici ne doit pas
+/// faire echouer l'operation principale, il est donc ignore.
+fn update_index(
+    store: &Store,
+    lock: &KeyIndexLock,
+    service: &str,
+    changed: &[&str],
+    present: bool,
+) {
+    if changed.is_empty() {
+        return;
+    }
+    let _guard = lock.0.lock().unwrap_or_else(|e| e.into_inner());
+    let mut keys = match read_index(store, service) {
+        Ok(keys) => keys,
+        Err(_) => return,
+    };
+    let before = keys.len();
+    if present {
+        keys.extend(changed.iter().map(|k| k.to_string()));
+        keys.sort();
+        keys.dedup();
+    } else {
+        keys.retain(|k| !changed.contains(&k.as_str()));
+    }
+    if keys.len() != before {
+        let _ = write_index(store, service, &keys);
+    }
+}
+
+/// Chaine secrete effacee de la memoire a sa liberation. Toute valeur de
+/// secret cote Rust passe par ce type, jusqu'a la serialisation vers le
+/// webview. `Debug` affiche `[REDACTED]` et `Display` n'existe pas : la
+/// valeur ne peut pas finir dans le journal ni dans un message d'erreur.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub(crate) struct Secret(Zeroizing<String>);
+
+impl Secret {
+    pub(crate) fn new(value: String) -> Self {
+        Secret(Zeroizing::new(value))
+    }
+}
+
+impl From<Zeroizing<String>> for Secret {
+    fn from(value: Zeroizing<String>) -> Self {
+        Secret(value)
+    }
+}
+
+/// Le seul champ est un `Zeroizing`.
+impl zeroize::ZeroizeOnDrop for Secret {}
+
+/// Decode du base64 dans un buffer efface a sa liberation.
+fn decode_base64(encoded: &str) -> Result<Zeroizing<Vec<u8>>, base64::DecodeError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .map(Zeroizing::new)
+}
+
+impl std::ops::Deref for Secret {
+    type Target = String;
+
+    fn deref(&self) -> &String {
+        &self.0
+    }
+}
+
+impl std::ops::DerefMut for Secret {
+    fn deref_mut(&mut self) -> &mut String {
+        &mut self.0
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+const ENVELOPE_VERSION: u32 = 1;
+
+/// Enveloppe JSON ecrite par ARGOS autour de chaque secret texte.
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    v: u32,
+    value: String,
+    label: Option<String>,
+    created_at: String,
+    updated_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expires_at: Option<String>,
+    /// Lecture soumise a une verification de l'utilisateur par le systeme.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    require_auth: bool,
+    /// Parametres TOTP, pour une graine lue par `totp_generate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    totp: Option<TotpParams>,
+    /// Lectures servies par le cache memoire, meme sans `cache: true`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    cache: bool,
+    /// Cle ecrite par `keychain_set_encryption_key`, jamais renvoyee.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    encryption_key: bool,
+}
+
+impl Drop for Envelope {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+/// Indique si une date d'expiration, delai de grace compris, est depassee.
+/// Une date illisible est consideree comme non expiree.
+fn is_past(expires_at: Option<&str>) -> bool {
+    let Some(expires_at) = expires_at else {
+        return false;
+    };
+    let Ok(expires_at) = chrono::DateTime::parse_from_rfc3339(expires_at) else {
+        return false;
+    };
+    let grace = chrono::Duration::seconds(EXPIRY_GRACE_SECS.load(Ordering::Relaxed) as i64);
+    chrono::Utc::now() > expires_at + grace
+}
+
+impl Envelope {
+    fn is_expired(&self) -> bool {
+        is_past(self.expires_at.as_deref())
+    }
+}
+
+/// Contenu d'une entree : une enveloppe, ou une chaine brute ecrite par une
+/// version anterieure d'ARGOS (migree a la prochaine ecriture).
+enum Stored {
+    Envelope(Envelope),
+    Legacy(Secret),
+}
+
+impl Stored {
+    fn parse(raw: Secret) -> Self {
+        match serde_json::from_str::<Envelope>(&raw) {
+            Ok(envelope) if envelope.v == ENVELOPE_VERSION => Stored::Envelope(envelope),
+            _ => Stored::Legacy(raw),
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        matches!(self, Stored::Envelope(envelope) if envelope.is_expired())
+    }
+
+    fn requires_auth(&self) -> bool {
+        matches!(self, Stored::Envelope(envelope) if envelope.require_auth)
+    }
+
+    fn is_encryption_key(&self) -> bool {
+        matches!(self, Stored::Envelope(envelope) if envelope.encryption_key)
+    }
+
+    fn is_totp(&self) -> bool {
+        matches!(self, Stored::Envelope(envelope) if envelope.totp.is_some())
+    }
+
+    /// Parametres par defaut (SHA-1, 6 chiffres, 30 s) si l'enveloppe n'en
+    /// porte pas.
+    fn totp_params(&self) -> TotpParams {
+        match self {
+            Stored::Envelope(envelope) => envelope.totp.unwrap_or_default(),
+            Stored::Legacy(_) => TotpParams::default(),
+        }
+    }
+
+    /// Expiration et politique de cache de l'enveloppe.
+    fn cache_hint(&self) -> (Option<String>, bool) {
+        match self {
+            Stored::Envelope(envelope) => (envelope.expires_at.clone(), envelope.cache),
+            Stored::Legacy(_) => (None, false),
+        }
+    }
+
+    fn into_value(self) -> Secret {
+        match self {
+            Stored::Envelope(mut envelope) => Secret::new(std::mem::take(&mut envelope.value)),
+            Stored::Legacy(value) => value,
+        }
+    }
+}
+
+/// Indique si un contenu brut est une enveloppe marquee `require_auth`.
+fn raw_requires_auth(raw: &str) -> bool {
+    matches!(
+        serde_json::from_str::<Envelope>(raw),
+        Ok(envelope) if envelope.v == ENVELOPE_VERSION && envelope.require_auth
+    )
+}
+
+/// Indique si un contenu brut est une cle de `keychain_set_encryption_key`.
+fn raw_is_encryption_key(raw: &[u8]) -> bool {
+    matches!(
+        serde_json::from_slice::<Envelope>(raw),
+        Ok(envelope) if envelope.v == ENVELOPE_VERSION && envelope.encryption_key
+    )
+}
+
+fn encryption_key_protected(key: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::EncryptionKeyProtected,
+        format!(
+            "{} est une cle de chiffrement : elle ne quitte pas le keychain",
+            key
+        ),
+    )
+}
+
+fn now_rfc3339() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+/// Manifeste stocke sous la cle d'un secret fragmente.
+#[derive(Serialize, Deserialize)]
+struct ChunkManifest {
+    argos_chunks: u32,
+    count: usize,
+    len: usize,
+    sha256: String,
+}
+
+impl ChunkManifest {
+    fn parse(raw: &str) -> Option<Self> {
+        serde_json::from_str::<ChunkManifest>(raw)
+            .ok()
+            .filter(|m| m.argos_chunks == CHUNK_FORMAT_VERSION)
+    }
+}
+
+fn chunk_key(key: &str, index: usize) -> String {
+    format!("{}#{}", key, index)
+}
+
+fn is_chunk_key(key: &str) -> bool {
+    matches!(key.rsplit_once('#'), Some((_, n)) if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+}
+
+fn sha256_hex(data: &str) -> String {
+    Sha256::digest(data.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn corrupt(key: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::Corrupt,
+        format!("le secret fragmente {} est incomplet ou altere", key),
+    )
+}
+
+/// Decoupe `raw` en fragments d'au plus `CHUNK_UNITS` unites UTF-16, sans
+/// couper un caractere.
+fn split_chunks(raw: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    let mut units = 0;
+    for (offset, c) in raw.char_indices() {
+        if units + c.len_utf16() > CHUNK_UNITS {
+            chunks.push(&raw[start..offset]);
+            start = offset;
+            units = 0;
+        }
+        units += c.len_utf16();
+    }
+    chunks.push(&raw[start..]);
+    chunks
+}
+
+/// Nombre de fragments de l'entree actuelle, 0 si elle n'est pas fragmentee.
+fn chunk_count(store: &Store, retry: RetryPolicy, service: &str, key: &str) -> usize {
+    found(keyring_call(store, retry, service, key, |entry| {
+        entry.get_password()
+    }))
+    .ok()
+    .flatten()
+    .and_then(|raw| ChunkManifest::parse(&raw))
+    .map_or(0, |m| m.count)
+}
+
+/// Cle de l'entree suivie de celles de ses fragments.
+fn entry_keys(store: &Store, retry: RetryPolicy, service: &str, key: &str) -> Vec<String> {
+    let count = chunk_count(store, retry, service, key);
+    std::iter::once(key.to_string())
+        .chain((0..count).map(|i| chunk_key(key, i)))
+        .collect()
+}
+
+/// Applique `access` a l'entree et a chacun de ses fragments.
+fn protect_entry(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    access: &MacosAccess,
+) -> Result<MacosReport, KeychainError> {
+    for entry_key in entry_keys(store, retry, service, key) {
+        if !store.protect(service, &entry_key, access)? {
+            return Ok(MacosReport::ignored());
+        }
+    }
+    Ok(MacosReport::applied(*access))
+}
+
+/// Applique `persistence` a l'entree et a chacun de ses fragments.
+fn persist_entry(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    persistence: WindowsPersistence,
+) -> Result<WindowsReport, KeychainError> {
+    for entry_key in entry_keys(store, retry, service, key) {
+        if !store.persist(service, &entry_key, persistence)? {
+            return Ok(WindowsReport::ignored());
+        }
+    }
+    Ok(WindowsReport::applied(persistence))
+}
+
+fn delete_chunks(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    from: usize,
+    to: usize,
+) {
+    for index in from..to {
+        let _ = keyring_call(store, retry, service, &chunk_key(key, index), |entry| {
+            entry.delete_credential()
+        });
+    }
+}
+
+/// Lit le contenu brut d'une entree, enveloppe comprise, en reassemblant
+/// les fragments. Un fragment manquant ou une somme de controle fausse (ecriture
+/// interrompue) donne `Corrupt`.
+fn read_raw(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+) -> Result<Option<Secret>, KeychainError> {
+    check_user_key(key)?;
+    let Some(raw) = found(keyring_call(store, retry, service, key, |entry| {
+        entry.get_password()
+    }))?
+    .map(Secret::from) else {
+        return Ok(None);
+    };
+    let Some(manifest) = ChunkManifest::parse(&raw) else {
+        return Ok(Some(raw));
+    };
+    let mut value = Secret::new(String::with_capacity(manifest.len));
+    for index in 0..manifest.count {
+        match found(keyring_call(
+            store,
+            retry,
+            service,
+            &chunk_key(key, index),
+            |entry| entry.get_password(),
+        ))? {
+            Some(part) => value.push_str(&part),
+            None => return Err(corrupt(key)),
+        }
+    }
+    if value.len() != manifest.len || sha256_hex(&value) != manifest.sha256 {
+        return Err(corrupt(key));
+    }
+    Ok(Some(value))
+}
+
+/// Ecrit le contenu brut d'une entree, fragmente si besoin. Les fragments
+/// sont ecrits avant le manifeste, et les fragments en trop supprimes apres.
+fn write_raw(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    raw: &str,
+) -> Result<(), KeychainError> {
+    check_user_key(key)?;
+    let previous = chunk_count(store, retry, service, key);
+    if raw.encode_utf16().count() <= CHUNK_UNITS {
+        keyring_call(store, retry, service, key, |entry| entry.set_password(raw))?;
+        delete_chunks(store, retry, service, key, 0, previous);
+        return Ok(());
+    }
+    let chunks = split_chunks(raw);
+    if chunks.len() > MAX_CHUNKS {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!("secret trop volumineux ({} octets)", raw.len()),
+        ));
+    }
+    for (index, chunk) in chunks.iter().enumerate() {
+        keyring_call(store, retry, service, &chunk_key(key, index), |entry| {
+            entry.set_password(chunk)
+        })?;
+    }
+    let manifest = ChunkManifest {
+        argos_chunks: CHUNK_FORMAT_VERSION,
+        count: chunks.len(),
+        len: raw.len(),
+        sha256: sha256_hex(raw),
+    };
+    let manifest = serde_json::to_string(&manifest).unwrap_or_default();
+    keyring_call(store, retry, service, key, |entry| {
+        entry.set_password(&manifest)
+    })?;
+    delete_chunks(store, retry, service, key, chunks.len(), previous);
+    Ok(())
+}
+
+/// Supprime une entree et ses fragments. Renvoie `None` si elle n'existait
+/// pas.
+fn delete_entry(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+) -> Result<Option<()>, KeychainError> {
+    let count = chunk_count(store, retry, service, key);
+    let deleted = found(keyring_call(store, retry, service, key, |entry| {
+        entry.delete_credential()
+    }))?;
+    delete_chunks(store, retry, service, key, 0, count);
+    Ok(deleted)
+}
+
+/// Metadonnees d'enveloppe fournies a l'ecriture.
+#[derive(Default)]
+struct EnvelopeMeta<'a> {
+    label: Option<&'a str>,
+    expires_at: Option<String>,
+    require_auth: Option<bool>,
+    totp: Option<TotpParams>,
+    cache: Option<bool>,
+    encryption_key: bool,
+}
+
+/// Ecrit `value` dans une enveloppe. La date de creation, ainsi que le
+/// libelle, `require_auth`, les parametres TOTP et la politique de cache
+/// s'ils ne sont pas fournis, sont repris d'une enveloppe existante ;
+/// l'expiration, elle, est toujours remplacee. Renvoie le contenu remplace.
+fn write_password(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    value: &str,
+    meta: EnvelopeMeta<'_>,
+) -> Result<Option<Stored>, KeychainError> {
+    let now = now_rfc3339();
+    let previous = match read_raw(store, retry, service, key) {
+        Ok(raw) => raw.map(Stored::parse),
+        // Une entree binaire ou illisible est simplement remplacee.
+        Err(e) if e.code() == KeychainErrorCode::BadEncoding => None,
+        Err(e) => return Err(e),
+    };
+    let (created_at, previous_label, previous_auth, previous_totp, previous_cache) = match &previous
+    {
+        Some(Stored::Envelope(envelope)) => (
+            envelope.created_at.clone(),
+            envelope.label.clone(),
+            envelope.require_auth,
+            envelope.totp,
+            envelope.cache,
+        ),
+        _ => (now.clone(), None, false, None, false),
+    };
+    let envelope = Envelope {
+        v: ENVELOPE_VERSION,
+        value: value.to_string(),
+        label: meta.label.map(str::to_string).or(previous_label),
+        created_at,
+        updated_at: now,
+        expires_at: meta.expires_at,
+        require_auth: meta.require_auth.unwrap_or(previous_auth),
+        totp: meta.totp.or(previous_totp),
+        cache: meta.cache.unwrap_or(previous_cache),
+        encryption_key: meta.encryption_key,
+    };
+    let raw = Zeroizing::new(serde_json::to_string(&envelope).map_err(|e| {
+        KeychainError::rejected(
+            KeychainErrorCode::PlatformError,
+            format!("serialisation de l'enveloppe impossible : {}", e),
+        )
+    })?);
+    write_raw(store, retry, service, key, &raw)?;
+    Ok(previous)
+}
+
+/// Message affiche par le systeme lors de la verification.
+fn auth_reason(key: &str) -> String {
+    format!("ARGOS demande l'acces au secret {}", key)
+}
+
+fn expired(key: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::Expired,
+        format!("le secret {} a expire", key),
+    )
+}
+
+/// Lit le contenu d'un secret, qu'il soit enveloppe ou non. Un secret
+/// expire donne une erreur `Expired`, une cle de chiffrement
+/// `EncryptionKeyProtected`.
+fn read_stored(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+) -> Result<Option<Stored>, KeychainError> {
+    match read_raw(store, retry, service, key)?.map(Stored::parse) {
+        Some(stored) if stored.is_encryption_key() => Err(encryption_key_protected(key)),
+        Some(stored) if stored.is_expired() => Err(expired(key)),
+        stored => Ok(stored),
+    }
+}
+
+/// Compare la valeur actuelle a `expected` par empreinte SHA-256, pour ne
+/// pas dependre du temps de comparaison. Une entree expiree compte avec sa
+/// valeur, une entree binaire ne correspond a rien.
+fn current_matches(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    expected: Option<&str>,
+) -> Result<bool, KeychainError> {
+    let current = match read_raw(store, retry, service, key) {
+        Ok(raw) => raw.map(|raw| Stored::parse(raw).into_value()),
+        Err(e) if e.code() == KeychainErrorCode::BadEncoding => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    Ok(match (current, expected) {
+        (None, None) => true,
+        (Some(current), Some(expected)) => {
+            Sha256::digest(current.as_bytes()) == Sha256::digest(expected.as_bytes())
+        }
+        _ => false,
+    })
+}
+
+/// Version precedente d'un secret, la plus recente en tete.
+#[derive(Serialize, Deserialize)]
+struct HistoryEntry {
+    id: u64,
+    value: String,
+    saved_at: String,
+}
+
+impl Drop for HistoryEntry {
+    fn drop(&mut self) {
+        self.value.zeroize();
+    }
+}
+
+fn history_key(key: &str) -> String {
+    format!("{}{}", key, HISTORY_SUFFIX)
+}
+
+/// Un historique absent ou illisible est traite comme vide.
+fn read_history(store: &Store, retry: RetryPolicy, service: &str, key: &str) -> Vec<HistoryEntry> {
+    found(keyring_call(
+        store,
+        retry,
+        service,
+        &history_key(key),
+        |entry| entry.get_password(),
+    ))
+    .ok()
+    .flatten()
+    .and_then(|raw| serde_json::from_str(&raw).ok())
+    .unwrap_or_default()
+}
+
+/// Ecrit l'historique en le tronquant a `depth` versions puis a
+/// `MAX_HISTORY_UNITS`. Un historique vide supprime l'entree.
+fn write_history(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    mut history: Vec<HistoryEntry>,
+    depth: u32,
+) -> Result<(), KeychainError> {
+    history.truncate(depth as usize);
+    let mut raw = Zeroizing::new(serde_json::to_string(&history).unwrap_or_default());
+    while raw.encode_utf16().count() > MAX_HISTORY_UNITS && !history.is_empty() {
+        history.pop();
+        raw = Zeroizing::new(serde_json::to_string(&history).unwrap_or_default());
+    }
+    if history.is_empty() {
+        return delete_history(store, retry, service, key);
+    }
+    keyring_call(store, retry, service, &history_key(key), |entry| {
+        entry.set_password(&raw)
+    })
+}
+
+fn delete_history(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+) -> Result<(), KeychainError> {
+    found(keyring_call(
+        store,
+        retry,
+        service,
+        &history_key(key),
+        |entry| entry.delete_credential(),
+    ))
+    .map(|_| ())
+}
+
+/// Ajoute la valeur remplacee en tete de l'historique. Comme pour l'index, un
+/// echec ici ne fait pas echouer l'ecriture principale. Une cle de
+/// chiffrement remplacee n'y entre pas : l'historique se relit.
+fn record_history(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    previous: Option<Stored>,
+    depth: u32,
+) {
+    if let Some(previous) = previous.filter(|previous| !previous.is_encryption_key()) {
+        let _ = push_history(store, retry, service, key, previous, depth);
+    }
+}
+
+/// Comme `record_history`, en remontant l'echec. Renvoie l'identifiant de la
+/// version ajoutee.
+fn push_history(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    previous: Stored,
+    depth: u32,
+) -> Result<u64, KeychainError> {
+    let saved_at = match &previous {
+        Stored::Envelope(envelope) => envelope.updated_at.clone(),
+        Stored::Legacy(_) => now_rfc3339(),
+    };
+    let mut history = read_history(store, retry, service, key);
+    let id = history.iter().map(|h| h.id).max().unwrap_or(0) + 1;
+    history.insert(
+        0,
+        HistoryEntry {
+            id,
+            value: std::mem::take(&mut *previous.into_value()),
+            saved_at,
+        },
+    );
+    write_history(store, retry, service, key, history, depth)?;
+    Ok(id)
+}
+
+/// Empreinte courte d'une valeur, pour distinguer les versions sans les
+/// exposer.
+fn fingerprint(value: &str) -> String {
+    sha256_hex(value)[..8].to_string()
+}
+
+fn worker_interrupted(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::PlatformError,
+        format!("tache keychain interrompue : {}", detail),
+    )
+}
+
+/// Execute un appel keyring bloquant hors du thread principal : sur macOS
+/// le keychain peut afficher une fenetre d'autorisation, et le webview doit
+/// continuer a se redessiner pendant ce temps.
+///
+/// Ailleurs l'appel est borne par `TIMEOUT_MS` : un gnome-keyring fige ou une
+/// session DBus disparue peuvent bloquer indefiniment. Le thread d'un appel
+/// expire est abandonne (il se termine seul si le backend repond un jour) et
+/// compte dans `HUNG_WORKERS`. Il garde les verrous de cles qu'il a pris :
+/// les relacher laisserait une autre ecriture s'entrelacer avec la sienne.
+/// Les appels suivants sur ces cles attendent donc, expirent a leur tour, et
+/// `keychain_status` les compte dans `held_keys`.
+async fn run_blocking<T, F>(f: F) -> Result<T, KeychainError>
+where
+    F: FnOnce() -> Result<T, KeychainError> + Send + 'static,
+    T: Send + 'static,
+{
+    if cfg!(target_os = "macos") {
+        return tauri::async_runtime::spawn_blocking(f)
+            .await
+            .map_err(worker_interrupted)?;
+    }
+
+    if HUNG_WORKERS.load(Ordering::SeqCst) >= MAX_HUNG_WORKERS {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::Timeout,
+            "backend keychain bloque, trop d'appels en attente",
+        ));
+    }
+
+    // 0 = en cours, 1 = termine, 2 = abandonne apres expiration.
+    let state = Arc::new(AtomicU8::new(0));
+    let worker_state = Arc::clone(&state);
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    std::thread::Builder::new()
+        .name("argos-keychain".to_string())
+        .spawn(move || {
+            let result = f();
+            if worker_state
+                .compare_exchange(0, 1, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                HUNG_WORKERS.fetch_sub(1, Ordering::SeqCst);
+            }
+            let _ = tx.send(result);
+        })
+        .map_err(worker_interrupted)?;
+
+    let timeout = Duration::from_millis(TIMEOUT_MS.load(Ordering::SeqCst));
+    match tokio::time::timeout(timeout, rx).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => Err(worker_interrupted(e)),
+        Err(_) => {
+            HUNG_WORKERS.fetch_add(1, Ordering::SeqCst);
+            tracing::warn!(
+                timeout_ms = timeout.as_millis() as u64,
+                "appel keychain bloque"
+            );
+            if state
+                .compare_exchange(0, 2, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                // Le thread a fini juste apres l'expiration.
+                HUNG_WORKERS.fetch_sub(1, Ordering::SeqCst);
+            }
+            Err(KeychainError::rejected(
+                KeychainErrorCode::Timeout,
+                format!(
+                    "le backend keychain n'a pas repondu en {} ms",
+                    timeout.as_millis()
+                ),
+            ))
+        }
+    }
+}
+
+/// Reglages d'execution des appels keychain.
+#[derive(Serialize)]
+pub(crate) struct KeychainSettings {
+    timeout_ms: u64,
+    expiry_grace_seconds: u64,
+    auth_grace_seconds: u64,
+    hung_workers: usize,
+    retried_calls: u64,
+    retry_recovered: u64,
+}
+
+/// Backend keychain retenu au demarrage et resultat de la sonde de sante.
+#[derive(Serialize)]
+pub(crate) struct KeychainStatus {
+    backend: &'static str,
+    /// `false` si les secrets sont gardes en memoire (aucun keychain OS).
+    persistent: bool,
+    fallback_reason: Option<String>,
+    /// Nombre d'entrees du store memoire (chunks et cles internes compris).
+    memory_entries: usize,
+    /// En mode portable, indique si le coffre est verrouille.
+    vault_locked: Option<bool>,
+    /// Lignes du journal d'audit perdues depuis le demarrage.
+    audit_failures: u64,
+    /// Cles verrouillees au moment de la sonde, y compris celles que garde
+    /// un appel abandonne par `run_blocking`.
+    held_keys: usize,
+    /// `true` si le backend a repondu a la sonde, meme par un refus.
+    reachable: bool,
+    /// `None` quand l'etat de verrouillage ne peut pas etre deduit.
+    locked: Option<bool>,
+    latency_ms: u64,
+    platform: &'static str,
+    last_error: Option<KeychainError>,
+    /// Collection Secret Service utilisee, sous Linux seulement.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    collection: Option<CollectionStatus>,
+}
+
+impl KeychainStatus {
+    pub(crate) fn backend(&self) -> &'static str {
+        self.backend
+    }
+
+    /// Le backend repond et n'est pas verrouille.
+    pub(crate) fn is_available(&self) -> bool {
+        self.reachable && self.locked != Some(true)
+    }
+}
+
+/// Options d'ecriture de `keychain_set`.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub(crate) struct SetOptions {
+    label: Option<String>,
+    /// Duree de vie du secret ; `keychain_get` renvoie `Expired` au-dela.
+    ttl_seconds: Option<u64>,
+    /// Nombre de valeurs precedentes conservees (3 par defaut, 0 pour
+    /// desactiver l'historique).
+    history: Option<u32>,
+    /// Exige une verification de l'utilisateur (Windows Hello, Touch ID,
+    /// polkit) avant de renvoyer le secret. Non fourni, le reglage de
+    /// l'enveloppe existante est conserve.
+    require_auth: Option<bool>,
+    /// Marque la valeur comme graine TOTP en base32, verifiee a l'ecriture.
+    totp: Option<TotpParams>,
+    /// Sert toujours ce secret depuis le cache memoire, comme si chaque
+    /// `keychain_get` passait `cache: true`. Non fourni, le reglage de
+    /// l'enveloppe existante est conserve.
+    cache: Option<bool>,
+    /// Accessibilite et presence de l'utilisateur dans le trousseau macOS ;
+    /// ignore par les autres backends.
+    macos: Option<MacosAccess>,
+    /// Portee de persistance dans le gestionnaire d'identifiants Windows ;
+    /// ignoree par les autres backends.
+    windows: Option<WindowsOptions>,
+    /// Ecrit seulement si la valeur actuelle est celle-ci, ou si l'entree
+    /// est absente pour `null` ; sinon `Conflict`. Absent, l'ecriture est
+    /// inconditionnelle.
+    #[serde(deserialize_with = "present")]
+    if_matches: Option<Option<Secret>>,
+    /// Reserve a `keychain_set_encryption_key`, hors de portee du webview.
+    #[serde(skip)]
+    encryption_key: bool,
+}
+
+/// Distingue un champ `null` (`Some(None)`) d'un champ absent (`None`).
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Resultat d'une ecriture. `persistent` vaut `false` quand le secret n'est
+/// garde qu'en memoire et sera perdu a la fermeture d'ARGOS.
+#[derive(Serialize)]
+pub(crate) struct SetReport {
+    persistent: bool,
+    /// Options macOS appliquees, present seulement si elles ont ete demandees.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    macos: Option<MacosReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    windows: Option<WindowsReport>,
+}
+
+/// Valeur effectivement stockee par `keychain_get_or_set`.
+#[derive(Serialize)]
+pub(crate) struct GetOrSetResult {
+    value: Secret,
+    created: bool,
+    persistent: bool,
+}
+
+/// Metadonnees d'un secret, sans sa valeur. Les entrees anterieures aux
+/// enveloppes n'ont ni libelle ni dates et sont marquees `legacy`.
+#[derive(Serialize)]
+pub(crate) struct SecretMetadata {
+    label: Option<String>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    expires_at: Option<String>,
+    legacy: bool,
+}
+
+impl SecretMetadata {
+    /// Entree sans enveloppe : chaine d'une version anterieure ou octets.
+    fn legacy() -> Self {
+        SecretMetadata {
+            label: None,
+            created_at: None,
+            updated_at: None,
+            expires_at: None,
+            legacy: true,
+        }
+    }
+
+    fn of(stored: &Stored) -> Self {
+        match stored {
+            Stored::Envelope(envelope) => SecretMetadata {
+                label: envelope.label.clone(),
+                created_at: Some(envelope.created_at.clone()),
+                updated_at: Some(envelope.updated_at.clone()),
+                expires_at: envelope.expires_at.clone(),
+                legacy: false,
+            },
+            Stored::Legacy(_) => SecretMetadata::legacy(),
+        }
+    }
+
+    fn is_expired(&self) -> bool {
+        is_past(self.expires_at.as_deref())
+    }
+}
+
+/// Metadonnees de l'entree, sans verifier son expiration.
+fn read_metadata(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+) -> Result<Option<SecretMetadata>, KeychainError> {
+    match read_raw(store, retry, service, key) {
+        Ok(raw) => Ok(raw.map(|raw| SecretMetadata::of(&Stored::parse(raw)))),
+        // Secret binaire ecrit par keychain_set_bytes : pas d'enveloppe.
+        Err(e) if e.code() == KeychainErrorCode::BadEncoding => Ok(Some(SecretMetadata::legacy())),
+        Err(e) => Err(e),
+    }
+}
+
+/// Version de l'historique telle qu'exposee au webview : jamais la valeur.
+#[derive(Serialize)]
+pub(crate) struct HistoryVersion {
+    id: u64,
+    saved_at: String,
+    fingerprint: String,
+}
+
+/// Resultat de `keychain_rename`. La copie a toujours reussi ; si
+/// `cleaned_up` est faux, l'ancienne entree existe encore et la suppression
+/// peut etre relancee.
+#[derive(Serialize)]
+pub(crate) struct RenameResult {
+    cleaned_up: bool,
+    cleanup_error: Option<KeychainError>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct KeychainItem {
+    key: String,
+    value: Secret,
+    #[serde(default)]
+    label: Option<String>,
+}
+
+/// Resultat par cle d'une operation groupee : une cle en erreur ne fait pas
+/// echouer le lot.
+#[derive(Serialize)]
+pub(crate) struct KeychainBatchResult {
+    key: String,
+    value: Option<Secret>,
+    error: Option<KeychainError>,
+}
+
+/// Bilan de `keychain_clear_service`.
+#[derive(Serialize)]
+pub(crate) struct ClearReport {
+    removed: Vec<String>,
+    failed: Vec<KeychainBatchResult>,
+    /// Premiere cle non traitee si `bulk_cancel` a interrompu le balayage.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped_at: Option<String>,
+}
+
+/// Secret tel qu'il est stocke, texte ou binaire, pour une copie a
+/// l'identique.
+#[derive(PartialEq, Eq)]
+enum RawSecret {
+    Text(Secret),
+    Bytes(Zeroizing<Vec<u8>>),
+}
+
+fn read_raw_secret(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+) -> Result<Option<RawSecret>, KeychainError> {
+    match read_raw(store, retry, service, key) {
+        Ok(raw) => Ok(raw.map(RawSecret::Text)),
+        Err(e) if e.code() == KeychainErrorCode::BadEncoding => {
+            Ok(found(keyring_call(store, retry, service, key, |entry| {
+                entry.get_secret()
+            }))?
+            .map(RawSecret::Bytes))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn write_raw_secret(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    key: &str,
+    secret: &RawSecret,
+) -> Result<(), KeychainError> {
+    match secret {
+        RawSecret::Text(raw) => write_raw(store, retry, service, key, raw),
+        RawSecret::Bytes(bytes) => {
+            check_user_key(key)?;
+            keyring_call(store, retry, service, key, |entry| entry.set_secret(bytes))
+        }
+    }
+}
+
+/// Bilan de `keychain_migrate_service`. `conflicts` liste les cles deja
+/// presentes avec une autre valeur dans le nouveau service : elles ne sont
+/// ni ecrasees ni supprimees de l'ancien.
+#[derive(Serialize)]
+pub(crate) struct MigrationReport {
+    migrated: Vec<String>,
+    already_migrated: Vec<String>,
+    conflicts: Vec<String>,
+    failed: Vec<KeychainBatchResult>,
+    /// Premiere cle non migree si `bulk_cancel` a interrompu la migration.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stopped_at: Option<String>,
+}
+
+enum MigrationOutcome {
+    Migrated,
+    AlreadyMigrated,
+    Conflict,
+    Missing,
+}
+
+fn migrate_key(
+    store: &Store,
+    retry: RetryPolicy,
+    old_service: &str,
+    new_service: &str,
+    key: &str,
+    delete_old: bool,
+) -> Result<MigrationOutcome, KeychainError> {
+    let Some(secret) = read_raw_secret(store, retry, old_service, key)? else {
+        return Ok(MigrationOutcome::Missing);
+    };
+    let outcome = match read_raw_secret(store, retry, new_service, key)? {
+        Some(existing) if existing == secret => MigrationOutcome::AlreadyMigrated,
+        Some(_) => return Ok(MigrationOutcome::Conflict),
+        None => {
+            write_raw_secret(store, retry, new_service, key, &secret)?;
+            if read_raw_secret(store, retry, new_service, key)?.as_ref() != Some(&secret) {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::VerificationFailed,
+                    format!("la relecture de {} ne correspond pas", key),
+                ));
+            }
+            MigrationOutcome::Migrated
+        }
+    };
+    migrate_history(store, retry, old_service, new_service, key)?;
+    if delete_old {
+        delete_entry(store, retry, old_service, key)?;
+        let _ = delete_history(store, retry, old_service, key);
+    }
+    Ok(outcome)
+}
+
+/// Copie l'historique brut de `key` vers le nouveau service, sauf s'il en a
+/// deja un, avant que `delete_old` ne supprime celui de l'ancien.
+fn migrate_history(
+    store: &Store,
+    retry: RetryPolicy,
+    old_service: &str,
+    new_service: &str,
+    key: &str,
+) -> Result<(), KeychainError> {
+    let read = |service: &str| {
+        found(keyring_call(
+            store,
+            retry,
+            service,
+            &history_key(key),
+            |entry| entry.get_password(),
+        ))
+        .map(|raw| raw.map(Zeroizing::new))
+    };
+    let Some(history) = read(old_service)? else {
+        return Ok(());
+    };
+    if read(new_service)?.is_none() {
+        keyring_call(store, retry, new_service, &history_key(key), |entry| {
+            entry.set_password(&history)
+        })?;
+    }
+    Ok(())
+}
+
+/// Copie toutes les entrees d'un service vers un autre, avec relecture de
+/// controle. Les cles viennent de l'index de l'ancien service, completees
+/// par `legacy_keys` pour les entrees ecrites avant l'index. Relancer la
+/// migration ne duplique rien et n'echoue pas, y compris apres une annulation.
+fn migrate_service(
+    manager: &KeychainManager,
+    retry: RetryPolicy,
+    old_service: &str,
+    new_service: &str,
+    delete_old: bool,
+    legacy_keys: &[String],
+    progress: &Progress,
+) -> Result<MigrationReport, KeychainError> {
+    let (store, index, locks) = (&manager.store, &manager.index, &manager.locks);
+    let mut keys = {
+        let _guard = index.0.lock().unwrap_or_else(|e| e.into_inner());
+        read_index(store, old_service)?
+    };
+    keys.extend(legacy_keys.iter().cloned());
+    keys.sort();
+    keys.dedup();
+
+    let mut report = MigrationReport {
+        migrated: Vec::new(),
+        already_migrated: Vec::new(),
+        conflicts: Vec::new(),
+        failed: Vec::new(),
+        stopped_at: None,
+    };
+    let mut removed = Vec::new();
+    let total = keys.len();
+    for (done, key) in keys.into_iter().enumerate() {
+        if !progress.step(BulkPhase::Migrating, done, total, Some(&key)) {
+            report.stopped_at = Some(key);
+            break;
+        }
+        let _guard = locks.lock_entries([
+            (old_service.to_string(), key.clone()),
+            (new_service.to_string(), key.clone()),
+        ]);
+        match migrate_key(store, retry, old_service, new_service, &key, delete_old) {
+            Ok(MigrationOutcome::Migrated) => {
+                removed.push(key.clone());
+                report.migrated.push(key);
+            }
+            Ok(MigrationOutcome::AlreadyMigrated) => {
+                removed.push(key.clone());
+                report.already_migrated.push(key);
+            }
+            Ok(MigrationOutcome::Conflict) => report.conflicts.push(key),
+            Ok(MigrationOutcome::Missing) => removed.push(key),
+            Err(e) => report.failed.push(KeychainBatchResult {
+                key,
+                value: None,
+                error: Some(e),
+            }),
+        }
+    }
+    let present: Vec<&str> = report
+        .migrated
+        .iter()
+        .chain(&report.already_migrated)
+        .map(String::as_str)
+        .collect();
+    update_index(store, index, new_service, &present, true);
+    if delete_old {
+        let removed: Vec<&str> = removed.iter().map(String::as_str).collect();
+        update_index(store, index, old_service, &removed, false);
+    }
+    Ok(report)
+}
+
+/// Execute une fois par installation les migrations de `STARTUP_MIGRATIONS`.
+/// Une migration n'est marquee terminee que si aucune cle n'a echoue ni
+/// n'est en conflit : elle est retentee au prochain demarrage.
+fn run_startup_migrations(manager: &KeychainManager) {
+    let (store, events) = (&manager.store, &manager.events);
+    let retry = RetryPolicy::default();
+    for (old_service, new_service) in STARTUP_MIGRATIONS {
+        let done: Vec<String> = found(keyring_call(
+            store,
+            retry,
+            new_service,
+            MIGRATION_MARKER_KEY,
+            |entry| entry.get_password(),
+        ))
+        .ok()
+        .flatten()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default();
+        if done.iter().any(|s| s == old_service) {
+            continue;
+        }
+        let report = migrate_service(
+            manager,
+            retry,
+            old_service,
+            new_service,
+            true,
+            &[],
+            &Progress::none(),
+        );
+        if let Ok(report) = &report {
+            events.emit_all(
+                new_service,
+                report.migrated.iter().map(String::as_str),
+                SecretOperation::Migrate,
+            );
+        }
+        match report {
+            Ok(report) if !report.conflicts.is_empty() => {
+                tracing::warn!(
+                    old_service,
+                    new_service,
+                    conflicts = ?report.conflicts,
+                    "migration de demarrage incomplete : cles en conflit"
+                );
+            }
+            Ok(report) if report.failed.is_empty() => {
+                let mut done = done;
+                done.push(old_service.to_string());
+                let raw = serde_json::to_string(&done).unwrap_or_else(|_| "[]".to_string());
+                let _ = keyring_call(store, retry, new_service, MIGRATION_MARKER_KEY, |entry| {
+                    entry.set_password(&raw)
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct ListedKey {
+    key: String,
+    expired: bool,
+    /// Rotation commencee par `secret_rotate`, ni confirmee ni annulee.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rotation: Option<RotationState>,
+}
+
+/// Etat partage des commandes keychain : le backend retenu au demarrage, les
+/// verrous de l'index et des cles, et le coffre portable s'il y en a un. Tous
+/// les champs sont partages : un clone peut etre deplace dans une tache
+/// bloquante.
+#[derive(Clone)]
+pub(crate) struct KeychainManager {
+    store: Store,
+    index: KeyIndexLock,
+    locks: KeyLocks,
+    /// Store de repli, compte par `keychain_status` meme s'il n'est pas retenu.
+    memory: MemoryStore,
+    /// Raison du repli en memoire, `None` si le keychain OS repond.
+    fallback_reason: Option<String>,
+    vault: Option<Arc<FileVault>>,
+    /// Prefixes de service acceptes, `keychain.service_prefix` de la
+    /// configuration en tete.
+    service_prefixes: Arc<RwLock<Vec<String>>>,
+    /// Prefixes d'`argos.json`, `SERVICE_PREFIX` en tete : seuls prefixes
+    /// que `keychain.service_prefix` peut restreindre.
+    allowed_prefixes: Arc<Vec<String>>,
+    profiles: Arc<Profiles>,
+    audit: Arc<AuditLog>,
+    events: SecretEvents,
+    auth: Arc<UserAuth>,
+    confirmation: Arc<Confirmation>,
+    /// Debit des lectures demandees par le webview.
+    reads: Arc<ReadLimiter>,
+    session: Arc<SessionState>,
+    cache: Arc<SecretCache>,
+    bulk: BulkOperations,
+    /// Delai de confirmation d'une rotation avant son signalement.
+    rotation_window_minutes: u64,
+    /// Programmes que `process_run_with_secret` peut lancer.
+    allowed_programs: Arc<Vec<PathBuf>>,
+}
+
+impl KeychainManager {
+    /// Choisit le backend avant toute commande. En mode portable le keychain
+    /// OS n'est jamais sollicite ; sinon, s'il ne repond pas, les secrets sont
+    /// gardes par le store memoire. Un coffre portable sans emplacement est
+    /// remplace de meme par le store memoire, avec sa raison.
+    pub(crate) fn open(app: &tauri::App, config: AppConfig) -> Self {
+        let mut manager = Self::from_config(config, app.path_resolver().app_data_dir());
+        manager.events = SecretEvents::new(app.handle());
+        manager
+    }
+
+    /// Comme `open`, sans application Tauri : `data_dir` remplace le dossier
+    /// de donnees de l'application, et aucun evenement n'est emis.
+    pub(crate) fn from_config(config: AppConfig, data_dir: Option<PathBuf>) -> Self {
+        let memory = MemoryStore::default();
+        let vault = config
+            .vault_path
+            .clone()
+            .or_else(|| data_dir.as_ref().map(|dir| dir.join(vault::VAULT_FILE)))
+            .filter(|_| config.portable)
+            .map(|path| Arc::new(FileVault::new(path)));
+        let (store, fallback_reason): (Store, Option<String>) = match &vault {
+            Some(vault) => (vault.clone(), None),
+            None if config.portable => (
+                Arc::new(memory.clone()),
+                Some(
+                    "coffre portable sans emplacement : dossier de donnees introuvable".to_string(),
+                ),
+            ),
+            #[cfg(feature = "mock-store")]
+            None if config.mock_store => (Arc::new(store::MockSecretStore::from_env()), None),
+            None => detect_backend(&memory, &config.linux_collection, config.linux_backend),
+        };
+        KeychainManager {
+            vault,
+            fallback_reason,
+            ..Self::with_store(config, data_dir, memory, store)
+        }
+    }
+
+    /// Manager sur un `store` deja choisi, sans coffre portable ni raison de
+    /// repli. `memory` garde les secrets si `store` est le store memoire.
+    fn with_store(
+        config: AppConfig,
+        data_dir: Option<PathBuf>,
+        memory: MemoryStore,
+        store: Store,
+    ) -> Self {
+        let audit = AuditLog::new(
+            data_dir.map(|dir| dir.join(audit::AUDIT_FILE)),
+            config
+                .audit_max_bytes
+                .unwrap_or(audit::DEFAULT_AUDIT_MAX_BYTES),
+        );
+        store::activate(&store);
+        let confirmation = Confirmation::new(
+            config.confirm_destructive.unwrap_or(true),
+            config.confirm_every_delete,
+            store.name() == "mock",
+        );
+        let reads = ReadLimiter::new(
+            config
+                .reads_per_key_per_minute
+                .unwrap_or(limiter::DEFAULT_READS_PER_KEY_PER_MINUTE),
+            config
+                .reads_per_minute
+                .unwrap_or(limiter::DEFAULT_READS_PER_MINUTE),
+        );
+        let profiles = Profiles::default();
+        profiles.set_active(config.profile.clone());
+        let mut allowed_prefixes = vec![SERVICE_PREFIX.to_string()];
+        allowed_prefixes.extend(
+            config
+                .allowed_service_prefixes
+                .into_iter()
+                .filter(|prefix| !prefix.is_empty()),
+        );
+        KeychainManager {
+            store,
+            index: KeyIndexLock::default(),
+            locks: KeyLocks::default(),
+            memory,
+            fallback_reason: None,
+            vault: None,
+            service_prefixes: Arc::new(RwLock::new(allowed_prefixes.clone())),
+            allowed_prefixes: Arc::new(allowed_prefixes),
+            profiles: Arc::new(profiles),
+            audit: Arc::new(audit),
+            events: SecretEvents::default(),
+            auth: Arc::new(UserAuth::default()),
+            confirmation: Arc::new(confirmation),
+            reads: Arc::new(reads),
+            session: Arc::new(SessionState::new(
+                config
+                    .session_idle_minutes
+                    .unwrap_or(session::DEFAULT_IDLE_MINUTES),
+            )),
+            cache: Arc::new(SecretCache::new(
+                config
+                    .cache_ttl_seconds
+                    .unwrap_or(cache::DEFAULT_CACHE_TTL_SECS),
+                config
+                    .cache_max_entries
+                    .unwrap_or(cache::DEFAULT_CACHE_MAX_ENTRIES),
+            )),
+            bulk: BulkOperations::default(),
+            rotation_window_minutes: config
+                .rotation_window_minutes
+                .unwrap_or(rotation::DEFAULT_ROTATION_WINDOW_MINUTES),
+            allowed_programs: Arc::new(config.allowed_programs),
+        }
+    }
+
+    /// Lance `run_startup_migrations` en tache de fond, sans delai maximal :
+    /// elle peut durer sur une grosse installation et ne bloque aucune
+    /// commande. Le coffre portable, verrouille au demarrage, n'a rien a
+    /// migrer.
+    pub(crate) fn spawn_startup_migrations(&self) {
+        if self.vault.is_some() {
+            return;
+        }
+        let manager = self.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            run_startup_migrations(&manager);
+            manager.cache.flush();
+        });
+    }
+
+    /// `false` si les secrets ne sont gardes qu'en memoire et seront perdus a
+    /// la fermeture.
+    fn is_persistent(&self) -> bool {
+        self.store.persistent()
+    }
+
+    /// Demande a l'utilisateur de confirmer une operation destructrice,
+    /// sauf si `confirm_destructive` est desactive.
+    pub(crate) async fn confirm(
+        &self,
+        window: Option<&tauri::Window>,
+        question: String,
+    ) -> Result<(), KeychainError> {
+        self.confirmation.ask(window, question).await
+    }
+
+    /// Confirmation de `keychain_delete` : seule la suppression d'un secret
+    /// avec historique la demande, sauf avec `confirm_every_delete`.
+    pub(crate) async fn confirm_delete(
+        &self,
+        window: Option<&tauri::Window>,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<(), KeychainError> {
+        if !self.confirmation.is_enabled() {
+            return Ok(());
+        }
+        let manager = self.clone();
+        let (history_service, history_key) = (service.to_string(), key.to_string());
+        let versions =
+            run_blocking(move || manager.history(&history_service, &history_key, retry)).await?;
+        let question = match (versions.len(), self.confirmation.every_delete()) {
+            (0, false) => return Ok(()),
+            (0, true) => format!("Supprimer le secret {} du service {} ?", key, service),
+            (count, _) => format!(
+                "Supprimer le secret {} du service {}, avec ses {} versions precedentes ?",
+                key, service, count
+            ),
+        };
+        self.confirm(window, question).await
+    }
+
+    /// Raison du repli en memoire decide au demarrage, s'il y en a eu un.
+    pub(crate) fn fallback_reason(&self) -> Option<&str> {
+        self.fallback_reason.as_deref()
+    }
+
+    /// Backend detecte a l'ouverture, tel que `keychain_status` le nomme.
+    pub(crate) fn backend_name(&self) -> &'static str {
+        self.store.name()
+    }
+
+    /// Les lectures de secrets sont refusees tant que la session est
+    /// verrouillee.
+    fn check_unlocked(&self) -> Result<(), KeychainError> {
+        if self.session.is_locked() {
+            return Err(session::session_locked());
+        }
+        Ok(())
+    }
+
+    /// Refuse les services hors de la liste autorisee avant tout acces au
+    /// backend : le webview ne peut pas lire les secrets d'autres
+    /// applications.
+    pub(crate) fn check_service(&self, service: &str) -> Result<(), KeychainError> {
+        let prefixes = self.service_prefixes();
+        if service == ARGOS_SERVICE
+            || prefixes
+                .iter()
+                .any(|prefix| service.starts_with(prefix.as_str()))
+        {
+            return Ok(());
+        }
+        Err(KeychainError::rejected(
+            KeychainErrorCode::ServiceNotAllowed,
+            format!(
+                "le service {} n'est pas autorise : il doit etre {} ou commencer par {}",
+                service,
+                ARGOS_SERVICE,
+                prefixes.join(" ou ")
+            ),
+        ))
+    }
+
+    fn service_prefixes(&self) -> Vec<String> {
+        self.service_prefixes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Refuse un `keychain.service_prefix` qui etendrait les services
+    /// autorises par `argos.json`.
+    pub(crate) fn check_service_prefix(&self, prefix: &str) -> Result<(), KeychainError> {
+        if self
+            .allowed_prefixes
+            .iter()
+            .any(|allowed| prefix.starts_with(allowed.as_str()))
+        {
+            return Ok(());
+        }
+        Err(KeychainError::rejected(
+            KeychainErrorCode::ServiceNotAllowed,
+            format!(
+                "le prefixe {} n'est pas autorise : il doit commencer par {}",
+                prefix,
+                self.allowed_prefixes.join(" ou ")
+            ),
+        ))
+    }
+
+    /// Remplace `SERVICE_PREFIX` par `prefix` dans les services autorises,
+    /// a chaque application de la configuration.
+    pub(crate) fn set_service_prefix(&self, prefix: &str) {
+        let mut prefixes = vec![prefix.to_string()];
+        prefixes.extend(self.allowed_prefixes.iter().skip(1).cloned());
+        *self
+            .service_prefixes
+            .write()
+            .unwrap_or_else(|e| e.into_inner()) = prefixes;
+    }
+
+    /// Normalise et verifie `service` puis renvoie son nom reel dans le
+    /// profil actif ; sans profil, le nom normalise.
+    pub(crate) fn resolve_service(&self, service: &str) -> Result<String, KeychainError> {
+        let service = &names::service(service)?;
+        self.check_service(service)?;
+        let profile = self.profiles.active();
+        let effective = match &profile {
+            Some(profile) => profiles::profile_service(service, profile, &self.service_prefixes()),
+            None => service.to_string(),
+        };
+        profiles::record_service(&self.profiles, &self.store, profile.as_deref(), &effective);
+        Ok(effective)
+    }
+
+    /// Applique le profil de la configuration ; `None` pour le profil par
+    /// defaut.
+    pub(crate) fn set_active_profile(&self, profile: Option<String>) {
+        self.profiles.set_active(profile);
+    }
+
+    /// Prepare la ligne d'audit d'une commande appelee depuis `window`.
+    fn audit(
+        &self,
+        window: &tauri::Window,
+        operation: &str,
+        service: &str,
+        key: Option<&str>,
+    ) -> PendingAudit {
+        PendingAudit::new(self.audit.clone(), window.label(), operation, service, key)
+    }
+
+    fn vault(&self) -> Result<Arc<FileVault>, KeychainError> {
+        self.vault.clone().ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                "ARGOS n'est pas en mode portable",
+            )
+        })
+    }
+
+    /// Lit l'etat de la collection Secret Service, ce qui peut attendre le
+    /// bus : a appeler hors du thread principal.
+    pub(crate) fn collection_status(&self) -> Option<CollectionStatus> {
+        self.store.collection()
+    }
+
+    /// Ecrit, relit puis supprime `HEALTH_KEY`, sans nouvelle tentative pour
+    /// que la latence mesuree soit celle d'un appel. L'entree est supprimee
+    /// meme si l'ecriture ou la relecture echoue.
+    pub(crate) fn probe_health(&self) -> Result<(), KeychainError> {
+        let store = &self.store;
+        let retry = RetryPolicy {
+            attempts: 1,
+            ..RetryPolicy::default()
+        };
+        let value = format!("{:016x}", rand::thread_rng().gen::<u64>());
+        let read = keyring_call(store, retry, ARGOS_SERVICE, HEALTH_KEY, |entry| {
+            entry.set_password(&value)
+        })
+        .and_then(|()| {
+            keyring_call(store, retry, ARGOS_SERVICE, HEALTH_KEY, |entry| {
+                entry.get_password()
+            })
+        });
+        let deleted = found(keyring_call(
+            store,
+            retry,
+            ARGOS_SERVICE,
+            HEALTH_KEY,
+            |entry| entry.delete_credential(),
+        ));
+        if read?.as_str() != value {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::VerificationFailed,
+                "la relecture de la sonde ne correspond pas",
+            ));
+        }
+        deleted.map(drop)
+    }
+
+    /// Demande au backend d'afficher sa demande de deverrouillage.
+    pub(crate) fn unlock_backend(&self) -> Result<(), KeychainError> {
+        self.store.unlock()
+    }
+
+    /// Etat du backend, d'apres le resultat de `probe_health`.
+    pub(crate) fn status(
+        &self,
+        probe: Result<(), KeychainError>,
+        latency: Duration,
+        collection: Option<CollectionStatus>,
+    ) -> KeychainStatus {
+        let (reachable, locked) = match &probe {
+            Ok(()) => (true, Some(false)),
+            Err(e) => match e.code() {
+                KeychainErrorCode::AccessDenied
+                | KeychainErrorCode::VaultLocked
+                | KeychainErrorCode::BackendLocked => (true, Some(true)),
+                KeychainErrorCode::PlatformError
+                | KeychainErrorCode::BackendUnavailable
+                | KeychainErrorCode::Timeout => (false, None),
+                _ => (true, None),
+            },
+        };
+        KeychainStatus {
+            backend: self.store.name(),
+            persistent: self.is_persistent(),
+            fallback_reason: self.fallback_reason.clone(),
+            memory_entries: self.memory.map().len(),
+            vault_locked: self.vault.as_ref().map(|vault| vault.is_locked()),
+            audit_failures: self.audit.failures(),
+            held_keys: self.locks.held_count(),
+            reachable,
+            locked,
+            latency_ms: latency.as_millis() as u64,
+            platform: std::env::consts::OS,
+            last_error: probe.err(),
+            collection,
+        }
+    }
+
+    pub(crate) fn set(
+        &self,
+        service: &str,
+        key: &str,
+        value: &str,
+        options: SetOptions,
+        retry: RetryPolicy,
+    ) -> Result<SetReport, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.claim_key(service, key, retry)?;
+        let key = name.as_str();
+        let depth = options
+            .history
+            .unwrap_or(DEFAULT_HISTORY_DEPTH)
+            .min(MAX_HISTORY_DEPTH);
+        let expires_at = match options.ttl_seconds {
+            None => None,
+            Some(ttl) if !(1..=MAX_TTL_SECS).contains(&ttl) => {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::InvalidArgument,
+                    format!("ttl_seconds doit etre compris entre 1 et {}", MAX_TTL_SECS),
+                ))
+            }
+            Some(ttl) => Some(
+                (chrono::Utc::now() + chrono::Duration::seconds(ttl as i64))
+                    .to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            ),
+        };
+        if let Some(params) = &options.totp {
+            params.validate()?;
+            totp::decode_base32(value)?;
+        }
+        let _guard = self.locks.lock(service, [key]);
+        if let Some(expected) = &options.if_matches {
+            let expected = expected.as_ref().map(|expected| expected.as_str());
+            if !current_matches(&self.store, retry, service, key, expected)? {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::Conflict,
+                    format!("{} a change depuis sa lecture : rien n'a ete ecrit", key),
+                ));
+            }
+        }
+        let macos = options.macos.filter(|access| !access.is_empty());
+        let persistence = options.windows.and_then(|windows| windows.persistence);
+        if let Some(persistence) = persistence {
+            self.store.check_persistence(persistence)?;
+        }
+        let store = &self.store;
+        let previous = write_password(
+            store,
+            retry,
+            service,
+            key,
+            value,
+            EnvelopeMeta {
+                label: options.label.as_deref(),
+                expires_at,
+                require_auth: options.require_auth,
+                totp: options.totp,
+                cache: options.cache,
+                encryption_key: options.encryption_key,
+            },
+        )?;
+        let macos = match macos {
+            Some(access) => Some(protect_entry(store, retry, service, key, &access)?),
+            None => None,
+        };
+        let windows = match persistence {
+            Some(persistence) => Some(persist_entry(store, retry, service, key, persistence)?),
+            None => None,
+        };
+        self.cache.invalidate(service, [key]);
+        if depth > 0 {
+            record_history(store, retry, service, key, previous, depth);
+        }
+        update_index(store, &self.index, service, &[key], true);
+        self.events.emit(service, key, SecretOperation::Set);
+        Ok(SetReport {
+            persistent: self.is_persistent(),
+            macos,
+            windows,
+        })
+    }
+
+    /// La valeur d'un secret marque `require_auth` n'est renvoyee qu'apres
+    /// `UserAuth::release`, qu'elle vienne du backend ou du cache. Avec
+    /// `cache`, ou si l'enveloppe le demande, la valeur lue est gardee en
+    /// memoire pour les lectures suivantes.
+    pub(crate) fn get(
+        &self,
+        service: &str,
+        key: &str,
+        cache: bool,
+        retry: RetryPolicy,
+    ) -> Result<Gated<Option<Secret>>, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        if let Some(hit) = self.cache.lookup(service, key, cache) {
+            let reason = hit.require_auth.then(|| auth_reason(key));
+            return Ok(Gated::new(Some(hit.value), reason));
+        }
+        let generation = self.cache.generation();
+        let stored = {
+            let _guard = self.locks.lock(service, [key]);
+            read_stored(&self.store, retry, service, key)?
+        };
+        let reason = stored
+            .as_ref()
+            .filter(|stored| stored.requires_auth())
+            .map(|_| auth_reason(key));
+        let Some(stored) = stored else {
+            return Ok(Gated::new(None, reason));
+        };
+        let (expires_at, pinned) = stored.cache_hint();
+        let value = stored.into_value();
+        if cache || pinned {
+            let hit = CacheHit {
+                value: value.clone(),
+                require_auth: reason.is_some(),
+            };
+            self.cache
+                .insert(generation, service, key, hit, expires_at, pinned);
+        }
+        Ok(Gated::new(Some(value), reason))
+    }
+
+    /// Graine TOTP d'un secret, pour `totp_generate`.
+    pub(crate) fn totp_seed(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<Gated<TotpSeed>, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let stored = {
+            let _guard = self.locks.lock(service, [key]);
+            read_stored(&self.store, retry, service, key)?
+        };
+        let stored = stored.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::NotFound,
+                format!("aucun secret {} dans {}", key, service),
+            )
+        })?;
+        let reason = stored.requires_auth().then(|| auth_reason(key));
+        let params = stored.totp_params();
+        Ok(Gated::new(
+            TotpSeed::new(stored.into_value(), params),
+            reason,
+        ))
+    }
+
+    /// La lecture et l'ecriture se font sous un verrou propre a la cle : deux
+    /// appels simultanes obtiennent la meme valeur, un seul la cree.
+    pub(crate) fn get_or_set(
+        &self,
+        service: &str,
+        key: &str,
+        default_value: Secret,
+        retry: RetryPolicy,
+    ) -> Result<Gated<GetOrSetResult>, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.claim_key(service, key, retry)?;
+        let key = name.as_str();
+        let store = &self.store;
+        let _guard = self.locks.lock(service, [key]);
+        // Un secret expire est remplace comme s'il etait absent.
+        match read_stored(store, retry, service, key) {
+            Ok(Some(stored)) => {
+                let reason = stored.requires_auth().then(|| auth_reason(key));
+                return Ok(Gated::new(
+                    GetOrSetResult {
+                        value: stored.into_value(),
+                        created: false,
+                        persistent: self.is_persistent(),
+                    },
+                    reason,
+                ));
+            }
+            Ok(None) => {}
+            Err(e) if e.code() == KeychainErrorCode::Expired => {}
+            Err(e) => return Err(e),
+        }
+        write_password(
+            store,
+            retry,
+            service,
+            key,
+            &default_value,
+            EnvelopeMeta::default(),
+        )?;
+        self.cache.invalidate(service, [key]);
+        update_index(store, &self.index, service, &[key], true);
+        self.events.emit(service, key, SecretOperation::Set);
+        Ok(Gated::new(
+            GetOrSetResult {
+                value: default_value,
+                created: true,
+                persistent: self.is_persistent(),
+            },
+            None,
+        ))
+    }
+
+    pub(crate) fn get_metadata(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<Option<SecretMetadata>, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let _guard = self.locks.lock(service, [key]);
+        read_metadata(&self.store, retry, service, key)
+    }
+
+    /// Renvoie `false` si l'entree n'existait deja plus.
+    pub(crate) fn delete(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<bool, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let store = &self.store;
+        let _guard = self.locks.lock(service, [key]);
+        let deleted = delete_entry(store, retry, service, key)?;
+        self.cache.invalidate(service, [key]);
+        update_index(store, &self.index, service, &[key], false);
+        let _ = delete_history(store, retry, service, key);
+        if deleted.is_some() {
+            self.events.emit(service, key, SecretOperation::Delete);
+        }
+        Ok(deleted.is_some())
+    }
+
+    pub(crate) fn history(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<Vec<HistoryVersion>, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        Ok(read_history(&self.store, retry, service, key)
+            .into_iter()
+            .map(|h| HistoryVersion {
+                id: h.id,
+                fingerprint: fingerprint(&h.value),
+                saved_at: h.saved_at.clone(),
+            })
+            .collect())
+    }
+
+    pub(crate) fn rollback(
+        &self,
+        service: &str,
+        key: &str,
+        version_id: u64,
+        retry: RetryPolicy,
+    ) -> Result<(), KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let _guard = self.locks.lock(service, [key]);
+        self.restore_version(service, key, version_id, retry)
+    }
+
+    /// Corps de `rollback`, sous le verrou de la cle.
+    fn restore_version(
+        &self,
+        service: &str,
+        key: &str,
+        version_id: u64,
+        retry: RetryPolicy,
+    ) -> Result<(), KeychainError> {
+        let store = &self.store;
+        let mut history = read_history(store, retry, service, key);
+        let position = history
+            .iter()
+            .position(|h| h.id == version_id)
+            .ok_or_else(|| {
+                KeychainError::rejected(
+                    KeychainErrorCode::VersionNotFound,
+                    format!("version {} introuvable pour {}", version_id, key),
+                )
+            })?;
+        let restored = history.remove(position);
+        let previous = write_password(
+            store,
+            retry,
+            service,
+            key,
+            &restored.value,
+            EnvelopeMeta::default(),
+        )?;
+        self.cache.invalidate(service, [key]);
+        update_index(store, &self.index, service, &[key], true);
+        let _ = write_history(store, retry, service, key, history, MAX_HISTORY_DEPTH);
+        record_history(store, retry, service, key, previous, MAX_HISTORY_DEPTH);
+        self.events.emit(service, key, SecretOperation::Rollback);
+        Ok(())
+    }
+
+    pub(crate) fn rename(
+        &self,
+        service: &str,
+        old_key: &str,
+        new_key: &str,
+        overwrite: bool,
+        retry: RetryPolicy,
+    ) -> Result<RenameResult, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let old_name = self.resolve_key(service, old_key, retry)?;
+        let old_key = old_name.as_str();
+        // Renommer vers la forme normalisee de l'ancien nom corrige son
+        // orthographe, ce n'est pas une collision.
+        let new_name = if names::normalize(old_key) == names::normalize(new_key) {
+            names::normalize(new_key)
+        } else {
+            self.claim_key(service, new_key, retry)?
+        };
+        let new_key = new_name.as_str();
+        if old_key == new_key {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                "l'ancienne et la nouvelle cle sont identiques",
+            ));
+        }
+        let store = &self.store;
+        let _guard = self.locks.lock(service, [old_key, new_key]);
+
+        // L'enveloppe est copiee telle quelle pour conserver les metadonnees.
+        let raw = read_raw(store, retry, service, old_key)?.ok_or_else(store::not_found)?;
+        if !overwrite {
+            let existing = found(keyring_call(store, retry, service, new_key, |entry| {
+                entry.probe()
+            }))?;
+            if existing.is_some() {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::AlreadyExists,
+                    format!("la cle {} existe deja", new_key),
+                ));
+            }
+        }
+        write_raw(store, retry, service, new_key, &raw)?;
+        self.cache.invalidate(service, [new_key]);
+        update_index(store, &self.index, service, &[new_key], true);
+        if read_raw(store, retry, service, new_key)?.as_deref() != Some(&*raw) {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::VerificationFailed,
+                format!("la relecture de {} ne correspond pas", new_key),
+            ));
+        }
+
+        let cleanup_error = delete_entry(store, retry, service, old_key).err();
+        self.cache.invalidate(service, [old_key]);
+        if cleanup_error.is_none() {
+            update_index(store, &self.index, service, &[old_key], false);
+            let history = read_history(store, retry, service, old_key);
+            if write_history(store, retry, service, new_key, history, MAX_HISTORY_DEPTH).is_ok() {
+                let _ = delete_history(store, retry, service, old_key);
+            }
+        }
+        self.events.emit_rename(service, old_key, new_key);
+        Ok(RenameResult {
+            cleaned_up: cleanup_error.is_none(),
+            cleanup_error,
+        })
+    }
+
+    pub(crate) fn exists(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<bool, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let attributes = found(keyring_call(&self.store, retry, service, key, |entry| {
+            entry.probe()
+        }))?;
+        Ok(attributes.is_some())
+    }
+
+    /// Nom de cible de l'entree dans le gestionnaire d'identifiants Windows,
+    /// `Unsupported` pour les autres backends.
+    pub(crate) fn target_name(&self, service: &str, key: &str) -> Result<String, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, RetryPolicy::default())?;
+        let key = name.as_str();
+        self.store.target_name(service, key)?.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::Unsupported,
+                format!(
+                    "nom de cible propre au gestionnaire d'identifiants Windows (backend {})",
+                    self.store.name()
+                ),
+            )
+        })
+    }
+
+    pub(crate) fn set_bytes(
+        &self,
+        service: &str,
+        key: &str,
+        value_base64: &str,
+        retry: RetryPolicy,
+    ) -> Result<SetReport, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.claim_key(service, key, retry)?;
+        let key = name.as_str();
+        let bytes = decode_base64(value_base64.trim()).map_err(|e| {
+            KeychainError::rejected(
+                KeychainErrorCode::InvalidBase64,
+                format!("base64 invalide : {}", e),
+            )
+        })?;
+        let _guard = self.locks.lock(service, [key]);
+        keyring_call(&self.store, retry, service, key, |entry| {
+            entry.set_secret(&bytes)
+        })?;
+        self.cache.invalidate(service, [key]);
+        update_index(&self.store, &self.index, service, &[key], true);
+        self.events.emit(service, key, SecretOperation::Set);
+        Ok(SetReport {
+            persistent: self.is_persistent(),
+            macos: None,
+            windows: None,
+        })
+    }
+
+    pub(crate) fn get_bytes(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<Option<Secret>, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let bytes = found(keyring_call(&self.store, retry, service, key, |entry| {
+            entry.get_secret()
+        }))?;
+        if bytes
+            .as_deref()
+            .is_some_and(|bytes| raw_is_encryption_key(bytes))
+        {
+            return Err(encryption_key_protected(key));
+        }
+        Ok(bytes
+            .map(|bytes| Secret::new(base64::engine::general_purpose::STANDARD.encode(&bytes[..]))))
+    }
+
+    pub(crate) fn get_many(
+        &self,
+        service: &str,
+        keys: Vec<String>,
+        retry: RetryPolicy,
+    ) -> Result<Gated<Vec<KeychainBatchResult>>, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let mut protected = Vec::new();
+        let keys: Vec<(String, Result<String, KeychainError>)> = keys
+            .into_iter()
+            .map(|key| {
+                let name = self.resolve_key(service, &key, retry);
+                (key, name)
+            })
+            .collect();
+        let _guard = self.locks.lock(
+            service,
+            keys.iter().filter_map(|(_, name)| name.as_deref().ok()),
+        );
+        let results = keys
+            .into_iter()
+            .map(|(key, name)| {
+                match name.and_then(|name| read_stored(&self.store, retry, service, &name)) {
+                    Ok(stored) => {
+                        if stored.as_ref().is_some_and(Stored::requires_auth) {
+                            protected.push(key.clone());
+                        }
+                        KeychainBatchResult {
+                            key,
+                            value: stored.map(Stored::into_value),
+                            error: None,
+                        }
+                    }
+                    Err(e) => KeychainBatchResult {
+                        key,
+                        value: None,
+                        error: Some(e),
+                    },
+                }
+            })
+            .collect();
+        // Une seule verification couvre toutes les cles protegees.
+        let reason = (!protected.is_empty()).then(|| auth_reason(&protected.join(", ")));
+        Ok(Gated::new(results, reason))
+    }
+
+    pub(crate) fn set_many(
+        &self,
+        service: &str,
+        items: Vec<KeychainItem>,
+        retry: RetryPolicy,
+    ) -> Result<Vec<KeychainBatchResult>, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let names: Vec<Result<String, KeychainError>> = items
+            .iter()
+            .map(|item| self.claim_key(service, &item.key, retry))
+            .collect();
+        let _guard = self.locks.lock(
+            service,
+            names.iter().filter_map(|name| name.as_deref().ok()),
+        );
+        // Les resultats gardent le nom envoye, l'index et les evenements
+        // le nom normalise.
+        let mut stored = Vec::new();
+        let results: Vec<KeychainBatchResult> = items
+            .into_iter()
+            .zip(names)
+            .map(|(item, name)| {
+                let written = name.and_then(|name| {
+                    write_password(
+                        &self.store,
+                        retry,
+                        service,
+                        &name,
+                        &item.value,
+                        EnvelopeMeta {
+                            label: item.label.as_deref(),
+                            ..EnvelopeMeta::default()
+                        },
+                    )?;
+                    stored.push(name);
+                    Ok(())
+                });
+                KeychainBatchResult {
+                    error: written.err(),
+                    key: item.key,
+                    value: None,
+                }
+            })
+            .collect();
+        let written: Vec<&str> = stored.iter().map(String::as_str).collect();
+        self.cache.invalidate(service, written.iter().copied());
+        update_index(&self.store, &self.index, service, &written, true);
+        self.events
+            .emit_all(service, written.iter().copied(), SecretOperation::Set);
+        Ok(results)
+    }
+
+    /// Les echecs n'interrompent pas le balayage ; les cles non supprimees
+    /// restent dans l'index pour une nouvelle tentative.
+    pub(crate) fn clear_service(
+        &self,
+        service: &str,
+        retry: RetryPolicy,
+        progress: &Progress,
+    ) -> Result<ClearReport, KeychainError> {
+        let service = self.resolve_service(service)?;
+        self.sweep_service(&service, retry, progress)
+    }
+
+    /// Corps de `clear_service`, sur un nom de service deja resolu.
+    fn sweep_service(
+        &self,
+        service: &str,
+        retry: RetryPolicy,
+        progress: &Progress,
+    ) -> Result<ClearReport, KeychainError> {
+        let store = &self.store;
+        // Les cles se verrouillent avant l'index, comme pour les ecritures.
+        let indexed = {
+            let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+            read_index(store, service)?
+        };
+        let _keys = self.locks.lock(service, indexed.iter().map(String::as_str));
+        let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut report = ClearReport {
+            removed: Vec::new(),
+            failed: Vec::new(),
+            stopped_at: None,
+        };
+        // Cles ecrites depuis le debut du balayage, ou laissees par une
+        // annulation : elles sont conservees.
+        let mut kept = Vec::new();
+        let current = read_index(store, service)?;
+        let total = current.len();
+        for (done, key) in current.into_iter().enumerate() {
+            if !indexed.contains(&key) || report.stopped_at.is_some() {
+                kept.push(key);
+                continue;
+            }
+            if !progress.step(BulkPhase::Deleting, done, total, Some(&key)) {
+                report.stopped_at = Some(key.clone());
+                kept.push(key);
+                continue;
+            }
+            match delete_entry(store, retry, service, &key) {
+                Ok(_) => {
+                    let _ = delete_history(store, retry, service, &key);
+                    report.removed.push(key);
+                }
+                Err(e) => report.failed.push(KeychainBatchResult {
+                    key,
+                    value: None,
+                    error: Some(e),
+                }),
+            }
+        }
+        self.cache.invalidate_service(service);
+        // Un index perime est sans consequence : ses cles deja supprimees
+        // seront simplement retrouvees absentes au prochain balayage.
+        if report.failed.is_empty() && kept.is_empty() {
+            for reserved in [INDEX_KEY, rotation::ROTATIONS_KEY] {
+                let _ = keyring_call(store, retry, service, reserved, |entry| {
+                    entry.delete_credential()
+                });
+            }
+        } else {
+            let mut remaining: Vec<String> = report.failed.iter().map(|r| r.key.clone()).collect();
+            remaining.extend(kept);
+            remaining.sort();
+            let _ = write_index(store, service, &remaining);
+        }
+        self.events.emit_all(
+            service,
+            report.removed.iter().map(String::as_str),
+            SecretOperation::Delete,
+        );
+        Ok(report)
+    }
+
+    pub(crate) fn migrate_service(
+        &self,
+        old_service: &str,
+        new_service: &str,
+        delete_old: bool,
+        legacy_keys: &[String],
+        retry: RetryPolicy,
+        progress: &Progress,
+    ) -> Result<MigrationReport, KeychainError> {
+        let new_service = &self.resolve_service(new_service)?;
+        // Les anciens noms de STARTUP_MIGRATIONS restent acceptes, pour
+        // relancer une migration de demarrage incomplete.
+        let old_service = &if STARTUP_MIGRATIONS
+            .iter()
+            .any(|(old, _)| *old == old_service)
+        {
+            old_service.to_string()
+        } else {
+            self.resolve_service(old_service)?
+        };
+        if old_service == new_service {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                "l'ancien et le nouveau service sont identiques",
+            ));
+        }
+        let report = migrate_service(
+            self,
+            retry,
+            old_service,
+            new_service,
+            delete_old,
+            legacy_keys,
+            progress,
+        );
+        self.cache.invalidate_service(old_service);
+        self.cache.invalidate_service(new_service);
+        let report = report?;
+        self.events.emit_all(
+            new_service,
+            report.migrated.iter().map(String::as_str),
+            SecretOperation::Migrate,
+        );
+        Ok(report)
+    }
+
+    /// Cles connues d'un service (jamais les valeurs), en signalant celles
+    /// dont le TTL est depasse.
+    pub(crate) fn list(&self, service: &str) -> Result<Vec<ListedKey>, KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let keys = {
+            let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+            read_index(&self.store, service)?
+        };
+        let mut rotations = self.rotation_states(service);
+        Ok(keys
+            .into_iter()
+            .map(|key| ListedKey {
+                expired: matches!(
+                    read_metadata(&self.store, RetryPolicy::default(), service, &key),
+                    Ok(Some(metadata)) if metadata.is_expired()
+                ),
+                rotation: rotations.remove(&key),
+                key,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::store::MockOp;
+    use super::store::SecretStore;
+    use super::testing::mock_manager;
+    use super::testing::{get, memory_manager, set, stored_keys, NO_RETRY, SERVICE};
+    use super::*;
+
+    fn confirm_delete(manager: &KeychainManager, key: &str) -> Result<(), KeychainError> {
+        tauri::async_runtime::block_on(manager.confirm_delete(None, SERVICE, key, NO_RETRY))
+    }
+
+    #[test]
+    fn secret_buffers_are_zeroizing() {
+        // Assertions de type : ne compile plus si l'un de ces buffers cesse
+        // d'etre efface a sa liberation.
+        type Cleared<T> = Result<Zeroizing<T>, KeychainError>;
+        fn zeroize_on_drop<T: zeroize::ZeroizeOnDrop>() {}
+        let _: fn(&StoreEntry<'static>) -> Cleared<String> = StoreEntry::get_password;
+        let _: fn(&StoreEntry<'static>) -> Cleared<Vec<u8>> = StoreEntry::get_secret;
+        let _: fn(&str) -> Result<Zeroizing<Vec<u8>>, base64::DecodeError> = decode_base64;
+        // `value` de keychain_set, et toute valeur renvoyee au webview.
+        zeroize_on_drop::<Secret>();
+        zeroize_on_drop::<Zeroizing<String>>();
+    }
+
+    #[test]
+    fn from_config_without_data_dir_falls_back_to_memory() {
+        let config = AppConfig {
+            portable: true,
+            allowed_service_prefixes: vec!["com.example.".to_string()],
+            ..AppConfig::default()
+        };
+        let manager = KeychainManager::from_config(config, None);
+        assert_eq!(manager.store.name(), "memory");
+        assert!(!manager.is_persistent());
+        assert!(manager.fallback_reason.is_some());
+        manager.check_service("com.example.tokens").unwrap();
+        manager.check_service(SERVICE).unwrap();
+
+        set(&manager, "token", "v1");
+        let status = manager.status(Ok(()), Duration::ZERO, None);
+        assert_eq!(status.backend(), "memory");
+        assert!(status.is_available());
+        assert!(stored_keys(&manager).contains(&"token".to_string()));
+        assert_eq!(status.memory_entries, manager.memory.map().len());
+        assert_eq!(status.held_keys, 0);
+    }
+
+    #[cfg(feature = "mock-store")]
+    #[test]
+    fn from_config_selects_the_mock_store() {
+        let config = AppConfig {
+            mock_store: true,
+            ..AppConfig::default()
+        };
+        let manager = KeychainManager::from_config(config, None);
+        assert_eq!(manager.store.name(), "mock");
+        assert!(manager.fallback_reason.is_none());
+        assert!(manager.confirmation.is_enabled());
+    }
+
+    #[test]
+    fn clones_share_the_store_and_the_locks() {
+        let manager = memory_manager();
+        let clone = manager.clone();
+        set(&clone, "token", "v1");
+        assert_eq!(get(&manager, "token").as_deref(), Some("v1"));
+        let _guard = clone.locks.lock(SERVICE, ["token"]);
+        assert_eq!(manager.locks.held_count(), 1);
+    }
+
+    #[test]
+    fn configured_prefix_replaces_the_argos_prefix() {
+        let manager = memory_manager();
+        manager
+            .check_service_prefix("fr.bbrain.argos.team.")
+            .unwrap();
+        assert_eq!(
+            manager
+                .check_service_prefix("com.example.")
+                .unwrap_err()
+                .code(),
+            KeychainErrorCode::ServiceNotAllowed
+        );
+        manager.set_service_prefix("fr.bbrain.argos.team.");
+        manager
+            .check_service("fr.bbrain.argos.team.tokens")
+            .unwrap();
+        manager.check_service(ARGOS_SERVICE).unwrap();
+        assert_eq!(
+            manager.check_service(SERVICE).unwrap_err().code(),
+            KeychainErrorCode::ServiceNotAllowed
+        );
+        manager.set_service_prefix(SERVICE_PREFIX);
+        manager.check_service(SERVICE).unwrap();
+    }
+
+    #[test]
+    fn delete_with_history_follows_preset_answer() {
+        let (mut manager, _) = mock_manager();
+        set(&manager, "token", "v1");
+        set(&manager, "token", "v2");
+
+        manager.confirmation = Arc::new(Confirmation::preset(false, false));
+        let refused = confirm_delete(&manager, "token").unwrap_err();
+        assert_eq!(refused.code(), KeychainErrorCode::CancelledByUser);
+        assert_eq!(get(&manager, "token").as_deref(), Some("v2"));
+
+        manager.confirmation = Arc::new(Confirmation::preset(true, false));
+        confirm_delete(&manager, "token").unwrap();
+        assert!(manager.delete(SERVICE, "token", NO_RETRY).unwrap());
+        assert_eq!(get(&manager, "token"), None);
+    }
+
+    #[test]
+    fn delete_without_history_asks_only_with_every_delete() {
+        let (mut manager, _) = mock_manager();
+        manager
+            .set(
+                SERVICE,
+                "token",
+                "v1",
+                SetOptions {
+                    history: Some(0),
+                    ..SetOptions::default()
+                },
+                NO_RETRY,
+            )
+            .unwrap();
+
+        manager.confirmation = Arc::new(Confirmation::preset(false, false));
+        confirm_delete(&manager, "token").unwrap();
+
+        manager.confirmation = Arc::new(Confirmation::preset(false, true));
+        let refused = confirm_delete(&manager, "token").unwrap_err();
+        assert_eq!(refused.code(), KeychainErrorCode::CancelledByUser);
+    }
+
+    #[test]
+    fn concurrent_operations_keep_index_and_chunks_consistent() {
+        const KEYS: [&str; 5] = ["alpha", "bravo", "charlie", "delta", "echo"];
+        let manager = memory_manager();
+        std::thread::scope(|scope| {
+            for worker in 0..10 {
+                let manager = &manager;
+                scope.spawn(move || {
+                    for step in 0..10 {
+                        let key = KEYS[(worker + step) % KEYS.len()];
+                        match (worker * 7 + step * 3) % 4 {
+                            0 => set(manager, key, &format!("{}:{}", key, step)),
+                            // Au-dela de CHUNK_UNITS : ecrit en fragments.
+                            1 => set(manager, key, &format!("{}:{}", key, "x".repeat(3_000))),
+                            2 => {
+                                manager.delete(SERVICE, key, NO_RETRY).unwrap();
+                            }
+                            _ => {
+                                if let Some(value) = get(manager, key) {
+                                    assert!(value.starts_with(&format!("{}:", key)));
+                                }
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        let stored = stored_keys(&manager);
+        let present: Vec<String> = stored
+            .iter()
+            .filter(|key| !key.starts_with(RESERVED_KEY_PREFIX) && !key.contains('#'))
+            .cloned()
+            .collect();
+        assert_eq!(read_index(&manager.store, SERVICE).unwrap(), present);
+        for key in &present {
+            assert!(get(&manager, key).is_some());
+            let chunks: Vec<String> = stored
+                .iter()
+                .filter(|entry| is_chunk_key(entry) && entry.starts_with(&format!("{}#", key)))
+                .cloned()
+                .collect();
+            let count = chunk_count(&manager.store, NO_RETRY, SERVICE, key);
+            assert_eq!(chunks.len(), count, "fragments orphelins pour {}", key);
+        }
+        for key in KEYS.iter().filter(|key| !present.iter().any(|p| p == *key)) {
+            assert!(stored
+                .iter()
+                .all(|entry| !entry.starts_with(&format!("{}#", key))
+                    || entry.ends_with(HISTORY_SUFFIX)));
+        }
+        assert_eq!(manager.locks.held_count(), 0);
+    }
+
+    #[test]
+    fn legacy_entry_migrates_to_an_envelope_on_write() {
+        let manager = memory_manager();
+        manager.memory.set(SERVICE, "old", b"plain").unwrap();
+        assert_eq!(get(&manager, "old").as_deref(), Some("plain"));
+        let metadata = manager
+            .get_metadata(SERVICE, "old", NO_RETRY)
+            .unwrap()
+            .unwrap();
+        assert!(metadata.legacy);
+        assert!(metadata.created_at.is_none());
+
+        set(&manager, "old", "wrapped");
+        assert_eq!(get(&manager, "old").as_deref(), Some("wrapped"));
+        let metadata = manager
+            .get_metadata(SERVICE, "old", NO_RETRY)
+            .unwrap()
+            .unwrap();
+        assert!(!metadata.legacy);
+        assert!(metadata.created_at.is_some());
+        let raw = String::from_utf8(manager.memory.get(SERVICE, "old").unwrap()).unwrap();
+        assert!(matches!(
+            Stored::parse(Secret::new(raw)),
+            Stored::Envelope(_)
+        ));
+    }
+
+    #[test]
+    fn list_reads_expiry_from_metadata() {
+        let manager = memory_manager();
+        set(&manager, "plain", "v1");
+        manager
+            .set(
+                SERVICE,
+                "ttl",
+                "v1",
+                SetOptions {
+                    ttl_seconds: Some(3_600),
+                    ..SetOptions::default()
+                },
+                NO_RETRY,
+            )
+            .unwrap();
+        let metadata = manager
+            .get_metadata(SERVICE, "ttl", NO_RETRY)
+            .unwrap()
+            .unwrap();
+        assert!(metadata.expires_at.is_some());
+        assert!(!metadata.is_expired());
+
+        let listed = manager.list(SERVICE).unwrap();
+        assert_eq!(
+            listed
+                .iter()
+                .map(|entry| entry.key.as_str())
+                .collect::<Vec<_>>(),
+            ["plain", "ttl"]
+        );
+        assert!(listed.iter().all(|entry| !entry.expired));
+    }
+
+    fn code<T>(result: Result<T, KeychainError>) -> Option<KeychainErrorCode> {
+        result.err().map(|e| e.code())
+    }
+
+    fn get_or_set(manager: &KeychainManager, key: &str, value: &str) -> (String, bool) {
+        let result = manager
+            .get_or_set(SERVICE, key, Secret::new(value.to_string()), NO_RETRY)
+            .unwrap()
+            .into_unverified();
+        (result.value.to_string(), result.created)
+    }
+
+    #[test]
+    fn operations_succeed_on_the_mock_store() {
+        let (manager, _) = mock_manager();
+        set(&manager, "token", "v1");
+        set(&manager, "token", "v2");
+        assert_eq!(get(&manager, "token").as_deref(), Some("v2"));
+        assert_eq!(
+            manager.history(SERVICE, "token", NO_RETRY).unwrap().len(),
+            1
+        );
+
+        let renamed = manager
+            .rename(SERVICE, "token", "api", false, NO_RETRY)
+            .unwrap();
+        assert!(renamed.cleaned_up);
+        assert_eq!(get(&manager, "token"), None);
+        assert_eq!(get(&manager, "api").as_deref(), Some("v2"));
+        assert_eq!(manager.history(SERVICE, "api", NO_RETRY).unwrap().len(), 1);
+
+        assert_eq!(
+            get_or_set(&manager, "device", "d1"),
+            ("d1".to_string(), true)
+        );
+        assert_eq!(
+            get_or_set(&manager, "device", "d2"),
+            ("d1".to_string(), false)
+        );
+        let listed: Vec<String> = manager
+            .list(SERVICE)
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.key)
+            .collect();
+        assert_eq!(listed, ["api", "device"]);
+
+        assert!(manager.delete(SERVICE, "api", NO_RETRY).unwrap());
+        assert_eq!(get(&manager, "api"), None);
+    }
+
+    #[test]
+    fn missing_keys_are_not_errors() {
+        let manager = memory_manager();
+        assert_eq!(get(&manager, "absent"), None);
+        assert!(!manager.delete(SERVICE, "absent", NO_RETRY).unwrap());
+        assert!(manager
+            .history(SERVICE, "absent", NO_RETRY)
+            .unwrap()
+            .is_empty());
+        assert!(manager.list(SERVICE).unwrap().is_empty());
+        assert_eq!(
+            code(manager.rename(SERVICE, "absent", "other", false, NO_RETRY)),
+            Some(KeychainErrorCode::NotFound)
+        );
+    }
+
+    #[test]
+    fn injected_failures_surface_their_code() {
+        let (manager, mock) = mock_manager();
+        set(&manager, "token", "v1");
+
+        mock.fail_next(MockOp::Set, KeychainErrorCode::AccessDenied, 1);
+        assert_eq!(
+            code(manager.set(SERVICE, "token", "v2", SetOptions::default(), NO_RETRY)),
+            Some(KeychainErrorCode::AccessDenied)
+        );
+        assert_eq!(get(&manager, "token").as_deref(), Some("v1"));
+
+        mock.fail_next(MockOp::Get, KeychainErrorCode::BackendUnavailable, 1);
+        assert_eq!(
+            code(manager.get(SERVICE, "token", false, NO_RETRY)),
+            Some(KeychainErrorCode::BackendUnavailable)
+        );
+
+        mock.fail_next(MockOp::Delete, KeychainErrorCode::PlatformError, 1);
+        assert_eq!(
+            code(manager.delete(SERVICE, "token", NO_RETRY)),
+            Some(KeychainErrorCode::PlatformError)
+        );
+        assert_eq!(get(&manager, "token").as_deref(), Some("v1"));
+
+        mock.fail_next(MockOp::Get, KeychainErrorCode::BackendLocked, 1);
+        assert_eq!(
+            code(manager.list(SERVICE)),
+            Some(KeychainErrorCode::BackendLocked)
+        );
+
+        mock.fail_next(MockOp::Get, KeychainErrorCode::AccessDenied, 1);
+        assert_eq!(
+            code(manager.rename(SERVICE, "token", "api", false, NO_RETRY)),
+            Some(KeychainErrorCode::AccessDenied)
+        );
+        assert_eq!(get(&manager, "api"), None);
+
+        mock.fail_next(MockOp::Set, KeychainErrorCode::AccessDenied, 1);
+        assert_eq!(
+            code(manager.get_or_set(SERVICE, "device", Secret::new("d1".to_string()), NO_RETRY)),
+            Some(KeychainErrorCode::AccessDenied)
+        );
+        assert_eq!(
+            get_or_set(&manager, "device", "d2"),
+            ("d2".to_string(), true)
+        );
+    }
+
+    #[test]
+    fn transient_failures_are_retried() {
+        let (manager, mock) = mock_manager();
+        set(&manager, "token", "v1");
+        let retry = RetryPolicy {
+            attempts: 3,
+            base_delay_ms: 0,
+        };
+        mock.fail_next(MockOp::Get, KeychainErrorCode::BackendUnavailable, 2);
+        let value = manager.get(SERVICE, "token", false, retry).unwrap();
+        assert_eq!(
+            value.into_unverified().as_deref().map(String::as_str),
+            Some("v1")
+        );
+    }
+
+    #[test]
+    fn concurrent_get_or_set_creates_once() {
+        let manager = memory_manager();
+        let barrier = std::sync::Barrier::new(8);
+        let results: Vec<(String, bool)> = std::thread::scope(|scope| {
+            let workers: Vec<_> = (0..8)
+                .map(|worker| {
+                    let (manager, barrier) = (&manager, &barrier);
+                    scope.spawn(move || {
+                        barrier.wait();
+                        get_or_set(manager, "device", &format!("device-{}", worker))
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .map(|worker| worker.join().unwrap())
+                .collect()
+        });
+        let created: Vec<&String> = results
+            .iter()
+            .filter(|(_, created)| *created)
+            .map(|(value, _)| value)
+            .collect();
+        assert_eq!(created.len(), 1);
+        assert!(results.iter().all(|(value, _)| value == created[0]));
+        assert_eq!(get(&manager, "device").as_ref(), Some(created[0]));
+    }
+
+    fn round_trip_bytes(manager: &KeychainManager, bytes: &[u8]) {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        manager
+            .set_bytes(SERVICE, "der", &encoded, NO_RETRY)
+            .unwrap();
+        let read = manager.get_bytes(SERVICE, "der", NO_RETRY).unwrap();
+        assert_eq!(read.as_deref().map(String::as_str), Some(encoded.as_str()));
+    }
+
+    #[test]
+    fn binary_secrets_round_trip_exactly() {
+        let manager = memory_manager();
+        let every_byte: Vec<u8> = (0..=255).collect();
+        for bytes in [&b"\0\xff\0"[..], b"\xff\xfe", &every_byte] {
+            round_trip_bytes(&manager, bytes);
+            let stored = manager.memory.map()[&(SERVICE.to_string(), "der".to_string())].clone();
+            assert_eq!(&stored[..], bytes);
+        }
+        assert_eq!(read_index(&manager.store, SERVICE).unwrap(), ["der"]);
+        assert_eq!(
+            manager.get_bytes(SERVICE, "absent", NO_RETRY).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn binary_secrets_round_trip_on_the_mock_store() {
+        let (manager, _) = mock_manager();
+        round_trip_bytes(&manager, b"\0cle\xff\x80\0");
+    }
+
+    #[test]
+    fn invalid_base64_is_not_stored() {
+        let manager = memory_manager();
+        for input in ["pas du base64", "AP8", "AP8A=="] {
+            assert_eq!(
+                code(manager.set_bytes(SERVICE, "der", input, NO_RETRY)),
+                Some(KeychainErrorCode::InvalidBase64),
+                "{}",
+                input
+            );
+        }
+        assert!(stored_keys(&manager).is_empty());
+    }
+
+    /// Store dont les lectures de `slow-*` attendent qu'une deuxieme lecture
+    /// soit en cours, au plus deux secondes, jusqu'a la premiere rencontre :
+    /// deux lectures servies l'une apres l'autre ne se rencontrent jamais.
+    struct RendezvousStore {
+        memory: store::MemoryStore,
+        reading: std::sync::Mutex<(usize, usize)>,
+        arrived: std::sync::Condvar,
+    }
+
+    impl SecretStore for RendezvousStore {
+        fn name(&self) -> &'static str {
+            "rendezvous"
+        }
+
+        fn persistent(&self) -> bool {
+            false
+        }
+
+        fn set(&self, service: &str, key: &str, value: &[u8]) -> Result<(), KeychainError> {
+            self.memory.set(service, key, value)
+        }
+
+        fn get(&self, service: &str, key: &str) -> Result<Vec<u8>, KeychainError> {
+            if key.starts_with("slow-") {
+                let mut reading = self.reading.lock().unwrap();
+                reading.0 += 1;
+                reading.1 = reading.1.max(reading.0);
+                self.arrived.notify_all();
+                let (mut reading, _) = self
+                    .arrived
+                    .wait_timeout_while(reading, Duration::from_secs(2), |r| r.1 < 2)
+                    .unwrap();
+                reading.0 -= 1;
+            }
+            self.memory.get(service, key)
+        }
+
+        fn delete(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+            self.memory.delete(service, key)
+        }
+    }
+
+    #[test]
+    fn concurrent_gets_do_not_wait_for_each_other() {
+        let mut manager = memory_manager();
+        set(&manager, "slow-a", "a");
+        set(&manager, "slow-b", "b");
+        let store = Arc::new(RendezvousStore {
+            memory: manager.memory.clone(),
+            reading: Default::default(),
+            arrived: Default::default(),
+        });
+        manager.store = store.clone();
+
+        let started = Instant::now();
+        let values = tauri::async_runtime::block_on(async {
+            let reads: Vec<_> = ["slow-a", "slow-b"]
+                .into_iter()
+                .map(|key| {
+                    let manager = manager.clone();
+                    tauri::async_runtime::spawn(run_blocking(move || {
+                        let value = manager.get(SERVICE, key, false, NO_RETRY)?;
+                        Ok(value.into_unverified().map(|value| value.to_string()))
+                    }))
+                })
+                .collect();
+            let mut values = Vec::new();
+            for read in reads {
+                values.push(read.await.unwrap().unwrap());
+            }
+            values
+        });
+        assert_eq!(values, [Some("a".to_string()), Some("b".to_string())]);
+        assert_eq!(store.reading.lock().unwrap().1, 2);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn deleting_twice_reports_a_clean_not_found() {
+        let manager = memory_manager();
+        set(&manager, "token", "v1");
+        set(&manager, "blob", &"x".repeat(CHUNK_UNITS * 2));
+        for key in ["token", "blob"] {
+            assert!(manager.delete(SERVICE, key, NO_RETRY).unwrap());
+            assert!(!manager.delete(SERVICE, key, NO_RETRY).unwrap());
+            assert_eq!(get(&manager, key), None);
+        }
+        assert!(read_index(&manager.store, SERVICE).unwrap().is_empty());
+        assert!(stored_keys(&manager)
+            .iter()
+            .all(|key| key.starts_with(RESERVED_KEY_PREFIX)));
+    }
+
+    #[test]
+    fn backend_failures_are_not_reported_as_not_found() {
+        let (manager, mock) = mock_manager();
+        // Le premier acces au service l'enregistre : il ne doit pas
+        // consommer la panne injectee.
+        set(&manager, "token", "v1");
+        mock.fail_next(MockOp::Get, KeychainErrorCode::BackendUnavailable, 1);
+        assert_eq!(
+            code(manager.get(SERVICE, "absent", false, NO_RETRY)),
+            Some(KeychainErrorCode::BackendUnavailable)
+        );
+        mock.fail_next(MockOp::Delete, KeychainErrorCode::BackendUnavailable, 1);
+        assert_eq!(
+            code(manager.delete(SERVICE, "absent", NO_RETRY)),
+            Some(KeychainErrorCode::BackendUnavailable)
+        );
+        assert!(!manager.delete(SERVICE, "absent", NO_RETRY).unwrap());
+    }
+
+    #[test]
+    fn chunks_split_at_the_exact_unit_limit() {
+        let exact = "x".repeat(CHUNK_UNITS);
+        assert_eq!(split_chunks(&exact), vec![exact.as_str()]);
+        let over = "x".repeat(CHUNK_UNITS + 1);
+        let chunks = split_chunks(&over);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!((chunks[0].len(), chunks[1]), (CHUNK_UNITS, "x"));
+
+        // Une paire de substitution a cheval sur la limite passe entiere
+        // dans le fragment suivant.
+        let pairs = "\u{1F600}".repeat(CHUNK_UNITS / 2);
+        assert_eq!(split_chunks(&pairs).len(), 1);
+        let straddling = format!("{}\u{1F600}", "x".repeat(CHUNK_UNITS - 1));
+        let chunks = split_chunks(&straddling);
+        assert_eq!(chunks, vec![&straddling[..CHUNK_UNITS - 1], "\u{1F600}"]);
+        for chunk in chunks {
+            assert!(chunk.encode_utf16().count() <= CHUNK_UNITS);
+        }
+    }
+
+    #[test]
+    fn raw_values_at_the_limit_round_trip() {
+        let manager = memory_manager();
+        let straddling = format!("{}\u{1F600}", "x".repeat(CHUNK_UNITS - 1));
+        let cases = [
+            ("x".repeat(CHUNK_UNITS), 0),
+            ("x".repeat(CHUNK_UNITS + 1), 2),
+            ("\u{1F600}".repeat(CHUNK_UNITS / 2), 0),
+            (straddling, 2),
+        ];
+        for (value, chunks) in cases {
+            write_raw(&manager.store, NO_RETRY, SERVICE, "blob", &value).unwrap();
+            assert_eq!(
+                chunk_count(&manager.store, NO_RETRY, SERVICE, "blob"),
+                chunks
+            );
+            assert_eq!(
+                stored_keys(&manager).len(),
+                chunks + 1,
+                "fragments orphelins"
+            );
+            let read = read_raw(&manager.store, NO_RETRY, SERVICE, "blob").unwrap();
+            assert_eq!(read.as_deref().map(String::as_str), Some(value.as_str()));
+        }
+    }
+
+    #[test]
+    fn missing_or_altered_chunks_are_corrupt() {
+        let manager = memory_manager();
+        let value = "x".repeat(CHUNK_UNITS * 2 + 1);
+        let read =
+            |manager: &KeychainManager| code(read_raw(&manager.store, NO_RETRY, SERVICE, "blob"));
+
+        write_raw(&manager.store, NO_RETRY, SERVICE, "blob", &value).unwrap();
+        manager
+            .store
+            .delete(SERVICE, &chunk_key("blob", 1))
+            .unwrap();
+        assert_eq!(read(&manager), Some(KeychainErrorCode::Corrupt));
+
+        write_raw(&manager.store, NO_RETRY, SERVICE, "blob", &value).unwrap();
+        let altered = format!("y{}", "x".repeat(CHUNK_UNITS - 1));
+        manager
+            .store
+            .set_password(SERVICE, &chunk_key("blob", 0), &altered)
+            .unwrap();
+        assert_eq!(read(&manager), Some(KeychainErrorCode::Corrupt));
+
+        write_raw(&manager.store, NO_RETRY, SERVICE, "blob", &value).unwrap();
+        manager
+            .store
+            .set_password(SERVICE, &chunk_key("blob", 2), "xx")
+            .unwrap();
+        assert_eq!(read(&manager), Some(KeychainErrorCode::Corrupt));
+    }
+
+    /// Sonde un `MockSecretStore` comme le backend reel.
+    fn probed(mock: &Arc<store::MockSecretStore>) -> Result<Store, String> {
+        let store: Store = mock.clone();
+        match probe_backend(Arc::clone(&store)) {
+            None => Ok(store),
+            Some(reason) => Err(reason),
+        }
+    }
+
+    #[test]
+    fn backend_selection_falls_back_in_order() {
+        let memory = store::MemoryStore::default();
+        let is = |store: &Store, mock: &Arc<store::MockSecretStore>| {
+            std::ptr::addr_eq(Arc::as_ptr(store), Arc::as_ptr(mock))
+        };
+        let failing = |code| {
+            let mock = Arc::new(store::MockSecretStore::default());
+            mock.fail_next(MockOp::Get, code, 1);
+            mock
+        };
+        let (os, keyutils) = (
+            Arc::new(store::MockSecretStore::default()),
+            Arc::new(store::MockSecretStore::default()),
+        );
+
+        // Le backend OS repond : keyutils n'est pas sonde.
+        let (store, reason) = select_backend(&memory, false, || probed(&os), || unreachable!());
+        assert!(is(&store, &os) && reason.is_none());
+        // Une entree absente ou refusee n'est pas une panne.
+        let denied = failing(KeychainErrorCode::AccessDenied);
+        let (store, _) = select_backend(&memory, false, || probed(&denied), || unreachable!());
+        assert!(is(&store, &denied));
+
+        let down = || failing(KeychainErrorCode::BackendUnavailable);
+        let (store, reason) = select_backend(
+            &memory,
+            false,
+            || probed(&down()),
+            || probed(&keyutils).map_err(Some),
+        );
+        assert!(is(&store, &keyutils));
+        assert!(reason.unwrap().contains("panne injectee"));
+
+        let (store, reason) = select_backend(
+            &memory,
+            false,
+            || probed(&down()),
+            || {
+                probed(&failing(KeychainErrorCode::PlatformError))
+                    .map_err(|e| Some(format!("keyutils indisponible : {}", e)))
+            },
+        );
+        assert_eq!(store.name(), "memory");
+        let reason = reason.unwrap();
+        assert!(reason.contains(" ; keyutils indisponible : "), "{}", reason);
+
+        // keyutils exclu par la configuration : seule la raison OS reste.
+        let (store, reason) = select_backend(&memory, false, || probed(&down()), || Err(None));
+        assert_eq!(store.name(), "memory");
+        assert!(!reason.unwrap().contains("keyutils"));
+    }
+
+    #[test]
+    fn forced_keyutils_skips_the_os_probe() {
+        let memory = store::MemoryStore::default();
+        let keyutils = Arc::new(store::MockSecretStore::default());
+        let (store, reason) = select_backend(
+            &memory,
+            true,
+            || unreachable!(),
+            || probed(&keyutils).map_err(Some),
+        );
+        assert!(std::ptr::addr_eq(
+            Arc::as_ptr(&store),
+            Arc::as_ptr(&keyutils)
+        ));
+        assert!(reason.is_none());
+
+        let (store, reason) = select_backend(
+            &memory,
+            true,
+            || unreachable!(),
+            || Err(Some("keyutils indisponible".to_string())),
+        );
+        assert_eq!(store.name(), "memory");
+        assert_eq!(reason.as_deref(), Some("keyutils indisponible"));
+    }
+
+    fn set_if(
+        manager: &KeychainManager,
+        key: &str,
+        value: &str,
+        expected: Option<&str>,
+    ) -> Result<SetReport, KeychainError> {
+        let options = SetOptions {
+            if_matches: Some(expected.map(|expected| Secret::new(expected.to_string()))),
+            ..SetOptions::default()
+        };
+        manager.set(SERVICE, key, value, options, NO_RETRY)
+    }
+
+    #[test]
+    fn conditional_sets_check_the_current_value() {
+        let manager = memory_manager();
+        assert!(set_if(&manager, "counter", "1", None).is_ok());
+        assert_eq!(
+            code(set_if(&manager, "counter", "2", None)),
+            Some(KeychainErrorCode::Conflict)
+        );
+        assert_eq!(
+            code(set_if(&manager, "counter", "2", Some("0"))),
+            Some(KeychainErrorCode::Conflict)
+        );
+        assert_eq!(get(&manager, "counter").as_deref(), Some("1"));
+        assert!(set_if(&manager, "counter", "2", Some("1")).is_ok());
+        assert_eq!(get(&manager, "counter").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn concurrent_conditional_sets_lose_no_update() {
+        const WORKERS: usize = 8;
+        const INCREMENTS: usize = 25;
+        let manager = memory_manager();
+        set(&manager, "counter", "0");
+        std::thread::scope(|scope| {
+            for _ in 0..WORKERS {
+                scope.spawn(|| {
+                    let mut done = 0;
+                    while done < INCREMENTS {
+                        let current = get(&manager, "counter").unwrap();
+                        let next = (current.parse::<usize>().unwrap() + 1).to_string();
+                        match set_if(&manager, "counter", &next, Some(&current)) {
+                            Ok(_) => done += 1,
+                            Err(e) if e.code() == KeychainErrorCode::Conflict => {}
+                            Err(e) => panic!("erreur inattendue : {:?}", e.code()),
+                        }
+                    }
+                });
+            }
+        });
+        // Chaque ecriture acceptee partait de la derniere valeur : aucune
+        // n'en a ecrase une autre.
+        let total = WORKERS * INCREMENTS;
+        assert_eq!(get(&manager, "counter"), Some(total.to_string()));
+    }
+
+    #[test]
+    fn a_single_expected_value_wins_once() {
+        let manager = memory_manager();
+        set(&manager, "counter", "0");
+        let barrier = std::sync::Barrier::new(6);
+        let winners: Vec<bool> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..6)
+                .map(|worker| {
+                    let (manager, barrier) = (&manager, &barrier);
+                    scope.spawn(move || {
+                        barrier.wait();
+                        set_if(manager, "counter", &format!("w{}", worker), Some("0")).is_ok()
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(winners.iter().filter(|won| **won).count(), 1);
+        let winner = winners.iter().position(|won| *won).unwrap();
+        assert_eq!(get(&manager, "counter"), Some(format!("w{}", winner)));
+    }
+
+    #[test]
+    fn migration_keeps_the_history() {
+        let manager = memory_manager();
+        let next = format!("{}.next", SERVICE);
+        set(&manager, "token", "v1");
+        set(&manager, "token", "v2");
+        manager
+            .migrate_service(SERVICE, &next, true, &[], NO_RETRY, &Progress::none())
+            .unwrap();
+
+        assert_eq!(get(&manager, "token"), None);
+        assert!(read_history(&manager.store, NO_RETRY, SERVICE, "token").is_empty());
+        let history = read_history(&manager.store, NO_RETRY, &next, "token");
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].value, "v1");
+    }
+
+    #[test]
+    fn startup_migration_stays_pending_on_conflict() {
+        let manager = memory_manager();
+        let (old_service, new_service) = STARTUP_MIGRATIONS[0];
+        for (service, value) in [(old_service, "ancien"), (new_service, "nouveau")] {
+            keyring_call(&manager.store, NO_RETRY, service, "token", |entry| {
+                entry.set_password(value)
+            })
+            .unwrap();
+        }
+        update_index(
+            &manager.store,
+            &manager.index,
+            old_service,
+            &["token"],
+            true,
+        );
+        run_startup_migrations(&manager);
+
+        let marker = found(keyring_call(
+            &manager.store,
+            NO_RETRY,
+            new_service,
+            MIGRATION_MARKER_KEY,
+            |entry| entry.get_password(),
+        ));
+        assert_eq!(marker.unwrap(), None);
+    }
+}
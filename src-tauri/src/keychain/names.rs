@@ -0,0 +1,211 @@
+//! Normalisation des noms de services et de cles : espaces de bord retires
+//! et forme NFC. "Cle" compose ou decompose, ou suivi d'un espace, designe
+//! ainsi la meme entree au lieu de deux entrees que rien ne distingue a
+//! l'ecran. Les entrees creees avant la normalisation restent lisibles sous
+//! leur orthographe exacte comme sous la forme normalisee.
+
+use unicode_normalization::UnicodeNormalization;
+
+use super::{
+    check_user_key, read_index_with, KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy,
+};
+
+/// Longueur maximale d'un nom de service.
+const MAX_SERVICE_CHARS: usize = 255;
+
+pub(crate) fn normalize(name: &str) -> String {
+    name.trim().nfc().collect()
+}
+
+/// Longueur telle que le backend la compte : en unites UTF-16 pour le
+/// gestionnaire d'identifiants Windows, en caracteres ailleurs.
+pub(crate) fn length(name: &str) -> usize {
+    if cfg!(windows) {
+        name.encode_utf16().count()
+    } else {
+        name.chars().count()
+    }
+}
+
+/// Nom de service normalise, refuse s'il est vide, trop long ou contient
+/// des caracteres de controle.
+pub(crate) fn service(service: &str) -> Result<String, KeychainError> {
+    let normalized = normalize(service);
+    if normalized.is_empty() {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            "le nom de service est vide",
+        ));
+    }
+    if normalized.chars().any(char::is_control) {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            "le nom de service contient des caracteres de controle",
+        ));
+    }
+    if length(&normalized) > MAX_SERVICE_CHARS {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!("le nom de service depasse {} caracteres", MAX_SERVICE_CHARS),
+        ));
+    }
+    Ok(normalized)
+}
+
+impl KeychainManager {
+    /// Nom sous lequel lire `key` dans `service`, deja resolu. L'index dit
+    /// si l'entree existe sous la forme normalisee ou sous une orthographe
+    /// d'avant la normalisation, meme quand `key` est deja en NFC.
+    pub(crate) fn resolve_key(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<String, KeychainError> {
+        self.key_name(service, key, false, retry)
+    }
+
+    /// Nom sous lequel ecrire `key`. Une entree existante de meme nom
+    /// normalise mais d'orthographe differente donne `NameCollision` plutot
+    /// qu'une seconde entree ; son orthographe exacte reste utilisable.
+    pub(crate) fn claim_key(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<String, KeychainError> {
+        self.key_name(service, key, true, retry)
+    }
+
+    fn key_name(
+        &self,
+        service: &str,
+        key: &str,
+        claim: bool,
+        retry: RetryPolicy,
+    ) -> Result<String, KeychainError> {
+        let normalized = normalize(key);
+        check_user_key(&normalized)?;
+        // Un index illisible compte comme vide ; une erreur est donc une
+        // panne du backend, que la lecture de l'entree subirait aussi.
+        let index = read_index_with(&self.store, service, retry)?;
+        if index.contains(&normalized) {
+            return Ok(normalized);
+        }
+        if index.iter().any(|existing| existing == key) {
+            return Ok(key.to_string());
+        }
+        match index
+            .into_iter()
+            .find(|existing| normalize(existing) == normalized)
+        {
+            Some(existing) if claim => Err(KeychainError::rejected(
+                KeychainErrorCode::NameCollision,
+                format!(
+                    "{:?} designe la meme cle que l'entree existante {:?}",
+                    key, existing
+                ),
+            )),
+            Some(existing) => Ok(existing),
+            None => Ok(normalized),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_trims_and_composes() {
+        let cases = [
+            ("token", "token"),
+            ("  token\t", "token"),
+            ("\ncle api\n", "cle api"),
+            ("Cle\u{301}", "Clé"),
+            ("Clé", "Clé"),
+            (" cafe\u{301} ", "café"),
+            ("\u{212B}", "\u{C5}"),
+            ("   ", ""),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(normalize(name), expected, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn service_rejects_empty_control_and_long_names() {
+        let cases = [
+            ("fr.bbrain.argos", Ok("fr.bbrain.argos")),
+            (" fr.bbrain.argos ", Ok("fr.bbrain.argos")),
+            ("", Err(KeychainErrorCode::InvalidArgument)),
+            (" \t ", Err(KeychainErrorCode::InvalidArgument)),
+            (
+                "fr.bbrain\u{7}.argos",
+                Err(KeychainErrorCode::InvalidArgument),
+            ),
+            ("fr.bbrain\n.argos", Err(KeychainErrorCode::InvalidArgument)),
+        ];
+        for (name, expected) in cases {
+            let result = service(name);
+            assert_eq!(
+                result.as_deref().map_err(|e| e.code()),
+                expected,
+                "{:?}",
+                name
+            );
+        }
+        assert!(service(&"a".repeat(MAX_SERVICE_CHARS)).is_ok());
+        assert_eq!(
+            service(&"a".repeat(MAX_SERVICE_CHARS + 1))
+                .unwrap_err()
+                .code(),
+            KeychainErrorCode::InvalidArgument
+        );
+    }
+
+    #[test]
+    fn claim_refuses_a_second_spelling_of_an_indexed_key() {
+        use super::super::testing::{memory_manager, NO_RETRY, SERVICE};
+        use super::super::write_index;
+
+        let manager = memory_manager();
+        write_index(&manager.store, SERVICE, &["Cle\u{301}".to_string()]).unwrap();
+        let cases = [
+            // Deja normalise : l'index retrouve l'entree d'avant la
+            // normalisation.
+            (
+                "Clé",
+                Err(KeychainErrorCode::NameCollision),
+                Ok("Cle\u{301}"),
+            ),
+            (
+                " Clé ",
+                Err(KeychainErrorCode::NameCollision),
+                Ok("Cle\u{301}"),
+            ),
+            ("Cle\u{301}", Ok("Cle\u{301}"), Ok("Cle\u{301}")),
+            (" other ", Ok("other"), Ok("other")),
+        ];
+        for (key, claimed, resolved) in cases {
+            assert_eq!(
+                manager
+                    .claim_key(SERVICE, key, NO_RETRY)
+                    .as_deref()
+                    .map_err(|e| e.code()),
+                claimed,
+                "{:?}",
+                key
+            );
+            assert_eq!(
+                manager
+                    .resolve_key(SERVICE, key, NO_RETRY)
+                    .as_deref()
+                    .map_err(|e| e.code()),
+                resolved,
+                "{:?}",
+                key
+            );
+        }
+    }
+}
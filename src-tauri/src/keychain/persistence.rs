@@ -0,0 +1,144 @@
+//! Portee de persistance des identifiants du gestionnaire d'identifiants
+//! Windows, demandee par `keychain_set`, et nom de cible exact des entrees
+//! pour les audits. Le crate keyring ecrit toujours en `enterprise` : la
+//! portee demandee est appliquee en reecrivant l'identifiant.
+
+use serde::{Deserialize, Serialize};
+
+/// `session` disparait a la fermeture de session, `local_machine` reste sur
+/// le poste, `enterprise` suit le profil itinerant du domaine.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum WindowsPersistence {
+    Session,
+    LocalMachine,
+    Enterprise,
+}
+
+impl WindowsPersistence {
+    /// Nom serialise, repris dans les erreurs.
+    #[cfg(target_os = "windows")]
+    fn name(self) -> &'static str {
+        match self {
+            WindowsPersistence::Session => "session",
+            WindowsPersistence::LocalMachine => "local_machine",
+            WindowsPersistence::Enterprise => "enterprise",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Default, Debug)]
+#[serde(default)]
+pub(crate) struct WindowsOptions {
+    pub(crate) persistence: Option<WindowsPersistence>,
+}
+
+/// Options reellement appliquees, renvoyees par `keychain_set`.
+#[derive(Serialize)]
+pub(crate) struct WindowsReport {
+    persistence: Option<WindowsPersistence>,
+    /// `true` si le backend ne les prend pas en charge : rien n'a ete
+    /// applique.
+    ignored: bool,
+}
+
+impl WindowsReport {
+    pub(crate) fn applied(persistence: WindowsPersistence) -> Self {
+        WindowsReport {
+            persistence: Some(persistence),
+            ignored: false,
+        }
+    }
+
+    pub(crate) fn ignored() -> Self {
+        WindowsReport {
+            persistence: None,
+            ignored: true,
+        }
+    }
+}
+
+/// Nom de cible de l'entree, celui affiche par le gestionnaire
+/// d'identifiants.
+#[cfg(target_os = "windows")]
+pub(super) fn target_name(entry: &keyring::Entry) -> Option<String> {
+    entry
+        .get_credential()
+        .downcast_ref::<keyring::windows::WinCredential>()
+        .map(|credential| credential.target_name.clone())
+}
+
+#[cfg(target_os = "windows")]
+mod credentials {
+    use windows::core::HSTRING;
+    use windows::Win32::Security::Credentials::{
+        CredFree, CredGetSessionTypes, CredReadW, CredWriteW, CREDENTIALW, CRED_PERSIST,
+        CRED_PERSIST_ENTERPRISE, CRED_PERSIST_LOCAL_MACHINE, CRED_PERSIST_NONE,
+        CRED_PERSIST_SESSION, CRED_TYPE_GENERIC, CRED_TYPE_MAXIMUM,
+    };
+
+    use super::WindowsPersistence;
+    use crate::keychain::{KeychainError, KeychainErrorCode};
+
+    fn platform(e: windows::core::Error) -> KeychainError {
+        KeychainError::rejected(
+            KeychainErrorCode::PlatformError,
+            format!("persistance Windows non appliquee : {}", e),
+        )
+    }
+
+    fn flag(persistence: WindowsPersistence) -> CRED_PERSIST {
+        match persistence {
+            WindowsPersistence::Session => CRED_PERSIST_SESSION,
+            WindowsPersistence::LocalMachine => CRED_PERSIST_LOCAL_MACHINE,
+            WindowsPersistence::Enterprise => CRED_PERSIST_ENTERPRISE,
+        }
+    }
+
+    /// Une portee au-dela du maximum autorise sur le poste, `enterprise` hors
+    /// domaine par exemple, est refusee avant l'ecriture plutot que ramenee
+    /// en silence a une portee locale.
+    pub(crate) fn check_allowed(persistence: WindowsPersistence) -> Result<(), KeychainError> {
+        let mut maximum = [0u32; CRED_TYPE_MAXIMUM.0 as usize];
+        unsafe { CredGetSessionTypes(&mut maximum) }.map_err(platform)?;
+        let allowed = CRED_PERSIST(maximum[CRED_TYPE_GENERIC.0 as usize]);
+        if flag(persistence).0 > allowed.0 {
+            let allowed = match allowed {
+                CRED_PERSIST_NONE => "aucune",
+                CRED_PERSIST_SESSION => WindowsPersistence::Session.name(),
+                CRED_PERSIST_LOCAL_MACHINE => WindowsPersistence::LocalMachine.name(),
+                _ => WindowsPersistence::Enterprise.name(),
+            };
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::Unsupported,
+                format!(
+                    "persistance {} non autorisee sur ce poste (maximum : {})",
+                    persistence.name(),
+                    allowed
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reecrit l'identifiant `target_name` avec `persistence` ; le contenu
+    /// et les attributs relus sont conserves, seule la portee change.
+    pub(crate) fn persist(
+        target_name: &str,
+        persistence: WindowsPersistence,
+    ) -> Result<(), KeychainError> {
+        check_allowed(persistence)?;
+        let target = HSTRING::from(target_name);
+        let mut credential: *mut CREDENTIALW = std::ptr::null_mut();
+        unsafe { CredReadW(&target, CRED_TYPE_GENERIC, 0, &mut credential) }.map_err(platform)?;
+        let written = unsafe {
+            (*credential).Persist = flag(persistence);
+            CredWriteW(credential, 0)
+        };
+        unsafe { CredFree(credential as *const _) };
+        written.map_err(platform)
+    }
+}
+
+#[cfg(target_os = "windows")]
+pub(super) use credentials::{check_allowed, persist};
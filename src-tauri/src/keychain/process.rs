@@ -0,0 +1,184 @@
+//! Lancement d'un outil local (`terraform`, une CLI...) avec des secrets en
+//! variables d'environnement. Les secrets sont lus cote Rust et ne passent
+//! jamais par le webview, un shell ou un fichier ; le programme doit figurer
+//! dans `allowed_programs` et ses arguments sont transmis tels quels.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+use tauri::{Manager, State, Window};
+
+use super::{
+    run_blocking, KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy, Secret,
+    ARGOS_SERVICE,
+};
+
+pub(crate) const PROCESS_EXITED: &str = "argos://process-exited";
+
+/// Variable `var` renseignee avec le secret `key` de `service`.
+#[derive(Deserialize)]
+pub(crate) struct EnvMapping {
+    var: String,
+    service: String,
+    key: String,
+}
+
+#[derive(Serialize, Clone)]
+struct ProcessExited {
+    pid: u32,
+    /// `None` si le processus a ete tue par un signal.
+    code: Option<i32>,
+    success: bool,
+}
+
+fn not_allowed(program: &Path) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::ProgramNotAllowed,
+        format!("programme {} absent de allowed_programs", program.display()),
+    )
+}
+
+/// Nom de variable portable : lettres, chiffres et `_`, sans chiffre en tete.
+fn check_var(var: &str) -> Result<(), KeychainError> {
+    let valid = var
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        return Ok(());
+    }
+    Err(KeychainError::rejected(
+        KeychainErrorCode::InvalidArgument,
+        format!("nom de variable d'environnement invalide : {}", var),
+    ))
+}
+
+impl KeychainManager {
+    /// Chemin reel de `program`, s'il designe l'un des `allowed_programs`.
+    /// Les liens symboliques sont resolus des deux cotes.
+    fn allowed_program(&self, program: &str) -> Result<PathBuf, KeychainError> {
+        let program = Path::new(program);
+        if !program.is_absolute() {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                "le programme doit etre designe par un chemin absolu",
+            ));
+        }
+        let resolved = program.canonicalize().map_err(|_| not_allowed(program))?;
+        self.allowed_programs
+            .iter()
+            .any(|allowed| {
+                allowed
+                    .canonicalize()
+                    .is_ok_and(|allowed| allowed == resolved)
+            })
+            .then_some(resolved)
+            .ok_or_else(|| not_allowed(program))
+    }
+}
+
+/// Secret `key` de `service`, apres verification de l'utilisateur s'il la
+/// demande.
+async fn read_secret(
+    manager: &KeychainManager,
+    service: String,
+    key: String,
+    retry: RetryPolicy,
+) -> Result<Secret, KeychainError> {
+    let reader = manager.clone();
+    let value = {
+        let (service, key) = (service.clone(), key.clone());
+        manager
+            .auth
+            .release(run_blocking(move || reader.get(&service, &key, false, retry)).await)
+            .await?
+    };
+    value.ok_or_else(|| {
+        KeychainError::rejected(
+            KeychainErrorCode::NotFound,
+            format!("aucun secret {} dans {}", key, service),
+        )
+    })
+}
+
+/// Lance `program` avec `args`, sans shell, et les secrets de `env` dans
+/// son environnement. Renvoie le pid ; la fin du processus emet
+/// `argos://process-exited`. Les secrets lus sont effaces apres le
+/// lancement.
+#[tauri::command]
+pub(crate) async fn process_run_with_secret(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    program: String,
+    args: Vec<String>,
+    env: Vec<EnvMapping>,
+    cwd: Option<String>,
+    retry: Option<RetryPolicy>,
+) -> Result<u32, KeychainError> {
+    let audit = manager
+        .audit(&window, "process_run", ARGOS_SERVICE, None)
+        .detail(program.as_str());
+    let program = match manager.allowed_program(&program) {
+        Ok(program) => program,
+        Err(e) => return audit.finish(Err(e)),
+    };
+    if let Some(e) = env.iter().find_map(|mapping| check_var(&mapping.var).err()) {
+        return audit.finish(Err(e));
+    }
+    let retry = retry.unwrap_or_default();
+    let mut secrets: Vec<(String, Secret)> = Vec::with_capacity(env.len());
+    for EnvMapping { var, service, key } in env {
+        let read = manager
+            .audit(&window, "process_env", &service, Some(&key))
+            .detail(program.to_string_lossy().as_ref());
+        let value = match manager.reads.acquire(&service, [key.as_str()]) {
+            Ok(_) => read_secret(manager.inner(), service, key, retry).await,
+            Err(e) => Err(e),
+        };
+        match read.finish(value) {
+            Ok(value) => secrets.push((var, value)),
+            Err(e) => return audit.finish(Err(e)),
+        }
+    }
+
+    let mut command = Command::new(&program);
+    command
+        .args(&args)
+        .envs(secrets.iter().map(|(var, value)| (var, value.as_str())))
+        .stdin(Stdio::null());
+    if let Some(cwd) = &cwd {
+        command.current_dir(cwd);
+    }
+    let spawned = command.spawn();
+    // `Command` garde sa propre copie de l'environnement, liberee avec lui ;
+    // les valeurs lues, elles, sont effacees ici.
+    drop(command);
+    drop(secrets);
+    let mut child = match spawned {
+        Ok(child) => child,
+        Err(e) => {
+            return audit.finish(Err(KeychainError::rejected(
+                KeychainErrorCode::IoError,
+                format!("lancement de {} impossible : {}", program.display(), e),
+            )))
+        }
+    };
+    let pid = child.id();
+    let app = window.app_handle();
+    let waiter = std::thread::Builder::new()
+        .name("argos-process".to_string())
+        .spawn(move || {
+            let status = child.wait();
+            let (code, success) = match &status {
+                Ok(status) => (status.code(), status.success()),
+                Err(_) => (None, false),
+            };
+            let _ = app.emit_all(PROCESS_EXITED, ProcessExited { pid, code, success });
+        });
+    if let Err(e) = waiter {
+        tracing::warn!(pid, error = %e, "fin du processus non suivie");
+    }
+    audit.finish(Ok(pid))
+}
@@ -0,0 +1,269 @@
+//! Profils, par exemple `staging` et `production` : chacun a ses propres
+//! services. Le frontend passe toujours les noms logiques ; le profil actif
+//! est insere apres le prefixe autorise par `KeychainManager::resolve_service`,
+//! `fr.bbrain.argos.api` devenant `fr.bbrain.argos.staging.api`.
+
+use std::collections::HashSet;
+use std::sync::{Mutex, MutexGuard, RwLock};
+
+use serde::Serialize;
+use tauri::{AppHandle, State, Window};
+
+use super::{
+    bulk::Progress, keyring_call, run_blocking, ClearReport, KeychainError, KeychainErrorCode,
+    KeychainManager, RetryPolicy, Store, ARGOS_SERVICE,
+};
+use crate::cli;
+use crate::config::{Config, ConfigStore, ProfilesConfig};
+
+/// Profil actif, partage par le `KeychainManager`. `None` designe le profil
+/// par defaut, sans nom.
+#[derive(Default)]
+pub(crate) struct Profiles {
+    active: RwLock<Option<String>>,
+    /// Services deja notes pour le profil actif depuis son activation.
+    recorded: Mutex<HashSet<String>>,
+}
+
+impl Profiles {
+    pub(crate) fn active(&self) -> Option<String> {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub(crate) fn set_active(&self, profile: Option<String>) {
+        *self.active.write().unwrap_or_else(|e| e.into_inner()) = profile;
+        self.recorded().clear();
+    }
+
+    fn recorded(&self) -> MutexGuard<'_, HashSet<String>> {
+        self.recorded.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Service reel de `service` dans `profile`. `service` a deja ete accepte
+/// par `check_service` : il est `ARGOS_SERVICE` ou commence par l'un des
+/// `prefixes`.
+pub(super) fn profile_service(service: &str, profile: &str, prefixes: &[String]) -> String {
+    let prefix = prefixes
+        .iter()
+        .filter(|prefix| service.starts_with(prefix.as_str()))
+        .max_by_key(|prefix| prefix.len());
+    match prefix {
+        Some(prefix) => format!("{}{}.{}", prefix, profile, &service[prefix.len()..]),
+        None => format!("{}.{}", service, profile),
+    }
+}
+
+/// Cle du service ARGOS qui liste les services reels d'un profil, pour
+/// pouvoir les vider a sa suppression ou par `wipe_all_data`. `None` designe
+/// le profil par defaut.
+fn services_key(profile: Option<&str>) -> String {
+    match profile {
+        Some(profile) => format!("profile.{}.services", profile),
+        None => "profile.services".to_string(),
+    }
+}
+
+pub(super) fn read_services(
+    store: &Store,
+    profile: Option<&str>,
+) -> Result<Vec<String>, KeychainError> {
+    let raw = super::found(keyring_call(
+        store,
+        RetryPolicy::default(),
+        ARGOS_SERVICE,
+        &services_key(profile),
+        |entry| entry.get_password(),
+    ))?;
+    Ok(raw
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default())
+}
+
+/// Note `service` parmi ceux de `profile`, une fois par activation. Un echec
+/// n'empeche pas la commande : le service ne serait seulement pas vide par
+/// `profile_delete` ni par `wipe_all_data`.
+pub(super) fn record_service(
+    profiles: &Profiles,
+    store: &Store,
+    profile: Option<&str>,
+    service: &str,
+) {
+    let mut recorded = profiles.recorded();
+    if recorded.contains(service) {
+        return;
+    }
+    let mut services = read_services(store, profile).unwrap_or_default();
+    if !services.iter().any(|known| known == service) {
+        services.push(service.to_string());
+        let Ok(data) = serde_json::to_string(&services) else {
+            return;
+        };
+        let written = keyring_call(
+            store,
+            RetryPolicy::default(),
+            ARGOS_SERVICE,
+            &services_key(profile),
+            |entry| entry.set_password(&data),
+        );
+        if let Err(e) = written {
+            tracing::warn!(profile, error = %e, "service du profil non enregistre");
+            return;
+        }
+    }
+    recorded.insert(service.to_string());
+}
+
+#[derive(Serialize)]
+pub(crate) struct ProfileWipeReport {
+    pub(super) services: Vec<String>,
+    removed: usize,
+    /// Cles non supprimees, a retenter par `keychain_clear_service`.
+    pub(super) failed: usize,
+}
+
+impl KeychainManager {
+    /// Vide chaque service note pour `profile` avec le balayage de
+    /// `clear_service`, puis oublie la liste si tout a ete supprime.
+    pub(crate) fn wipe_profile(
+        &self,
+        profile: Option<&str>,
+        retry: RetryPolicy,
+    ) -> Result<ProfileWipeReport, KeychainError> {
+        let services = read_services(&self.store, profile)?;
+        let mut report = ProfileWipeReport {
+            services: Vec::new(),
+            removed: 0,
+            failed: 0,
+        };
+        for service in services {
+            let ClearReport {
+                removed, failed, ..
+            } = self.sweep_service(&service, retry, &Progress::none())?;
+            report.removed += removed.len();
+            report.failed += failed.len();
+            report.services.push(service);
+        }
+        if report.failed == 0 {
+            let _ = keyring_call(
+                &self.store,
+                retry,
+                ARGOS_SERVICE,
+                &services_key(profile),
+                |entry| entry.delete_credential(),
+            );
+        }
+        Ok(report)
+    }
+}
+
+fn check_name(name: &str) -> Result<(), KeychainError> {
+    if cli::is_valid_profile(name) {
+        return Ok(());
+    }
+    Err(KeychainError::rejected(
+        KeychainErrorCode::InvalidArgument,
+        "nom de profil invalide : minuscules, chiffres et tirets, sans point ni barre oblique",
+    ))
+}
+
+fn unknown_profile(name: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::NotFound,
+        format!("profil {} introuvable", name),
+    )
+}
+
+#[tauri::command]
+pub(crate) fn profile_list(store: State<'_, ConfigStore>) -> ProfilesConfig {
+    store.get().profiles.clone()
+}
+
+#[tauri::command]
+pub(crate) fn profile_create(
+    app: AppHandle,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+    name: String,
+) -> Result<ProfilesConfig, KeychainError> {
+    check_name(&name)?;
+    let config = store.update(&app, &manager, |config| {
+        if config.profiles.names.contains(&name) {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::AlreadyExists,
+                format!("le profil {} existe deja", name),
+            ));
+        }
+        config.profiles.names.push(name);
+        Ok(())
+    })?;
+    Ok(config.profiles)
+}
+
+/// `None` revient au profil par defaut. Le choix est enregistre et repris au
+/// prochain lancement.
+#[tauri::command]
+pub(crate) fn profile_set_active(
+    app: AppHandle,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+    name: Option<String>,
+) -> Result<ProfilesConfig, KeychainError> {
+    let config = store.update(&app, &manager, |config| {
+        if let Some(name) = &name {
+            if !config.profiles.names.contains(name) {
+                return Err(unknown_profile(name));
+            }
+        }
+        config.profiles.active = name;
+        Ok(())
+    })?;
+    Ok(config.profiles)
+}
+
+/// Supprime le profil, et avec `wipe_secrets` tous ses secrets apres la
+/// confirmation de l'utilisateur. Le profil actif supprime laisse place au
+/// profil par defaut.
+#[tauri::command]
+pub(crate) async fn profile_delete(
+    app: AppHandle,
+    window: Window,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+    name: String,
+    wipe_secrets: bool,
+    retry: Option<RetryPolicy>,
+) -> Result<Option<ProfileWipeReport>, KeychainError> {
+    if !store.get().profiles.names.contains(&name) {
+        return Err(unknown_profile(&name));
+    }
+    let report = if wipe_secrets {
+        let audit = manager.audit(
+            &window,
+            "profile_delete",
+            &format!("{}.{}", ARGOS_SERVICE, name),
+            None,
+        );
+        let question = format!("Supprimer le profil {} et tous ses secrets ?", name);
+        if let Err(e) = manager.confirm(Some(&window), question).await {
+            return audit.finish(Err(e));
+        }
+        let wiping = manager.inner().clone();
+        let profile = name.clone();
+        let retry = retry.unwrap_or_default();
+        Some(audit.finish(run_blocking(move || wiping.wipe_profile(Some(&profile), retry)).await)?)
+    } else {
+        None
+    };
+    store.update(&app, &manager, |config: &mut Config| {
+        config.profiles.names.retain(|known| *known != name);
+        if config.profiles.active.as_deref() == Some(name.as_str()) {
+            config.profiles.active = None;
+        }
+        Ok(())
+    })?;
+    Ok(report)
+}
@@ -0,0 +1,238 @@
+//! Export d'un secret en QR code, pour le passer a un telephone sans le
+//! taper. L'image est produite en memoire et renvoyee au webview, jamais
+//! ecrite sur disque ; une graine TOTP peut etre exportee en URI
+//! `otpauth://` pour les applications d'authentification.
+
+use base64::Engine;
+use qrcode::types::QrError;
+use qrcode::{Color, EcLevel, QrCode};
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+use zeroize::Zeroizing;
+
+use super::{
+    auth_reason, read_stored, run_blocking, Gated, KeychainError, KeychainErrorCode,
+    KeychainManager, RetryPolicy, Secret, Stored, TotpSeed,
+};
+
+/// Modules blancs autour du code, minimum de la norme.
+const QUIET_ZONE: usize = 4;
+/// Cote vise de l'image, en pixels, avant arrondi a un multiple du module.
+const TARGET_PIXELS: usize = 320;
+const MIN_MODULE_PIXELS: usize = 4;
+/// Emetteur affiche par les applications d'authentification.
+const OTPAUTH_ISSUER: &str = "ARGOS";
+
+/// Contenu du QR code et de la reponse.
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum QrFormat {
+    /// Valeur brute, renvoyee aussi en clair.
+    #[default]
+    WithValue,
+    /// Valeur brute dans l'image seulement.
+    QrOnly,
+    /// URI `otpauth://`, pour une entree marquee TOTP.
+    Otpauth,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum QrImage {
+    #[default]
+    Png,
+    Svg,
+}
+
+#[derive(Serialize)]
+pub(crate) struct QrExport {
+    /// PNG en base64, ou document SVG.
+    image: Secret,
+    mime: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<Secret>,
+}
+
+/// Secret lu pour l'export, avec de quoi construire l'URI `otpauth://`.
+pub(crate) struct QrSource {
+    seed: TotpSeed,
+    totp: bool,
+    label: Option<String>,
+}
+
+fn encode(data: &str) -> Result<QrCode, KeychainError> {
+    QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M).map_err(|e| match e {
+        QrError::DataTooLong => KeychainError::rejected(
+            KeychainErrorCode::QrCapacityExceeded,
+            format!(
+                "{} octets : trop long pour un QR code lisible par un telephone",
+                data.len()
+            ),
+        ),
+        other => KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!("QR code impossible : {}", other),
+        ),
+    })
+}
+
+/// Modules ligne par ligne, marge comprise : 1 pour un module sombre.
+fn modules(code: &QrCode) -> (usize, Zeroizing<Vec<u8>>) {
+    let width = code.width();
+    let side = width + 2 * QUIET_ZONE;
+    let mut dark = Zeroizing::new(vec![0u8; side * side]);
+    for (i, color) in code.to_colors().into_iter().enumerate() {
+        let (x, y) = (i % width + QUIET_ZONE, i / width + QUIET_ZONE);
+        dark[y * side + x] = u8::from(color == Color::Dark);
+    }
+    (side, dark)
+}
+
+fn render_png(code: &QrCode) -> Result<Secret, KeychainError> {
+    let (side, dark) = modules(code);
+    let scale = (TARGET_PIXELS / side).max(MIN_MODULE_PIXELS);
+    let pixels_side = side * scale;
+    let mut pixels = Zeroizing::new(Vec::with_capacity(pixels_side * pixels_side));
+    for y in 0..pixels_side {
+        for x in 0..pixels_side {
+            let on = dark[(y / scale) * side + x / scale] != 0;
+            pixels.push(if on { 0 } else { 255 });
+        }
+    }
+    let mut out = Zeroizing::new(Vec::new());
+    {
+        let mut encoder = png::Encoder::new(&mut *out, pixels_side as u32, pixels_side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let png_failed = |e: png::EncodingError| {
+            KeychainError::rejected(
+                KeychainErrorCode::PlatformError,
+                format!("encodage PNG impossible : {}", e),
+            )
+        };
+        let mut writer = encoder.write_header().map_err(png_failed)?;
+        writer.write_image_data(&pixels).map_err(png_failed)?;
+        writer.finish().map_err(png_failed)?;
+    }
+    Ok(Secret::new(
+        base64::engine::general_purpose::STANDARD.encode(&out[..]),
+    ))
+}
+
+/// Un carre par module sombre, dans un seul chemin.
+fn render_svg(code: &QrCode) -> Secret {
+    let (side, dark) = modules(code);
+    let scale = (TARGET_PIXELS / side).max(MIN_MODULE_PIXELS);
+    let mut svg = String::with_capacity(side * side * 4);
+    svg.push_str(&format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {side} {side}\" \
+         width=\"{px}\" height=\"{px}\" shape-rendering=\"crispEdges\">\
+         <rect width=\"{side}\" height=\"{side}\" fill=\"#fff\"/><path fill=\"#000\" d=\"",
+        side = side,
+        px = side * scale,
+    ));
+    for (i, _) in dark.iter().enumerate().filter(|(_, on)| **on != 0) {
+        svg.push_str(&format!("M{},{}h1v1h-1z", i % side, i / side));
+    }
+    svg.push_str("\"/></svg>");
+    Secret::new(svg)
+}
+
+impl KeychainManager {
+    /// Valeur de `key` et les informations TOTP de son enveloppe.
+    pub(crate) fn qr_source(
+        &self,
+        service: &str,
+        key: &str,
+        retry: RetryPolicy,
+    ) -> Result<Gated<QrSource>, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let stored = {
+            let _guard = self.locks.lock(service, [key]);
+            read_stored(&self.store, retry, service, key)?
+        };
+        let stored = stored.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::NotFound,
+                format!("aucun secret {} dans {}", key, service),
+            )
+        })?;
+        let reason = stored.requires_auth().then(|| auth_reason(key));
+        let totp = stored.is_totp();
+        let params = stored.totp_params();
+        let label = match &stored {
+            Stored::Envelope(envelope) => envelope.label.clone(),
+            Stored::Legacy(_) => None,
+        };
+        Ok(Gated::new(
+            QrSource {
+                seed: TotpSeed::new(stored.into_value(), params),
+                totp,
+                label,
+            },
+            reason,
+        ))
+    }
+}
+
+/// Rend `key` en QR code. `qr_only` n'inclut pas la valeur dans la reponse ;
+/// `otpauth` exige une entree marquee TOTP. Un secret trop long donne
+/// `QrCapacityExceeded`.
+#[tauri::command]
+pub(crate) async fn secret_to_qr(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    format: Option<QrFormat>,
+    image: Option<QrImage>,
+    retry: Option<RetryPolicy>,
+) -> Result<QrExport, KeychainError> {
+    let format = format.unwrap_or_default();
+    let audit = manager
+        .audit(&window, "qr_export", &service, Some(&key))
+        .detail(match format {
+            QrFormat::WithValue => "with_value",
+            QrFormat::QrOnly => "qr_only",
+            QrFormat::Otpauth => "otpauth",
+        });
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let reader = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    let source = {
+        let key = key.clone();
+        auth.release(run_blocking(move || reader.qr_source(&service, &key, retry)).await)
+            .await
+    };
+    let export = source.and_then(|source| {
+        let data = match format {
+            QrFormat::Otpauth if !source.totp => {
+                return Err(KeychainError::rejected(
+                    KeychainErrorCode::InvalidArgument,
+                    format!("{} n'est pas marque comme graine TOTP", key),
+                ))
+            }
+            QrFormat::Otpauth => source
+                .seed
+                .otpauth_uri(OTPAUTH_ISSUER, source.label.as_deref().unwrap_or(&key)),
+            QrFormat::WithValue | QrFormat::QrOnly => source.seed.into_seed(),
+        };
+        let code = encode(&data)?;
+        let (image, mime) = match image.unwrap_or_default() {
+            QrImage::Png => (render_png(&code)?, "image/png"),
+            QrImage::Svg => (render_svg(&code), "image/svg+xml"),
+        };
+        Ok(QrExport {
+            image,
+            mime,
+            value: (format != QrFormat::QrOnly).then_some(data),
+        })
+    });
+    audit.finish(export)
+}
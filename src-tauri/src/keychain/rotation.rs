@@ -0,0 +1,312 @@
+//! Rotation d'un secret : `secret_rotate` genere une nouvelle valeur et
+//! range l'actuelle dans l'historique, `secret_rotate_confirm` la valide une
+//! fois le service distant mis a jour et `secret_rotate_abort` remet
+//! l'ancienne. Les rotations en attente sont notees par service, sous
+//! `ROTATIONS_KEY`, et signalees par `keychain_list` au-dela du delai
+//! configure.
+
+use serde::{Deserialize, Serialize};
+use tauri::{State, Window};
+
+use super::generate::{generate, SecretSpec};
+use super::{
+    fingerprint, found, keyring_call, now_rfc3339, push_history, read_history, read_stored,
+    run_blocking, write_history, write_password, EnvelopeMeta, KeychainError, KeychainErrorCode,
+    KeychainManager, RetryPolicy, Secret, SecretOperation, Store, DEFAULT_HISTORY_DEPTH,
+    MAX_HISTORY_DEPTH,
+};
+
+/// Rotations en attente d'un service.
+pub(super) const ROTATIONS_KEY: &str = "__argos_rotations__";
+
+pub(crate) const DEFAULT_ROTATION_WINDOW_MINUTES: u64 = 60;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct PendingRotation {
+    key: String,
+    rotation_id: String,
+    /// Version de l'historique qui garde la valeur remplacee.
+    version_id: u64,
+    started_at: String,
+}
+
+/// Rotation en attente d'une cle, rapportee par `keychain_list`.
+#[derive(Serialize)]
+pub(crate) struct RotationState {
+    rotation_id: String,
+    started_at: String,
+    /// Non confirmee ni annulee dans le delai de `rotation_window_minutes`.
+    overdue: bool,
+}
+
+/// Nouvelle valeur, a transmettre au service distant avant
+/// `secret_rotate_confirm`.
+#[derive(Serialize)]
+pub(crate) struct RotationResult {
+    value: Secret,
+    fingerprint: String,
+    rotation_id: String,
+    persistent: bool,
+}
+
+/// Une liste illisible est traitee comme vide : la valeur remplacee reste
+/// dans l'historique.
+fn read_rotations(store: &Store, retry: RetryPolicy, service: &str) -> Vec<PendingRotation> {
+    found(keyring_call(
+        store,
+        retry,
+        service,
+        ROTATIONS_KEY,
+        |entry| entry.get_password(),
+    ))
+    .ok()
+    .flatten()
+    .and_then(|raw| serde_json::from_str(&raw).ok())
+    .unwrap_or_default()
+}
+
+fn write_rotations(
+    store: &Store,
+    retry: RetryPolicy,
+    service: &str,
+    rotations: &[PendingRotation],
+) -> Result<(), KeychainError> {
+    if rotations.is_empty() {
+        return found(keyring_call(
+            store,
+            retry,
+            service,
+            ROTATIONS_KEY,
+            |entry| entry.delete_credential(),
+        ))
+        .map(|_| ());
+    }
+    let raw = serde_json::to_string(rotations).unwrap_or_else(|_| "[]".to_string());
+    keyring_call(store, retry, service, ROTATIONS_KEY, |entry| {
+        entry.set_password(&raw)
+    })
+}
+
+fn new_rotation_id() -> String {
+    let mut bytes = [0u8; 8];
+    rand::RngCore::fill_bytes(&mut rand::thread_rng(), &mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn unknown_rotation(key: &str, rotation_id: &str) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::NotFound,
+        format!("aucune rotation {} en attente pour {}", rotation_id, key),
+    )
+}
+
+impl KeychainManager {
+    fn rotation_overdue(&self, started_at: &str) -> bool {
+        chrono::DateTime::parse_from_rfc3339(started_at).is_ok_and(|started| {
+            chrono::Utc::now() - started.with_timezone(&chrono::Utc)
+                > chrono::Duration::minutes(self.rotation_window_minutes as i64)
+        })
+    }
+
+    /// Rotations en attente de `service`, par cle, pour `list`.
+    pub(super) fn rotation_states(
+        &self,
+        service: &str,
+    ) -> std::collections::HashMap<String, RotationState> {
+        read_rotations(&self.store, RetryPolicy::default(), service)
+            .into_iter()
+            .map(|pending| {
+                let state = RotationState {
+                    overdue: self.rotation_overdue(&pending.started_at),
+                    rotation_id: pending.rotation_id,
+                    started_at: pending.started_at,
+                };
+                (pending.key, state)
+            })
+            .collect()
+    }
+
+    /// Retire la rotation `rotation_id` de `key`, sous le verrou de l'index
+    /// comme toute ecriture des listes d'un service.
+    fn take_rotation(
+        &self,
+        service: &str,
+        key: &str,
+        rotation_id: &str,
+        retry: RetryPolicy,
+    ) -> Result<PendingRotation, KeychainError> {
+        let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+        let mut rotations = read_rotations(&self.store, retry, service);
+        let position = rotations
+            .iter()
+            .position(|r| r.key == key && r.rotation_id == rotation_id)
+            .ok_or_else(|| unknown_rotation(key, rotation_id))?;
+        let pending = rotations.remove(position);
+        write_rotations(&self.store, retry, service, &rotations)?;
+        Ok(pending)
+    }
+
+    /// La valeur actuelle est ajoutee a l'historique avant l'ecriture de la
+    /// nouvelle : si l'ajout echoue, rien n'est modifie. Une seule rotation
+    /// par cle a la fois.
+    pub(crate) fn rotate(
+        &self,
+        service: &str,
+        key: &str,
+        value: &Secret,
+        retry: RetryPolicy,
+    ) -> Result<RotationResult, KeychainError> {
+        self.check_unlocked()?;
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let store = &self.store;
+        let _guard = self.locks.lock(service, [key]);
+        if read_rotations(store, retry, service)
+            .iter()
+            .any(|r| r.key == key)
+        {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::AlreadyExists,
+                format!("une rotation de {} est deja en attente", key),
+            ));
+        }
+        let current = read_stored(store, retry, service, key)?.ok_or_else(|| {
+            KeychainError::rejected(
+                KeychainErrorCode::NotFound,
+                format!("aucun secret {} dans {}", key, service),
+            )
+        })?;
+        if current.is_totp() {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                "une graine TOTP ne se regenere pas : elle vient du service distant",
+            ));
+        }
+        let version_id = push_history(store, retry, service, key, current, MAX_HISTORY_DEPTH)?;
+        write_password(store, retry, service, key, value, EnvelopeMeta::default())?;
+        self.cache.invalidate(service, [key]);
+        let rotation_id = new_rotation_id();
+        {
+            let _guard = self.index.0.lock().unwrap_or_else(|e| e.into_inner());
+            let mut rotations = read_rotations(store, retry, service);
+            rotations.push(PendingRotation {
+                key: key.to_string(),
+                rotation_id: rotation_id.clone(),
+                version_id,
+                started_at: now_rfc3339(),
+            });
+            // Sans note, la rotation ne peut plus etre annulee par son
+            // identifiant, mais l'ancienne valeur reste dans l'historique.
+            if let Err(e) = write_rotations(store, retry, service, &rotations) {
+                tracing::warn!(service, key, error = %e, "rotation non notee");
+            }
+        }
+        self.events.emit(service, key, SecretOperation::Set);
+        Ok(RotationResult {
+            fingerprint: fingerprint(value),
+            value: value.clone(),
+            rotation_id,
+            persistent: self.is_persistent(),
+        })
+    }
+
+    /// Valide la rotation et ramene l'historique a `history` versions
+    /// (3 par defaut).
+    pub(crate) fn rotate_confirm(
+        &self,
+        service: &str,
+        key: &str,
+        rotation_id: &str,
+        history: Option<u32>,
+        retry: RetryPolicy,
+    ) -> Result<(), KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let _guard = self.locks.lock(service, [key]);
+        self.take_rotation(service, key, rotation_id, retry)?;
+        let depth = history
+            .unwrap_or(DEFAULT_HISTORY_DEPTH)
+            .min(MAX_HISTORY_DEPTH);
+        let history = read_history(&self.store, retry, service, key);
+        write_history(&self.store, retry, service, key, history, depth)
+    }
+
+    /// Remet la valeur remplacee par la rotation ; la valeur generee passe
+    /// dans l'historique, au cas ou le service distant l'aurait acceptee.
+    pub(crate) fn rotate_abort(
+        &self,
+        service: &str,
+        key: &str,
+        rotation_id: &str,
+        retry: RetryPolicy,
+    ) -> Result<(), KeychainError> {
+        let service = &self.resolve_service(service)?;
+        let name = self.resolve_key(service, key, retry)?;
+        let key = name.as_str();
+        let _guard = self.locks.lock(service, [key]);
+        let pending = self.take_rotation(service, key, rotation_id, retry)?;
+        self.restore_version(service, key, pending.version_id, retry)
+    }
+}
+
+/// Genere une nouvelle valeur selon `spec` et l'ecrit a la place de
+/// l'actuelle, gardee dans l'historique jusqu'a confirmation.
+#[tauri::command]
+pub(crate) async fn secret_rotate(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    spec: SecretSpec,
+    retry: Option<RetryPolicy>,
+) -> Result<RotationResult, KeychainError> {
+    let value = generate(&spec)?;
+    let audit = manager
+        .audit(&window, "rotate", &service, Some(&key))
+        .detail(fingerprint(&value).as_str());
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(run_blocking(move || manager.rotate(&service, &key, &value, retry)).await)
+}
+
+#[tauri::command]
+pub(crate) async fn secret_rotate_confirm(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    rotation_id: String,
+    history: Option<u32>,
+    retry: Option<RetryPolicy>,
+) -> Result<(), KeychainError> {
+    let audit = manager
+        .audit(&window, "rotate_confirm", &service, Some(&key))
+        .detail(rotation_id.as_str());
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        run_blocking(move || manager.rotate_confirm(&service, &key, &rotation_id, history, retry))
+            .await,
+    )
+}
+
+#[tauri::command]
+pub(crate) async fn secret_rotate_abort(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    rotation_id: String,
+    retry: Option<RetryPolicy>,
+) -> Result<(), KeychainError> {
+    let audit = manager
+        .audit(&window, "rotate_abort", &service, Some(&key))
+        .detail(rotation_id.as_str());
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    audit.finish(
+        run_blocking(move || manager.rotate_abort(&service, &key, &rotation_id, retry)).await,
+    )
+}
@@ -0,0 +1,324 @@
+//! Verrouillage de la session : apres une periode d'inactivite, a la mise
+//! en veille, au changement d'utilisateur ou a la demande, les lectures de
+//! secrets sont refusees jusqu'au deverrouillage.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use serde::Serialize;
+use tauri::{AppHandle, GlobalWindowEvent, Manager, State, Window, WindowEvent};
+
+use super::auth::Gated;
+use super::{KeychainError, KeychainErrorCode, KeychainManager, ARGOS_SERVICE};
+
+pub(crate) const SESSION_LOCKED: &str = "argos://session-locked";
+pub(crate) const SESSION_UNLOCKED: &str = "argos://session-unlocked";
+
+/// Inactivite, en minutes, au-dela de laquelle la session se verrouille.
+pub(crate) const DEFAULT_IDLE_MINUTES: u64 = 15;
+
+const WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Un tour de surveillance qui dure bien plus que `WATCH_INTERVAL`, selon
+/// l'horloge murale ou monotone, signale une mise en veille : le processus
+/// etait suspendu.
+const SUSPEND_GAP: Duration = Duration::from_secs(30);
+
+const UNLOCK_REASON: &str = "ARGOS demande le deverrouillage de la session";
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LockReason {
+    Manual,
+    Idle,
+    Suspend,
+    /// Changement d'utilisateur ou verrouillage de la session du systeme.
+    UserSwitch,
+    /// Lancement avec `--locked`.
+    Startup,
+}
+
+#[derive(Serialize, Clone)]
+struct SessionLocked {
+    reason: LockReason,
+}
+
+/// Etat de la session, partage par le `KeychainManager`. La session est
+/// deverrouillee au demarrage.
+pub(crate) struct SessionState {
+    locked: AtomicBool,
+    last_activity: Mutex<Instant>,
+    /// En secondes, 0 desactive le verrouillage sur inactivite. Modifiable
+    /// par `config_set`.
+    idle_timeout_secs: AtomicU64,
+    /// Bascules `session.lock_on_suspend` et `session.lock_on_user_switch`.
+    lock_on_suspend: AtomicBool,
+    lock_on_user_switch: AtomicBool,
+}
+
+impl SessionState {
+    pub(crate) fn new(idle_minutes: u64) -> Self {
+        SessionState {
+            locked: AtomicBool::new(false),
+            last_activity: Mutex::new(Instant::now()),
+            idle_timeout_secs: AtomicU64::new(idle_minutes * 60),
+            lock_on_suspend: AtomicBool::new(true),
+            lock_on_user_switch: AtomicBool::new(true),
+        }
+    }
+
+    fn idle_timeout(&self) -> Option<Duration> {
+        match self.idle_timeout_secs.load(Ordering::SeqCst) {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        }
+    }
+
+    pub(crate) fn is_locked(&self) -> bool {
+        self.locked.load(Ordering::SeqCst)
+    }
+
+    /// Repart de zero pour le delai d'inactivite.
+    pub(crate) fn touch(&self) {
+        *self.last_activity.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    fn idle_for(&self) -> Duration {
+        self.last_activity
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .elapsed()
+    }
+
+    fn idle_expired(&self) -> bool {
+        matches!(self.idle_timeout(), Some(timeout) if self.idle_for() > timeout)
+    }
+
+    /// Passe a l'etat verrouille ; `false` si la session l'etait deja.
+    fn lock(&self) -> bool {
+        !self.locked.swap(true, Ordering::SeqCst)
+    }
+
+    fn unlock(&self) {
+        self.touch();
+        self.locked.store(false, Ordering::SeqCst);
+    }
+
+    fn status(&self) -> SessionStatus {
+        SessionStatus {
+            locked: self.is_locked(),
+            idle_timeout_seconds: self.idle_timeout().map(|timeout| timeout.as_secs()),
+            idle_seconds: self.idle_for().as_secs(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct SessionStatus {
+    locked: bool,
+    idle_timeout_seconds: Option<u64>,
+    idle_seconds: u64,
+}
+
+/// Previent les fenetres et les ecouteurs globaux, dont l'icone de la zone
+/// de notification.
+fn announce<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    app.trigger_global(event, serde_json::to_string(&payload).ok());
+    let _ = app.emit_all(event, payload);
+}
+
+pub(crate) fn session_locked() -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::SessionLocked,
+        "session verrouillee : deverrouiller ARGOS pour lire les secrets",
+    )
+}
+
+impl KeychainManager {
+    /// Verrouille la session, oublie la derniere verification de
+    /// l'utilisateur, vide le cache et referme le coffre portable, puis
+    /// previent toutes les fenetres.
+    pub(crate) fn lock_session(&self, reason: LockReason) {
+        if !self.session.lock() {
+            return;
+        }
+        self.auth.forget();
+        self.cache.flush();
+        if let Some(vault) = &self.vault {
+            vault.lock();
+        }
+        if let Some(app) = self.events.app() {
+            announce(app, SESSION_LOCKED, SessionLocked { reason });
+        }
+    }
+
+    pub(crate) fn is_session_locked(&self) -> bool {
+        self.session.is_locked()
+    }
+
+    /// Applique un nouveau delai d'inactivite, 0 pour ne plus verrouiller.
+    pub(crate) fn set_idle_minutes(&self, minutes: u64) {
+        self.session
+            .idle_timeout_secs
+            .store(minutes * 60, Ordering::SeqCst);
+    }
+
+    /// Applique les bascules de verrouillage sur evenement du systeme.
+    pub(crate) fn set_lock_triggers(&self, suspend: bool, user_switch: bool) {
+        self.session
+            .lock_on_suspend
+            .store(suspend, Ordering::SeqCst);
+        self.session
+            .lock_on_user_switch
+            .store(user_switch, Ordering::SeqCst);
+    }
+
+    /// Verrouille pour une mise en veille ou un changement d'utilisateur,
+    /// si la bascule correspondante est active.
+    pub(crate) fn lock_on_system_event(&self, reason: LockReason) {
+        let enabled = match reason {
+            LockReason::Suspend => self.session.lock_on_suspend.load(Ordering::SeqCst),
+            LockReason::UserSwitch => self.session.lock_on_user_switch.load(Ordering::SeqCst),
+            LockReason::Manual | LockReason::Idle | LockReason::Startup => true,
+        };
+        if enabled {
+            self.lock_session(reason);
+        }
+    }
+
+    /// Surveille l'inactivite et les mises en veille, pendant toute la vie
+    /// de l'application.
+    pub(crate) fn spawn_session_watcher(&self) {
+        let manager = self.clone();
+        tauri::async_runtime::spawn(async move {
+            let mut wall = SystemTime::now();
+            let mut monotonic = Instant::now();
+            loop {
+                tokio::time::sleep(WATCH_INTERVAL).await;
+                let (now_wall, now_monotonic) = (SystemTime::now(), Instant::now());
+                let elapsed = now_wall
+                    .duration_since(wall)
+                    .unwrap_or_default()
+                    .max(now_monotonic - monotonic);
+                (wall, monotonic) = (now_wall, now_monotonic);
+                if elapsed > WATCH_INTERVAL + SUSPEND_GAP {
+                    manager.lock_on_system_event(LockReason::Suspend);
+                } else if manager.session.idle_expired() {
+                    manager.lock_session(LockReason::Idle);
+                }
+            }
+        });
+    }
+}
+
+/// Compte la prise de focus d'une fenetre comme une activite.
+pub(crate) fn on_window_event(event: &GlobalWindowEvent) {
+    if let WindowEvent::Focused(true) = event.event() {
+        if let Some(manager) = event.window().try_state::<KeychainManager>() {
+            manager.session.touch();
+        }
+    }
+}
+
+/// Signale une activite de l'utilisateur et renvoie l'etat de la session.
+#[tauri::command]
+pub(crate) fn session_touch(manager: State<'_, KeychainManager>) -> SessionStatus {
+    if !manager.session.is_locked() {
+        manager.session.touch();
+    }
+    manager.session.status()
+}
+
+/// Verrouille depuis la zone de notification, un raccourci ou un lien, en
+/// passant par `session_lock` pour le journal d'audit.
+pub(crate) fn lock_app(app: &AppHandle) {
+    let Some(manager) = app.try_state::<KeychainManager>() else {
+        return;
+    };
+    match app.get_window(crate::instance::MAIN_WINDOW) {
+        Some(window) => {
+            session_lock(window, manager);
+        }
+        None => manager.lock_session(LockReason::Manual),
+    }
+}
+
+/// Verrouille la session immediatement.
+#[tauri::command]
+pub(crate) fn session_lock(window: Window, manager: State<'_, KeychainManager>) -> SessionStatus {
+    let audit = manager.audit(&window, "session_lock", ARGOS_SERVICE, None);
+    manager.lock_session(LockReason::Manual);
+    let _ = audit.finish(Ok(()));
+    manager.session.status()
+}
+
+/// Deverrouille la session apres verification de l'utilisateur par le
+/// systeme. Sans verification disponible sur le poste, le deverrouillage
+/// est accorde.
+#[tauri::command]
+pub(crate) async fn session_unlock(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+) -> Result<SessionStatus, KeychainError> {
+    let audit = manager.audit(&window, "session_unlock", ARGOS_SERVICE, None);
+    let verified = match manager
+        .auth
+        .release(Ok(Gated::new((), Some(UNLOCK_REASON.to_string()))))
+        .await
+    {
+        Err(e) if e.code() == KeychainErrorCode::AuthUnavailable => Ok(()),
+        verified => verified,
+    };
+    audit.finish(verified)?;
+    manager.session.unlock();
+    if let Some(app) = manager.events.app() {
+        announce(app, SESSION_UNLOCKED, ());
+    }
+    Ok(manager.session.status())
+}
+
+/// Rejoue une mise en veille signalee par le systeme, pour verifier le
+/// verrouillage de bout en bout. Absente des versions publiees.
+#[cfg(debug_assertions)]
+#[tauri::command]
+pub(crate) fn debug_simulate_suspend(
+    manager: State<'_, KeychainManager>,
+) -> Result<SessionStatus, KeychainError> {
+    crate::power::handle(&manager, LockReason::Suspend);
+    Ok(manager.session.status())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{memory_manager, set, NO_RETRY, SERVICE};
+    use super::*;
+
+    #[test]
+    fn suspend_locks_the_session_and_flushes_the_cache() {
+        let manager = memory_manager();
+        set(&manager, "token", "v1");
+        manager.get(SERVICE, "token", true, NO_RETRY).unwrap();
+        assert!(manager.cache.lookup(SERVICE, "token", true).is_some());
+
+        crate::power::handle(&manager, LockReason::Suspend);
+        assert!(manager.is_session_locked());
+        assert!(manager.cache.lookup(SERVICE, "token", true).is_none());
+        let locked = manager.get(SERVICE, "token", false, NO_RETRY).err();
+        assert_eq!(
+            locked.map(|e| e.code()),
+            Some(KeychainErrorCode::SessionLocked)
+        );
+    }
+
+    #[test]
+    fn disabled_trigger_keeps_the_session_open() {
+        let manager = memory_manager();
+        manager.set_lock_triggers(false, true);
+        crate::power::handle(&manager, LockReason::Suspend);
+        assert!(!manager.is_session_locked());
+
+        crate::power::handle(&manager, LockReason::UserSwitch);
+        assert!(manager.is_session_locked());
+    }
+}
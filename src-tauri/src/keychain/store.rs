@@ -0,0 +1,555 @@
+//! Backends de stockage des secrets. Le backend retenu au demarrage est
+//! enregistre dans l'etat Tauri (`Store`) et passe explicitement a toutes
+//! les operations.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+
+use serde::Deserialize;
+use zeroize::Zeroizing;
+
+use keyring::Entry;
+
+use super::access::MacosAccess;
+use super::collection::CollectionStatus;
+#[cfg(target_os = "linux")]
+use super::collection::LinuxCollection;
+use super::persistence::WindowsPersistence;
+use super::{KeychainError, KeychainErrorCode};
+
+/// Stockage de secrets binaires par couple (service, cle).
+pub(crate) trait SecretStore: Send + Sync {
+    /// Nom expose dans les erreurs et par `keychain_status`.
+    fn name(&self) -> &'static str;
+
+    /// `false` si les secrets sont perdus a la fermeture d'ARGOS.
+    fn persistent(&self) -> bool;
+
+    fn set(&self, service: &str, key: &str, value: &[u8]) -> Result<(), KeychainError>;
+
+    /// `NotFound` si l'entree n'existe pas, comme les backends OS.
+    fn get(&self, service: &str, key: &str) -> Result<Vec<u8>, KeychainError>;
+
+    /// `NotFound` si l'entree n'existe pas, comme les backends OS.
+    fn delete(&self, service: &str, key: &str) -> Result<(), KeychainError>;
+
+    fn set_password(&self, service: &str, key: &str, value: &str) -> Result<(), KeychainError> {
+        self.set(service, key, value.as_bytes())
+    }
+
+    /// `BadEncoding` si le contenu n'est pas de l'UTF-8.
+    fn get_password(&self, service: &str, key: &str) -> Result<String, KeychainError> {
+        let bytes = Zeroizing::new(self.get(service, key)?);
+        std::str::from_utf8(&bytes)
+            .map(str::to_string)
+            .map_err(|_| KeychainError::from_keyring(&keyring::Error::BadEncoding(Vec::new())))
+    }
+
+    /// Verifie que l'entree existe sans renvoyer son contenu.
+    fn probe(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+        self.get(service, key)
+            .map(|bytes| drop(Zeroizing::new(bytes)))
+    }
+
+    /// Recree l'entree avec les protections du trousseau macOS ; `false` si
+    /// le backend ne les prend pas en charge.
+    fn protect(
+        &self,
+        _service: &str,
+        _key: &str,
+        _access: &MacosAccess,
+    ) -> Result<bool, KeychainError> {
+        Ok(false)
+    }
+
+    /// Refuse avant l'ecriture une portee de persistance Windows que le
+    /// poste n'autorise pas.
+    fn check_persistence(&self, _persistence: WindowsPersistence) -> Result<(), KeychainError> {
+        Ok(())
+    }
+
+    /// Change la portee de persistance Windows de l'entree ; `false` si le
+    /// backend ne la gere pas.
+    fn persist(
+        &self,
+        _service: &str,
+        _key: &str,
+        _persistence: WindowsPersistence,
+    ) -> Result<bool, KeychainError> {
+        Ok(false)
+    }
+
+    /// Nom de l'entree dans le gestionnaire d'identifiants Windows ; `None`
+    /// pour les autres backends.
+    fn target_name(&self, _service: &str, _key: &str) -> Result<Option<String>, KeychainError> {
+        Ok(None)
+    }
+
+    /// Collection Secret Service utilisee et son verrouillage ; `None` hors
+    /// Linux et pour les stores d'ARGOS.
+    fn collection(&self) -> Option<CollectionStatus> {
+        None
+    }
+
+    /// Demande le deverrouillage du keychain a l'utilisateur ; `Unsupported`
+    /// si le backend ne se verrouille pas de lui-meme.
+    fn unlock(&self) -> Result<(), KeychainError> {
+        Err(KeychainError::rejected(
+            KeychainErrorCode::Unsupported,
+            format!("le backend {} ne se deverrouille pas", self.name()),
+        ))
+    }
+}
+
+/// Backend partage par les commandes.
+pub(crate) type Store = Arc<dyn SecretStore>;
+
+static ACTIVE: OnceLock<Store> = OnceLock::new();
+
+static OS_KEYCHAIN: KeyringStore = KeyringStore {
+    target: None,
+    #[cfg(target_os = "linux")]
+    collection: None,
+};
+
+/// Backend retenu au demarrage, pour les erreurs serialisees sans acces a
+/// l'etat Tauri. Le keychain OS tant que `activate` n'a pas ete appele.
+pub(crate) fn active() -> &'static dyn SecretStore {
+    match ACTIVE.get() {
+        Some(store) => store.as_ref(),
+        None => &OS_KEYCHAIN,
+    }
+}
+
+/// Enregistre le backend du processus. Sans effet au-dela du premier appel.
+pub(crate) fn activate(store: &Store) {
+    let _ = ACTIVE.set(Arc::clone(store));
+}
+
+/// Entree absente, avec le meme detail que celui des backends OS.
+pub(crate) fn not_found() -> KeychainError {
+    KeychainError::from_keyring(&keyring::Error::NoEntry)
+}
+
+/// Keychain OS (Windows Credential Manager, macOS Keychain, Linux Secret
+/// Service), via les backends natifs du crate keyring.
+#[derive(Default)]
+pub(crate) struct KeyringStore {
+    /// `target` du crate keyring : sous Linux, libelle de la collection.
+    target: Option<String>,
+    #[cfg(target_os = "linux")]
+    collection: Option<LinuxCollection>,
+}
+
+impl KeyringStore {
+    #[cfg(target_os = "linux")]
+    pub(crate) fn in_collection(collection: LinuxCollection, target: String) -> Self {
+        KeyringStore {
+            target: Some(target),
+            collection: Some(collection),
+        }
+    }
+
+    fn open_entry(&self, service: &str, key: &str) -> Result<Entry, KeychainError> {
+        match &self.target {
+            Some(target) => Entry::new_with_target(target, service, key),
+            None => Entry::new(service, key),
+        }
+        .map_err(|e| KeychainError::from_entry_creation(&e))
+    }
+
+    /// Un refus d'acces alors que la collection est verrouillee devient
+    /// `BackendLocked` : l'utilisateur a ferme la demande de deverrouillage.
+    /// Sous macOS, de meme pour un trousseau verrouille qui ne peut pas
+    /// afficher sa demande.
+    fn error(&self, e: keyring::Error) -> KeychainError {
+        #[cfg(target_os = "macos")]
+        if super::access::is_locked_error(&e) {
+            return KeychainError::rejected(
+                KeychainErrorCode::BackendLocked,
+                format!("trousseau macOS verrouille : {}", e),
+            );
+        }
+        let error = KeychainError::from_keyring(&e);
+        #[cfg(target_os = "linux")]
+        if let Some(collection) = &self.collection {
+            if error.code() == KeychainErrorCode::AccessDenied
+                && super::collection::is_locked(collection) == Some(true)
+            {
+                return super::collection::locked_error(collection);
+            }
+        }
+        error
+    }
+}
+
+impl SecretStore for KeyringStore {
+    fn name(&self) -> &'static str {
+        if cfg!(target_os = "windows") {
+            "windows-credential-manager"
+        } else if cfg!(target_os = "macos") {
+            "macos-keychain"
+        } else if cfg!(target_os = "linux") {
+            "secret-service"
+        } else {
+            "unknown"
+        }
+    }
+
+    fn persistent(&self) -> bool {
+        true
+    }
+
+    fn set(&self, service: &str, key: &str, value: &[u8]) -> Result<(), KeychainError> {
+        self.open_entry(service, key)?
+            .set_secret(value)
+            .map_err(|e| self.error(e))
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<Vec<u8>, KeychainError> {
+        self.open_entry(service, key)?
+            .get_secret()
+            .map_err(|e| self.error(e))
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+        self.open_entry(service, key)?
+            .delete_credential()
+            .map_err(|e| self.error(e))
+    }
+
+    // Windows stocke `set_password` en UTF-16 : les secrets texte doivent
+    // passer par les methodes texte du crate keyring.
+    fn set_password(&self, service: &str, key: &str, value: &str) -> Result<(), KeychainError> {
+        self.open_entry(service, key)?
+            .set_password(value)
+            .map_err(|e| self.error(e))
+    }
+
+    fn get_password(&self, service: &str, key: &str) -> Result<String, KeychainError> {
+        self.open_entry(service, key)?
+            .get_password()
+            .map_err(|e| self.error(e))
+    }
+
+    fn probe(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+        self.open_entry(service, key)?
+            .get_attributes()
+            .map(drop)
+            .map_err(|e| self.error(e))
+    }
+
+    #[cfg(target_os = "macos")]
+    fn protect(
+        &self,
+        service: &str,
+        key: &str,
+        access: &MacosAccess,
+    ) -> Result<bool, KeychainError> {
+        super::access::protect(service, key, access).map(|()| true)
+    }
+
+    #[cfg(target_os = "macos")]
+    fn unlock(&self) -> Result<(), KeychainError> {
+        super::access::unlock_keychain()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn unlock(&self) -> Result<(), KeychainError> {
+        match &self.collection {
+            Some(collection) => super::collection::unlock_collection(collection),
+            None => super::collection::unlock_collection(&LinuxCollection::Default),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    fn check_persistence(&self, persistence: WindowsPersistence) -> Result<(), KeychainError> {
+        super::persistence::check_allowed(persistence)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn persist(
+        &self,
+        service: &str,
+        key: &str,
+        persistence: WindowsPersistence,
+    ) -> Result<bool, KeychainError> {
+        let target = self.target_name(service, key)?.unwrap_or_default();
+        super::persistence::persist(&target, persistence).map(|()| true)
+    }
+
+    #[cfg(target_os = "windows")]
+    fn target_name(&self, service: &str, key: &str) -> Result<Option<String>, KeychainError> {
+        let entry = self.open_entry(service, key)?;
+        Ok(super::persistence::target_name(&entry))
+    }
+
+    #[cfg(target_os = "linux")]
+    fn collection(&self) -> Option<CollectionStatus> {
+        let collection = self.collection.as_ref()?;
+        let target = self.target.as_deref().unwrap_or_default();
+        Some(super::collection::status(collection, target))
+    }
+}
+
+/// Backend Linux : `auto` essaie le Secret Service puis keyutils.
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum LinuxBackend {
+    #[default]
+    Auto,
+    SecretService,
+    Keyutils,
+}
+
+/// Trousseau du noyau Linux (keyutils), quand aucune session DBus ne fournit
+/// de Secret Service : SSH avec deport X, conteneurs minimaux. Les cles
+/// vivent dans les trousseaux de session et persistant de l'utilisateur et
+/// ne survivent pas a un redemarrage.
+#[cfg(target_os = "linux")]
+pub(crate) struct KeyutilsStore;
+
+#[cfg(target_os = "linux")]
+impl KeyutilsStore {
+    fn open_entry(service: &str, key: &str) -> Result<Entry, KeychainError> {
+        keyring::keyutils::KeyutilsCredential::new_with_target(None, service, key)
+            .map(|credential| Entry::new_with_credential(Box::new(credential)))
+            .map_err(|e| KeychainError::from_entry_creation(&e))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl SecretStore for KeyutilsStore {
+    fn name(&self) -> &'static str {
+        "keyutils"
+    }
+
+    fn persistent(&self) -> bool {
+        false
+    }
+
+    fn set(&self, service: &str, key: &str, value: &[u8]) -> Result<(), KeychainError> {
+        Self::open_entry(service, key)?
+            .set_secret(value)
+            .map_err(|e| KeychainError::from_keyring(&e))
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<Vec<u8>, KeychainError> {
+        Self::open_entry(service, key)?
+            .get_secret()
+            .map_err(|e| KeychainError::from_keyring(&e))
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+        Self::open_entry(service, key)?
+            .delete_credential()
+            .map_err(|e| KeychainError::from_keyring(&e))
+    }
+}
+
+pub(crate) type MemoryMap = HashMap<(String, String), Zeroizing<Vec<u8>>>;
+
+/// Store de repli en memoire, utilise quand le keychain OS est absent (CI,
+/// WSL sans DBus, conteneurs).
+#[derive(Default, Clone)]
+pub(crate) struct MemoryStore(Arc<Mutex<MemoryMap>>);
+
+impl MemoryStore {
+    pub(crate) fn map(&self) -> MutexGuard<'_, MemoryMap> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SecretStore for MemoryStore {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn persistent(&self) -> bool {
+        false
+    }
+
+    fn set(&self, service: &str, key: &str, value: &[u8]) -> Result<(), KeychainError> {
+        self.map().insert(
+            (service.to_string(), key.to_string()),
+            Zeroizing::new(value.to_vec()),
+        );
+        Ok(())
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<Vec<u8>, KeychainError> {
+        self.map()
+            .get(&(service.to_string(), key.to_string()))
+            .map(|value| value.to_vec())
+            .ok_or_else(not_found)
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+        self.map()
+            .remove(&(service.to_string(), key.to_string()))
+            .map(drop)
+            .ok_or_else(not_found)
+    }
+}
+
+/// Operation visee par une panne injectee dans `MockSecretStore`.
+#[cfg(any(test, feature = "mock-store"))]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MockOp {
+    Set,
+    Get,
+    Delete,
+}
+
+/// Store en memoire dont on peut faire echouer les appels, pour tester les
+/// commandes sans keychain. Compile dans les tests ; l'application ne l'utilise
+/// qu'avec la feature `mock-store` et l'option `--mock-store`.
+#[cfg(any(test, feature = "mock-store"))]
+#[derive(Default)]
+pub(crate) struct MockSecretStore {
+    memory: MemoryStore,
+    failures: Mutex<Vec<(MockOp, KeychainErrorCode, u32)>>,
+}
+
+#[cfg(any(test, feature = "mock-store"))]
+impl MockSecretStore {
+    /// Fait echouer les `times` prochains appels `op` avec `code`.
+    pub(crate) fn fail_next(&self, op: MockOp, code: KeychainErrorCode, times: u32) {
+        self.failures
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push((op, code, times));
+    }
+
+    /// Lit les pannes de `ARGOS_MOCK_FAILURES`, au format
+    /// `get:PlatformError:2,set:AccessDenied` (une fois par defaut).
+    #[cfg(feature = "mock-store")]
+    pub(crate) fn from_env() -> Self {
+        let store = MockSecretStore::default();
+        let spec = std::env::var("ARGOS_MOCK_FAILURES").unwrap_or_default();
+        for item in spec.split(',').filter(|item| !item.is_empty()) {
+            let mut parts = item.split(':');
+            let op = match parts.next() {
+                Some("set") => MockOp::Set,
+                Some("get") => MockOp::Get,
+                Some("delete") => MockOp::Delete,
+                _ => continue,
+            };
+            let Some(Ok(code)) = parts
+                .next()
+                .map(|code| serde_json::from_value(serde_json::Value::String(code.to_string())))
+            else {
+                continue;
+            };
+            let times = parts.next().and_then(|n| n.parse().ok()).unwrap_or(1);
+            store.fail_next(op, code, times);
+        }
+        store
+    }
+
+    fn injected(&self, op: MockOp) -> Result<(), KeychainError> {
+        let mut failures = self.failures.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(failure) = failures.iter_mut().find(|f| f.0 == op && f.2 > 0) else {
+            return Ok(());
+        };
+        failure.2 -= 1;
+        Err(KeychainError::Keyring {
+            code: failure.1,
+            kind: "Injected",
+            detail: format!("panne injectee : {:?}", failure.1),
+            attempts: 1,
+        })
+    }
+}
+
+#[cfg(any(test, feature = "mock-store"))]
+impl SecretStore for MockSecretStore {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    fn persistent(&self) -> bool {
+        false
+    }
+
+    fn set(&self, service: &str, key: &str, value: &[u8]) -> Result<(), KeychainError> {
+        self.injected(MockOp::Set)?;
+        self.memory.set(service, key, value)
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<Vec<u8>, KeychainError> {
+        self.injected(MockOp::Get)?;
+        self.memory.get(service, key)
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+        self.injected(MockOp::Delete)?;
+        self.memory.delete(service, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code<T>(result: Result<T, KeychainError>) -> Option<KeychainErrorCode> {
+        result.err().map(|e| e.code())
+    }
+
+    /// Semantique attendue de tout backend : `NotFound` pour une entree
+    /// absente, valeur remplacee a la reecriture, suppression definitive.
+    fn check_semantics(store: &dyn SecretStore) {
+        assert_eq!(
+            code(store.get("svc", "key")),
+            Some(KeychainErrorCode::NotFound)
+        );
+        assert_eq!(
+            code(store.delete("svc", "key")),
+            Some(KeychainErrorCode::NotFound)
+        );
+
+        store.set("svc", "key", b"v1").unwrap();
+        store.set("svc", "key", b"v2").unwrap();
+        assert_eq!(store.get("svc", "key").unwrap(), b"v2");
+        assert_eq!(
+            code(store.get("other", "key")),
+            Some(KeychainErrorCode::NotFound)
+        );
+
+        store.delete("svc", "key").unwrap();
+        assert_eq!(
+            code(store.get("svc", "key")),
+            Some(KeychainErrorCode::NotFound)
+        );
+        assert_eq!(
+            code(store.delete("svc", "key")),
+            Some(KeychainErrorCode::NotFound)
+        );
+
+        store.set("svc", "bytes", &[0xff, 0xfe]).unwrap();
+        assert_eq!(
+            code(store.get_password("svc", "bytes")),
+            Some(KeychainErrorCode::BadEncoding)
+        );
+    }
+
+    #[test]
+    fn memory_store_behaves_like_a_backend() {
+        let store = MemoryStore::default();
+        check_semantics(&store);
+        assert!(!store.persistent());
+    }
+
+    #[test]
+    fn memory_store_clones_share_entries() {
+        let store = MemoryStore::default();
+        store.clone().set("svc", "key", b"v1").unwrap();
+        assert_eq!(store.get("svc", "key").unwrap(), b"v1");
+        assert_eq!(store.map().len(), 1);
+    }
+
+    #[test]
+    fn mock_store_behaves_like_a_backend() {
+        check_semantics(&MockSecretStore::default());
+    }
+}
@@ -0,0 +1,61 @@
+//! Estimation de la robustesse d'une phrase de passe (zxcvbn), pour la
+//! jauge du formulaire d'export et du mode portable. La valeur analysee
+//! n'est ni journalisee ni conservee.
+
+use serde::Serialize;
+
+use super::{worker_interrupted, KeychainError, Secret};
+
+/// Au-dela, seul le debut de la valeur est analyse : le cout de zxcvbn
+/// croit vite avec la longueur, et un tel secret est de toute facon fort.
+const MAX_ANALYZED_CHARS: usize = 256;
+
+#[derive(Serialize, Default)]
+pub(crate) struct StrengthReport {
+    /// De 0 (tres faible) a 4 (tres fort).
+    score: u8,
+    /// Entropie estimee, en bits.
+    entropy_bits: f64,
+    /// Temps estime pour casser un hachage lent (1e4 essais par seconde).
+    crack_time: String,
+    warning: Option<String>,
+    suggestions: Vec<String>,
+    /// `true` si la valeur a ete tronquee a `MAX_ANALYZED_CHARS`.
+    truncated: bool,
+}
+
+fn estimate(value: &str) -> StrengthReport {
+    if value.is_empty() {
+        return StrengthReport::default();
+    }
+    let (analyzed, truncated) = match value.char_indices().nth(MAX_ANALYZED_CHARS) {
+        Some((end, _)) => (&value[..end], true),
+        None => (value, false),
+    };
+    let Ok(entropy) = zxcvbn::zxcvbn(analyzed, &[]) else {
+        return StrengthReport::default();
+    };
+    let feedback = entropy.feedback().as_ref();
+    StrengthReport {
+        score: entropy.score(),
+        entropy_bits: entropy.guesses_log10() * std::f64::consts::LOG2_10,
+        crack_time: entropy
+            .crack_times()
+            .offline_slow_hashing_1e4_per_second()
+            .to_string(),
+        warning: feedback.and_then(|f| f.warning()).map(|w| w.to_string()),
+        suggestions: feedback
+            .map(|f| f.suggestions().iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        truncated,
+    }
+}
+
+/// Evalue la robustesse de `value`. Le tampon recu est efface des que
+/// l'analyse est terminee.
+#[tauri::command]
+pub(crate) async fn secret_strength(value: Secret) -> Result<StrengthReport, KeychainError> {
+    tauri::async_runtime::spawn_blocking(move || estimate(&value))
+        .await
+        .map_err(worker_interrupted)
+}
@@ -0,0 +1,73 @@
+//! Outils des tests : un `KeychainManager` propre a chaque test, sur le store
+//! memoire ou sur un `MockSecretStore`, sans fenetre ni application Tauri.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::store::MemoryStore;
+use super::store::MockSecretStore;
+use super::{KeychainManager, RetryPolicy, SetOptions};
+use crate::config::AppConfig;
+
+pub(crate) const SERVICE: &str = "fr.bbrain.argos.tests";
+
+pub(crate) const NO_RETRY: RetryPolicy = RetryPolicy {
+    attempts: 1,
+    base_delay_ms: 0,
+};
+
+/// Manager explicitement pose sur le store memoire, quelle que soit la
+/// selection de backend de `from_config`.
+pub(crate) fn memory_manager() -> KeychainManager {
+    let memory = MemoryStore::default();
+    let store = Arc::new(memory.clone());
+    KeychainManager::with_store(AppConfig::default(), None, memory, store)
+}
+
+/// Manager sur un `MockSecretStore` propre au test.
+pub(crate) fn mock_manager() -> (KeychainManager, Arc<MockSecretStore>) {
+    let mock = Arc::new(MockSecretStore::default());
+    let manager = KeychainManager::with_store(
+        AppConfig::default(),
+        None,
+        MemoryStore::default(),
+        mock.clone(),
+    );
+    (manager, mock)
+}
+
+pub(crate) fn set(manager: &KeychainManager, key: &str, value: &str) {
+    manager
+        .set(SERVICE, key, value, SetOptions::default(), NO_RETRY)
+        .unwrap();
+}
+
+pub(crate) fn get(manager: &KeychainManager, key: &str) -> Option<String> {
+    manager
+        .get(SERVICE, key, false, NO_RETRY)
+        .unwrap()
+        .into_unverified()
+        .map(|value| value.to_string())
+}
+
+/// Cles du store memoire pour `SERVICE`, fragments et entrees internes
+/// compris.
+pub(crate) fn stored_keys(manager: &KeychainManager) -> Vec<String> {
+    let mut keys: Vec<String> = manager
+        .memory
+        .map()
+        .keys()
+        .filter(|(service, _)| service == SERVICE)
+        .map(|(_, key)| key.clone())
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Dossier temporaire vide, propre au test `name` et au processus.
+pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("argos-tests-{}-{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
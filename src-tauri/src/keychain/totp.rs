@@ -0,0 +1,300 @@
+//! Codes TOTP (RFC 6238) calcules cote Rust : la graine base32 ne quitte
+//! jamais le keychain, seul le code courant est renvoye au webview.
+
+use hmac::digest::KeyInit;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+use tauri::{State, Window};
+use zeroize::Zeroizing;
+
+use super::{run_blocking, KeychainError, KeychainErrorCode, KeychainManager, RetryPolicy, Secret};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TotpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+/// Parametres TOTP enregistres dans l'enveloppe a l'ecriture du secret.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(default)]
+pub(crate) struct TotpParams {
+    algorithm: TotpAlgorithm,
+    /// 6 ou 8 chiffres.
+    digits: u32,
+    /// Duree d'une fenetre, en secondes.
+    period: u64,
+}
+
+impl Default for TotpParams {
+    fn default() -> Self {
+        TotpParams {
+            algorithm: TotpAlgorithm::Sha1,
+            digits: 6,
+            period: 30,
+        }
+    }
+}
+
+const MAX_PERIOD_SECS: u64 = 300;
+
+impl TotpParams {
+    pub(crate) fn validate(&self) -> Result<(), KeychainError> {
+        if self.digits != 6 && self.digits != 8 {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                "totp.digits doit valoir 6 ou 8",
+            ));
+        }
+        if !(1..=MAX_PERIOD_SECS).contains(&self.period) {
+            return Err(KeychainError::rejected(
+                KeychainErrorCode::InvalidArgument,
+                format!(
+                    "totp.period doit etre compris entre 1 et {}",
+                    MAX_PERIOD_SECS
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Code courant et secondes restantes avant le suivant.
+#[derive(Serialize)]
+pub(crate) struct TotpCode {
+    code: String,
+    remaining_seconds: u64,
+}
+
+/// Decode une graine base32 (RFC 4648), sans tenir compte de la casse, des
+/// espaces ni du remplissage `=`.
+pub(crate) fn decode_base32(seed: &str) -> Result<Zeroizing<Vec<u8>>, KeychainError> {
+    let mut bytes = Zeroizing::new(Vec::with_capacity(seed.len() * 5 / 8));
+    let mut buffer: u64 = 0;
+    let mut bits = 0;
+    for c in seed.chars().filter(|c| !c.is_whitespace() && *c != '=') {
+        let value = match c.to_ascii_uppercase() {
+            c @ 'A'..='Z' => c as u64 - 'A' as u64,
+            c @ '2'..='7' => c as u64 - '2' as u64 + 26,
+            _ => return Err(invalid_base32()),
+        };
+        buffer = (buffer << 5) | value;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    if bytes.is_empty() {
+        return Err(invalid_base32());
+    }
+    Ok(bytes)
+}
+
+fn invalid_base32() -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::InvalidBase32,
+        "la graine TOTP n'est pas en base32 valide",
+    )
+}
+
+/// Code HOTP (RFC 4226) de `counter`.
+fn hotp(key: &[u8], counter: u64, params: TotpParams) -> String {
+    let digest = match params.algorithm {
+        TotpAlgorithm::Sha1 => hmac::<Hmac<Sha1>>(key, counter),
+        TotpAlgorithm::Sha256 => hmac::<Hmac<Sha256>>(key, counter),
+        TotpAlgorithm::Sha512 => hmac::<Hmac<Sha512>>(key, counter),
+    };
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = u32::from_be_bytes([
+        digest[offset] & 0x7f,
+        digest[offset + 1],
+        digest[offset + 2],
+        digest[offset + 3],
+    ]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(params.digits),
+        width = params.digits as usize
+    )
+}
+
+fn hmac<M: Mac + KeyInit>(key: &[u8], counter: u64) -> Zeroizing<Vec<u8>> {
+    let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepte toute longueur de cle");
+    mac.update(&counter.to_be_bytes());
+    Zeroizing::new(mac.finalize().into_bytes().to_vec())
+}
+
+/// Graine lue dans le keychain, avec les parametres de son enveloppe.
+pub(crate) struct TotpSeed {
+    seed: Secret,
+    params: TotpParams,
+}
+
+impl TotpSeed {
+    pub(crate) fn new(seed: Secret, params: TotpParams) -> Self {
+        TotpSeed { seed, params }
+    }
+
+    pub(crate) fn into_seed(self) -> Secret {
+        self.seed
+    }
+
+    /// Code a l'instant `unix_time`, en secondes.
+    pub(crate) fn code_at(&self, unix_time: u64) -> Result<TotpCode, KeychainError> {
+        let key = decode_base32(&self.seed)?;
+        let period = self.params.period;
+        Ok(TotpCode {
+            code: hotp(&key, unix_time / period, self.params),
+            remaining_seconds: period - unix_time % period,
+        })
+    }
+
+    /// URI `otpauth://totp/...` reconnue par les applications
+    /// d'authentification, pour `secret_to_qr` : seule exception a la regle
+    /// du module, sur demande explicite.
+    pub(crate) fn otpauth_uri(&self, issuer: &str, account: &str) -> Secret {
+        let seed: Zeroizing<String> = Zeroizing::new(
+            self.seed
+                .chars()
+                .filter(|c| !c.is_whitespace() && *c != '=')
+                .map(|c| c.to_ascii_uppercase())
+                .collect(),
+        );
+        let algorithm = match self.params.algorithm {
+            TotpAlgorithm::Sha1 => "SHA1",
+            TotpAlgorithm::Sha256 => "SHA256",
+            TotpAlgorithm::Sha512 => "SHA512",
+        };
+        let mut uri = url::Url::parse("otpauth://totp/").expect("URI otpauth valide");
+        uri.path_segments_mut()
+            .expect("URI otpauth avec chemin")
+            .push(&format!("{}:{}", issuer, account));
+        uri.query_pairs_mut()
+            .append_pair("secret", &seed)
+            .append_pair("issuer", issuer)
+            .append_pair("algorithm", algorithm)
+            .append_pair("digits", &self.params.digits.to_string())
+            .append_pair("period", &self.params.period.to_string());
+        Secret::new(uri.into())
+    }
+}
+
+/// Calcule le code TOTP courant d'une graine stockee, sans jamais la
+/// renvoyer. Une entree absente donne `NotFound`, une graine illisible
+/// `InvalidBase32`.
+#[tauri::command]
+pub(crate) async fn totp_generate(
+    window: Window,
+    manager: State<'_, KeychainManager>,
+    service: String,
+    key: String,
+    retry: Option<RetryPolicy>,
+) -> Result<TotpCode, KeychainError> {
+    let audit = manager.audit(&window, "totp", &service, Some(&key));
+    if let Err(e) = manager.reads.acquire(&service, [key.as_str()]) {
+        return audit.finish(Err(e));
+    }
+    let auth = manager.auth.clone();
+    let manager = manager.inner().clone();
+    let retry = retry.unwrap_or_default();
+    // Le code est calcule apres une eventuelle verification de
+    // l'utilisateur, pour ne pas renvoyer une fenetre deja ecoulee.
+    let seed = auth
+        .release(run_blocking(move || manager.totp_seed(&service, &key, retry)).await)
+        .await;
+    audit.finish(seed.and_then(|seed| seed.code_at(chrono::Utc::now().timestamp() as u64)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHA1_SEED: &[u8] = b"12345678901234567890";
+    const SHA256_SEED: &[u8] = b"12345678901234567890123456789012";
+    const SHA512_SEED: &[u8] = b"1234567890123456789012345678901234567890123456789012345678901234";
+
+    fn params(algorithm: TotpAlgorithm, digits: u32) -> TotpParams {
+        TotpParams {
+            algorithm,
+            digits,
+            period: 30,
+        }
+    }
+
+    #[test]
+    fn hotp_matches_rfc_4226() {
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            let params = params(TotpAlgorithm::Sha1, 6);
+            assert_eq!(hotp(SHA1_SEED, counter as u64, params), *code);
+        }
+    }
+
+    #[test]
+    fn totp_matches_rfc_6238() {
+        // Annexe B de la RFC 6238 : (T, SHA1, SHA256, SHA512).
+        let vectors = [
+            (59, "94287082", "46119246", "90693936"),
+            (1111111109, "07081804", "68084774", "25091201"),
+            (1111111111, "14050471", "67062674", "99943326"),
+            (1234567890, "89005924", "91819424", "93441116"),
+            (2000000000, "69279037", "90698825", "38618901"),
+            (20000000000, "65353130", "77737706", "47863826"),
+        ];
+        for (time, sha1, sha256, sha512) in vectors {
+            let counter = time / 30;
+            let cases = [
+                (TotpAlgorithm::Sha1, SHA1_SEED, sha1),
+                (TotpAlgorithm::Sha256, SHA256_SEED, sha256),
+                (TotpAlgorithm::Sha512, SHA512_SEED, sha512),
+            ];
+            for (algorithm, seed, code) in cases {
+                assert_eq!(
+                    hotp(seed, counter, params(algorithm, 8)),
+                    code,
+                    "T={}",
+                    time
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn code_at_decodes_the_stored_seed() {
+        // Base32 de "12345678901234567890", en minuscules et par blocs.
+        let seed = Secret::new("gezd gnbv gy3t qojq gezd gnbv gy3t qojq".to_string());
+        let totp = TotpSeed::new(seed, params(TotpAlgorithm::Sha1, 8));
+        let code = totp.code_at(59).unwrap();
+        assert_eq!(code.code, "94287082");
+        assert_eq!(code.remaining_seconds, 1);
+        assert_eq!(totp.code_at(60).unwrap().remaining_seconds, 30);
+    }
+
+    #[test]
+    fn invalid_seeds_are_rejected() {
+        for seed in ["", "====", "GEZD1", "GEZD GNB!"] {
+            let code = decode_base32(seed).err().map(|e| e.code());
+            assert_eq!(code, Some(KeychainErrorCode::InvalidBase32), "{:?}", seed);
+        }
+    }
+
+    #[test]
+    fn params_are_validated() {
+        assert!(TotpParams::default().validate().is_ok());
+        assert!(params(TotpAlgorithm::Sha512, 7).validate().is_err());
+        let mut params = params(TotpAlgorithm::Sha1, 6);
+        params.period = 0;
+        assert!(params.validate().is_err());
+        params.period = MAX_PERIOD_SECS + 1;
+        assert!(params.validate().is_err());
+    }
+}
@@ -0,0 +1,380 @@
+//! Mode portable : les secrets sont chiffres dans un fichier plutot que dans
+//! le keychain OS, pour les postes partages ou ARGOS tourne depuis une cle
+//! USB.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::State;
+use zeroize::Zeroizing;
+
+use super::backup::{
+    argon2id, check_passphrase, crypto_error, io_error, KDF_M_COST, KDF_P_COST, KDF_T_COST,
+    KEY_LEN, MAX_KDF_M_COST, MAX_KDF_P_COST, MAX_KDF_T_COST, NONCE_LEN, SALT_LEN,
+};
+use super::store::{not_found, MemoryMap, SecretStore};
+use super::{worker_interrupted, KeychainError, KeychainErrorCode, KeychainManager, Secret};
+
+/// Nom du fichier dans le dossier de donnees de l'application.
+pub(crate) const VAULT_FILE: &str = "argos.vault";
+
+const MAGIC: &[u8; 8] = b"ARGOSVLT";
+const FORMAT_VERSION: u8 = 1;
+
+/// Empreinte de la cle derivee, en clair dans l'en-tete : elle distingue une
+/// phrase de passe fausse d'un fichier altere, que le tag GCM seul confond.
+const CHECK_LEN: usize = 16;
+
+const HEADER_LEN: usize = 8 + 1 + 12 + SALT_LEN + CHECK_LEN + NONCE_LEN;
+
+/// Erreur renvoyee par le coffre tant qu'il n'est pas deverrouille.
+fn vault_locked() -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::VaultLocked,
+        "coffre portable verrouille, deverrouillez-le avec vault_unlock",
+    )
+}
+
+fn vault_corrupt(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::VaultCorrupt,
+        format!("coffre portable illisible : {}", detail),
+    )
+}
+
+/// Parametres de derivation, repris tels quels a chaque reecriture.
+#[derive(Clone, Copy)]
+struct Kdf {
+    cost: [u32; 3],
+    salt: [u8; SALT_LEN],
+}
+
+impl Kdf {
+    fn header(&self, key: &[u8; KEY_LEN], nonce: &[u8; NONCE_LEN]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN);
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+        for cost in self.cost {
+            out.extend_from_slice(&cost.to_le_bytes());
+        }
+        out.extend_from_slice(&self.salt);
+        out.extend_from_slice(&key_check(key));
+        out.extend_from_slice(nonce);
+        out
+    }
+}
+
+fn key_check(key: &[u8; KEY_LEN]) -> [u8; CHECK_LEN] {
+    let digest = Sha256::new()
+        .chain_update(b"argos-vault-check")
+        .chain_update(key)
+        .finalize();
+    let mut check = [0u8; CHECK_LEN];
+    check.copy_from_slice(&digest[..CHECK_LEN]);
+    check
+}
+
+/// Contenu chiffre du fichier.
+#[derive(Serialize, Deserialize)]
+struct VaultRecord {
+    service: String,
+    key: String,
+    /// Octets du secret en base64.
+    data: Zeroizing<String>,
+}
+
+/// Etat d'un coffre deverrouille : la cle derivee et les secrets en clair.
+struct Unlocked {
+    kdf: Kdf,
+    key: Zeroizing<[u8; KEY_LEN]>,
+    entries: MemoryMap,
+}
+
+/// Coffre chiffre (Argon2id + AES-256-GCM). Les secrets sont gardes en
+/// memoire une fois deverrouille, et le fichier est reecrit a chaque
+/// modification.
+pub(crate) struct FileVault {
+    path: PathBuf,
+    state: Mutex<Option<Unlocked>>,
+}
+
+impl FileVault {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        FileVault {
+            path,
+            state: Mutex::new(None),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, Option<Unlocked>> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn is_locked(&self) -> bool {
+        self.state().is_none()
+    }
+
+    /// Dechiffre le fichier, ou le cree s'il n'existe pas et que `create` est
+    /// demande.
+    fn unlock(&self, passphrase: &str, create: bool) -> Result<UnlockReport, KeychainError> {
+        let data = match std::fs::read(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                if !create {
+                    return Err(KeychainError::rejected(
+                        KeychainErrorCode::VaultNotFound,
+                        format!("aucun coffre portable a {}", self.path.display()),
+                    ));
+                }
+                return self.create(passphrase);
+            }
+            Err(e) => return Err(io_error(&self.path.display().to_string(), e)),
+        };
+        let unlocked = open(&data, passphrase)?;
+        let entries = unlocked.entries.len();
+        *self.state() = Some(unlocked);
+        Ok(UnlockReport {
+            created: false,
+            entries,
+        })
+    }
+
+    fn create(&self, passphrase: &str) -> Result<UnlockReport, KeychainError> {
+        check_passphrase(passphrase)?;
+        let mut kdf = Kdf {
+            cost: [KDF_M_COST, KDF_T_COST, KDF_P_COST],
+            salt: [0u8; SALT_LEN],
+        };
+        rand::thread_rng().fill_bytes(&mut kdf.salt);
+        let unlocked = Unlocked {
+            key: argon2id(passphrase, kdf.cost, &kdf.salt)?,
+            kdf,
+            entries: MemoryMap::new(),
+        };
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| io_error(&dir.display().to_string(), e))?;
+        }
+        self.persist(&unlocked)?;
+        *self.state() = Some(unlocked);
+        Ok(UnlockReport {
+            created: true,
+            entries: 0,
+        })
+    }
+
+    pub(crate) fn lock(&self) {
+        *self.state() = None;
+    }
+
+    /// Rechiffre tout le coffre avec un nouveau nonce, ecrit a cote puis
+    /// renomme : une coupure en pleine ecriture laisse l'ancien fichier.
+    fn persist(&self, unlocked: &Unlocked) -> Result<(), KeychainError> {
+        let records: Vec<VaultRecord> = unlocked
+            .entries
+            .iter()
+            .map(|((service, key), value)| VaultRecord {
+                service: service.clone(),
+                key: key.clone(),
+                data: Zeroizing::new(base64::engine::general_purpose::STANDARD.encode(&value[..])),
+            })
+            .collect();
+        let plaintext = Zeroizing::new(serde_json::to_vec(&records).map_err(crypto_error)?);
+
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let mut file = unlocked.kdf.header(&unlocked.key, &nonce);
+        let cipher = Aes256Gcm::new_from_slice(&unlocked.key[..]).map_err(crypto_error)?;
+        let ciphertext = cipher
+            .encrypt(
+                Nonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &file,
+                },
+            )
+            .map_err(crypto_error)?;
+        file.extend_from_slice(&ciphertext);
+        write_atomic(&self.path, &file)
+    }
+
+    /// Applique `change` aux secrets puis reecrit le fichier ; en cas d'echec
+    /// d'ecriture, l'etat en memoire est restaure.
+    fn update<T>(
+        &self,
+        change: impl FnOnce(&mut MemoryMap) -> Result<T, KeychainError>,
+    ) -> Result<T, KeychainError> {
+        let mut state = self.state();
+        let unlocked = state.as_mut().ok_or_else(vault_locked)?;
+        let before = unlocked.entries.clone();
+        let result = change(&mut unlocked.entries)?;
+        if let Err(e) = self.persist(unlocked) {
+            unlocked.entries = before;
+            return Err(e);
+        }
+        Ok(result)
+    }
+}
+
+fn write_atomic(path: &Path, data: &[u8]) -> Result<(), KeychainError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let written = std::fs::File::create(&tmp)
+        .and_then(|mut file| file.write_all(data).and_then(|()| file.sync_all()))
+        .and_then(|()| std::fs::rename(&tmp, path));
+    written.map_err(|e| {
+        let _ = std::fs::remove_file(&tmp);
+        io_error(&path.display().to_string(), e)
+    })
+}
+
+/// Verifie l'en-tete puis dechiffre. `WrongPassphrase` si l'empreinte de la
+/// cle ne correspond pas, `VaultCorrupt` si le fichier a ete altere.
+fn open(data: &[u8], passphrase: &str) -> Result<Unlocked, KeychainError> {
+    if data.len() < HEADER_LEN || &data[..MAGIC.len()] != MAGIC {
+        return Err(vault_corrupt("en-tete absent"));
+    }
+    let version = data[MAGIC.len()];
+    if version > FORMAT_VERSION {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::UnsupportedVersion,
+            format!(
+                "coffre au format {} cree par une version plus recente d'ARGOS",
+                version
+            ),
+        ));
+    }
+    if version == 0 {
+        return Err(vault_corrupt("version invalide"));
+    }
+    let u32_at = |offset: usize| {
+        u32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    let cost = [u32_at(9), u32_at(13), u32_at(17)];
+    if cost[0] > MAX_KDF_M_COST || cost[1] > MAX_KDF_T_COST || cost[2] > MAX_KDF_P_COST {
+        return Err(vault_corrupt("parametres de derivation hors limites"));
+    }
+    let salt_at = 21;
+    let check_at = salt_at + SALT_LEN;
+    let nonce_at = check_at + CHECK_LEN;
+    let kdf = Kdf {
+        cost,
+        salt: data[salt_at..check_at].try_into().unwrap_or_default(),
+    };
+
+    let key = argon2id(passphrase, kdf.cost, &kdf.salt)?;
+    if key_check(&key)[..] != data[check_at..nonce_at] {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::WrongPassphrase,
+            "phrase de passe du coffre incorrecte",
+        ));
+    }
+    let cipher = Aes256Gcm::new_from_slice(&key[..]).map_err(crypto_error)?;
+    let plaintext = cipher
+        .decrypt(
+            Nonce::from_slice(&data[nonce_at..HEADER_LEN]),
+            Payload {
+                msg: &data[HEADER_LEN..],
+                aad: &data[..HEADER_LEN],
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| vault_corrupt("contenu altere"))?;
+    let records: Vec<VaultRecord> = serde_json::from_slice(&plaintext).map_err(vault_corrupt)?;
+
+    let mut entries = MemoryMap::new();
+    for record in records {
+        let value = base64::engine::general_purpose::STANDARD
+            .decode(record.data.as_bytes())
+            .map(Zeroizing::new)
+            .map_err(vault_corrupt)?;
+        entries.insert((record.service, record.key), value);
+    }
+    Ok(Unlocked { kdf, key, entries })
+}
+
+impl SecretStore for FileVault {
+    fn name(&self) -> &'static str {
+        "portable-vault"
+    }
+
+    fn persistent(&self) -> bool {
+        true
+    }
+
+    fn set(&self, service: &str, key: &str, value: &[u8]) -> Result<(), KeychainError> {
+        self.update(|entries| {
+            entries.insert(
+                (service.to_string(), key.to_string()),
+                Zeroizing::new(value.to_vec()),
+            );
+            Ok(())
+        })
+    }
+
+    fn get(&self, service: &str, key: &str) -> Result<Vec<u8>, KeychainError> {
+        let state = self.state();
+        let unlocked = state.as_ref().ok_or_else(vault_locked)?;
+        unlocked
+            .entries
+            .get(&(service.to_string(), key.to_string()))
+            .map(|value| value.to_vec())
+            .ok_or_else(not_found)
+    }
+
+    fn delete(&self, service: &str, key: &str) -> Result<(), KeychainError> {
+        self.update(|entries| {
+            entries
+                .remove(&(service.to_string(), key.to_string()))
+                .map(drop)
+                .ok_or_else(not_found)
+        })
+    }
+}
+
+#[derive(Serialize)]
+pub(crate) struct UnlockReport {
+    created: bool,
+    entries: usize,
+}
+
+/// Deverrouille le coffre portable. Avec `create`, un coffre absent est cree
+/// avec cette phrase de passe.
+#[tauri::command]
+pub(crate) async fn vault_unlock(
+    manager: State<'_, KeychainManager>,
+    passphrase: Secret,
+    create: Option<bool>,
+) -> Result<UnlockReport, KeychainError> {
+    let vault = manager.vault()?;
+    let create = create.unwrap_or(false);
+    // La derivation Argon2 prend du temps : hors du delai des appels keychain.
+    tauri::async_runtime::spawn_blocking(move || vault.unlock(&passphrase, create))
+        .await
+        .map_err(worker_interrupted)?
+}
+
+/// Verrouille le coffre portable : la cle et les secrets en clair sont
+/// effaces de la memoire.
+#[tauri::command]
+pub(crate) fn vault_lock(manager: State<'_, KeychainManager>) -> Result<(), KeychainError> {
+    manager.vault()?.lock();
+    manager.cache.flush();
+    Ok(())
+}
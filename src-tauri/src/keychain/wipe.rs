@@ -0,0 +1,310 @@
+//! "Tout effacer" : supprime toutes les traces d'ARGOS sur le poste, pour
+//! le depart d'un utilisateur. Les secrets de tous les profils sont balayes
+//! comme par `keychain_clear_service`, puis le dossier de donnees est vide :
+//! configuration, coffre portable (ecrase avant suppression), journaux,
+//! audit, etat des fenetres. Les sauvegardes automatiques d'un
+//! `backup.directory` hors du dossier de donnees sont supprimees aussi, sans
+//! toucher aux autres fichiers de ce dossier.
+
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, State, Window};
+
+use super::session::LockReason;
+use super::{
+    audit, autobackup, bulk, keyring_call, vault, KeychainError, KeychainErrorCode,
+    KeychainManager, RetryPolicy, ARGOS_SERVICE, HEALTH_KEY, MIGRATION_MARKER_KEY, PROBE_KEY,
+};
+use crate::config::{ConfigStore, CONFIG_FILE, SETTINGS_FILE};
+use crate::{crash, instance, logging, window_state};
+
+/// Phrase a recopier exactement pour confirmer l'effacement.
+pub(crate) const WIPE_PHRASE: &str = "EFFACER TOUTES LES DONNEES ARGOS";
+
+/// Delai laisse a la reponse pour atteindre le webview avant `exit`.
+const EXIT_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Serialize)]
+struct WipeFailure {
+    item: String,
+    error: String,
+}
+
+/// Elements supprimes ou restes en place, pour une categorie.
+#[derive(Serialize, Default)]
+struct WipeCategory {
+    removed: Vec<String>,
+    failed: Vec<WipeFailure>,
+}
+
+impl WipeCategory {
+    fn fail(&mut self, item: impl Into<String>, error: impl std::fmt::Display) {
+        self.failed.push(WipeFailure {
+            item: item.into(),
+            error: error.to_string(),
+        });
+    }
+}
+
+/// Bilan de `wipe_all_data`, par categorie.
+#[derive(Serialize, Default)]
+pub(crate) struct WipeReport {
+    /// Services balayes ; un echec garde le nombre de cles restantes.
+    keychain: WipeCategory,
+    vault: WipeCategory,
+    config: WipeCategory,
+    logs: WipeCategory,
+    audit: WipeCategory,
+    window_state: WipeCategory,
+    backups: WipeCategory,
+    other: WipeCategory,
+}
+
+impl WipeReport {
+    fn is_complete(&self) -> bool {
+        [
+            &self.keychain,
+            &self.vault,
+            &self.config,
+            &self.logs,
+            &self.audit,
+            &self.window_state,
+            &self.backups,
+            &self.other,
+        ]
+        .iter()
+        .all(|category| category.failed.is_empty())
+    }
+
+    /// Categorie d'un element du dossier de donnees.
+    fn category(&mut self, name: &str) -> &mut WipeCategory {
+        if name == CONFIG_FILE
+            || name == SETTINGS_FILE
+            || (name.starts_with("config-") && name.ends_with(".json"))
+        {
+            &mut self.config
+        } else if name == vault::VAULT_FILE {
+            &mut self.vault
+        } else if name == logging::LOG_DIR || name == crash::CRASH_DIR {
+            &mut self.logs
+        } else if name.starts_with(audit::AUDIT_FILE) {
+            &mut self.audit
+        } else if name == window_state::STATE_FILE {
+            &mut self.window_state
+        } else if name == autobackup::DEFAULT_DIR || name == autobackup::STATUS_FILE {
+            &mut self.backups
+        } else {
+            &mut self.other
+        }
+    }
+}
+
+/// Ecrase le contenu du fichier avant de le supprimer. Sur un SSD ou un
+/// systeme de fichiers journalise, l'ecrasement reste un meilleur effort.
+pub(super) fn overwrite_and_remove(path: &Path) -> std::io::Result<()> {
+    let len = std::fs::metadata(path)?.len();
+    {
+        let mut file = std::fs::OpenOptions::new().write(true).open(path)?;
+        let zeros = [0u8; 8192];
+        let mut left = len;
+        while left > 0 {
+            let chunk = left.min(zeros.len() as u64) as usize;
+            file.write_all(&zeros[..chunk])?;
+            left -= chunk as u64;
+        }
+        file.sync_all()?;
+    }
+    std::fs::remove_file(path)
+}
+
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+impl KeychainManager {
+    /// Balaye les services notes pour chaque profil, puis les entrees
+    /// internes du service ARGOS. En mode portable, les secrets sont dans le
+    /// coffre, supprime avec le dossier de donnees.
+    fn wipe_keychain(&self, profiles: &[String], report: &mut WipeCategory) {
+        if self.vault.is_some() {
+            return;
+        }
+        let retry = RetryPolicy::default();
+        let profiles = std::iter::once(None).chain(profiles.iter().map(|name| Some(name.as_str())));
+        for profile in profiles {
+            let label = profile.unwrap_or("(defaut)");
+            match self.wipe_profile(profile, retry) {
+                Ok(wiped) if wiped.failed == 0 => report.removed.extend(wiped.services),
+                Ok(wiped) => report.fail(
+                    format!("profil {}", label),
+                    format!("{} cles non supprimees", wiped.failed),
+                ),
+                Err(e) => report.fail(format!("profil {}", label), e),
+            }
+        }
+        for key in [
+            PROBE_KEY,
+            HEALTH_KEY,
+            MIGRATION_MARKER_KEY,
+            autobackup::BACKUP_KEY,
+        ] {
+            let _ = keyring_call(&self.store, retry, ARGOS_SERVICE, key, |entry| {
+                entry.delete_credential()
+            });
+        }
+    }
+
+    /// Supprime les sauvegardes automatiques de `dir`, et `dir` lui-meme
+    /// s'il ne reste rien d'autre dedans.
+    fn wipe_backups(&self, dir: &Path, report: &mut WipeCategory) {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => return report.fail(dir.display().to_string(), e),
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !autobackup::is_backup_file(&name) {
+                continue;
+            }
+            let path = entry.path();
+            match overwrite_and_remove(&path) {
+                Ok(()) => report.removed.push(path.display().to_string()),
+                Err(e) => report.fail(path.display().to_string(), e),
+            }
+        }
+        let _ = std::fs::remove_dir(dir);
+    }
+
+    /// Vide le dossier de donnees, le coffre et les fichiers d'instance a
+    /// part : ces derniers ne contiennent rien et servent jusqu'a la sortie.
+    fn wipe_files(&self, data_dir: Option<&Path>, report: &mut WipeReport) {
+        if let Some(vault) = &self.vault {
+            let path = vault.path();
+            if path.exists() {
+                match overwrite_and_remove(path) {
+                    Ok(()) => report.vault.removed.push(path.display().to_string()),
+                    Err(e) => report.vault.fail(path.display().to_string(), e),
+                }
+            }
+        }
+        let Some(dir) = data_dir else {
+            report
+                .other
+                .fail("dossier de donnees", "dossier de donnees introuvable");
+            return;
+        };
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(e) => return report.other.fail(dir.display().to_string(), e),
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name == instance::LOCK_FILE || name == instance::ENDPOINT_FILE {
+                continue;
+            }
+            let path = entry.path();
+            let removed = if name == vault::VAULT_FILE {
+                overwrite_and_remove(&path)
+            } else {
+                remove_path(&path)
+            };
+            let category = report.category(&name);
+            match removed {
+                Ok(()) => category.removed.push(name),
+                Err(e) => category.fail(name, e),
+            }
+        }
+    }
+}
+
+/// Efface toutes les donnees d'ARGOS si `confirm_phrase` vaut exactement
+/// `WIPE_PHRASE` et que l'utilisateur le confirme. Apres un effacement
+/// complet la session est verrouillee, et avec `exit` ARGOS se ferme. Rien
+/// n'est ecrit au journal d'audit : il fait partie des donnees effacees.
+#[tauri::command]
+pub(crate) async fn wipe_all_data(
+    app: AppHandle,
+    window: Window,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+    confirm_phrase: String,
+    exit: Option<bool>,
+) -> Result<WipeReport, KeychainError> {
+    if confirm_phrase != WIPE_PHRASE {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            "phrase de confirmation incorrecte : rien n'a ete efface",
+        ));
+    }
+    let backup_dir = store.get().backup.directory.clone();
+    let backups = backup_dir
+        .as_ref()
+        .map_or(String::new(), |dir| format!(" (dans {})", dir.display()));
+    manager
+        .confirm(
+            Some(&window),
+            format!(
+                "Effacer toutes les donnees d'ARGOS : secrets, configuration, journaux et \
+                 sauvegardes automatiques{} ?",
+                backups
+            ),
+        )
+        .await?;
+    let profiles = store.get().profiles.names.clone();
+    let data_dir = app.path_resolver().app_data_dir();
+    let manager = manager.inner().clone();
+    // Sans delai maximal, comme les operations en masse.
+    let report = bulk::blocking(move || {
+        let mut report = WipeReport::default();
+        manager.wipe_keychain(&profiles, &mut report.keychain);
+        if let Some(dir) = &backup_dir {
+            manager.wipe_backups(dir, &mut report.backups);
+        }
+        manager.wipe_files(data_dir.as_deref(), &mut report);
+        if report.is_complete() {
+            manager.lock_session(LockReason::Manual);
+        }
+        Ok(report)
+    })
+    .await?;
+    if report.is_complete() && exit.unwrap_or(false) {
+        tauri::async_runtime::spawn(async move {
+            tokio::time::sleep(EXIT_DELAY).await;
+            app.exit(0);
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{memory_manager, scratch_dir};
+    use super::*;
+
+    #[test]
+    fn backup_wipe_spares_other_files() {
+        let manager = memory_manager();
+        let dir = scratch_dir("wipe-backups");
+        let backup = "fr.bbrain.argos-20260101T000000Z.argosbak";
+        for name in [backup, "notes.txt", "copie.argosbak"] {
+            std::fs::write(dir.join(name), b"data").unwrap();
+        }
+        let mut report = WipeCategory::default();
+        manager.wipe_backups(&dir, &mut report);
+
+        assert!(report.failed.is_empty());
+        assert_eq!(report.removed, [dir.join(backup).display().to_string()]);
+        assert!(dir.join("notes.txt").exists());
+        assert!(dir.join("copie.argosbak").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
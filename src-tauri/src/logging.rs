@@ -0,0 +1,181 @@
+//! Journal de diagnostic dans `logs/` du dossier de donnees : un fichier par
+//! jour, les sept derniers gardes, a joindre a un ticket. Les valeurs des
+//! secrets n'y figurent jamais : `Secret` s'affiche `[REDACTED]`.
+
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::Instant;
+
+use tauri::State;
+use tracing::field::Empty;
+use tracing::level_filters::LevelFilter;
+use tracing::Span;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{Builder as RollingBuilder, Rotation};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{reload, Registry};
+
+use crate::keychain::{metrics, KeychainError, KeychainErrorCode};
+use crate::telemetry;
+
+pub(crate) const LOG_DIR: &str = "logs";
+const LOG_PREFIX: &str = "argos";
+const LOG_SUFFIX: &str = "log";
+const KEPT_FILES: usize = 7;
+
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::INFO;
+
+/// Journal installe au demarrage, enregistre dans l'etat Tauri.
+pub(crate) struct Logging {
+    /// `None` si le dossier n'a pas pu etre cree : rien n'est ecrit.
+    dir: Option<PathBuf>,
+    level: reload::Handle<LevelFilter, Registry>,
+    /// Vide le tampon d'ecriture a la fermeture d'ARGOS.
+    _guard: Option<WorkerGuard>,
+}
+
+/// Installe le journal dans `data_dir/logs`. Un dossier inaccessible est
+/// signale sur la sortie d'erreur et ARGOS demarre sans journal.
+pub(crate) fn init(data_dir: Option<PathBuf>) -> Logging {
+    let (level_layer, level) = reload::Layer::new(DEFAULT_LEVEL);
+    let appender = data_dir.map(|dir| dir.join(LOG_DIR)).and_then(|dir| {
+        RollingBuilder::new()
+            .rotation(Rotation::DAILY)
+            .filename_prefix(LOG_PREFIX)
+            .filename_suffix(LOG_SUFFIX)
+            .max_log_files(KEPT_FILES)
+            .build(&dir)
+            .map(|appender| (dir, appender))
+            .map_err(|e| eprintln!("journal desactive : {}", e))
+            .ok()
+    });
+    let (dir, writer, guard) = match appender {
+        Some((dir, appender)) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            (Some(dir), Some(writer), Some(guard))
+        }
+        None => (None, None, None),
+    };
+    let file_layer = writer.map(|writer| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(writer)
+            .with_ansi(false)
+    });
+    if let Err(e) = tracing_subscriber::registry()
+        .with(level_layer)
+        .with(file_layer)
+        .try_init()
+    {
+        eprintln!("journal desactive : {}", e);
+    }
+    Logging {
+        dir,
+        level,
+        _guard: guard,
+    }
+}
+
+impl Logging {
+    /// Les `count` fichiers les plus recents, du plus ancien au plus recent :
+    /// la date du nom les trie.
+    pub(crate) fn recent_files(&self, count: usize) -> Vec<PathBuf> {
+        let Some(entries) = self
+            .dir
+            .as_ref()
+            .and_then(|dir| std::fs::read_dir(dir).ok())
+        else {
+            return Vec::new();
+        };
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(LOG_PREFIX) && name.ends_with(LOG_SUFFIX))
+            })
+            .collect();
+        files.sort();
+        files.split_off(files.len().saturating_sub(count))
+    }
+}
+
+/// Span d'une commande keychain : service, cle, resultat et duree. Les
+/// arguments sont empruntes le temps de la creation, avant que la commande
+/// ne les deplace.
+pub(crate) struct CommandSpan {
+    span: Span,
+    command: String,
+    started: Instant,
+}
+
+impl CommandSpan {
+    pub(crate) fn new(command: &str, service: &str, key: Option<&str>) -> Self {
+        CommandSpan {
+            span: tracing::info_span!(
+                "keychain",
+                command,
+                service,
+                key,
+                outcome = Empty,
+                duration_ms = Empty
+            ),
+            command: command.to_string(),
+            started: Instant::now(),
+        }
+    }
+
+    /// Cloture le span avec le resultat de la commande ; seul le code
+    /// d'erreur et son message sont journalises, jamais la valeur renvoyee.
+    pub(crate) fn finish<T>(self, result: &Result<T, KeychainError>) {
+        let elapsed = self.started.elapsed();
+        metrics::record(
+            &self.command,
+            elapsed,
+            result.as_ref().err().map(KeychainError::code),
+        );
+        telemetry::record(&self.command);
+        let duration_ms = elapsed.as_millis() as u64;
+        self.span.record("duration_ms", duration_ms);
+        let _entered = self.span.enter();
+        match result {
+            Ok(_) => {
+                self.span.record("outcome", "success");
+                tracing::info!("commande terminee");
+            }
+            Err(e) => {
+                self.span.record("outcome", "failure");
+                tracing::warn!(code = ?e.code(), error = %e, "commande en echec");
+            }
+        }
+    }
+}
+
+/// Change le niveau du journal sans redemarrer : `error`, `warn`, `info`,
+/// `debug`, `trace` ou `off`.
+#[tauri::command]
+pub(crate) fn log_set_level(
+    logging: State<'_, Logging>,
+    level: String,
+) -> Result<String, KeychainError> {
+    let filter = LevelFilter::from_str(level.trim()).map_err(|_| {
+        KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            "niveau de journal invalide : error, warn, info, debug, trace ou off",
+        )
+    })?;
+    logging.level.reload(filter).map_err(|e| {
+        KeychainError::rejected(
+            KeychainErrorCode::PlatformError,
+            format!("niveau de journal inchange : {}", e),
+        )
+    })?;
+    tracing::info!(level = %filter, "niveau du journal modifie");
+    Ok(filter.to_string())
+}
+
+/// Dossier des fichiers du journal pour l'ecran de diagnostic, `None` si le
+/// journal est desactive.
+#[tauri::command]
+pub(crate) fn log_get_path(logging: State<'_, Logging>) -> Option<PathBuf> {
+    logging.dir.clone()
+}
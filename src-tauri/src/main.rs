@@ -3,96 +3,264 @@
     windows_subsystem = "windows"
 )]
 
-use keyring::Entry;
-use serde::{Deserialize, Serialize};
+mod appinfo;
+mod autostart;
+mod cli;
+mod config;
+mod crash;
+mod deeplink;
+mod diagnostics;
+mod hotkey;
+mod instance;
+mod keychain;
+mod links;
+mod logging;
+mod network;
+mod notifications;
+mod permissions;
+mod power;
+mod protection;
+mod quickcopy;
+mod startup;
+mod status;
+mod telemetry;
+mod tray;
+mod update;
+mod window_state;
 
-#[derive(Serialize, Deserialize)]
-struct KeychainResult {
-    success: bool,
-    value: Option<String>,
-    error: Option<String>,
-}
+use keychain::{
+    audit, autobackup, backup, bulk, cache, cipher, clipboard, commands, expiry, external,
+    fingerprint, generate, json, kdf, metrics, process, profiles, qr, rotation, session, strength,
+    totp, vault, wipe, KeychainManager,
+};
+use startup::{StartupFeature, StartupReport};
+use tauri::{Invoke, Manager, Runtime};
 
-/// Stocke un secret dans le keychain OS (Windows Credential Manager,
-/// macOS Keychain, Linux Secret Service).
-#[tauri::command]
-fn keychain_set(service: String, key: String, value: String) -> KeychainResult {
-    match Entry::new(&service, &key) {
-        Ok(entry) => match entry.set_password(&value) {
-            Ok(()) => KeychainResult {
-                success: true,
-                value: None,
-                error: None,
-            },
-            Err(e) => KeychainResult {
-                success: false,
-                value: None,
-                error: Some(format!("{}", e)),
-            },
-        },
-        Err(e) => KeychainResult {
-            success: false,
-            value: None,
-            error: Some(format!("{}", e)),
-        },
+/// Ajoute au gestionnaire d'appels les commandes de developpement, absentes
+/// des versions publiees.
+#[cfg(debug_assertions)]
+fn with_debug_commands<R: Runtime>(
+    handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+    fn typed<R: Runtime>(
+        handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+    ) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+        handler
     }
-}
-
-/// Recupere un secret depuis le keychain OS.
-#[tauri::command]
-fn keychain_get(service: String, key: String) -> KeychainResult {
-    match Entry::new(&service, &key) {
-        Ok(entry) => match entry.get_password() {
-            Ok(password) => KeychainResult {
-                success: true,
-                value: Some(password),
-                error: None,
-            },
-            Err(e) => KeychainResult {
-                success: false,
-                value: None,
-                error: Some(format!("{}", e)),
-            },
-        },
-        Err(e) => KeychainResult {
-            success: false,
-            value: None,
-            error: Some(format!("{}", e)),
-        },
+    let debug = typed(tauri::generate_handler![
+        crash::debug_trigger_panic,
+        session::debug_simulate_suspend
+    ]);
+    move |invoke: Invoke<R>| match invoke.message.command() {
+        "debug_trigger_panic" | "debug_simulate_suspend" => debug(invoke),
+        _ => handler(invoke),
     }
 }
 
-/// Supprime un secret du keychain OS.
-#[tauri::command]
-fn keychain_delete(service: String, key: String) -> KeychainResult {
-    match Entry::new(&service, &key) {
-        Ok(entry) => match entry.delete_credential() {
-            Ok(()) => KeychainResult {
-                success: true,
-                value: None,
-                error: None,
-            },
-            Err(e) => KeychainResult {
-                success: false,
-                value: None,
-                error: Some(format!("{}", e)),
-            },
-        },
-        Err(e) => KeychainResult {
-            success: false,
-            value: None,
-            error: Some(format!("{}", e)),
-        },
-    }
+#[cfg(not(debug_assertions))]
+fn with_debug_commands<R: Runtime>(
+    handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+    handler
 }
 
 fn main() {
-    tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![
-            keychain_set,
-            keychain_get,
-            keychain_delete
-        ])
-        .run(tauri::generate_context!())
-        .expect("Erreur lors du demarrage d'ARGOS");
+    let args = cli::from_env();
+    let context = tauri::generate_context!();
+    crash::install(
+        tauri::api::path::app_data_dir(context.config()),
+        context.package_info().version.to_string(),
+    );
+    let logging = logging::init(tauri::api::path::app_data_dir(context.config()));
+    // Sans dossier de donnees ni verrou, ARGOS demarre quand meme.
+    let instance = match tauri::api::path::app_local_data_dir(context.config()) {
+        Some(dir) => match instance::acquire(&dir) {
+            Ok(Some(instance)) => Some(instance),
+            Ok(None) => return,
+            Err(e) => {
+                eprintln!("instance unique non garantie : {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+    deeplink::install();
+    deeplink::register_scheme();
+    deeplink::receive_args(std::env::args().skip(1));
+    let report = StartupReport::default();
+    let config = config::load(&args, &report);
+    let protect_secrets_window = config.protect_secrets_window.unwrap_or(true);
+    let close_to_tray = config.close_to_tray;
+    let lock_hotkey = config.lock_hotkey.clone();
+    let settings = config::Config::defaults(&config);
+    let profile = config.profile.clone();
+    let telemetry_endpoint = config.telemetry_endpoint.clone();
+    let network = network::NetworkMonitor::new(config.network_probe_url.clone());
+    let permissions = permissions::WindowPermissions::new(config.window_permissions.clone());
+    let app = tauri::Builder::default()
+        .manage(logging)
+        .manage(network)
+        .manage(report)
+        .on_page_load(move |window, _| {
+            if protect_secrets_window && window.label() == protection::SECRETS_WINDOW {
+                let _ = protection::set_content_protection(&window, true);
+            }
+        })
+        .on_system_tray_event(tray::on_tray_event)
+        .on_window_event(move |event| {
+            session::on_window_event(&event);
+            window_state::on_window_event(&event);
+            tray::hide_on_close(&event, close_to_tray);
+        })
+        .setup(move |app| {
+            if let Some(instance) = instance {
+                instance.listen(app.handle());
+            }
+            window_state::restore_startup(app, !args.minimized);
+            crash::report_previous(app);
+            let report = app.state::<StartupReport>();
+            if let Err(e) = tray::install(app) {
+                report.record(StartupFeature::Tray, e);
+            }
+            let manager = KeychainManager::open(app, config);
+            if let Some(reason) = manager.fallback_reason() {
+                report.record(StartupFeature::Keychain, reason);
+            }
+            appinfo::set_backend(&manager);
+            manager.spawn_startup_migrations();
+            manager.spawn_session_watcher();
+            power::install(&app.handle());
+            if args.locked {
+                manager.lock_session(session::LockReason::Startup);
+            }
+            telemetry::init(app, telemetry_endpoint);
+            app.manage(config::ConfigStore::load(
+                app,
+                settings,
+                profile.as_deref(),
+                &manager,
+                &report,
+            ));
+            app.manage(manager);
+            app.manage(autobackup::BackupScheduler::load(app));
+            autobackup::spawn_scheduler(&app.handle());
+            expiry::spawn_scanner(&app.handle());
+            deeplink::attach(&app.handle());
+            app.manage(status::StatusManager::default());
+            status::watch(&app.handle());
+            tray::refresh_status(&app.handle());
+            app.manage(quickcopy::QuickCopy::default());
+            quickcopy::watch_session(&app.handle());
+            app.manage(hotkey::Hotkey::default());
+            if let Err(e) = hotkey::register_startup(&app.handle(), lock_hotkey) {
+                report.record(StartupFeature::Hotkey, e);
+            }
+            if let Err(e) = autostart::check() {
+                report.record(StartupFeature::Autostart, e);
+            }
+            app.manage(update::Updater::default());
+            network::spawn_watcher(&app.handle());
+            telemetry::spawn_flusher(&app.handle());
+            Ok(())
+        })
+        .invoke_handler(
+            permissions.guard(with_debug_commands(tauri::generate_handler![
+                commands::keychain_unlock,
+                commands::keychain_set,
+                json::keychain_set_json,
+                json::keychain_get_field,
+                json::keychain_set_field,
+                commands::keychain_get,
+                commands::keychain_get_metadata,
+                commands::keychain_get_or_set,
+                commands::keychain_delete,
+                commands::keychain_rename,
+                commands::keychain_history,
+                commands::keychain_rollback,
+                commands::keychain_exists,
+                commands::keychain_target_name,
+                commands::keychain_list,
+                commands::keychain_clear_service,
+                commands::keychain_migrate_service,
+                backup::keychain_export,
+                backup::keychain_import,
+                external::keychain_import_external,
+                autobackup::backup_run_now,
+                autobackup::backup_last_status,
+                bulk::bulk_cancel,
+                commands::keychain_set_bytes,
+                commands::keychain_get_bytes,
+                fingerprint::keychain_fingerprint,
+                fingerprint::keychain_find_duplicates,
+                commands::keychain_get_many,
+                commands::keychain_set_many,
+                commands::keychain_configure,
+                commands::keychain_status,
+                vault::vault_unlock,
+                vault::vault_lock,
+                audit::audit_read,
+                metrics::metrics_snapshot,
+                metrics::metrics_reset,
+                audit::audit_clear,
+                audit::audit_export,
+                totp::totp_generate,
+                generate::secret_generate,
+                rotation::secret_rotate,
+                rotation::secret_rotate_confirm,
+                rotation::secret_rotate_abort,
+                strength::secret_strength,
+                kdf::kdf_derive,
+                kdf::kdf_generate_salt,
+                cipher::keychain_set_encryption_key,
+                cipher::keychain_encrypt,
+                cipher::keychain_decrypt,
+                clipboard::clipboard_copy_secret,
+                process::process_run_with_secret,
+                qr::secret_to_qr,
+                protection::window_set_content_protection,
+                quickcopy::quickcopy_open,
+                quickcopy::quickcopy_pinned,
+                quickcopy::quickcopy_close,
+                session::session_unlock,
+                session::session_lock,
+                session::session_touch,
+                cache::keychain_cache_stats,
+                hotkey::hotkey_set,
+                hotkey::hotkey_status,
+                deeplink::deep_link_take,
+                update::check_for_update,
+                update::download_and_install_update,
+                update::update_status,
+                update::update_restart,
+                network::network_status,
+                telemetry::telemetry_set_consent,
+                telemetry::telemetry_preview,
+                config::config_get,
+                config::config_set,
+                config::config_reset,
+                config::set_locale,
+                autostart::autostart_get,
+                autostart::autostart_set,
+                profiles::profile_create,
+                profiles::profile_list,
+                profiles::profile_delete,
+                wipe::wipe_all_data,
+                profiles::profile_set_active,
+                logging::log_set_level,
+                logging::log_get_path,
+                diagnostics::diagnostics_collect,
+                notifications::notify,
+                links::open_external,
+                startup::startup_report,
+                status::status_get,
+                appinfo::app_info,
+                commands::keychain_set_v1,
+                commands::keychain_get_v1,
+                commands::keychain_delete_v1,
+                commands::keychain_exists_v1
+            ])),
+        )
+        .build(context)
+        .unwrap_or_else(|e| startup::fatal(e));
+    app.run(|app, event| update::on_run_event(app, &event));
 }
@@ -0,0 +1,240 @@
+//! Etat du reseau, pour que la mise a jour et la synchronisation echouent
+//! vite hors ligne au lieu d'attendre le delai de leurs requetes. Le
+//! systeme dit d'abord si une interface est connectee ; une requete HEAD
+//! vers `network_probe_url` confirme ensuite l'acces et repere un portail
+//! captif, qui repond par une redirection ou un 511.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::keychain::{KeychainError, KeychainErrorCode};
+
+pub(crate) const NETWORK_CHANGED: &str = "argos://network-changed";
+
+/// Sonde par defaut, remplacee par `network_probe_url` dans `argos.json` ;
+/// une chaine vide n'interroge que le systeme.
+pub(crate) const DEFAULT_PROBE_URL: &str = "http://detectportal.firefox.com/success.txt";
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Verifications en ligne, puis espacement croissant hors ligne.
+const ONLINE_INTERVAL: Duration = Duration::from_secs(60);
+const OFFLINE_INITIAL_INTERVAL: Duration = Duration::from_secs(10);
+const OFFLINE_MAX_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Serialize, Clone, PartialEq, Eq)]
+pub(crate) struct NetworkStatus {
+    online: bool,
+    captive_portal_suspected: bool,
+    checked_at: String,
+}
+
+impl NetworkStatus {
+    pub(crate) fn is_online(&self) -> bool {
+        self.online
+    }
+
+    fn same_state(&self, other: &NetworkStatus) -> bool {
+        self.online == other.online
+            && self.captive_portal_suspected == other.captive_portal_suspected
+    }
+}
+
+/// Ce que le systeme sait des interfaces.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Link {
+    Down,
+    Up,
+    /// Acces limite signale par le systeme, typique d'un portail captif.
+    Constrained,
+}
+
+/// Niveau de connectivite du profil Internet de Windows.
+#[cfg(windows)]
+fn link() -> Link {
+    use windows::Networking::Connectivity::{NetworkConnectivityLevel, NetworkInformation};
+
+    let Ok(profile) = NetworkInformation::GetInternetConnectionProfile() else {
+        return Link::Down;
+    };
+    match profile.GetNetworkConnectivityLevel() {
+        Ok(NetworkConnectivityLevel::ConstrainedInternetAccess) => Link::Constrained,
+        // Un reseau local sans Internet suffit a un poste isole : la sonde
+        // tranche.
+        Ok(NetworkConnectivityLevel::InternetAccess | NetworkConnectivityLevel::LocalAccess) => {
+            Link::Up
+        }
+        _ => Link::Down,
+    }
+}
+
+/// Une interface autre que la boucle locale est active.
+#[cfg(target_os = "linux")]
+fn link() -> Link {
+    let Ok(entries) = std::fs::read_dir("/sys/class/net") else {
+        return Link::Up;
+    };
+    let up = entries.flatten().any(|entry| {
+        entry.file_name() != "lo"
+            && std::fs::read_to_string(entry.path().join("operstate"))
+                .is_ok_and(|state| matches!(state.trim(), "up" | "unknown"))
+    });
+    if up {
+        Link::Up
+    } else {
+        Link::Down
+    }
+}
+
+/// Une route existe vers une adresse publique : `connect` sur un socket UDP
+/// consulte la table de routage sans rien envoyer.
+#[cfg(not(any(windows, target_os = "linux")))]
+fn link() -> Link {
+    use std::net::UdpSocket;
+
+    let routed = |bind: &str, target: &str| {
+        UdpSocket::bind(bind).is_ok_and(|socket| socket.connect(target).is_ok())
+    };
+    if routed("0.0.0.0:0", "192.0.2.1:9") || routed("[::]:0", "[2001:db8::1]:9") {
+        Link::Up
+    } else {
+        Link::Down
+    }
+}
+
+/// Sonde et dernier etat connu, enregistres dans l'etat Tauri.
+pub(crate) struct NetworkMonitor {
+    probe_url: Option<String>,
+    client: Option<reqwest::Client>,
+    last: Mutex<Option<NetworkStatus>>,
+}
+
+impl NetworkMonitor {
+    /// `probe_url` vide : aucune requete, seul le systeme est consulte.
+    pub(crate) fn new(probe_url: Option<String>) -> Self {
+        let probe_url = match probe_url {
+            Some(url) if url.trim().is_empty() => None,
+            Some(url) => Some(url),
+            None => Some(DEFAULT_PROBE_URL.to_string()),
+        };
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .connect_timeout(PROBE_TIMEOUT)
+            .timeout(PROBE_TIMEOUT)
+            .build()
+            .map_err(|e| tracing::warn!(error = %e, "sonde reseau indisponible"))
+            .ok();
+        NetworkMonitor {
+            probe_url,
+            client,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Dernier etat mesure, `None` avant la premiere verification.
+    pub(crate) fn last(&self) -> Option<NetworkStatus> {
+        self.last.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// `Some(portail suspecte)` si la sonde a repondu, `None` sinon.
+    async fn probe(&self) -> Option<bool> {
+        let (Some(url), Some(client)) = (&self.probe_url, &self.client) else {
+            return Some(false);
+        };
+        match client.head(url).send().await {
+            Ok(response) => {
+                let status = response.status();
+                Some(
+                    status.is_redirection()
+                        || status == reqwest::StatusCode::NETWORK_AUTHENTICATION_REQUIRED,
+                )
+            }
+            Err(e) => {
+                tracing::debug!(error = %e, "sonde reseau sans reponse");
+                None
+            }
+        }
+    }
+
+    /// Mesure l'etat du reseau ; la sonde n'est interrogee que si une
+    /// interface est active. Emet `argos://network-changed` si l'etat a
+    /// change depuis la mesure precedente.
+    async fn check(&self, app: &AppHandle) -> NetworkStatus {
+        let link = tauri::async_runtime::spawn_blocking(link)
+            .await
+            .unwrap_or(Link::Up);
+        let (online, captive_portal_suspected) = match link {
+            Link::Down => (false, false),
+            Link::Up | Link::Constrained => match self.probe().await {
+                Some(captive) => (true, captive || link == Link::Constrained),
+                None => (false, link == Link::Constrained),
+            },
+        };
+        let status = NetworkStatus {
+            online,
+            captive_portal_suspected,
+            checked_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        };
+        let previous = self
+            .last
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .replace(status.clone());
+        if previous.is_some_and(|previous| !previous.same_state(&status)) {
+            tracing::info!(online, captive_portal_suspected, "etat du reseau change");
+            let _ = app.emit_all(NETWORK_CHANGED, status.clone());
+        }
+        status
+    }
+}
+
+/// Surveille le reseau pendant toute la vie de l'application : une mesure
+/// par `ONLINE_INTERVAL` en ligne, puis des mesures de plus en plus
+/// espacees tant que le reseau reste coupe.
+pub(crate) fn spawn_watcher(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let mut offline_interval = OFFLINE_INITIAL_INTERVAL;
+        loop {
+            let Some(monitor) = app.try_state::<NetworkMonitor>() else {
+                return;
+            };
+            let delay = if monitor.check(&app).await.online {
+                offline_interval = OFFLINE_INITIAL_INTERVAL;
+                ONLINE_INTERVAL
+            } else {
+                let delay = offline_interval;
+                offline_interval = (offline_interval * 2).min(OFFLINE_MAX_INTERVAL);
+                delay
+            };
+            tokio::time::sleep(delay).await;
+        }
+    });
+}
+
+/// Erreur immediate d'une requete reseau quand le reseau est connu coupe ;
+/// rien avant la premiere mesure.
+pub(crate) fn require_online(app: &AppHandle) -> Result<(), KeychainError> {
+    let offline = app
+        .try_state::<NetworkMonitor>()
+        .and_then(|monitor| monitor.last())
+        .is_some_and(|status| !status.is_online());
+    if offline {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::Offline,
+            "aucune connexion reseau",
+        ));
+    }
+    Ok(())
+}
+
+/// Mesure immediate de l'etat du reseau, en quelques secondes au plus.
+#[tauri::command]
+pub(crate) async fn network_status(
+    app: AppHandle,
+    monitor: State<'_, NetworkMonitor>,
+) -> Result<NetworkStatus, KeychainError> {
+    Ok(monitor.check(&app).await)
+}
@@ -0,0 +1,126 @@
+//! Notifications natives du systeme, envoyees par le frontend avec `notify`
+//! ou par les taches de fond. Un clic sur une notification de Rust ramene la
+//! fenetre principale et indique au frontend quelle page ouvrir.
+//!
+//! Le texte ne nomme que des services et des cles : aucune valeur de secret
+//! n'est jamais passee a `send`.
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::config::ConfigStore;
+use crate::instance::{self, MAIN_WINDOW};
+use crate::keychain::{KeychainError, KeychainErrorCode};
+
+/// Emis apres un clic sur une notification, avec sa `Target`.
+pub(crate) const NOTIFICATION_OPEN: &str = "argos://notification-open";
+
+const MAX_TITLE_CHARS: usize = 128;
+const MAX_BODY_CHARS: usize = 1024;
+
+/// Action de la notification choisie par un clic sur son corps.
+const DEFAULT_ACTION: &str = "default";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum NoticeKind {
+    #[default]
+    Info,
+    Warning,
+    Error,
+}
+
+/// Page du frontend a ouvrir, et la cle concernee s'il y en a une.
+#[derive(Serialize, Clone)]
+pub(crate) struct Target {
+    pub(crate) page: &'static str,
+    pub(crate) service: Option<String>,
+    pub(crate) key: Option<String>,
+}
+
+pub(crate) struct Notice {
+    pub(crate) title: String,
+    pub(crate) body: String,
+    pub(crate) kind: NoticeKind,
+    pub(crate) target: Option<Target>,
+}
+
+/// `false` si l'utilisateur a coupe les notifications dans ses reglages.
+fn enabled(app: &AppHandle) -> bool {
+    app.try_state::<ConfigStore>()
+        .is_none_or(|store| store.get().notifications.enabled)
+}
+
+/// Affiche `notice` depuis un thread a part : l'attente du clic bloque
+/// jusqu'a la fermeture de la notification. Un echec n'est que journalise.
+pub(crate) fn send(app: &AppHandle, notice: Notice) {
+    if !enabled(app) {
+        return;
+    }
+    let mut notification = notify_rust::Notification::new();
+    notification
+        .summary(&notice.title)
+        .body(&notice.body)
+        .appname("ARGOS")
+        .action(DEFAULT_ACTION, "Ouvrir");
+    #[cfg(not(target_os = "macos"))]
+    notification.urgency(match notice.kind {
+        NoticeKind::Info => notify_rust::Urgency::Low,
+        NoticeKind::Warning => notify_rust::Urgency::Normal,
+        NoticeKind::Error => notify_rust::Urgency::Critical,
+    });
+    #[cfg(target_os = "windows")]
+    notification.app_id(&app.config().tauri.bundle.identifier);
+    #[cfg(target_os = "macos")]
+    let _ = notify_rust::set_application(&app.config().tauri.bundle.identifier);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let handle = match notification.show() {
+            Ok(handle) => handle,
+            Err(e) => return tracing::warn!(error = %e, "notification non affichee"),
+        };
+        handle.wait_for_action(|action| {
+            if action != DEFAULT_ACTION {
+                return;
+            }
+            instance::show_main_window(&app);
+            if let (Some(target), Some(window)) = (notice.target, app.get_window(MAIN_WINDOW)) {
+                let _ = window.emit(NOTIFICATION_OPEN, target);
+            }
+        });
+    });
+}
+
+fn check_length(name: &str, text: &str, max: usize) -> Result<(), KeychainError> {
+    if text.trim().is_empty() || text.chars().count() > max {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!("{} doit contenir entre 1 et {} caracteres", name, max),
+        ));
+    }
+    Ok(())
+}
+
+/// Notification demandee par le frontend ; un clic ramene simplement la
+/// fenetre principale.
+#[tauri::command]
+pub(crate) fn notify(
+    app: AppHandle,
+    title: String,
+    body: String,
+    kind: Option<NoticeKind>,
+) -> Result<(), KeychainError> {
+    check_length("title", &title, MAX_TITLE_CHARS)?;
+    check_length("body", &body, MAX_BODY_CHARS)?;
+    send(
+        &app,
+        Notice {
+            title,
+            body,
+            kind: kind.unwrap_or_default(),
+            target: None,
+        },
+    );
+    Ok(())
+}
@@ -0,0 +1,341 @@
+//! Droits des fenetres sur les commandes d'ARGOS, d'apres leur libelle.
+//! La table vient de `window_permissions` dans `argos.config.json`, lue au
+//! demarrage : aucune commande ne la modifie. Une fenetre absente de la
+//! table, comme la fenetre principale, garde un acces complet ; la palette
+//! de copie rapide est en lecture seule par defaut.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{Invoke, Runtime};
+
+use crate::keychain::{KeychainError, KeychainErrorCode};
+use crate::quickcopy::QUICKCOPY_WINDOW;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum WindowAccess {
+    /// Commandes de `READ_COMMANDS` seulement.
+    ReadOnly,
+    Full,
+}
+
+/// Droits d'une fenetre, par exemple
+/// `{ "access": "read-only", "services": ["fr.bbrain.argos.tokens"] }`.
+#[derive(Deserialize, Clone, Debug)]
+pub(crate) struct WindowGrant {
+    access: WindowAccess,
+    /// Services accessibles, noms exacts ; absent, tous les services
+    /// autorises par ARGOS. Present, seules les commandes de `SERVICE_ARGS`
+    /// restent permises.
+    #[serde(default)]
+    services: Option<Vec<String>>,
+}
+
+/// Commandes permises en lecture seule : lire, copier ou lister, jamais
+/// ecrire, supprimer ni changer un reglage. Une commande absente, y compris
+/// une commande ajoutee plus tard, est refusee.
+const READ_COMMANDS: &[&str] = &[
+    "keychain_get",
+    "keychain_get_v1",
+    "keychain_get_field",
+    "keychain_get_metadata",
+    "keychain_fingerprint",
+    "keychain_find_duplicates",
+    "keychain_exists",
+    "keychain_exists_v1",
+    "keychain_list",
+    "keychain_status",
+    "status_get",
+    "app_info",
+    "totp_generate",
+    "clipboard_copy_secret",
+    "quickcopy_pinned",
+    "quickcopy_close",
+];
+
+/// Arguments d'une commande qui nomment les services qu'elle touche, pour
+/// les fenetres limitees a certains services.
+#[derive(Clone, Copy)]
+enum ServiceArgs {
+    /// La commande ne lit ni n'ecrit aucun secret.
+    Unrelated,
+    /// Arguments de premier niveau, en camelCase comme les envoie Tauri.
+    Fields(&'static [&'static str]),
+    /// Champ `service` de chaque element de la liste nommee.
+    Items(&'static str),
+}
+
+const SERVICE: ServiceArgs = ServiceArgs::Fields(&["service"]);
+
+/// Services touches par chaque commande. Une fenetre limitee a certains
+/// services ne peut appeler que ces commandes : les autres, comme
+/// `backup_run_now` ou `profile_delete`, touchent des services absents de
+/// leurs arguments.
+const SERVICE_ARGS: &[(&str, ServiceArgs)] = &[
+    ("keychain_status", ServiceArgs::Unrelated),
+    ("status_get", ServiceArgs::Unrelated),
+    ("app_info", ServiceArgs::Unrelated),
+    ("quickcopy_pinned", ServiceArgs::Unrelated),
+    ("quickcopy_close", ServiceArgs::Unrelated),
+    ("keychain_set", SERVICE),
+    ("keychain_set_v1", SERVICE),
+    ("keychain_set_json", SERVICE),
+    ("keychain_set_field", SERVICE),
+    ("keychain_set_bytes", SERVICE),
+    ("keychain_set_many", SERVICE),
+    ("keychain_set_encryption_key", SERVICE),
+    ("keychain_get", SERVICE),
+    ("keychain_get_v1", SERVICE),
+    ("keychain_get_field", SERVICE),
+    ("keychain_get_bytes", SERVICE),
+    ("keychain_get_many", SERVICE),
+    ("keychain_get_metadata", SERVICE),
+    ("keychain_get_or_set", SERVICE),
+    ("keychain_delete", SERVICE),
+    ("keychain_delete_v1", SERVICE),
+    ("keychain_rename", SERVICE),
+    ("keychain_history", SERVICE),
+    ("keychain_rollback", SERVICE),
+    ("keychain_exists", SERVICE),
+    ("keychain_exists_v1", SERVICE),
+    ("keychain_target_name", SERVICE),
+    ("keychain_list", SERVICE),
+    ("keychain_clear_service", SERVICE),
+    ("keychain_export", SERVICE),
+    ("keychain_import", SERVICE),
+    ("keychain_import_external", SERVICE),
+    ("keychain_fingerprint", SERVICE),
+    ("keychain_find_duplicates", SERVICE),
+    ("keychain_encrypt", SERVICE),
+    ("keychain_decrypt", SERVICE),
+    ("totp_generate", SERVICE),
+    ("secret_rotate", SERVICE),
+    ("secret_rotate_confirm", SERVICE),
+    ("secret_rotate_abort", SERVICE),
+    ("secret_to_qr", SERVICE),
+    ("clipboard_copy_secret", SERVICE),
+    (
+        "keychain_migrate_service",
+        ServiceArgs::Fields(&["oldService", "newService"]),
+    ),
+    ("process_run_with_secret", ServiceArgs::Items("env")),
+    ("quickcopy_open", ServiceArgs::Items("pinnedKeys")),
+];
+
+/// Services nommes par `payload` pour `command` ; `None` si la commande
+/// n'est pas dans `SERVICE_ARGS` ou si un service manque ou n'est pas une
+/// chaine.
+fn named_services<'a>(command: &str, payload: &'a Value) -> Option<Vec<&'a Value>> {
+    let (_, args) = SERVICE_ARGS.iter().find(|(name, _)| *name == command)?;
+    match *args {
+        ServiceArgs::Unrelated => Some(Vec::new()),
+        ServiceArgs::Fields(fields) => fields.iter().map(|field| payload.get(field)).collect(),
+        ServiceArgs::Items(list) => payload
+            .get(list)?
+            .as_array()?
+            .iter()
+            .map(|item| item.get("service"))
+            .collect(),
+    }
+}
+
+fn denied(detail: String) -> KeychainError {
+    KeychainError::rejected(KeychainErrorCode::PermissionDenied, detail)
+}
+
+/// Table des droits, partagee avec le gestionnaire d'appels.
+#[derive(Clone, Default)]
+pub(crate) struct WindowPermissions(Arc<HashMap<String, WindowGrant>>);
+
+impl WindowPermissions {
+    pub(crate) fn new(mut grants: HashMap<String, WindowGrant>) -> Self {
+        grants
+            .entry(QUICKCOPY_WINDOW.to_string())
+            .or_insert(WindowGrant {
+                access: WindowAccess::ReadOnly,
+                services: None,
+            });
+        WindowPermissions(Arc::new(grants))
+    }
+
+    /// Verifie `command` pour la fenetre `label`, avec les arguments de
+    /// l'appel pour les services vises.
+    pub(crate) fn check(
+        &self,
+        label: &str,
+        command: &str,
+        payload: &Value,
+    ) -> Result<(), KeychainError> {
+        let Some(grant) = self.0.get(label) else {
+            return Ok(());
+        };
+        if grant.access == WindowAccess::ReadOnly && !READ_COMMANDS.contains(&command) {
+            return Err(denied(format!(
+                "la fenetre {} est en lecture seule : {} refuse",
+                label, command
+            )));
+        }
+        let Some(services) = &grant.services else {
+            return Ok(());
+        };
+        let Some(named) = named_services(command, payload) else {
+            return Err(denied(format!(
+                "la fenetre {} est limitee a certains services : {} refuse",
+                label, command
+            )));
+        };
+        for service in named {
+            match service {
+                Value::String(service) if services.contains(service) => {}
+                Value::String(service) => {
+                    return Err(denied(format!(
+                        "la fenetre {} n'a pas acces au service {}",
+                        label, service
+                    )))
+                }
+                _ => {
+                    return Err(denied(format!(
+                        "service illisible pour la fenetre {}",
+                        label
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Enveloppe le gestionnaire d'appels genere par Tauri : un appel hors
+    /// des droits de sa fenetre est rejete avec `PermissionDenied` avant
+    /// d'atteindre la commande.
+    pub(crate) fn guard<R: Runtime>(
+        self,
+        handler: impl Fn(Invoke<R>) + Send + Sync + 'static,
+    ) -> impl Fn(Invoke<R>) + Send + Sync + 'static {
+        move |invoke: Invoke<R>| {
+            let label = invoke.message.window_ref().label().to_string();
+            let command = invoke.message.command().to_string();
+            if let Err(e) = self.check(&label, &command, invoke.message.payload()) {
+                tracing::warn!(window = %label, command = %command, "commande refusee");
+                invoke.resolver.reject(e);
+                return;
+            }
+            handler(invoke)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const TOKENS: &str = "fr.bbrain.argos.tokens";
+
+    fn permissions() -> WindowPermissions {
+        let grants = serde_json::from_value(json!({
+            "tokens": { "access": "full", "services": [TOKENS] },
+        }))
+        .unwrap();
+        WindowPermissions::new(grants)
+    }
+
+    fn code(result: Result<(), KeychainError>) -> Option<KeychainErrorCode> {
+        result.err().map(|e| e.code())
+    }
+
+    #[test]
+    fn unlisted_window_has_full_access() {
+        let permissions = permissions();
+        let payload = json!({ "service": "fr.bbrain.argos.other", "key": "k" });
+        assert!(permissions
+            .check("main", "keychain_delete", &payload)
+            .is_ok());
+        assert!(permissions
+            .check("main", "wipe_all_data", &json!({}))
+            .is_ok());
+    }
+
+    #[test]
+    fn quickcopy_is_read_only() {
+        let permissions = permissions();
+        let payload = json!({ "service": TOKENS, "key": "k" });
+        assert!(permissions
+            .check(QUICKCOPY_WINDOW, "keychain_get", &payload)
+            .is_ok());
+        for command in ["keychain_set", "keychain_delete", "config_set"] {
+            assert_eq!(
+                code(permissions.check(QUICKCOPY_WINDOW, command, &payload)),
+                Some(KeychainErrorCode::PermissionDenied),
+                "{}",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn restricted_window_checks_the_service_argument() {
+        let permissions = permissions();
+        let allowed = json!({ "service": TOKENS, "key": "k" });
+        let other = json!({ "service": "fr.bbrain.argos.other", "key": "k" });
+        assert!(permissions
+            .check("tokens", "keychain_set", &allowed)
+            .is_ok());
+        assert_eq!(
+            code(permissions.check("tokens", "keychain_set", &other)),
+            Some(KeychainErrorCode::PermissionDenied)
+        );
+        assert_eq!(
+            code(permissions.check("tokens", "keychain_get", &json!({ "key": "k" }))),
+            Some(KeychainErrorCode::PermissionDenied)
+        );
+        assert_eq!(
+            code(permissions.check("tokens", "keychain_get", &json!({ "service": 3 }))),
+            Some(KeychainErrorCode::PermissionDenied)
+        );
+    }
+
+    #[test]
+    fn restricted_window_checks_every_named_service() {
+        let permissions = permissions();
+        let migrate = json!({ "oldService": TOKENS, "newService": "fr.bbrain.argos.other" });
+        assert_eq!(
+            code(permissions.check("tokens", "keychain_migrate_service", &migrate)),
+            Some(KeychainErrorCode::PermissionDenied)
+        );
+        let run = json!({
+            "program": "/usr/bin/env",
+            "args": [],
+            "env": [
+                { "var": "A", "service": TOKENS, "key": "a" },
+                { "var": "B", "service": "fr.bbrain.argos.other", "key": "b" },
+            ],
+        });
+        assert_eq!(
+            code(permissions.check("tokens", "process_run_with_secret", &run)),
+            Some(KeychainErrorCode::PermissionDenied)
+        );
+        let pinned = json!({ "pinnedKeys": [{ "service": TOKENS, "key": "a" }] });
+        assert!(permissions
+            .check("tokens", "quickcopy_open", &pinned)
+            .is_ok());
+    }
+
+    #[test]
+    fn restricted_window_refuses_commands_without_service_arguments() {
+        let permissions = permissions();
+        for command in ["backup_run_now", "profile_delete", "wipe_all_data"] {
+            assert_eq!(
+                code(permissions.check("tokens", command, &json!({}))),
+                Some(KeychainErrorCode::PermissionDenied),
+                "{}",
+                command
+            );
+        }
+        assert!(permissions
+            .check("tokens", "status_get", &json!({}))
+            .is_ok());
+    }
+}
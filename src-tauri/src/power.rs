@@ -0,0 +1,246 @@
+//! Verrouillage sur notification du systeme : mise en veille et
+//! changement d'utilisateur. Sous Windows, une fenetre de messages sur son
+//! propre thread recoit `WM_POWERBROADCAST` et `WM_WTSSESSION_CHANGE` ; sous
+//! macOS, l'espace de travail signale la veille et la perte de la session
+//! active ; sous Linux, logind emet `PrepareForSleep` et `Lock`. Le reveil
+//! detecte par `spawn_session_watcher` reste un filet de securite.
+//!
+//! Les gestionnaires ne font que confier le verrouillage au runtime
+//! asynchrone : rien ne bloque le thread du systeme ni la boucle
+//! d'evenements de Tauri.
+
+use std::sync::OnceLock;
+
+use tauri::{AppHandle, Manager};
+
+use crate::keychain::session::LockReason;
+use crate::keychain::KeychainManager;
+
+static APP: OnceLock<AppHandle> = OnceLock::new();
+
+/// Installe les notifications du systeme, une seule fois. A appeler depuis
+/// `setup`, sur le thread principal.
+pub(crate) fn install(app: &AppHandle) {
+    if APP.set(app.clone()).is_ok() {
+        platform::install();
+    }
+}
+
+/// Verrouille pour `reason` si la bascule de la configuration le permet.
+pub(crate) fn handle(manager: &KeychainManager, reason: LockReason) {
+    tracing::info!(reason = ?reason, "evenement du systeme");
+    manager.lock_on_system_event(reason);
+}
+
+/// Appele depuis le thread qui recoit la notification.
+fn notify(reason: LockReason) {
+    let Some(app) = APP.get().cloned() else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Some(manager) = app.try_state::<KeychainManager>() {
+            handle(&manager, reason);
+        }
+    });
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::w;
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::System::RemoteDesktop::{
+        WTSRegisterSessionNotification, NOTIFY_FOR_THIS_SESSION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateWindowExW, DefWindowProcW, DispatchMessageW, GetMessageW, RegisterClassW, HMENU,
+        HWND_MESSAGE, MSG, WINDOW_EX_STYLE, WINDOW_STYLE, WM_POWERBROADCAST, WM_WTSSESSION_CHANGE,
+        WNDCLASSW,
+    };
+
+    use super::notify;
+    use crate::keychain::session::LockReason;
+
+    // Valeurs de `wParam` : `PBT_APMSUSPEND` pour `WM_POWERBROADCAST`,
+    // `WTS_CONSOLE_DISCONNECT`, `WTS_REMOTE_DISCONNECT` et
+    // `WTS_SESSION_LOCK` pour `WM_WTSSESSION_CHANGE`.
+    const PBT_APMSUSPEND: usize = 0x4;
+    const WTS_CONSOLE_DISCONNECT: usize = 0x2;
+    const WTS_REMOTE_DISCONNECT: usize = 0x4;
+    const WTS_SESSION_LOCK: usize = 0x7;
+
+    unsafe extern "system" fn window_proc(
+        hwnd: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        match (message, wparam.0) {
+            (WM_POWERBROADCAST, PBT_APMSUSPEND) => notify(LockReason::Suspend),
+            (
+                WM_WTSSESSION_CHANGE,
+                WTS_CONSOLE_DISCONNECT | WTS_REMOTE_DISCONNECT | WTS_SESSION_LOCK,
+            ) => notify(LockReason::UserSwitch),
+            _ => {}
+        }
+        DefWindowProcW(hwnd, message, wparam, lparam)
+    }
+
+    /// Fenetre de messages invisible et sa boucle, sur un thread dedie.
+    fn run() -> windows::core::Result<()> {
+        unsafe {
+            let instance = HINSTANCE::from(GetModuleHandleW(None)?);
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(window_proc),
+                hInstance: instance,
+                lpszClassName: w!("ArgosPowerEvents"),
+                ..Default::default()
+            };
+            RegisterClassW(&class);
+            let hwnd = CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                class.lpszClassName,
+                w!("ARGOS"),
+                WINDOW_STYLE::default(),
+                0,
+                0,
+                0,
+                0,
+                HWND_MESSAGE,
+                HMENU::default(),
+                instance,
+                None,
+            )?;
+            WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION)?;
+            let mut message = MSG::default();
+            while GetMessageW(&mut message, HWND::default(), 0, 0).as_bool() {
+                DispatchMessageW(&message);
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn install() {
+        let spawned = std::thread::Builder::new()
+            .name("argos-power".into())
+            .spawn(|| {
+                if let Err(e) = run() {
+                    tracing::warn!(error = %e.message(), "evenements du systeme indisponibles");
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::warn!(error = %e, "evenements du systeme indisponibles");
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use block2::RcBlock;
+    use objc2::rc::Id;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send, msg_send_id};
+    use objc2_foundation::NSString;
+
+    use super::notify;
+    use crate::keychain::session::LockReason;
+
+    /// Les notifications de `NSWorkspace` arrivent sur le thread principal,
+    /// ou `setup` les installe.
+    pub(super) fn install() {
+        let center: Id<AnyObject> = unsafe {
+            let workspace: Id<AnyObject> = msg_send_id![class!(NSWorkspace), sharedWorkspace];
+            msg_send_id![&workspace, notificationCenter]
+        };
+        for (name, reason) in [
+            ("NSWorkspaceWillSleepNotification", LockReason::Suspend),
+            (
+                "NSWorkspaceSessionDidResignActiveNotification",
+                LockReason::UserSwitch,
+            ),
+        ] {
+            let name = NSString::from_str(name);
+            let block = RcBlock::new(move |_notification: *mut AnyObject| notify(reason));
+            // Le centre de notifications retient l'observateur renvoye.
+            let _: *mut AnyObject = unsafe {
+                msg_send![
+                    &center,
+                    addObserverForName: &*name,
+                    object: std::ptr::null::<AnyObject>(),
+                    queue: std::ptr::null::<AnyObject>(),
+                    usingBlock: &*block
+                ]
+            };
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::time::Duration;
+
+    use dbus::blocking::Connection;
+    use dbus::message::MatchRule;
+
+    use super::notify;
+    use crate::keychain::session::LockReason;
+
+    const LOGIND: &str = "org.freedesktop.login1";
+    const LOGIND_PATH: &str = "/org/freedesktop/login1";
+    const MANAGER_INTERFACE: &str = "org.freedesktop.login1.Manager";
+    const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+    const CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Ecoute logind sur le bus systeme. Hors d'une session logind, seule la
+    /// mise en veille est suivie. Un changement d'utilisateur passe par
+    /// `Lock` : le bureau verrouille la session qu'il quitte.
+    fn run() -> Result<(), dbus::Error> {
+        let connection = Connection::new_system()?;
+        connection.add_match(
+            MatchRule::new_signal(MANAGER_INTERFACE, "PrepareForSleep"),
+            |(start,): (bool,), _, _| {
+                if start {
+                    notify(LockReason::Suspend);
+                }
+                true
+            },
+        )?;
+        let session: Result<(dbus::Path<'static>,), _> = connection
+            .with_proxy(LOGIND, LOGIND_PATH, CALL_TIMEOUT)
+            .method_call(MANAGER_INTERFACE, "GetSessionByPID", (std::process::id(),));
+        match session {
+            Ok((session,)) => {
+                connection.add_match(
+                    MatchRule::new_signal(SESSION_INTERFACE, "Lock").with_path(session),
+                    |(): (), _, _| {
+                        notify(LockReason::UserSwitch);
+                        true
+                    },
+                )?;
+            }
+            Err(e) => tracing::info!(error = %e, "session logind introuvable"),
+        }
+        loop {
+            connection.process(Duration::from_secs(60))?;
+        }
+    }
+
+    pub(super) fn install() {
+        let spawned = std::thread::Builder::new()
+            .name("argos-power".into())
+            .spawn(|| {
+                if let Err(e) = run() {
+                    tracing::warn!(error = %e, "evenements du systeme indisponibles");
+                }
+            });
+        if let Err(e) = spawned {
+            tracing::warn!(error = %e, "evenements du systeme indisponibles");
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+mod platform {
+    /// Seule la detection au reveil de `spawn_session_watcher` s'applique.
+    pub(super) fn install() {}
+}
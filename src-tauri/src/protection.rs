@@ -0,0 +1,106 @@
+//! Protection du contenu des fenetres contre les captures d'ecran et le
+//! partage d'ecran, pour les vues qui affichent des secrets.
+
+use tauri::{AppHandle, Manager, Window};
+
+use crate::keychain::{KeychainError, KeychainErrorCode};
+
+/// Fenetre dediee aux secrets, protegee des son chargement sauf si
+/// `argos.json` le desactive.
+pub(crate) const SECRETS_WINDOW: &str = "secrets";
+
+#[cfg(any(target_os = "windows", target_os = "macos"))]
+fn window_error(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::PlatformError,
+        format!("protection du contenu impossible : {}", detail),
+    )
+}
+
+/// Active ou retire la protection de `window`.
+pub(crate) fn set_content_protection(window: &Window, enabled: bool) -> Result<(), KeychainError> {
+    platform::set(window, enabled)
+}
+
+/// Exclut la fenetre `label` des captures et du partage d'ecran, ou la
+/// rend de nouveau visible. Sous Linux, renvoie `Unsupported`.
+#[tauri::command]
+pub(crate) fn window_set_content_protection(
+    app: AppHandle,
+    label: String,
+    enabled: bool,
+) -> Result<(), KeychainError> {
+    let window = app.get_window(&label).ok_or_else(|| {
+        KeychainError::rejected(
+            KeychainErrorCode::NotFound,
+            format!("fenetre {} introuvable", label),
+        )
+    })?;
+    set_content_protection(&window, enabled)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use tauri::Window;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SetWindowDisplayAffinity, WDA_EXCLUDEFROMCAPTURE, WDA_NONE,
+    };
+
+    use super::window_error;
+    use crate::keychain::KeychainError;
+
+    pub(super) fn set(window: &Window, enabled: bool) -> Result<(), KeychainError> {
+        let hwnd = window.hwnd().map_err(window_error)?;
+        let affinity = if enabled {
+            WDA_EXCLUDEFROMCAPTURE
+        } else {
+            WDA_NONE
+        };
+        unsafe { SetWindowDisplayAffinity(HWND(hwnd.0 as _), affinity) }
+            .map_err(|e| window_error(e.message()))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::msg_send;
+    use objc2::runtime::AnyObject;
+    use tauri::Window;
+
+    use super::window_error;
+    use crate::keychain::KeychainError;
+
+    // Valeurs de `NSWindowSharingType`.
+    const SHARING_NONE: usize = 0;
+    const SHARING_READ_ONLY: usize = 1;
+
+    /// Les commandes synchrones s'executent sur le thread principal, seul
+    /// autorise a modifier une `NSWindow`.
+    pub(super) fn set(window: &Window, enabled: bool) -> Result<(), KeychainError> {
+        let ns_window = window.ns_window().map_err(window_error)? as *mut AnyObject;
+        let sharing = if enabled {
+            SHARING_NONE
+        } else {
+            SHARING_READ_ONLY
+        };
+        let () = unsafe { msg_send![ns_window, setSharingType: sharing] };
+        Ok(())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use tauri::Window;
+
+    use crate::keychain::{KeychainError, KeychainErrorCode};
+
+    /// Ni X11 ni Wayland n'offrent de moyen d'exclure une fenetre des
+    /// captures.
+    pub(super) fn set(_window: &Window, _enabled: bool) -> Result<(), KeychainError> {
+        Err(KeychainError::rejected(
+            KeychainErrorCode::Unsupported,
+            "protection du contenu non prise en charge sous Linux",
+        ))
+    }
+}
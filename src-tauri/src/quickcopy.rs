@@ -0,0 +1,111 @@
+//! Palette de copie rapide : une petite fenetre toujours au premier plan,
+//! hors de la barre des taches, qui liste les cles epinglees. Elle copie
+//! par `clipboard_copy_secret`, en lecture seule sauf droits contraires
+//! dans `window_permissions`, et se ferme au verrouillage de la session.
+
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, WindowBuilder, WindowUrl};
+
+use crate::keychain::session::{self, SESSION_LOCKED};
+use crate::keychain::{KeychainError, KeychainErrorCode, KeychainManager};
+use crate::window_state;
+
+pub(crate) const QUICKCOPY_WINDOW: &str = "quickcopy";
+/// Recoit la liste a jour quand `quickcopy_open` vise la palette deja
+/// ouverte.
+pub(crate) const QUICKCOPY_PINNED: &str = "argos://quickcopy-pinned";
+
+const ROUTE: &str = "quickcopy";
+const WIDTH: f64 = 320.0;
+const HEIGHT: f64 = 420.0;
+const MAX_PINNED: usize = 50;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct PinnedKey {
+    service: String,
+    key: String,
+}
+
+/// Cles epinglees de la palette, enregistrees dans l'etat Tauri.
+#[derive(Default)]
+pub(crate) struct QuickCopy {
+    pinned: Mutex<Vec<PinnedKey>>,
+}
+
+fn window_error(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::PlatformError,
+        format!("palette de copie impossible : {}", detail),
+    )
+}
+
+/// Ferme la palette a chaque verrouillage de la session.
+pub(crate) fn watch_session(app: &AppHandle) {
+    let app_handle = app.clone();
+    app.listen_global(SESSION_LOCKED, move |_| {
+        if let Some(window) = app_handle.get_window(QUICKCOPY_WINDOW) {
+            let _ = window.close();
+        }
+    });
+}
+
+/// Ouvre la palette avec `pinned_keys`, ou met a jour et ramene au premier
+/// plan celle qui est deja ouverte : il n'y en a jamais deux.
+#[tauri::command]
+pub(crate) async fn quickcopy_open(
+    app: AppHandle,
+    manager: State<'_, KeychainManager>,
+    quickcopy: State<'_, QuickCopy>,
+    pinned_keys: Vec<PinnedKey>,
+) -> Result<(), KeychainError> {
+    if manager.is_session_locked() {
+        return Err(session::session_locked());
+    }
+    if pinned_keys.len() > MAX_PINNED {
+        return Err(KeychainError::rejected(
+            KeychainErrorCode::InvalidArgument,
+            format!("{} cles epinglees au plus", MAX_PINNED),
+        ));
+    }
+    for pinned in &pinned_keys {
+        manager.check_service(&pinned.service)?;
+    }
+    *quickcopy.pinned.lock().unwrap_or_else(|e| e.into_inner()) = pinned_keys.clone();
+    if let Some(window) = app.get_window(QUICKCOPY_WINDOW) {
+        let _ = window.emit(QUICKCOPY_PINNED, pinned_keys);
+        let _ = window.unminimize();
+        return window.set_focus().map_err(window_error);
+    }
+    let window = WindowBuilder::new(&app, QUICKCOPY_WINDOW, WindowUrl::App(ROUTE.into()))
+        .title("ARGOS - Copie rapide")
+        .inner_size(WIDTH, HEIGHT)
+        .min_inner_size(WIDTH / 2.0, HEIGHT / 2.0)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .visible(false)
+        .build()
+        .map_err(window_error)?;
+    window_state::restore_window(&window);
+    window.show().map_err(window_error)
+}
+
+/// Cles a afficher, lues par la palette a son chargement.
+#[tauri::command]
+pub(crate) fn quickcopy_pinned(quickcopy: State<'_, QuickCopy>) -> Vec<PinnedKey> {
+    quickcopy
+        .pinned
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone()
+}
+
+/// Ferme la palette ; sans effet si elle n'est pas ouverte.
+#[tauri::command]
+pub(crate) fn quickcopy_close(app: AppHandle) -> Result<(), KeychainError> {
+    match app.get_window(QUICKCOPY_WINDOW) {
+        Some(window) => window.close().map_err(window_error),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,134 @@
+//! Bilan du demarrage : une etape qui echoue (configuration, keychain,
+//! icone de notification, raccourci global, lancement a l'ouverture de
+//! session) desactive sa fonction sans empecher ARGOS de demarrer. Les
+//! echecs sont gardes pour le bandeau du frontend, lu par `startup_report`.
+//!
+//! Seul un echec de la construction de l'application, la creation du
+//! webview par exemple, arrete ARGOS : `fatal` l'affiche dans une boite de
+//! dialogue native, la console n'etant pas visible sous Windows.
+
+use std::fmt::Display;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::State;
+
+#[derive(Serialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum StartupFeature {
+    Config,
+    Keychain,
+    Tray,
+    Hotkey,
+    Autostart,
+}
+
+impl StartupFeature {
+    /// Phrase du bandeau.
+    fn summary(self) -> &'static str {
+        match self {
+            StartupFeature::Config => {
+                "La configuration n'a pas pu etre lue : les valeurs par defaut s'appliquent"
+            }
+            StartupFeature::Keychain => {
+                "Le keychain n'est pas disponible : les secrets sont gardes en memoire"
+            }
+            StartupFeature::Tray => "L'icone de la zone de notification n'a pas pu etre creee",
+            StartupFeature::Hotkey => "Le raccourci global n'a pas pu etre enregistre",
+            StartupFeature::Autostart => {
+                "Le lancement a l'ouverture de session n'a pas pu etre verifie"
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct StartupIssue {
+    feature: StartupFeature,
+    summary: &'static str,
+    detail: String,
+}
+
+/// Echecs du demarrage, enregistres dans l'etat Tauri.
+#[derive(Default)]
+pub(crate) struct StartupReport(Mutex<Vec<StartupIssue>>);
+
+impl StartupReport {
+    pub(crate) fn record(&self, feature: StartupFeature, detail: impl Display) {
+        let detail = detail.to_string();
+        tracing::warn!(feature = ?feature, error = %detail, "fonction desactivee au demarrage");
+        self.0
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(StartupIssue {
+                feature,
+                summary: feature.summary(),
+                detail,
+            });
+    }
+}
+
+/// Echecs du demarrage, dans l'ordre des etapes ; vide si tout a demarre.
+#[tauri::command]
+pub(crate) fn startup_report(report: State<'_, StartupReport>) -> Vec<StartupIssue> {
+    report.0.lock().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Affiche `error` dans une boite de dialogue native puis quitte. Aucune
+/// boucle d'evenements ne tourne encore : la boite ne passe pas par Tauri.
+pub(crate) fn fatal(error: impl Display) -> ! {
+    let message = format!("ARGOS n'a pas pu demarrer.\n\n{}", error);
+    tracing::error!(error = %error, "demarrage impossible");
+    eprintln!("{}", message);
+    platform::show_error(&message);
+    std::process::exit(1)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::core::{w, HSTRING};
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{MessageBoxW, MB_ICONERROR, MB_OK};
+
+    pub(super) fn show_error(message: &str) {
+        unsafe {
+            MessageBoxW(
+                HWND::default(),
+                &HSTRING::from(message),
+                w!("ARGOS"),
+                MB_OK | MB_ICONERROR,
+            );
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::process::Command;
+
+    pub(super) fn show_error(message: &str) {
+        let message = message.replace('\\', "\\\\").replace('"', "\\\"");
+        let _ = Command::new("osascript")
+            .arg("-e")
+            .arg(format!(
+                "display alert \"ARGOS\" message \"{}\" as critical",
+                message
+            ))
+            .status();
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use std::process::Command;
+
+    /// Sans `zenity`, le message reste sur la sortie d'erreur.
+    pub(super) fn show_error(message: &str) {
+        let _ = Command::new("zenity")
+            .arg("--error")
+            .arg("--title=ARGOS")
+            .arg("--no-markup")
+            .arg(format!("--text={}", message))
+            .status();
+    }
+}
@@ -0,0 +1,222 @@
+//! Etat visible hors de la fenetre : icone de la zone de notification,
+//! badge du Dock sous macOS et icone superposee de la barre des taches
+//! sous Windows. Toutes les surfaces sont mises a jour ensemble par
+//! `StatusManager`, a partir du verrouillage de la session et du nombre de
+//! secrets que l'analyse des TTL signale : elles ne peuvent pas diverger.
+//!
+//! Une surface absente du systeme est ignoree, avec un seul message au
+//! journal.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::keychain::session::{SESSION_LOCKED, SESSION_UNLOCKED};
+use crate::keychain::KeychainManager;
+use crate::tray;
+
+/// Emis a chaque changement de l'etat, avec le nouvel etat.
+pub(crate) const STATUS_CHANGED: &str = "argos://status-changed";
+
+/// Variante de l'icone : le verrouillage l'emporte sur les avertissements.
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum StatusIcon {
+    #[default]
+    Unlocked,
+    Locked,
+    Warning,
+}
+
+#[derive(Serialize, Clone, PartialEq, Eq, Default)]
+pub(crate) struct Status {
+    pub(crate) locked: bool,
+    /// Secrets qui expirent dans la fenetre d'avertissement.
+    pub(crate) warnings: usize,
+    pub(crate) icon: StatusIcon,
+}
+
+#[derive(Default)]
+pub(crate) struct StatusManager {
+    current: Mutex<Status>,
+    /// Surfaces deja signalees comme indisponibles.
+    unsupported: Mutex<HashSet<&'static str>>,
+}
+
+impl StatusManager {
+    pub(crate) fn get(&self) -> Status {
+        self.current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub(crate) fn set_locked(&self, app: &AppHandle, locked: bool) {
+        self.update(app, |status| status.locked = locked);
+    }
+
+    pub(crate) fn set_warnings(&self, app: &AppHandle, warnings: usize) {
+        self.update(app, |status| status.warnings = warnings);
+    }
+
+    /// Reaffiche l'etat sur chaque surface, par exemple apres le
+    /// remplacement du menu de l'icone.
+    pub(crate) fn refresh(&self, app: &AppHandle) {
+        let current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        self.show(app, &current);
+    }
+
+    /// Le verrou est garde pendant l'affichage : deux changements
+    /// simultanes s'affichent dans l'ordre ou ils sont appliques.
+    fn update(&self, app: &AppHandle, change: impl FnOnce(&mut Status)) {
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        let mut status = current.clone();
+        change(&mut status);
+        status.icon = match (status.locked, status.warnings) {
+            (true, _) => StatusIcon::Locked,
+            (false, 0) => StatusIcon::Unlocked,
+            (false, _) => StatusIcon::Warning,
+        };
+        if status == *current {
+            return;
+        }
+        *current = status;
+        self.show(app, &current);
+        let _ = app.emit_all(STATUS_CHANGED, &*current);
+    }
+
+    fn show(&self, app: &AppHandle, status: &Status) {
+        tray::show_status(app, status);
+        if let Err(e) = platform::show_badge(app, status) {
+            let mut unsupported = self.unsupported.lock().unwrap_or_else(|e| e.into_inner());
+            if unsupported.insert(platform::BADGE) {
+                tracing::info!(surface = platform::BADGE, error = %e, "surface d'etat ignoree");
+            }
+        }
+    }
+}
+
+/// Suit le verrouillage de la session, y compris s'il precede l'appel,
+/// par exemple avec `--locked`.
+pub(crate) fn watch(app: &AppHandle) {
+    for (event, locked) in [(SESSION_LOCKED, true), (SESSION_UNLOCKED, false)] {
+        let app_handle = app.clone();
+        app.listen_global(event, move |_| {
+            app_handle
+                .state::<StatusManager>()
+                .set_locked(&app_handle, locked)
+        });
+    }
+    let locked = app
+        .try_state::<KeychainManager>()
+        .is_some_and(|manager| manager.is_session_locked());
+    app.state::<StatusManager>().set_locked(app, locked);
+}
+
+/// Etat affiche par l'icone et le badge, pour l'en-tete du frontend.
+#[tauri::command]
+pub(crate) fn status_get(status: State<'_, StatusManager>) -> Status {
+    status.get()
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use tauri::{AppHandle, Manager};
+    use windows::core::{HSTRING, PCWSTR};
+    use windows::Win32::Foundation::{HWND, TRUE};
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::{ITaskbarList3, TaskbarList};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CreateIconFromResourceEx, DestroyIcon, HICON, LR_DEFAULTCOLOR,
+    };
+
+    use super::{Status, StatusIcon};
+    use crate::instance::MAIN_WINDOW;
+    use crate::tray::{LOCKED_ICON, WARNING_ICON};
+
+    pub(super) const BADGE: &str = "taskbar-overlay";
+
+    /// Version des ressources d'icone attendue par `CreateIconFromResourceEx`.
+    const ICON_VERSION: u32 = 0x0003_0000;
+
+    fn set_overlay(hwnd: HWND, icon: Option<(&[u8], &str)>) -> windows::core::Result<()> {
+        unsafe {
+            let taskbar: ITaskbarList3 =
+                CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)?;
+            taskbar.HrInit()?;
+            let Some((png, description)) = icon else {
+                return taskbar.SetOverlayIcon(hwnd, HICON::default(), PCWSTR::null());
+            };
+            let icon = CreateIconFromResourceEx(png, TRUE, ICON_VERSION, 0, 0, LR_DEFAULTCOLOR)?;
+            // La barre des taches garde sa propre copie de l'icone.
+            let shown = taskbar.SetOverlayIcon(hwnd, icon, &HSTRING::from(description));
+            let _ = DestroyIcon(icon);
+            shown
+        }
+    }
+
+    /// Verrou ou avertissement sur le bouton de la fenetre principale. La
+    /// barre des taches n'accepte d'appels que du thread de la fenetre.
+    pub(super) fn show_badge(app: &AppHandle, status: &Status) -> Result<(), String> {
+        let Some(window) = app.get_window(MAIN_WINDOW) else {
+            return Ok(());
+        };
+        let hwnd = window.hwnd().map_err(|e| e.to_string())?.0;
+        let icon = match status.icon {
+            StatusIcon::Unlocked => None,
+            StatusIcon::Locked => Some((LOCKED_ICON, "Session verrouillee")),
+            StatusIcon::Warning => Some((WARNING_ICON, "Secrets bientot expires")),
+        };
+        app.run_on_main_thread(move || {
+            if let Err(e) = set_overlay(HWND(hwnd as _), icon) {
+                tracing::debug!(error = %e.message(), "icone superposee non mise a jour");
+            }
+        })
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use objc2::rc::Id;
+    use objc2::runtime::AnyObject;
+    use objc2::{class, msg_send, msg_send_id};
+    use objc2_foundation::NSString;
+    use tauri::AppHandle;
+
+    use super::Status;
+
+    pub(super) const BADGE: &str = "dock-badge";
+
+    /// Nombre d'avertissements sur l'icone du Dock, sans badge a zero. Le
+    /// Dock ne se modifie que depuis le thread principal.
+    pub(super) fn show_badge(app: &AppHandle, status: &Status) -> Result<(), String> {
+        let warnings = status.warnings;
+        app.run_on_main_thread(move || unsafe {
+            let application: Id<AnyObject> = msg_send_id![class!(NSApplication), sharedApplication];
+            let tile: Id<AnyObject> = msg_send_id![&application, dockTile];
+            let label = (warnings > 0).then(|| NSString::from_str(&warnings.to_string()));
+            let label: *const NSString = label
+                .as_deref()
+                .map_or(std::ptr::null(), |label| label as *const NSString);
+            let () = msg_send![&tile, setBadgeLabel: label];
+        })
+        .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+mod platform {
+    use tauri::AppHandle;
+
+    use super::Status;
+
+    pub(super) const BADGE: &str = "launcher-badge";
+
+    /// Aucun badge commun aux bureaux Linux : seule l'icone change.
+    pub(super) fn show_badge(_app: &AppHandle, _status: &Status) -> Result<(), String> {
+        Err("badge non pris en charge sous Linux".to_string())
+    }
+}
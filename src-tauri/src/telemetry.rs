@@ -0,0 +1,267 @@
+//! Statistiques d'usage anonymes, seulement avec le consentement de
+//! l'utilisateur (`telemetry.enabled`, faux par defaut). Seuls des compteurs
+//! par nom de commande sont tenus, avec la version et le systeme : jamais un
+//! nom de service, de cle ni une valeur. Sans consentement, chaque mesure
+//! se limite a la lecture d'un booleen et rien n'est conserve.
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{App, AppHandle, Manager, State};
+
+use crate::config::{Config, ConfigStore};
+use crate::keychain::{KeychainError, KeychainManager};
+use crate::network::NetworkMonitor;
+
+const QUEUE_FILE: &str = "telemetry-queue.json";
+/// Ecriture de la file sur disque et recherche d'un envoi du.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// Un envoi par jour au plus.
+const SEND_INTERVAL: chrono::Duration = chrono::Duration::hours(24);
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+/// Commandes distinctes comptees ; au-dela, les mesures sont ignorees.
+const MAX_OPERATIONS: usize = 128;
+const MAX_OPERATION_CHARS: usize = 64;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static QUEUE: Mutex<Queue> = Mutex::new(Queue::new());
+static DIRTY: AtomicBool = AtomicBool::new(false);
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+struct Settings {
+    path: Option<PathBuf>,
+    /// Adresse d'envoi ; `None`, les compteurs ne quittent pas le poste.
+    endpoint: Option<String>,
+    version: String,
+}
+
+/// Compteurs en attente d'envoi, conserves dans `telemetry-queue.json`.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Queue {
+    since: Option<String>,
+    last_sent: Option<String>,
+    counts: BTreeMap<String, u64>,
+}
+
+impl Queue {
+    const fn new() -> Self {
+        Queue {
+            since: None,
+            last_sent: None,
+            counts: BTreeMap::new(),
+        }
+    }
+
+    /// Le dernier envoi date d'au moins `SEND_INTERVAL`.
+    fn due(&self) -> bool {
+        self.last_sent
+            .as_deref()
+            .and_then(|sent| chrono::DateTime::parse_from_rfc3339(sent).ok())
+            .is_none_or(|sent| chrono::Utc::now().signed_duration_since(sent) >= SEND_INTERVAL)
+    }
+}
+
+/// Exactement ce qu'un envoi transmet, renvoye tel quel par
+/// `telemetry_preview`.
+#[derive(Serialize, Clone)]
+pub(crate) struct TelemetryPayload {
+    version: String,
+    os: &'static str,
+    arch: &'static str,
+    since: Option<String>,
+    counts: BTreeMap<String, u64>,
+}
+
+fn now() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+}
+
+fn queue() -> std::sync::MutexGuard<'static, Queue> {
+    QUEUE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Lit la file laissee par la session precedente ; a appeler avant que la
+/// configuration ne fixe le consentement.
+pub(crate) fn init(app: &App, endpoint: Option<String>) {
+    let path = app
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(QUEUE_FILE));
+    if let Some(Ok(data)) = path.as_ref().map(std::fs::read) {
+        match serde_json::from_slice(&data) {
+            Ok(saved) => *queue() = saved,
+            Err(e) => tracing::warn!(error = %e, "file de statistiques illisible, ignoree"),
+        }
+    }
+    let _ = SETTINGS.set(Settings {
+        path,
+        endpoint,
+        version: app.package_info().version.to_string(),
+    });
+}
+
+/// Applique le consentement. Sans lui, les compteurs et leur fichier sont
+/// effaces.
+pub(crate) fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+    let mut queue = queue();
+    if enabled {
+        queue.since.get_or_insert_with(now);
+        return;
+    }
+    *queue = Queue::new();
+    DIRTY.store(false, Ordering::Relaxed);
+    if let Some(path) = SETTINGS.get().and_then(|settings| settings.path.as_ref()) {
+        if let Err(e) = std::fs::remove_file(path) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(error = %e, "file de statistiques non supprimee");
+            }
+        }
+    }
+}
+
+/// Compte un appel de `operation`, un nom de commande d'ARGOS.
+pub(crate) fn record(operation: &str) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    count(operation);
+}
+
+#[cold]
+fn count(operation: &str) {
+    // Un nom de commande, jamais une donnee de l'utilisateur.
+    if operation.is_empty()
+        || operation.len() > MAX_OPERATION_CHARS
+        || !operation
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+    {
+        return;
+    }
+    let mut queue = queue();
+    if let Some(count) = queue.counts.get_mut(operation) {
+        *count += 1;
+    } else if queue.counts.len() < MAX_OPERATIONS {
+        queue.counts.insert(operation.to_string(), 1);
+    } else {
+        return;
+    }
+    DIRTY.store(true, Ordering::Relaxed);
+}
+
+fn payload(queue: &Queue) -> TelemetryPayload {
+    TelemetryPayload {
+        version: SETTINGS
+            .get()
+            .map(|settings| settings.version.clone())
+            .unwrap_or_default(),
+        os: std::env::consts::OS,
+        arch: std::env::consts::ARCH,
+        since: queue.since.clone(),
+        counts: queue.counts.clone(),
+    }
+}
+
+/// Ecrit la file si elle a change depuis la derniere ecriture.
+fn save() {
+    if !DIRTY.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    let Some(path) = SETTINGS.get().and_then(|settings| settings.path.as_ref()) else {
+        return;
+    };
+    let data = match serde_json::to_vec(&*queue()) {
+        Ok(data) => data,
+        Err(e) => return tracing::warn!(error = %e, "file de statistiques non serialisee"),
+    };
+    if let Err(e) = crate::config::write_atomic(path, &data) {
+        DIRTY.store(true, Ordering::Relaxed);
+        tracing::warn!(error = %e, "file de statistiques non enregistree");
+    }
+}
+
+/// Envoie les compteurs a `endpoint` puis retire de la file ce qui a ete
+/// transmis ; les mesures prises pendant l'envoi restent pour le suivant.
+async fn send(client: &reqwest::Client, endpoint: &str) {
+    let sent = payload(&queue());
+    if sent.counts.is_empty() {
+        return;
+    }
+    match client.post(endpoint).json(&sent).send().await {
+        Ok(response) if response.status().is_success() => {
+            let mut queue = queue();
+            for (operation, count) in &sent.counts {
+                if let Some(current) = queue.counts.get_mut(operation) {
+                    *current = current.saturating_sub(*count);
+                }
+            }
+            queue.counts.retain(|_, count| *count > 0);
+            queue.since = Some(now());
+            queue.last_sent = queue.since.clone();
+            DIRTY.store(true, Ordering::Relaxed);
+            tracing::info!(
+                operations = sent.counts.len(),
+                "statistiques d'usage envoyees"
+            );
+        }
+        Ok(response) => {
+            tracing::warn!(status = %response.status(), "statistiques d'usage refusees")
+        }
+        Err(e) => tracing::warn!(error = %e, "statistiques d'usage non envoyees"),
+    }
+}
+
+/// Ecrit la file une fois par `FLUSH_INTERVAL` et envoie les compteurs une
+/// fois par jour au plus, avec le consentement et le reseau connu en ligne.
+pub(crate) fn spawn_flusher(app: &AppHandle) {
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let client = reqwest::Client::builder().timeout(SEND_TIMEOUT).build();
+        loop {
+            tokio::time::sleep(FLUSH_INTERVAL).await;
+            if !ENABLED.load(Ordering::Relaxed) {
+                continue;
+            }
+            let endpoint = SETTINGS
+                .get()
+                .and_then(|settings| settings.endpoint.as_deref());
+            let online = app
+                .try_state::<NetworkMonitor>()
+                .and_then(|monitor| monitor.last())
+                .is_some_and(|status| status.is_online());
+            if let (Some(endpoint), Ok(client), true) = (endpoint, &client, online) {
+                if queue().due() {
+                    send(client, endpoint).await;
+                }
+            }
+            save();
+        }
+    });
+}
+
+/// Donne ou retire le consentement aux statistiques d'usage ; un retrait
+/// efface les compteurs en attente.
+#[tauri::command]
+pub(crate) fn telemetry_set_consent(
+    app: AppHandle,
+    store: State<'_, ConfigStore>,
+    manager: State<'_, KeychainManager>,
+    enabled: bool,
+) -> Result<Config, KeychainError> {
+    store.update(&app, &manager, |config| {
+        config.telemetry.enabled = enabled;
+        Ok(())
+    })
+}
+
+/// Contenu exact du prochain envoi, tel qu'il partirait maintenant.
+#[tauri::command]
+pub(crate) fn telemetry_preview() -> TelemetryPayload {
+    payload(&queue())
+}
@@ -0,0 +1,146 @@
+//! Icone de la zone de notification : ARGOS reste actif fenetre fermee, et
+//! son menu donne acces au verrouillage de la session et a l'etat du
+//! keychain.
+
+use tauri::{
+    App, AppHandle, CustomMenuItem, GlobalWindowEvent, Icon, Manager, SystemTray, SystemTrayEvent,
+    SystemTrayHandle, SystemTrayMenu, SystemTrayMenuItem, WindowEvent,
+};
+
+use crate::instance::{self, MAIN_WINDOW};
+use crate::keychain::commands;
+use crate::keychain::session;
+use crate::keychain::KeychainManager;
+use crate::status::{Status, StatusIcon, StatusManager};
+
+const OPEN: &str = "open";
+const LOCK: &str = "lock";
+const STATUS: &str = "status";
+const QUIT: &str = "quit";
+const RESTART: &str = "restart";
+
+const TRAY_ID: &str = "main";
+
+/// L'icone deverrouillee est celle de `systemTray` dans `tauri.conf.json`.
+const UNLOCKED_ICON: &[u8] = include_bytes!("../icons/tray.png");
+pub(crate) const LOCKED_ICON: &[u8] = include_bytes!("../icons/tray-locked.png");
+/// Secrets bientot expires, session deverrouillee.
+pub(crate) const WARNING_ICON: &[u8] = include_bytes!("../icons/tray-warning.png");
+
+/// Cree l'icone depuis `setup`. En cas d'echec, ARGOS demarre sans icone
+/// et les mises a jour de l'icone ne font rien.
+pub(crate) fn install(app: &App) -> tauri::Result<()> {
+    SystemTray::new()
+        .with_id(TRAY_ID)
+        .with_menu(menu(false))
+        .with_tooltip("ARGOS")
+        .build(app)
+        .map(drop)
+}
+
+fn tray(app: &AppHandle) -> Option<SystemTrayHandle> {
+    app.tray_handle_by_id(TRAY_ID)
+}
+
+/// Le redemarrage n'apparait qu'une fois une mise a jour installee.
+fn menu(restart_pending: bool) -> SystemTrayMenu {
+    let mut menu = SystemTrayMenu::new();
+    if restart_pending {
+        menu = menu
+            .add_item(CustomMenuItem::new(
+                RESTART,
+                "Redemarrer pour mettre a jour",
+            ))
+            .add_native_item(SystemTrayMenuItem::Separator);
+    }
+    menu.add_item(CustomMenuItem::new(OPEN, "Ouvrir ARGOS"))
+        .add_item(CustomMenuItem::new(LOCK, "Verrouiller la session"))
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(STATUS, "Keychain : verification...").disabled())
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new(QUIT, "Quitter"))
+}
+
+/// Un clic sur l'icone ramene la fenetre principale ; sous Linux, ou le
+/// clic n'est pas remonte, le menu `Ouvrir ARGOS` la remplace.
+pub(crate) fn on_tray_event(app: &AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } | SystemTrayEvent::DoubleClick { .. } => {
+            instance::show_main_window(app);
+            refresh_status(app);
+        }
+        SystemTrayEvent::RightClick { .. } => refresh_status(app),
+        SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+            OPEN => instance::show_main_window(app),
+            LOCK => session::lock_app(app),
+            RESTART => app.restart(),
+            QUIT => app.exit(0),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// Icone, infobulle et menu pour l'etat de `StatusManager`, seul appelant.
+pub(crate) fn show_status(app: &AppHandle, status: &Status) {
+    let Some(tray) = tray(app) else {
+        return;
+    };
+    let (icon, tooltip) = match status.icon {
+        StatusIcon::Locked => (LOCKED_ICON, "ARGOS - session verrouillee".to_string()),
+        StatusIcon::Warning => (
+            WARNING_ICON,
+            format!("ARGOS - {} secret(s) bientot expire(s)", status.warnings),
+        ),
+        StatusIcon::Unlocked => (UNLOCKED_ICON, "ARGOS".to_string()),
+    };
+    let _ = tray.set_icon(Icon::Raw(icon.to_vec()));
+    #[cfg(target_os = "macos")]
+    let _ = tray.set_icon_as_template(true);
+    let _ = tray.set_tooltip(&tooltip);
+    let _ = tray.get_item(LOCK).set_enabled(!status.locked);
+}
+
+/// Ajoute `Redemarrer pour mettre a jour` au menu, puis restaure l'etat de
+/// la session et du keychain affiche par l'ancien menu.
+pub(crate) fn show_restart_pending(app: &AppHandle) {
+    if tray(app).is_none_or(|tray| tray.set_menu(menu(true)).is_err()) {
+        return;
+    }
+    if let Some(status) = app.try_state::<StatusManager>() {
+        status.refresh(app);
+    }
+    refresh_status(app);
+}
+
+/// Sonde le backend comme `keychain_status`, en tache de fond.
+pub(crate) fn refresh_status(app: &AppHandle) {
+    let Some(manager) = app.try_state::<KeychainManager>() else {
+        return;
+    };
+    let manager = manager.inner().clone();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let status = commands::probe_status(manager).await;
+        let title = if status.is_available() {
+            format!("\u{1f7e2} Keychain : {}", status.backend())
+        } else {
+            format!("\u{1f534} Keychain indisponible ({})", status.backend())
+        };
+        if let Some(tray) = tray(&app) {
+            let _ = tray.get_item(STATUS).set_title(title);
+        }
+    });
+}
+
+/// Avec `close_to_tray`, fermer la fenetre principale la cache seulement :
+/// la session et les taches de fond continuent.
+pub(crate) fn hide_on_close(event: &GlobalWindowEvent, enabled: bool) {
+    if !enabled || event.window().label() != MAIN_WINDOW {
+        return;
+    }
+    if let WindowEvent::CloseRequested { api, .. } = event.event() {
+        api.prevent_close();
+        let _ = event.window().hide();
+    }
+}
@@ -0,0 +1,238 @@
+//! Mises a jour d'ARGOS par l'updater Tauri. L'archive telechargee n'est
+//! installee qu'apres verification de sa signature par la cle publique
+//! embarquee dans `tauri.conf.json` : une signature invalide annule la mise
+//! a jour sans toucher a l'installation en cours.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use serde::Serialize;
+use tauri::updater::Error as UpdaterError;
+use tauri::{AppHandle, Manager, RunEvent, State, UpdaterEvent};
+
+use crate::keychain::{KeychainError, KeychainErrorCode};
+use crate::notifications::{self, Notice, NoticeKind, Target};
+use crate::{network, tray};
+
+pub(crate) const UPDATE_PROGRESS: &str = "argos://update-progress";
+pub(crate) const UPDATE_READY: &str = "argos://update-ready";
+
+#[derive(Serialize)]
+pub(crate) struct UpdateInfo {
+    available: bool,
+    version: Option<String>,
+    notes: Option<String>,
+    /// Date de publication, en RFC 3339.
+    published_at: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub(crate) struct UpdateStatus {
+    installing: bool,
+    /// Version installee en attente du redemarrage d'ARGOS.
+    restart_pending: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct UpdateProgress {
+    bytes: u64,
+    /// Taille annoncee par le serveur, `None` si inconnue.
+    total: Option<u64>,
+}
+
+/// Etat de la mise a jour, enregistre dans l'etat Tauri.
+#[derive(Default)]
+pub(crate) struct Updater {
+    installing: AtomicBool,
+    downloaded: AtomicU64,
+    restart_pending: Mutex<Option<String>>,
+}
+
+impl Updater {
+    fn restart_pending(&self) -> MutexGuard<'_, Option<String>> {
+        self.restart_pending
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn status(&self) -> UpdateStatus {
+        UpdateStatus {
+            installing: self.installing.load(Ordering::SeqCst),
+            restart_pending: self.restart_pending().clone(),
+        }
+    }
+}
+
+/// Libere `installing` a la fin du telechargement, meme en erreur.
+struct Installing<'a>(&'a Updater);
+
+impl Drop for Installing<'_> {
+    fn drop(&mut self) {
+        self.0.installing.store(false, Ordering::SeqCst);
+    }
+}
+
+fn update_failed(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::UpdateFailed,
+        format!("mise a jour impossible : {}", detail),
+    )
+}
+
+fn signature_invalid(detail: impl std::fmt::Display) -> KeychainError {
+    KeychainError::rejected(
+        KeychainErrorCode::UpdateSignatureInvalid,
+        format!(
+            "signature de la mise a jour invalide, installation annulee : {}",
+            detail
+        ),
+    )
+}
+
+/// Le telechargement peut finir fenetre cachee : un clic ouvre la page des
+/// mises a jour.
+fn notify(app: &AppHandle, title: &str, body: String, kind: NoticeKind) {
+    notifications::send(
+        app,
+        Notice {
+            title: title.to_string(),
+            body,
+            kind,
+            target: Some(Target {
+                page: "update",
+                service: None,
+                key: None,
+            }),
+        },
+    );
+}
+
+fn from_updater(e: UpdaterError) -> KeychainError {
+    match e {
+        UpdaterError::Minisign(_) | UpdaterError::Base64(_) | UpdaterError::SignatureUtf8(_) => {
+            signature_invalid(e)
+        }
+        e => update_failed(e),
+    }
+}
+
+/// Interroge les endpoints de `tauri.conf.json`. Sans version plus recente,
+/// `available` est faux et les autres champs sont vides ; reseau coupe,
+/// `Offline` est renvoye sans attendre.
+#[tauri::command]
+pub(crate) async fn check_for_update(app: AppHandle) -> Result<UpdateInfo, KeychainError> {
+    network::require_online(&app)?;
+    let update = match tauri::updater::builder(app).skip_events().check().await {
+        Ok(update) if update.is_update_available() => update,
+        Ok(_) | Err(UpdaterError::UpToDate) => {
+            return Ok(UpdateInfo {
+                available: false,
+                version: None,
+                notes: None,
+                published_at: None,
+            })
+        }
+        Err(e) => return Err(from_updater(e)),
+    };
+    Ok(UpdateInfo {
+        available: true,
+        version: Some(update.latest_version().to_string()),
+        notes: update.body().cloned(),
+        published_at: update.date().and_then(|date| {
+            chrono::DateTime::from_timestamp(date.unix_timestamp(), 0)
+                .map(|date| date.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        }),
+    })
+}
+
+/// Telecharge la mise a jour, emet `argos://update-progress` a chaque bloc,
+/// puis verifie la signature avant d'installer. Sous Windows l'installeur
+/// prend la main et ferme ARGOS ; ailleurs la nouvelle version attend le
+/// redemarrage, annonce par `argos://update-ready`.
+#[tauri::command]
+pub(crate) async fn download_and_install_update(
+    app: AppHandle,
+    updater: State<'_, Updater>,
+) -> Result<UpdateStatus, KeychainError> {
+    if app.config().tauri.updater.pubkey.trim().is_empty() {
+        return Err(signature_invalid("aucune cle publique embarquee"));
+    }
+    network::require_online(&app)?;
+    if updater.installing.swap(true, Ordering::SeqCst) {
+        return Err(update_failed("une mise a jour est deja en cours"));
+    }
+    let installing = Installing(&updater);
+    updater.downloaded.store(0, Ordering::SeqCst);
+
+    let update = match tauri::updater::builder(app.clone())
+        .skip_events()
+        .check()
+        .await
+    {
+        Ok(update) if update.is_update_available() => update,
+        Ok(_) | Err(UpdaterError::UpToDate) => {
+            return Err(update_failed("aucune version plus recente"))
+        }
+        Err(e) => return Err(from_updater(e)),
+    };
+    let version = update.latest_version().to_string();
+    if let Err(e) = update.download_and_install().await {
+        let e = from_updater(e);
+        notify(
+            &app,
+            "Mise a jour echouee",
+            e.to_string(),
+            NoticeKind::Error,
+        );
+        return Err(e);
+    }
+
+    *updater.restart_pending() = Some(version.clone());
+    tray::show_restart_pending(&app);
+    notify(
+        &app,
+        "Mise a jour prete",
+        format!("ARGOS {} sera installe au redemarrage", version),
+        NoticeKind::Info,
+    );
+    let _ = app.emit_all(UPDATE_READY, version);
+    drop(installing);
+    Ok(updater.status())
+}
+
+/// Suit le telechargement lance par `download_and_install_update` : l'updater
+/// ne remonte sa progression que par la boucle d'evenements.
+pub(crate) fn on_run_event(app: &AppHandle, event: &RunEvent) {
+    let RunEvent::Updater(UpdaterEvent::DownloadProgress {
+        chunk_length,
+        content_length,
+    }) = event
+    else {
+        return;
+    };
+    let Some(updater) = app.try_state::<Updater>() else {
+        return;
+    };
+    let bytes = updater
+        .downloaded
+        .fetch_add(*chunk_length as u64, Ordering::SeqCst)
+        + *chunk_length as u64;
+    let _ = app.emit_all(
+        UPDATE_PROGRESS,
+        UpdateProgress {
+            bytes,
+            total: *content_length,
+        },
+    );
+}
+
+#[tauri::command]
+pub(crate) fn update_status(updater: State<'_, Updater>) -> UpdateStatus {
+    updater.status()
+}
+
+/// Redemarre ARGOS sur la version installee.
+#[tauri::command]
+pub(crate) fn update_restart(app: AppHandle) {
+    app.restart();
+}
@@ -0,0 +1,235 @@
+//! Taille, position et etat agrandi de chaque fenetre, par label, conserves
+//! d'un lancement a l'autre dans `window-state.json`.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    App, GlobalWindowEvent, Manager, Monitor, PhysicalPosition, PhysicalSize, Window, WindowEvent,
+};
+
+use crate::config::write_atomic;
+use crate::instance::MAIN_WINDOW;
+
+pub(crate) const STATE_FILE: &str = "window-state.json";
+
+/// Un deplacement emet un evenement par pixel : l'ecriture attend la fin du
+/// geste.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+/// Largeur de barre de titre, en pixels physiques, qui doit rester sur un
+/// ecran pour que la fenetre puisse etre saisie.
+const GRAB_MARGIN: i64 = 64;
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Geometry {
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    maximized: bool,
+    /// Nom de l'ecran, pour y revenir s'il est rebranche.
+    monitor: Option<String>,
+}
+
+/// Geometrie des fenetres, enregistree dans l'etat Tauri.
+pub(crate) struct WindowStates {
+    path: Option<PathBuf>,
+    windows: Mutex<HashMap<String, Geometry>>,
+    save_scheduled: AtomicBool,
+}
+
+impl WindowStates {
+    /// Un fichier illisible ou corrompu est ignore : les fenetres gardent
+    /// leur taille par defaut.
+    fn load(path: Option<PathBuf>) -> Self {
+        let windows = match path.as_ref().map(std::fs::read) {
+            Some(Ok(data)) => serde_json::from_slice(&data).unwrap_or_else(|e| {
+                tracing::warn!(file = STATE_FILE, error = %e, "etat des fenetres ignore");
+                HashMap::new()
+            }),
+            _ => HashMap::new(),
+        };
+        WindowStates {
+            path,
+            windows: Mutex::new(windows),
+            save_scheduled: AtomicBool::new(false),
+        }
+    }
+
+    fn windows(&self) -> MutexGuard<'_, HashMap<String, Geometry>> {
+        self.windows.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Une fenetre reduite n'a pas de position utile. Agrandie, elle garde
+    /// la taille et la position d'avant l'agrandissement.
+    fn record(&self, window: &Window) {
+        if window.is_minimized().unwrap_or(true) {
+            return;
+        }
+        let (Ok(position), Ok(size)) = (window.outer_position(), window.inner_size()) else {
+            return;
+        };
+        let maximized = window.is_maximized().unwrap_or(false);
+        let monitor = window
+            .current_monitor()
+            .ok()
+            .flatten()
+            .and_then(|monitor| monitor.name().cloned());
+        let mut windows = self.windows();
+        let geometry = match windows.get(window.label()) {
+            Some(previous) if maximized => Geometry {
+                maximized,
+                monitor,
+                ..previous.clone()
+            },
+            _ => Geometry {
+                x: position.x,
+                y: position.y,
+                width: size.width,
+                height: size.height,
+                maximized,
+                monitor,
+            },
+        };
+        windows.insert(window.label().to_string(), geometry);
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let data = match serde_json::to_vec_pretty(&*self.windows()) {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!(file = STATE_FILE, error = %e, "etat des fenetres non enregistre");
+                return;
+            }
+        };
+        if let Err(e) = write_atomic(path, &data) {
+            tracing::warn!(file = STATE_FILE, error = %e, "etat des fenetres non enregistre");
+        }
+    }
+}
+
+/// Restaure la geometrie enregistree de chaque fenetre, puis affiche la
+/// fenetre principale, cachee par `tauri.conf.json` pour ne pas apparaitre
+/// d'abord au centre de l'ecran. Avec `--minimized`, elle reste cachee.
+pub(crate) fn restore_startup(app: &App, show: bool) {
+    let path = app
+        .path_resolver()
+        .app_data_dir()
+        .map(|dir| dir.join(STATE_FILE));
+    let states = WindowStates::load(path);
+    for (label, window) in app.windows() {
+        if let Some(geometry) = states.windows().get(&label) {
+            restore(&window, geometry);
+        }
+    }
+    app.manage(states);
+    if !show {
+        return;
+    }
+    if let Some(window) = app.get_window(MAIN_WINDOW) {
+        let _ = window.show();
+    }
+}
+
+/// Applique la geometrie enregistree d'une fenetre creee apres le
+/// demarrage.
+pub(crate) fn restore_window(window: &Window) {
+    let Some(states) = window.try_state::<WindowStates>() else {
+        return;
+    };
+    let geometry = states.windows().get(window.label()).cloned();
+    if let Some(geometry) = geometry {
+        restore(window, &geometry);
+    }
+}
+
+fn restore(window: &Window, geometry: &Geometry) {
+    let Ok(monitors) = window.available_monitors() else {
+        return;
+    };
+    let geometry = if monitors.iter().any(|monitor| grabbable(monitor, geometry)) {
+        geometry.clone()
+    } else {
+        // Ecran debranche ou resolution changee : la fenetre revient sur son
+        // ecran s'il est encore la, sinon sur l'ecran principal.
+        let target = monitors
+            .iter()
+            .find(|monitor| monitor.name().is_some() && monitor.name() == geometry.monitor.as_ref())
+            .cloned()
+            .or_else(|| window.primary_monitor().ok().flatten())
+            .or_else(|| monitors.first().cloned());
+        match target {
+            Some(monitor) => clamp(&monitor, geometry),
+            None => return,
+        }
+    };
+    let _ = window.set_size(PhysicalSize::new(geometry.width, geometry.height));
+    let _ = window.set_position(PhysicalPosition::new(geometry.x, geometry.y));
+    if geometry.maximized {
+        let _ = window.maximize();
+    }
+}
+
+/// Le haut de la fenetre doit etre sur l'ecran, sur au moins `GRAB_MARGIN`
+/// pixels de large.
+fn grabbable(monitor: &Monitor, geometry: &Geometry) -> bool {
+    let (left, top) = (monitor.position().x as i64, monitor.position().y as i64);
+    let right = left + monitor.size().width as i64;
+    let bottom = top + monitor.size().height as i64;
+    let (x, y) = (geometry.x as i64, geometry.y as i64);
+    let visible = (x + geometry.width as i64).min(right) - x.max(left);
+    y >= top && y + GRAB_MARGIN <= bottom && visible >= GRAB_MARGIN
+}
+
+fn clamp(monitor: &Monitor, geometry: &Geometry) -> Geometry {
+    let (left, top) = (monitor.position().x, monitor.position().y);
+    let width = geometry.width.min(monitor.size().width);
+    let height = geometry.height.min(monitor.size().height);
+    let max_x = left.saturating_add((monitor.size().width - width) as i32);
+    let max_y = top.saturating_add((monitor.size().height - height) as i32);
+    Geometry {
+        x: geometry.x.clamp(left, max_x),
+        y: geometry.y.clamp(top, max_y),
+        width,
+        height,
+        maximized: geometry.maximized,
+        monitor: monitor.name().cloned(),
+    }
+}
+
+/// Enregistre la geometrie apres un deplacement ou un redimensionnement, et
+/// tout de suite a la fermeture.
+pub(crate) fn on_window_event(event: &GlobalWindowEvent) {
+    let window = event.window();
+    let Some(states) = window.try_state::<WindowStates>() else {
+        return;
+    };
+    match event.event() {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            states.record(window);
+            if states.save_scheduled.swap(true, Ordering::SeqCst) {
+                return;
+            }
+            let app = window.app_handle();
+            std::thread::spawn(move || {
+                std::thread::sleep(SAVE_DELAY);
+                let states = app.state::<WindowStates>();
+                states.save_scheduled.store(false, Ordering::SeqCst);
+                states.save();
+            });
+        }
+        WindowEvent::CloseRequested { .. } => {
+            states.record(window);
+            states.save();
+        }
+        _ => {}
+    }
+}